@@ -5,7 +5,19 @@ use std::str::FromStr;
 pub mod models;
 
 pub use models::{Task, TaskStatus, CreateTask, UpdateTask};
-pub use models::chat::{ChatMessage, CreateChatMessage};
+pub use models::attachment::{Attachment, AttachmentOwner, CreateAttachment};
+pub use models::attempt::{AttemptStatus, CreateTaskAttempt, TaskAttempt};
+pub use models::chat::{ChatMessage, ChatToolCall, CreateChatMessage};
+pub use models::chat_thread::{ChatThread, CreateChatThread};
+pub use models::eval::{CreateEvalRun, CreateEvalSuite, EvalCase, EvalResult, EvalResultStatus, EvalRun, EvalRunStatus, EvalSuite, FinishEvalResult};
+pub use models::execution_log::ExecutionLog;
+pub use models::plan_session::PlanSessionRecord;
+pub use models::project::Project;
+pub use models::project_settings::ProjectSettings;
+pub use models::prompt_template::{CreatePromptTemplate, PromptTemplate, UpdatePromptTemplate};
+pub use models::run::{CheckStatus, RunCheck, RunStatus, RunUsage, TaskRun};
+pub use models::task_dependency::TaskDependency;
+pub use models::task_history::TaskDescriptionHistory;
 
 #[derive(Debug, thiserror::Error)]
 pub enum DbError {
@@ -44,6 +56,10 @@ pub fn get_worktrees_dir() -> PathBuf {
     get_data_dir().join("worktrees")
 }
 
+pub fn get_attachments_dir() -> PathBuf {
+    get_data_dir().join("attachments")
+}
+
 async fn run_migrations(pool: &Pool<Sqlite>) -> Result<(), DbError> {
     // Migration 001: Create tasks table
     sqlx::query(
@@ -148,6 +164,516 @@ async fn run_migrations(pool: &Pool<Sqlite>) -> Result<(), DbError> {
         .await
         .map_err(|e| DbError::Migration(e.to_string()))?;
 
+    // Migration 005: Add per-task budget limits
+    if !column_names.contains(&"budget_usd") {
+        sqlx::query("ALTER TABLE tasks ADD COLUMN budget_usd REAL")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    if !column_names.contains(&"budget_tokens") {
+        sqlx::query("ALTER TABLE tasks ADD COLUMN budget_tokens INTEGER")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    // Migration 006: Create task_runs table (one row per executor run, with usage)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_runs (
+            id TEXT PRIMARY KEY NOT NULL,
+            task_id TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'running',
+            input_tokens INTEGER NOT NULL DEFAULT 0,
+            output_tokens INTEGER NOT NULL DEFAULT 0,
+            cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+            cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+            cost_usd REAL NOT NULL DEFAULT 0,
+            started_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            finished_at DATETIME
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_task_runs_task ON task_runs(task_id, started_at)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    // Migration 007: Create project_settings table (project-level budgets)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS project_settings (
+            project_path TEXT PRIMARY KEY NOT NULL,
+            budget_usd REAL,
+            budget_tokens INTEGER,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    // Migration 008: Prompt templates, per-task template selection and project prefix/suffix
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS prompt_templates (
+            id TEXT PRIMARY KEY NOT NULL,
+            project_path TEXT NOT NULL,
+            name TEXT NOT NULL,
+            body TEXT NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_prompt_templates_project ON prompt_templates(project_path)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    if !column_names.contains(&"prompt_template_id") {
+        sqlx::query("ALTER TABLE tasks ADD COLUMN prompt_template_id TEXT")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    let settings_columns: Vec<(i64, String, String, i64, Option<String>, i64)> =
+        sqlx::query_as("PRAGMA table_info(project_settings)")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    let settings_column_names: Vec<&str> = settings_columns.iter().map(|(_, name, _, _, _, _)| name.as_str()).collect();
+
+    if !settings_column_names.contains(&"prompt_prefix") {
+        sqlx::query("ALTER TABLE project_settings ADD COLUMN prompt_prefix TEXT")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    if !settings_column_names.contains(&"prompt_suffix") {
+        sqlx::query("ALTER TABLE project_settings ADD COLUMN prompt_suffix TEXT")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    // Migration 009: Create attachments table (files uploaded for tasks and chat)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS attachments (
+            id TEXT PRIMARY KEY NOT NULL,
+            project_path TEXT NOT NULL,
+            owner_type TEXT NOT NULL,
+            owner_id TEXT,
+            filename TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            storage_path TEXT NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_attachments_owner ON attachments(owner_type, owner_id)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    // Migration 010: Create plan_sessions table (plan interviews survive restarts)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS plan_sessions (
+            id TEXT PRIMARY KEY NOT NULL,
+            project_path TEXT NOT NULL,
+            title TEXT NOT NULL,
+            prompt TEXT NOT NULL,
+            status TEXT NOT NULL,
+            questions TEXT NOT NULL DEFAULT '[]',
+            answers TEXT NOT NULL DEFAULT '[]',
+            pending_questions TEXT NOT NULL DEFAULT '[]',
+            summary TEXT,
+            plan_content TEXT,
+            accumulated_output TEXT NOT NULL DEFAULT '',
+            ask_questions BOOLEAN NOT NULL DEFAULT 0,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_plan_sessions_project ON plan_sessions(project_path, updated_at DESC)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    // Migration 011: Link plan sessions to existing tasks, keep replaced task descriptions
+    let plan_columns: Vec<(i64, String, String, i64, Option<String>, i64)> =
+        sqlx::query_as("PRAGMA table_info(plan_sessions)")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    let plan_column_names: Vec<&str> = plan_columns.iter().map(|(_, name, _, _, _, _)| name.as_str()).collect();
+
+    if !plan_column_names.contains(&"task_id") {
+        sqlx::query("ALTER TABLE plan_sessions ADD COLUMN task_id TEXT")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_description_history (
+            id TEXT PRIMARY KEY NOT NULL,
+            task_id TEXT NOT NULL,
+            description TEXT,
+            source TEXT NOT NULL,
+            plan_session_id TEXT,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_task_description_history_task ON task_description_history(task_id, created_at)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    // Migration 012: Plan breakdown into dependent tasks
+    if !plan_column_names.contains(&"breakdown") {
+        sqlx::query("ALTER TABLE plan_sessions ADD COLUMN breakdown BOOLEAN NOT NULL DEFAULT 0")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    if !plan_column_names.contains(&"steps") {
+        sqlx::query("ALTER TABLE plan_sessions ADD COLUMN steps TEXT NOT NULL DEFAULT '[]'")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    if !plan_column_names.contains(&"steps_error") {
+        sqlx::query("ALTER TABLE plan_sessions ADD COLUMN steps_error TEXT")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_dependencies (
+            task_id TEXT NOT NULL,
+            depends_on_task_id TEXT NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (task_id, depends_on_task_id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    // Migration 013: Claude conversation id for resuming plan sessions
+    if !plan_column_names.contains(&"claude_session_id") {
+        sqlx::query("ALTER TABLE plan_sessions ADD COLUMN claude_session_id TEXT")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    // Migration 014: Chat threads, and tool calls on chat messages
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS chat_threads (
+            id TEXT PRIMARY KEY NOT NULL,
+            project_path TEXT NOT NULL,
+            title TEXT NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    let chat_columns: Vec<(i64, String, String, i64, Option<String>, i64)> =
+        sqlx::query_as("PRAGMA table_info(chat_messages)")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    let chat_column_names: Vec<&str> = chat_columns.iter().map(|(_, name, _, _, _, _)| name.as_str()).collect();
+
+    if !chat_column_names.contains(&"thread_id") {
+        sqlx::query("ALTER TABLE chat_messages ADD COLUMN thread_id TEXT")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+
+        // Existing history becomes one thread per project
+        sqlx::query(
+            r#"
+            INSERT INTO chat_threads (id, project_path, title, created_at, updated_at)
+            SELECT lower(hex(randomblob(16))), project_path, 'Chat', MIN(created_at), MAX(created_at)
+            FROM chat_messages
+            GROUP BY project_path
+            "#,
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            UPDATE chat_messages SET thread_id = (
+                SELECT id FROM chat_threads WHERE chat_threads.project_path = chat_messages.project_path
+            )
+            WHERE thread_id IS NULL
+            "#,
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    if !chat_column_names.contains(&"tool_calls") {
+        sqlx::query("ALTER TABLE chat_messages ADD COLUMN tool_calls TEXT")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_chat_messages_thread ON chat_messages(thread_id, created_at)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_chat_threads_project ON chat_threads(project_path, updated_at)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    // Migration 015: Persist executor output
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS execution_logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id TEXT NOT NULL,
+            run_id TEXT,
+            stream TEXT NOT NULL,
+            content TEXT NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_execution_logs_task ON execution_logs(task_id, id)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    // Migration 016: Projects, registered from the paths already in use
+    let has_projects: Option<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'projects'"
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS projects (
+            id TEXT PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            path TEXT NOT NULL UNIQUE,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            last_opened_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    if has_projects.is_none() {
+        // The name is the last path component
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO projects (id, name, path, created_at, last_opened_at)
+            SELECT lower(hex(randomblob(16))),
+                   replace(project_path, rtrim(project_path, replace(project_path, '/', '')), ''),
+                   project_path, MIN(created_at), MAX(updated_at)
+            FROM tasks
+            WHERE project_path IS NOT NULL AND project_path != ''
+            GROUP BY project_path
+            "#,
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    // Migration 017: Verification checks run after each run
+    let run_columns: Vec<(i64, String, String, i64, Option<String>, i64)> =
+        sqlx::query_as("PRAGMA table_info(task_runs)")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    if !run_columns.iter().any(|(_, name, _, _, _, _)| name == "checks") {
+        sqlx::query("ALTER TABLE task_runs ADD COLUMN checks TEXT NOT NULL DEFAULT '[]'")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    if !column_names.contains(&"checks_status") {
+        sqlx::query("ALTER TABLE tasks ADD COLUMN checks_status TEXT")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    // Migration 018: Parallel attempts of a task, and the runs belonging to them
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_attempts (
+            id TEXT PRIMARY KEY NOT NULL,
+            task_id TEXT NOT NULL,
+            number INTEGER NOT NULL,
+            model TEXT,
+            prompt_template_id TEXT,
+            branch_name TEXT,
+            worktree_path TEXT,
+            status TEXT NOT NULL DEFAULT 'running',
+            error_message TEXT,
+            checks_status TEXT,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            finished_at DATETIME
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_task_attempts_task ON task_attempts(task_id, number)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    if !run_columns.iter().any(|(_, name, _, _, _, _)| name == "attempt_id") {
+        sqlx::query("ALTER TABLE task_runs ADD COLUMN attempt_id TEXT")
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::Migration(e.to_string()))?;
+    }
+
+    // Migration 019: Eval suites, their runs and per-case results
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS eval_suites (
+            id TEXT PRIMARY KEY NOT NULL,
+            project_path TEXT NOT NULL,
+            name TEXT NOT NULL,
+            base_commit TEXT NOT NULL,
+            score_command TEXT NOT NULL,
+            score_timeout_secs INTEGER NOT NULL,
+            cases TEXT NOT NULL DEFAULT '[]',
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS eval_runs (
+            id TEXT PRIMARY KEY NOT NULL,
+            suite_id TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'running',
+            label TEXT,
+            prompt_template_id TEXT,
+            model TEXT,
+            cli_version TEXT,
+            app_version TEXT NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            finished_at DATETIME
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS eval_results (
+            id TEXT PRIMARY KEY NOT NULL,
+            eval_run_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            case_name TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            score REAL,
+            exit_code INTEGER,
+            input_tokens INTEGER NOT NULL DEFAULT 0,
+            output_tokens INTEGER NOT NULL DEFAULT 0,
+            cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+            cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+            cost_usd REAL NOT NULL DEFAULT 0,
+            duration_ms INTEGER,
+            error TEXT,
+            output TEXT,
+            started_at DATETIME,
+            finished_at DATETIME
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_eval_runs_suite ON eval_runs(suite_id, created_at)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_eval_results_run ON eval_results(eval_run_id, position)")
+        .execute(pool)
+        .await
+        .map_err(|e| DbError::Migration(e.to_string()))?;
+
     tracing::info!("Database migrations completed");
     Ok(())
 }
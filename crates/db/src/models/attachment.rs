@@ -0,0 +1,294 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+use uuid::Uuid;
+
+/// What an attachment belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentOwner {
+    Task,
+    ChatMessage,
+}
+
+impl AttachmentOwner {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttachmentOwner::Task => "task",
+            AttachmentOwner::ChatMessage => "chat_message",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "task" => Some(AttachmentOwner::Task),
+            "chat_message" => Some(AttachmentOwner::ChatMessage),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AttachmentRow {
+    pub id: String,
+    pub project_path: String,
+    pub owner_type: String,
+    pub owner_id: Option<String>,
+    pub filename: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub storage_path: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A file uploaded for a task or chat message. The content is stored on disk
+/// at `storage_path`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    pub project_path: String,
+    pub owner_type: AttachmentOwner,
+    /// `None` for chat uploads not yet sent with a message
+    pub owner_id: Option<String>,
+    pub filename: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub storage_path: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AttachmentRow> for Attachment {
+    fn from(row: AttachmentRow) -> Self {
+        Attachment {
+            id: row.id,
+            project_path: row.project_path,
+            owner_type: AttachmentOwner::from_str(&row.owner_type).unwrap_or(AttachmentOwner::Task),
+            owner_id: row.owner_id,
+            filename: row.filename,
+            mime_type: row.mime_type,
+            size_bytes: row.size_bytes,
+            storage_path: row.storage_path,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateAttachment {
+    /// Pre-generated so the blob can be stored under the attachment's id
+    pub id: String,
+    pub project_path: String,
+    pub owner_type: AttachmentOwner,
+    pub owner_id: Option<String>,
+    pub filename: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub storage_path: String,
+}
+
+impl CreateAttachment {
+    pub fn new_id() -> String {
+        Uuid::new_v4().to_string()
+    }
+}
+
+const ATTACHMENT_COLUMNS: &str = "id, project_path, owner_type, owner_id, filename, mime_type, size_bytes, storage_path, created_at";
+
+impl Attachment {
+    pub async fn create(pool: &Pool<Sqlite>, input: CreateAttachment) -> Result<Attachment, sqlx::Error> {
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO attachments (id, project_path, owner_type, owner_id, filename, mime_type, size_bytes, storage_path, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&input.id)
+        .bind(&input.project_path)
+        .bind(input.owner_type.as_str())
+        .bind(&input.owner_id)
+        .bind(&input.filename)
+        .bind(&input.mime_type)
+        .bind(input.size_bytes)
+        .bind(&input.storage_path)
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+        Ok(Attachment {
+            id: input.id,
+            project_path: input.project_path,
+            owner_type: input.owner_type,
+            owner_id: input.owner_id,
+            filename: input.filename,
+            mime_type: input.mime_type,
+            size_bytes: input.size_bytes,
+            storage_path: input.storage_path,
+            created_at: now,
+        })
+    }
+
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<Attachment>, sqlx::Error> {
+        let row: Option<AttachmentRow> = sqlx::query_as(
+            &format!("SELECT {} FROM attachments WHERE id = ?", ATTACHMENT_COLUMNS)
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(Attachment::from))
+    }
+
+    pub async fn find_by_owner(
+        pool: &Pool<Sqlite>,
+        owner_type: AttachmentOwner,
+        owner_id: &str,
+    ) -> Result<Vec<Attachment>, sqlx::Error> {
+        let rows: Vec<AttachmentRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM attachments WHERE owner_type = ? AND owner_id = ? ORDER BY created_at ASC",
+            ATTACHMENT_COLUMNS
+        ))
+        .bind(owner_type.as_str())
+        .bind(owner_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(Attachment::from).collect())
+    }
+
+    pub async fn find_by_project(
+        pool: &Pool<Sqlite>,
+        project_path: &str,
+        owner_type: AttachmentOwner,
+    ) -> Result<Vec<Attachment>, sqlx::Error> {
+        let rows: Vec<AttachmentRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM attachments WHERE project_path = ? AND owner_type = ? ORDER BY created_at ASC",
+            ATTACHMENT_COLUMNS
+        ))
+        .bind(project_path)
+        .bind(owner_type.as_str())
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(Attachment::from).collect())
+    }
+
+    /// Attach an unassigned upload to its owner. Returns false if the
+    /// attachment does not exist or already belongs to something.
+    pub async fn assign(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        owner_type: AttachmentOwner,
+        owner_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE attachments SET owner_id = ? WHERE id = ? AND owner_type = ? AND owner_id IS NULL",
+        )
+        .bind(owner_id)
+        .bind(id)
+        .bind(owner_type.as_str())
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete(pool: &Pool<Sqlite>, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM attachments WHERE id = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE attachments (
+                id TEXT PRIMARY KEY NOT NULL,
+                project_path TEXT NOT NULL,
+                owner_type TEXT NOT NULL,
+                owner_id TEXT,
+                filename TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                storage_path TEXT NOT NULL,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    fn input(owner_type: AttachmentOwner, owner_id: Option<&str>) -> CreateAttachment {
+        let id = CreateAttachment::new_id();
+        CreateAttachment {
+            storage_path: format!("/blobs/{}/bug.png", id),
+            id,
+            project_path: "/project".to_string(),
+            owner_type,
+            owner_id: owner_id.map(String::from),
+            filename: "bug.png".to_string(),
+            mime_type: "image/png".to_string(),
+            size_bytes: 42,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_and_find_by_owner() {
+        let pool = setup_test_db().await;
+
+        let created = Attachment::create(&pool, input(AttachmentOwner::Task, Some("task-1"))).await.unwrap();
+        Attachment::create(&pool, input(AttachmentOwner::Task, Some("task-2"))).await.unwrap();
+
+        let found = Attachment::find_by_owner(&pool, AttachmentOwner::Task, "task-1").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, created.id);
+        assert_eq!(found[0].owner_type, AttachmentOwner::Task);
+        assert_eq!(found[0].size_bytes, 42);
+
+        assert!(Attachment::delete(&pool, &created.id).await.unwrap());
+        assert!(Attachment::find_by_id(&pool, &created.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_assign_only_unowned() {
+        let pool = setup_test_db().await;
+
+        let pending = Attachment::create(&pool, input(AttachmentOwner::ChatMessage, None)).await.unwrap();
+        assert!(Attachment::assign(&pool, &pending.id, AttachmentOwner::ChatMessage, "msg-1").await.unwrap());
+        assert!(!Attachment::assign(&pool, &pending.id, AttachmentOwner::ChatMessage, "msg-2").await.unwrap());
+
+        let found = Attachment::find_by_owner(&pool, AttachmentOwner::ChatMessage, "msg-1").await.unwrap();
+        assert_eq!(found.len(), 1);
+
+        let task_owned = Attachment::create(&pool, input(AttachmentOwner::Task, None)).await.unwrap();
+        assert!(!Attachment::assign(&pool, &task_owned.id, AttachmentOwner::ChatMessage, "msg-1").await.unwrap());
+    }
+
+    #[test]
+    fn test_owner_roundtrip() {
+        for owner in [AttachmentOwner::Task, AttachmentOwner::ChatMessage] {
+            assert_eq!(AttachmentOwner::from_str(owner.as_str()), Some(owner));
+        }
+        assert_eq!(AttachmentOwner::from_str("invalid"), None);
+    }
+}
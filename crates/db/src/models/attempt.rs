@@ -0,0 +1,364 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+use uuid::Uuid;
+
+use super::run::CheckStatus;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    BudgetExceeded,
+    /// Chosen as the task's result; its branch is now the task's
+    Picked,
+    /// Another attempt was picked and this one's worktree removed
+    Discarded,
+}
+
+impl AttemptStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttemptStatus::Running => "running",
+            AttemptStatus::Succeeded => "succeeded",
+            AttemptStatus::Failed => "failed",
+            AttemptStatus::Cancelled => "cancelled",
+            AttemptStatus::BudgetExceeded => "budget_exceeded",
+            AttemptStatus::Picked => "picked",
+            AttemptStatus::Discarded => "discarded",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "running" => Some(AttemptStatus::Running),
+            "succeeded" => Some(AttemptStatus::Succeeded),
+            "failed" => Some(AttemptStatus::Failed),
+            "cancelled" => Some(AttemptStatus::Cancelled),
+            "budget_exceeded" => Some(AttemptStatus::BudgetExceeded),
+            "picked" => Some(AttemptStatus::Picked),
+            "discarded" => Some(AttemptStatus::Discarded),
+            _ => None,
+        }
+    }
+
+    /// Whether the attempt has stopped running and can be picked or discarded.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            AttemptStatus::Succeeded | AttemptStatus::Failed | AttemptStatus::Cancelled | AttemptStatus::BudgetExceeded
+        )
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct TaskAttemptRow {
+    pub id: String,
+    pub task_id: String,
+    pub number: i64,
+    pub model: Option<String>,
+    pub prompt_template_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub worktree_path: Option<String>,
+    pub status: String,
+    pub error_message: Option<String>,
+    pub checks_status: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// One of several runs of a task started side by side, each in its own
+/// worktree, to be compared before one is picked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskAttempt {
+    pub id: String,
+    pub task_id: String,
+    /// 1-based position among the task's attempts
+    pub number: i64,
+    /// Model the attempt runs with, or the default
+    pub model: Option<String>,
+    /// Prompt template the attempt runs with, or the task's
+    pub prompt_template_id: Option<String>,
+    pub branch_name: Option<String>,
+    pub worktree_path: Option<String>,
+    pub status: AttemptStatus,
+    pub error_message: Option<String>,
+    /// Verification checks of the attempt's latest run, if any were run
+    pub checks_status: Option<CheckStatus>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl From<TaskAttemptRow> for TaskAttempt {
+    fn from(row: TaskAttemptRow) -> Self {
+        TaskAttempt {
+            id: row.id,
+            task_id: row.task_id,
+            number: row.number,
+            model: row.model,
+            prompt_template_id: row.prompt_template_id,
+            branch_name: row.branch_name,
+            worktree_path: row.worktree_path,
+            status: AttemptStatus::from_str(&row.status).unwrap_or(AttemptStatus::Failed),
+            error_message: row.error_message,
+            checks_status: row.checks_status.as_deref().and_then(CheckStatus::from_str),
+            created_at: row.created_at,
+            finished_at: row.finished_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateTaskAttempt {
+    pub task_id: String,
+    pub number: i64,
+    pub model: Option<String>,
+    pub prompt_template_id: Option<String>,
+}
+
+const ATTEMPT_COLUMNS: &str = "id, task_id, number, model, prompt_template_id, branch_name, worktree_path, status, error_message, checks_status, created_at, finished_at";
+
+impl TaskAttempt {
+    pub async fn create(pool: &Pool<Sqlite>, input: CreateTaskAttempt) -> Result<TaskAttempt, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO task_attempts (id, task_id, number, model, prompt_template_id, status, created_at)
+            VALUES (?, ?, ?, ?, ?, 'running', ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&input.task_id)
+        .bind(input.number)
+        .bind(&input.model)
+        .bind(&input.prompt_template_id)
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+        Ok(TaskAttempt {
+            id,
+            task_id: input.task_id,
+            number: input.number,
+            model: input.model,
+            prompt_template_id: input.prompt_template_id,
+            branch_name: None,
+            worktree_path: None,
+            status: AttemptStatus::Running,
+            error_message: None,
+            checks_status: None,
+            created_at: now,
+            finished_at: None,
+        })
+    }
+
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<TaskAttempt>, sqlx::Error> {
+        let row: Option<TaskAttemptRow> = sqlx::query_as(
+            &format!("SELECT {} FROM task_attempts WHERE id = ?", ATTEMPT_COLUMNS)
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(TaskAttempt::from))
+    }
+
+    pub async fn find_by_task(pool: &Pool<Sqlite>, task_id: &str) -> Result<Vec<TaskAttempt>, sqlx::Error> {
+        let rows: Vec<TaskAttemptRow> = sqlx::query_as(
+            &format!("SELECT {} FROM task_attempts WHERE task_id = ? ORDER BY number ASC", ATTEMPT_COLUMNS)
+        )
+        .bind(task_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(TaskAttempt::from).collect())
+    }
+
+    /// Worktrees of attempts that haven't been discarded.
+    pub async fn worktree_paths(pool: &Pool<Sqlite>) -> Result<Vec<String>, sqlx::Error> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT worktree_path FROM task_attempts WHERE worktree_path IS NOT NULL AND status != 'discarded'"
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(path,)| path).collect())
+    }
+
+    pub async fn set_worktree(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        branch_name: &str,
+        worktree_path: &str,
+    ) -> Result<Option<TaskAttempt>, sqlx::Error> {
+        let result = sqlx::query("UPDATE task_attempts SET branch_name = ?, worktree_path = ? WHERE id = ?")
+            .bind(branch_name)
+            .bind(worktree_path)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Self::find_by_id(pool, id).await
+    }
+
+    /// Record how the attempt's runs ended.
+    pub async fn finish(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        status: AttemptStatus,
+        error_message: Option<String>,
+    ) -> Result<Option<TaskAttempt>, sqlx::Error> {
+        let result = sqlx::query("UPDATE task_attempts SET status = ?, error_message = ?, finished_at = ? WHERE id = ?")
+            .bind(status.as_str())
+            .bind(error_message)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Self::find_by_id(pool, id).await
+    }
+
+    /// Mark a finished attempt as picked or discarded.
+    pub async fn set_status(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        status: AttemptStatus,
+    ) -> Result<Option<TaskAttempt>, sqlx::Error> {
+        let result = sqlx::query("UPDATE task_attempts SET status = ? WHERE id = ?")
+            .bind(status.as_str())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Self::find_by_id(pool, id).await
+    }
+
+    pub async fn set_checks_status(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        checks_status: Option<CheckStatus>,
+    ) -> Result<Option<TaskAttempt>, sqlx::Error> {
+        let result = sqlx::query("UPDATE task_attempts SET checks_status = ? WHERE id = ?")
+            .bind(checks_status.map(|s| s.as_str()))
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Self::find_by_id(pool, id).await
+    }
+
+    pub async fn delete_by_task(pool: &Pool<Sqlite>, task_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM task_attempts WHERE task_id = ?")
+            .bind(task_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE task_attempts (
+                id TEXT PRIMARY KEY NOT NULL,
+                task_id TEXT NOT NULL,
+                number INTEGER NOT NULL,
+                model TEXT,
+                prompt_template_id TEXT,
+                branch_name TEXT,
+                worktree_path TEXT,
+                status TEXT NOT NULL DEFAULT 'running',
+                error_message TEXT,
+                checks_status TEXT,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                finished_at DATETIME
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    fn attempt(number: i64, model: Option<&str>) -> CreateTaskAttempt {
+        CreateTaskAttempt {
+            task_id: "task-a".to_string(),
+            number,
+            model: model.map(str::to_string),
+            prompt_template_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attempt_lifecycle() {
+        let pool = setup_test_db().await;
+
+        let second = TaskAttempt::create(&pool, attempt(2, None)).await.unwrap();
+        let first = TaskAttempt::create(&pool, attempt(1, Some("opus"))).await.unwrap();
+        assert_eq!(first.status, AttemptStatus::Running);
+
+        TaskAttempt::set_worktree(&pool, &first.id, "ek/fix-1", "/wt/1").await.unwrap();
+        TaskAttempt::set_worktree(&pool, &second.id, "ek/fix-2", "/wt/2").await.unwrap();
+        TaskAttempt::set_checks_status(&pool, &first.id, Some(CheckStatus::Passed)).await.unwrap();
+
+        let finished = TaskAttempt::finish(&pool, &first.id, AttemptStatus::Succeeded, None).await.unwrap().unwrap();
+        assert_eq!(finished.status, AttemptStatus::Succeeded);
+        assert_eq!(finished.checks_status, Some(CheckStatus::Passed));
+        assert_eq!(finished.model.as_deref(), Some("opus"));
+        assert!(finished.finished_at.is_some());
+
+        let attempts = TaskAttempt::find_by_task(&pool, "task-a").await.unwrap();
+        assert_eq!(attempts.iter().map(|a| a.number).collect::<Vec<_>>(), vec![1, 2]);
+
+        TaskAttempt::set_status(&pool, &second.id, AttemptStatus::Discarded).await.unwrap();
+        assert_eq!(TaskAttempt::worktree_paths(&pool).await.unwrap(), vec!["/wt/1".to_string()]);
+
+        TaskAttempt::delete_by_task(&pool, "task-a").await.unwrap();
+        assert!(TaskAttempt::find_by_task(&pool, "task-a").await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_attempt_status_conversion() {
+        assert_eq!(AttemptStatus::BudgetExceeded.as_str(), "budget_exceeded");
+        assert_eq!(AttemptStatus::from_str("picked"), Some(AttemptStatus::Picked));
+        assert_eq!(AttemptStatus::from_str("invalid"), None);
+        assert!(AttemptStatus::Cancelled.is_finished());
+        assert!(!AttemptStatus::Discarded.is_finished());
+    }
+}
@@ -18,6 +18,7 @@ impl ChatRole {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "user" => Some(ChatRole::User),
@@ -27,13 +28,25 @@ impl ChatRole {
     }
 }
 
+/// A tool Claude called while writing an assistant message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatToolCall {
+    pub id: String,
+    pub name: String,
+    pub input: serde_json::Value,
+    #[serde(default)]
+    pub is_error: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ChatMessageRow {
     pub id: String,
     pub project_path: String,
+    pub thread_id: Option<String>,
     pub role: String,
     pub content: String,
     pub image_data: Option<String>,
+    pub tool_calls: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -41,20 +54,28 @@ pub struct ChatMessageRow {
 pub struct ChatMessage {
     pub id: String,
     pub project_path: String,
+    pub thread_id: Option<String>,
     pub role: ChatRole,
     pub content: String,
     pub image_data: Option<String>,
+    pub tool_calls: Vec<ChatToolCall>,
     pub created_at: DateTime<Utc>,
 }
 
+const CHAT_MESSAGE_COLUMNS: &str = "id, project_path, thread_id, role, content, image_data, tool_calls, created_at";
+
 impl From<ChatMessageRow> for ChatMessage {
     fn from(row: ChatMessageRow) -> Self {
         ChatMessage {
             id: row.id,
             project_path: row.project_path,
+            thread_id: row.thread_id,
             role: ChatRole::from_str(&row.role).unwrap_or(ChatRole::User),
             content: row.content,
             image_data: row.image_data,
+            tool_calls: row.tool_calls
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
             created_at: row.created_at,
         }
     }
@@ -63,27 +84,42 @@ impl From<ChatMessageRow> for ChatMessage {
 #[derive(Debug, Clone, Deserialize)]
 pub struct CreateChatMessage {
     pub project_path: String,
+    pub thread_id: Option<String>,
     pub role: ChatRole,
     pub content: String,
     pub image_data: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<ChatToolCall>,
 }
 
 impl ChatMessage {
     pub async fn create(pool: &Pool<Sqlite>, input: CreateChatMessage) -> Result<ChatMessage, sqlx::Error> {
-        let id = Uuid::new_v4().to_string();
+        ChatMessage::create_with_id(pool, Uuid::new_v4().to_string(), input).await
+    }
+
+    /// Create a message under an id chosen beforehand, e.g. a reply whose
+    /// chunks were streamed under that id.
+    pub async fn create_with_id(pool: &Pool<Sqlite>, id: String, input: CreateChatMessage) -> Result<ChatMessage, sqlx::Error> {
         let now = Utc::now();
+        let tool_calls = if input.tool_calls.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&input.tool_calls).ok()
+        };
 
         sqlx::query(
             r#"
-            INSERT INTO chat_messages (id, project_path, role, content, image_data, created_at)
-            VALUES (?, ?, ?, ?, ?, ?)
+            INSERT INTO chat_messages (id, project_path, thread_id, role, content, image_data, tool_calls, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&id)
         .bind(&input.project_path)
+        .bind(&input.thread_id)
         .bind(input.role.as_str())
         .bind(&input.content)
         .bind(&input.image_data)
+        .bind(tool_calls)
         .bind(now)
         .execute(pool)
         .await?;
@@ -91,9 +127,11 @@ impl ChatMessage {
         Ok(ChatMessage {
             id,
             project_path: input.project_path,
+            thread_id: input.thread_id,
             role: input.role,
             content: input.content,
             image_data: input.image_data,
+            tool_calls: input.tool_calls,
             created_at: now,
         })
     }
@@ -104,15 +142,10 @@ impl ChatMessage {
         limit: Option<i64>,
     ) -> Result<Vec<ChatMessage>, sqlx::Error> {
         let limit = limit.unwrap_or(100);
-        let rows: Vec<ChatMessageRow> = sqlx::query_as(
-            r#"
-            SELECT id, project_path, role, content, image_data, created_at
-            FROM chat_messages
-            WHERE project_path = ?
-            ORDER BY created_at ASC
-            LIMIT ?
-            "#,
-        )
+        let rows: Vec<ChatMessageRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM chat_messages WHERE project_path = ? ORDER BY created_at ASC LIMIT ?",
+            CHAT_MESSAGE_COLUMNS
+        ))
         .bind(project_path)
         .bind(limit)
         .fetch_all(pool)
@@ -121,6 +154,46 @@ impl ChatMessage {
         Ok(rows.into_iter().map(ChatMessage::from).collect())
     }
 
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<ChatMessage>, sqlx::Error> {
+        let row: Option<ChatMessageRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM chat_messages WHERE id = ?",
+            CHAT_MESSAGE_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(ChatMessage::from))
+    }
+
+    /// The most recent messages of a thread, oldest first.
+    pub async fn find_by_thread(
+        pool: &Pool<Sqlite>,
+        thread_id: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<ChatMessage>, sqlx::Error> {
+        let limit = limit.unwrap_or(100);
+        let rows: Vec<ChatMessageRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM (SELECT * FROM chat_messages WHERE thread_id = ? ORDER BY created_at DESC LIMIT ?) ORDER BY created_at ASC",
+            CHAT_MESSAGE_COLUMNS
+        ))
+        .bind(thread_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(ChatMessage::from).collect())
+    }
+
+    pub async fn delete_by_thread(pool: &Pool<Sqlite>, thread_id: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM chat_messages WHERE thread_id = ?")
+            .bind(thread_id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
     pub async fn delete_by_project(pool: &Pool<Sqlite>, project_path: &str) -> Result<u64, sqlx::Error> {
         let result = sqlx::query("DELETE FROM chat_messages WHERE project_path = ?")
             .bind(project_path)
@@ -147,9 +220,11 @@ mod tests {
             CREATE TABLE chat_messages (
                 id TEXT PRIMARY KEY NOT NULL,
                 project_path TEXT NOT NULL,
+                thread_id TEXT,
                 role TEXT NOT NULL,
                 content TEXT NOT NULL,
                 image_data TEXT,
+                tool_calls TEXT,
                 created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
             )
             "#,
@@ -172,6 +247,8 @@ mod tests {
                 role: ChatRole::User,
                 content: "Hello, Claude!".to_string(),
                 image_data: None,
+                thread_id: None,
+                tool_calls: Vec::new(),
             },
         )
         .await
@@ -182,6 +259,30 @@ mod tests {
         assert_eq!(msg.project_path, "/test/project");
     }
 
+    #[tokio::test]
+    async fn test_create_with_id() {
+        let pool = setup_test_db().await;
+
+        ChatMessage::create_with_id(
+            &pool,
+            "reply-1".to_string(),
+            CreateChatMessage {
+                project_path: "/test/project".to_string(),
+                role: ChatRole::Assistant,
+                content: "Partial answer".to_string(),
+                image_data: None,
+                thread_id: Some("thread-1".to_string()),
+                tool_calls: Vec::new(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let found = ChatMessage::find_by_id(&pool, "reply-1").await.unwrap().unwrap();
+        assert_eq!(found.content, "Partial answer");
+        assert_eq!(found.role, ChatRole::Assistant);
+    }
+
     #[tokio::test]
     async fn test_create_message_with_image() {
         let pool = setup_test_db().await;
@@ -193,6 +294,8 @@ mod tests {
                 role: ChatRole::User,
                 content: "Check this image".to_string(),
                 image_data: Some("base64encodeddata".to_string()),
+                thread_id: None,
+                tool_calls: Vec::new(),
             },
         )
         .await
@@ -213,6 +316,8 @@ mod tests {
                 role: ChatRole::User,
                 content: "Message 1".to_string(),
                 image_data: None,
+                thread_id: None,
+                tool_calls: Vec::new(),
             },
         )
         .await
@@ -225,6 +330,8 @@ mod tests {
                 role: ChatRole::Assistant,
                 content: "Response 1".to_string(),
                 image_data: None,
+                thread_id: None,
+                tool_calls: Vec::new(),
             },
         )
         .await
@@ -237,6 +344,8 @@ mod tests {
                 role: ChatRole::User,
                 content: "Different project".to_string(),
                 image_data: None,
+                thread_id: None,
+                tool_calls: Vec::new(),
             },
         )
         .await
@@ -266,6 +375,8 @@ mod tests {
                 role: ChatRole::User,
                 content: "To be deleted".to_string(),
                 image_data: None,
+                thread_id: None,
+                tool_calls: Vec::new(),
             },
         )
         .await
@@ -278,6 +389,8 @@ mod tests {
                 role: ChatRole::User,
                 content: "Should remain".to_string(),
                 image_data: None,
+                thread_id: None,
+                tool_calls: Vec::new(),
             },
         )
         .await
@@ -299,6 +412,46 @@ mod tests {
         assert_eq!(other.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_threads_and_tool_calls() {
+        let pool = setup_test_db().await;
+
+        let tool_call = ChatToolCall {
+            id: "toolu_1".to_string(),
+            name: "Read".to_string(),
+            input: serde_json::json!({ "file_path": "src/main.rs" }),
+            is_error: false,
+        };
+
+        for (thread_id, content) in [("t1", "First"), ("t2", "Other thread"), ("t1", "Second")] {
+            ChatMessage::create(
+                &pool,
+                CreateChatMessage {
+                    project_path: "/project/a".to_string(),
+                    thread_id: Some(thread_id.to_string()),
+                    role: ChatRole::Assistant,
+                    content: content.to_string(),
+                    image_data: None,
+                    tool_calls: vec![tool_call.clone()],
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let messages = ChatMessage::find_by_thread(&pool, "t1", None).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, "First");
+        assert_eq!(messages[1].tool_calls, vec![tool_call]);
+
+        // The limit keeps the most recent messages
+        let latest = ChatMessage::find_by_thread(&pool, "t1", Some(1)).await.unwrap();
+        assert_eq!(latest[0].content, "Second");
+
+        assert_eq!(ChatMessage::delete_by_thread(&pool, "t1").await.unwrap(), 2);
+        assert_eq!(ChatMessage::find_by_project(&pool, "/project/a", None).await.unwrap().len(), 1);
+    }
+
     #[test]
     fn test_chat_role_conversion() {
         assert_eq!(ChatRole::User.as_str(), "user");
@@ -0,0 +1,249 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+use uuid::Uuid;
+
+/// Title given to threads created without one.
+pub const DEFAULT_THREAD_TITLE: &str = "New chat";
+
+/// A named conversation within a project's chat.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ChatThread {
+    pub id: String,
+    pub project_path: String,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    /// Last time a message was added
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateChatThread {
+    pub project_path: String,
+    pub title: Option<String>,
+}
+
+const THREAD_COLUMNS: &str = "id, project_path, title, created_at, updated_at";
+
+impl ChatThread {
+    pub async fn create(pool: &Pool<Sqlite>, input: CreateChatThread) -> Result<ChatThread, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let title = input.title
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| DEFAULT_THREAD_TITLE.to_string());
+
+        sqlx::query(
+            r#"
+            INSERT INTO chat_threads (id, project_path, title, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&input.project_path)
+        .bind(&title)
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+        Ok(ChatThread {
+            id,
+            project_path: input.project_path,
+            title,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<ChatThread>, sqlx::Error> {
+        sqlx::query_as(&format!("SELECT {} FROM chat_threads WHERE id = ?", THREAD_COLUMNS))
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// All threads of a project, most recently active first.
+    pub async fn find_all_by_project(pool: &Pool<Sqlite>, project_path: &str) -> Result<Vec<ChatThread>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM chat_threads WHERE project_path = ? ORDER BY updated_at DESC",
+            THREAD_COLUMNS
+        ))
+        .bind(project_path)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// The most recently active thread, created if the project has none.
+    pub async fn find_or_create_latest(pool: &Pool<Sqlite>, project_path: &str) -> Result<ChatThread, sqlx::Error> {
+        let latest: Option<ChatThread> = sqlx::query_as(&format!(
+            "SELECT {} FROM chat_threads WHERE project_path = ? ORDER BY updated_at DESC LIMIT 1",
+            THREAD_COLUMNS
+        ))
+        .bind(project_path)
+        .fetch_optional(pool)
+        .await?;
+
+        match latest {
+            Some(thread) => Ok(thread),
+            None => {
+                ChatThread::create(
+                    pool,
+                    CreateChatThread {
+                        project_path: project_path.to_string(),
+                        title: None,
+                    },
+                )
+                .await
+            }
+        }
+    }
+
+    pub async fn rename(pool: &Pool<Sqlite>, id: &str, title: &str) -> Result<Option<ChatThread>, sqlx::Error> {
+        sqlx::query("UPDATE chat_threads SET title = ? WHERE id = ?")
+            .bind(title)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        ChatThread::find_by_id(pool, id).await
+    }
+
+    /// Mark the thread as active now.
+    pub async fn touch(pool: &Pool<Sqlite>, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE chat_threads SET updated_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete the thread and its messages.
+    pub async fn delete(pool: &Pool<Sqlite>, id: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("DELETE FROM chat_messages WHERE thread_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        let result = sqlx::query("DELETE FROM chat_threads WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE chat_threads (
+                id TEXT PRIMARY KEY NOT NULL,
+                project_path TEXT NOT NULL,
+                title TEXT NOT NULL,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE chat_messages (
+                id TEXT PRIMARY KEY NOT NULL,
+                project_path TEXT NOT NULL,
+                thread_id TEXT,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                image_data TEXT,
+                tool_calls TEXT,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_find_or_create_latest() {
+        let pool = setup_test_db().await;
+
+        let first = ChatThread::find_or_create_latest(&pool, "/project").await.unwrap();
+        assert_eq!(first.title, DEFAULT_THREAD_TITLE);
+
+        let again = ChatThread::find_or_create_latest(&pool, "/project").await.unwrap();
+        assert_eq!(again.id, first.id);
+
+        let second = ChatThread::create(
+            &pool,
+            CreateChatThread {
+                project_path: "/project".to_string(),
+                title: Some("Auth questions".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(ChatThread::find_or_create_latest(&pool, "/project").await.unwrap().id, second.id);
+
+        ChatThread::touch(&pool, &first.id).await.unwrap();
+        let threads = ChatThread::find_all_by_project(&pool, "/project").await.unwrap();
+        assert_eq!(threads[0].id, first.id);
+    }
+
+    #[tokio::test]
+    async fn test_rename_and_delete() {
+        let pool = setup_test_db().await;
+
+        let thread = ChatThread::create(
+            &pool,
+            CreateChatThread {
+                project_path: "/project".to_string(),
+                title: Some("  ".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(thread.title, DEFAULT_THREAD_TITLE);
+
+        let renamed = ChatThread::rename(&pool, &thread.id, "Renamed").await.unwrap().unwrap();
+        assert_eq!(renamed.title, "Renamed");
+
+        sqlx::query("INSERT INTO chat_messages (id, project_path, thread_id, role, content) VALUES ('m1', '/project', ?, 'user', 'Hi')")
+            .bind(&thread.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(ChatThread::delete(&pool, &thread.id).await.unwrap());
+        assert!(ChatThread::find_by_id(&pool, &thread.id).await.unwrap().is_none());
+        let (remaining,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM chat_messages")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
+    }
+}
@@ -0,0 +1,789 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+use uuid::Uuid;
+
+use super::run::RunUsage;
+
+/// A task prompt replayed by every run of a suite.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalCase {
+    /// Unique within the suite; results are matched across runs by it
+    pub name: String,
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct EvalSuiteRow {
+    pub id: String,
+    pub project_path: String,
+    pub name: String,
+    pub base_commit: String,
+    pub score_command: String,
+    pub score_timeout_secs: i64,
+    pub cases: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Task prompts pinned to a commit, each scored by the same command once
+/// Claude is done with it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalSuite {
+    pub id: String,
+    pub project_path: String,
+    pub name: String,
+    /// Full hash of the commit every case starts from
+    pub base_commit: String,
+    /// Run through the shell in the case's worktree; exit code 0 passes
+    pub score_command: String,
+    pub score_timeout_secs: i64,
+    pub cases: Vec<EvalCase>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<EvalSuiteRow> for EvalSuite {
+    fn from(row: EvalSuiteRow) -> Self {
+        EvalSuite {
+            id: row.id,
+            project_path: row.project_path,
+            name: row.name,
+            base_commit: row.base_commit,
+            score_command: row.score_command,
+            score_timeout_secs: row.score_timeout_secs,
+            cases: serde_json::from_str(&row.cases).unwrap_or_default(),
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateEvalSuite {
+    pub project_path: String,
+    pub name: String,
+    pub base_commit: String,
+    pub score_command: String,
+    pub score_timeout_secs: i64,
+    pub cases: Vec<EvalCase>,
+}
+
+const SUITE_COLUMNS: &str = "id, project_path, name, base_commit, score_command, score_timeout_secs, cases, created_at";
+
+impl EvalSuite {
+    pub async fn create(pool: &Pool<Sqlite>, input: CreateEvalSuite) -> Result<EvalSuite, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let cases = serde_json::to_string(&input.cases).unwrap_or_else(|_| "[]".to_string());
+
+        sqlx::query(
+            r#"
+            INSERT INTO eval_suites (id, project_path, name, base_commit, score_command, score_timeout_secs, cases, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&input.project_path)
+        .bind(&input.name)
+        .bind(&input.base_commit)
+        .bind(&input.score_command)
+        .bind(input.score_timeout_secs)
+        .bind(cases)
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+        Ok(EvalSuite {
+            id,
+            project_path: input.project_path,
+            name: input.name,
+            base_commit: input.base_commit,
+            score_command: input.score_command,
+            score_timeout_secs: input.score_timeout_secs,
+            cases: input.cases,
+            created_at: now,
+        })
+    }
+
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<EvalSuite>, sqlx::Error> {
+        let row: Option<EvalSuiteRow> = sqlx::query_as(
+            &format!("SELECT {} FROM eval_suites WHERE id = ?", SUITE_COLUMNS)
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(EvalSuite::from))
+    }
+
+    pub async fn find_all_by_project(pool: &Pool<Sqlite>, project_path: &str) -> Result<Vec<EvalSuite>, sqlx::Error> {
+        let rows: Vec<EvalSuiteRow> = sqlx::query_as(
+            &format!("SELECT {} FROM eval_suites WHERE project_path = ? ORDER BY name ASC", SUITE_COLUMNS)
+        )
+        .bind(project_path)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(EvalSuite::from).collect())
+    }
+
+    /// Delete a suite with all its runs and their results.
+    pub async fn delete(pool: &Pool<Sqlite>, id: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query("DELETE FROM eval_results WHERE eval_run_id IN (SELECT id FROM eval_runs WHERE suite_id = ?)")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        sqlx::query("DELETE FROM eval_runs WHERE suite_id = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        let result = sqlx::query("DELETE FROM eval_suites WHERE id = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvalRunStatus {
+    Running,
+    /// Every case was run and scored
+    Completed,
+    Cancelled,
+    /// Stopped early, e.g. by a server restart
+    Failed,
+}
+
+impl EvalRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvalRunStatus::Running => "running",
+            EvalRunStatus::Completed => "completed",
+            EvalRunStatus::Cancelled => "cancelled",
+            EvalRunStatus::Failed => "failed",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "running" => Some(EvalRunStatus::Running),
+            "completed" => Some(EvalRunStatus::Completed),
+            "cancelled" => Some(EvalRunStatus::Cancelled),
+            "failed" => Some(EvalRunStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct EvalRunRow {
+    pub id: String,
+    pub suite_id: String,
+    pub status: String,
+    pub label: Option<String>,
+    pub prompt_template_id: Option<String>,
+    pub model: Option<String>,
+    pub cli_version: Option<String>,
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// One pass over all cases of a suite, with the setup it was run with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalRun {
+    pub id: String,
+    pub suite_id: String,
+    pub status: EvalRunStatus,
+    /// Free text to tell runs apart in reports
+    pub label: Option<String>,
+    /// Template the prompts were rendered with, or the default prompt
+    pub prompt_template_id: Option<String>,
+    /// Model Claude ran with, or the CLI's default
+    pub model: Option<String>,
+    /// `claude --version`, if it could be read
+    pub cli_version: Option<String>,
+    /// eval-kanban version, which pins the bundled agents and config
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl From<EvalRunRow> for EvalRun {
+    fn from(row: EvalRunRow) -> Self {
+        EvalRun {
+            id: row.id,
+            suite_id: row.suite_id,
+            status: EvalRunStatus::from_str(&row.status).unwrap_or(EvalRunStatus::Failed),
+            label: row.label,
+            prompt_template_id: row.prompt_template_id,
+            model: row.model,
+            cli_version: row.cli_version,
+            app_version: row.app_version,
+            created_at: row.created_at,
+            finished_at: row.finished_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateEvalRun {
+    pub suite_id: String,
+    pub label: Option<String>,
+    pub prompt_template_id: Option<String>,
+    pub model: Option<String>,
+    pub cli_version: Option<String>,
+    pub app_version: String,
+}
+
+const EVAL_RUN_COLUMNS: &str = "id, suite_id, status, label, prompt_template_id, model, cli_version, app_version, created_at, finished_at";
+
+impl EvalRun {
+    pub async fn create(pool: &Pool<Sqlite>, input: CreateEvalRun) -> Result<EvalRun, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO eval_runs (id, suite_id, status, label, prompt_template_id, model, cli_version, app_version, created_at)
+            VALUES (?, ?, 'running', ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&input.suite_id)
+        .bind(&input.label)
+        .bind(&input.prompt_template_id)
+        .bind(&input.model)
+        .bind(&input.cli_version)
+        .bind(&input.app_version)
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+        Ok(EvalRun {
+            id,
+            suite_id: input.suite_id,
+            status: EvalRunStatus::Running,
+            label: input.label,
+            prompt_template_id: input.prompt_template_id,
+            model: input.model,
+            cli_version: input.cli_version,
+            app_version: input.app_version,
+            created_at: now,
+            finished_at: None,
+        })
+    }
+
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<EvalRun>, sqlx::Error> {
+        let row: Option<EvalRunRow> = sqlx::query_as(
+            &format!("SELECT {} FROM eval_runs WHERE id = ?", EVAL_RUN_COLUMNS)
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(EvalRun::from))
+    }
+
+    /// Runs of a suite, oldest first.
+    pub async fn find_by_suite(pool: &Pool<Sqlite>, suite_id: &str) -> Result<Vec<EvalRun>, sqlx::Error> {
+        let rows: Vec<EvalRunRow> = sqlx::query_as(
+            &format!("SELECT {} FROM eval_runs WHERE suite_id = ? ORDER BY created_at ASC", EVAL_RUN_COLUMNS)
+        )
+        .bind(suite_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(EvalRun::from).collect())
+    }
+
+    pub async fn finish(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        status: EvalRunStatus,
+    ) -> Result<Option<EvalRun>, sqlx::Error> {
+        let result = sqlx::query("UPDATE eval_runs SET status = ?, finished_at = ? WHERE id = ?")
+            .bind(status.as_str())
+            .bind(Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Self::find_by_id(pool, id).await
+    }
+
+    /// Mark runs left running by a previous server as failed, and their
+    /// unfinished cases as skipped. Returns how many runs were affected.
+    pub async fn fail_interrupted(pool: &Pool<Sqlite>) -> Result<u64, sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE eval_results SET status = 'skipped'
+            WHERE status IN ('pending', 'running')
+              AND eval_run_id IN (SELECT id FROM eval_runs WHERE status = 'running')
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        let result = sqlx::query("UPDATE eval_runs SET status = 'failed', finished_at = ? WHERE status = 'running'")
+            .bind(Utc::now())
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvalResultStatus {
+    Pending,
+    Running,
+    /// The score command exited with 0
+    Passed,
+    Failed,
+    /// Could not be scored, e.g. the worktree or Claude failed to start
+    Error,
+    /// Not run because the run was cancelled or interrupted
+    Skipped,
+}
+
+impl EvalResultStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvalResultStatus::Pending => "pending",
+            EvalResultStatus::Running => "running",
+            EvalResultStatus::Passed => "passed",
+            EvalResultStatus::Failed => "failed",
+            EvalResultStatus::Error => "error",
+            EvalResultStatus::Skipped => "skipped",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(EvalResultStatus::Pending),
+            "running" => Some(EvalResultStatus::Running),
+            "passed" => Some(EvalResultStatus::Passed),
+            "failed" => Some(EvalResultStatus::Failed),
+            "error" => Some(EvalResultStatus::Error),
+            "skipped" => Some(EvalResultStatus::Skipped),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct EvalResultRow {
+    pub id: String,
+    pub eval_run_id: String,
+    pub position: i64,
+    pub case_name: String,
+    pub status: String,
+    pub score: Option<f64>,
+    pub exit_code: Option<i64>,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_creation_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cost_usd: f64,
+    pub duration_ms: Option<i64>,
+    pub error: Option<String>,
+    pub output: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// How one case of a run went.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalResult {
+    pub id: String,
+    pub eval_run_id: String,
+    /// Index of the case in the suite
+    pub position: i64,
+    pub case_name: String,
+    pub status: EvalResultStatus,
+    /// Last line of the score command's output if it is a number, otherwise
+    /// 1 if it passed and 0 if it failed
+    pub score: Option<f64>,
+    /// Of the score command
+    pub exit_code: Option<i64>,
+    /// Of Claude's run
+    pub usage: RunUsage,
+    /// Claude's run and the score command together
+    pub duration_ms: Option<i64>,
+    pub error: Option<String>,
+    /// End of the score command's output
+    pub output: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl From<EvalResultRow> for EvalResult {
+    fn from(row: EvalResultRow) -> Self {
+        EvalResult {
+            id: row.id,
+            eval_run_id: row.eval_run_id,
+            position: row.position,
+            case_name: row.case_name,
+            status: EvalResultStatus::from_str(&row.status).unwrap_or(EvalResultStatus::Error),
+            score: row.score,
+            exit_code: row.exit_code,
+            usage: RunUsage {
+                input_tokens: row.input_tokens,
+                output_tokens: row.output_tokens,
+                cache_creation_tokens: row.cache_creation_tokens,
+                cache_read_tokens: row.cache_read_tokens,
+                cost_usd: row.cost_usd,
+            },
+            duration_ms: row.duration_ms,
+            error: row.error,
+            output: row.output,
+            started_at: row.started_at,
+            finished_at: row.finished_at,
+        }
+    }
+}
+
+/// Outcome of a case, recorded once it has been scored or given up on.
+#[derive(Debug, Clone)]
+pub struct FinishEvalResult {
+    pub status: EvalResultStatus,
+    pub score: Option<f64>,
+    pub exit_code: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub error: Option<String>,
+    pub output: Option<String>,
+}
+
+const EVAL_RESULT_COLUMNS: &str = "id, eval_run_id, position, case_name, status, score, exit_code, input_tokens, output_tokens, cache_creation_tokens, cache_read_tokens, cost_usd, duration_ms, error, output, started_at, finished_at";
+
+impl EvalResult {
+    /// Record a case of a run as pending.
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        eval_run_id: &str,
+        position: i64,
+        case_name: &str,
+    ) -> Result<EvalResult, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+
+        sqlx::query(
+            r#"
+            INSERT INTO eval_results (id, eval_run_id, position, case_name, status)
+            VALUES (?, ?, ?, ?, 'pending')
+            "#,
+        )
+        .bind(&id)
+        .bind(eval_run_id)
+        .bind(position)
+        .bind(case_name)
+        .execute(pool)
+        .await?;
+
+        Ok(EvalResult {
+            id,
+            eval_run_id: eval_run_id.to_string(),
+            position,
+            case_name: case_name.to_string(),
+            status: EvalResultStatus::Pending,
+            score: None,
+            exit_code: None,
+            usage: RunUsage::default(),
+            duration_ms: None,
+            error: None,
+            output: None,
+            started_at: None,
+            finished_at: None,
+        })
+    }
+
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<EvalResult>, sqlx::Error> {
+        let row: Option<EvalResultRow> = sqlx::query_as(
+            &format!("SELECT {} FROM eval_results WHERE id = ?", EVAL_RESULT_COLUMNS)
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(EvalResult::from))
+    }
+
+    /// Results of a run, in the suite's case order.
+    pub async fn find_by_run(pool: &Pool<Sqlite>, eval_run_id: &str) -> Result<Vec<EvalResult>, sqlx::Error> {
+        let rows: Vec<EvalResultRow> = sqlx::query_as(
+            &format!("SELECT {} FROM eval_results WHERE eval_run_id = ? ORDER BY position ASC", EVAL_RESULT_COLUMNS)
+        )
+        .bind(eval_run_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(EvalResult::from).collect())
+    }
+
+    pub async fn start(pool: &Pool<Sqlite>, id: &str) -> Result<Option<EvalResult>, sqlx::Error> {
+        let result = sqlx::query("UPDATE eval_results SET status = 'running', started_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Self::find_by_id(pool, id).await
+    }
+
+    pub async fn set_usage(pool: &Pool<Sqlite>, id: &str, usage: &RunUsage) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE eval_results
+            SET input_tokens = ?, output_tokens = ?, cache_creation_tokens = ?, cache_read_tokens = ?, cost_usd = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(usage.input_tokens)
+        .bind(usage.output_tokens)
+        .bind(usage.cache_creation_tokens)
+        .bind(usage.cache_read_tokens)
+        .bind(usage.cost_usd)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn finish(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        input: FinishEvalResult,
+    ) -> Result<Option<EvalResult>, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE eval_results
+            SET status = ?, score = ?, exit_code = ?, duration_ms = ?, error = ?, output = ?, finished_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(input.status.as_str())
+        .bind(input.score)
+        .bind(input.exit_code)
+        .bind(input.duration_ms)
+        .bind(input.error)
+        .bind(input.output)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Self::find_by_id(pool, id).await
+    }
+
+    /// Mark the cases of a run that haven't finished as skipped.
+    pub async fn skip_unfinished(pool: &Pool<Sqlite>, eval_run_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE eval_results SET status = 'skipped' WHERE eval_run_id = ? AND status IN ('pending', 'running')")
+            .bind(eval_run_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE eval_suites (
+                id TEXT PRIMARY KEY NOT NULL,
+                project_path TEXT NOT NULL,
+                name TEXT NOT NULL,
+                base_commit TEXT NOT NULL,
+                score_command TEXT NOT NULL,
+                score_timeout_secs INTEGER NOT NULL,
+                cases TEXT NOT NULL DEFAULT '[]',
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE eval_runs (
+                id TEXT PRIMARY KEY NOT NULL,
+                suite_id TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'running',
+                label TEXT,
+                prompt_template_id TEXT,
+                model TEXT,
+                cli_version TEXT,
+                app_version TEXT NOT NULL,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                finished_at DATETIME
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE eval_results (
+                id TEXT PRIMARY KEY NOT NULL,
+                eval_run_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                case_name TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                score REAL,
+                exit_code INTEGER,
+                input_tokens INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0,
+                cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+                cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+                cost_usd REAL NOT NULL DEFAULT 0,
+                duration_ms INTEGER,
+                error TEXT,
+                output TEXT,
+                started_at DATETIME,
+                finished_at DATETIME
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    fn suite() -> CreateEvalSuite {
+        CreateEvalSuite {
+            project_path: "/project/a".to_string(),
+            name: "Smoke".to_string(),
+            base_commit: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            score_command: "make test".to_string(),
+            score_timeout_secs: 600,
+            cases: vec![
+                EvalCase { name: "login".to_string(), prompt: "Add a login form".to_string() },
+                EvalCase { name: "logout".to_string(), prompt: "Add a logout button".to_string() },
+            ],
+        }
+    }
+
+    fn run(suite_id: &str) -> CreateEvalRun {
+        CreateEvalRun {
+            suite_id: suite_id.to_string(),
+            label: Some("baseline".to_string()),
+            prompt_template_id: None,
+            model: Some("sonnet".to_string()),
+            cli_version: Some("2.0.0 (Claude Code)".to_string()),
+            app_version: "0.1.0".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_suite_run_and_results() {
+        let pool = setup_test_db().await;
+
+        let suite = EvalSuite::create(&pool, suite()).await.unwrap();
+        let found = EvalSuite::find_by_id(&pool, &suite.id).await.unwrap().unwrap();
+        assert_eq!(found.cases, suite.cases);
+        assert_eq!(EvalSuite::find_all_by_project(&pool, "/project/a").await.unwrap().len(), 1);
+
+        let eval_run = EvalRun::create(&pool, run(&suite.id)).await.unwrap();
+        assert_eq!(eval_run.status, EvalRunStatus::Running);
+
+        let second = EvalResult::create(&pool, &eval_run.id, 1, "logout").await.unwrap();
+        let first = EvalResult::create(&pool, &eval_run.id, 0, "login").await.unwrap();
+
+        EvalResult::start(&pool, &first.id).await.unwrap();
+        let usage = RunUsage { input_tokens: 100, cost_usd: 0.25, ..Default::default() };
+        EvalResult::set_usage(&pool, &first.id, &usage).await.unwrap();
+        let finished = EvalResult::finish(
+            &pool,
+            &first.id,
+            FinishEvalResult {
+                status: EvalResultStatus::Passed,
+                score: Some(0.75),
+                exit_code: Some(0),
+                duration_ms: Some(1200),
+                error: None,
+                output: Some("0.75".to_string()),
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(finished.status, EvalResultStatus::Passed);
+        assert_eq!(finished.usage, usage);
+        assert!(finished.started_at.is_some() && finished.finished_at.is_some());
+
+        EvalResult::skip_unfinished(&pool, &eval_run.id).await.unwrap();
+        let results = EvalResult::find_by_run(&pool, &eval_run.id).await.unwrap();
+        assert_eq!(results.iter().map(|r| r.case_name.as_str()).collect::<Vec<_>>(), vec!["login", "logout"]);
+        assert_eq!(results[1].id, second.id);
+        assert_eq!(results[1].status, EvalResultStatus::Skipped);
+
+        let finished = EvalRun::finish(&pool, &eval_run.id, EvalRunStatus::Cancelled).await.unwrap().unwrap();
+        assert_eq!(finished.status, EvalRunStatus::Cancelled);
+        assert_eq!(EvalRun::find_by_suite(&pool, &suite.id).await.unwrap().len(), 1);
+
+        assert!(EvalSuite::delete(&pool, &suite.id).await.unwrap());
+        assert!(EvalRun::find_by_id(&pool, &eval_run.id).await.unwrap().is_none());
+        assert!(EvalResult::find_by_run(&pool, &eval_run.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fail_interrupted() {
+        let pool = setup_test_db().await;
+        let suite = EvalSuite::create(&pool, suite()).await.unwrap();
+
+        let done = EvalRun::create(&pool, run(&suite.id)).await.unwrap();
+        EvalRun::finish(&pool, &done.id, EvalRunStatus::Completed).await.unwrap();
+        let interrupted = EvalRun::create(&pool, run(&suite.id)).await.unwrap();
+        let result = EvalResult::create(&pool, &interrupted.id, 0, "login").await.unwrap();
+        EvalResult::start(&pool, &result.id).await.unwrap();
+
+        assert_eq!(EvalRun::fail_interrupted(&pool).await.unwrap(), 1);
+        let runs = EvalRun::find_by_suite(&pool, &suite.id).await.unwrap();
+        assert_eq!(runs.iter().map(|r| r.status).collect::<Vec<_>>(), vec![EvalRunStatus::Completed, EvalRunStatus::Failed]);
+        let result = EvalResult::find_by_id(&pool, &result.id).await.unwrap().unwrap();
+        assert_eq!(result.status, EvalResultStatus::Skipped);
+    }
+
+    #[test]
+    fn test_status_conversion() {
+        assert_eq!(EvalRunStatus::from_str("completed"), Some(EvalRunStatus::Completed));
+        assert_eq!(EvalResultStatus::Skipped.as_str(), "skipped");
+        assert_eq!(EvalResultStatus::from_str("invalid"), None);
+    }
+}
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+
+/// A line of executor output, kept so logs survive reloads and can be
+/// quoted as context.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExecutionLog {
+    pub id: i64,
+    pub task_id: String,
+    pub run_id: Option<String>,
+    /// "stdout" or "stderr"
+    pub stream: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+const LOG_COLUMNS: &str = "id, task_id, run_id, stream, content, created_at";
+
+impl ExecutionLog {
+    pub async fn append(
+        pool: &Pool<Sqlite>,
+        task_id: &str,
+        run_id: Option<&str>,
+        stream: &str,
+        content: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO execution_logs (task_id, run_id, stream, content, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(task_id)
+        .bind(run_id)
+        .bind(stream)
+        .bind(content)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The last `limit` lines logged for a task, oldest first.
+    pub async fn find_latest_by_task(pool: &Pool<Sqlite>, task_id: &str, limit: i64) -> Result<Vec<ExecutionLog>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM (SELECT * FROM execution_logs WHERE task_id = ? ORDER BY id DESC LIMIT ?) ORDER BY id ASC",
+            LOG_COLUMNS
+        ))
+        .bind(task_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Up to `limit` lines logged for a task after the line with id `after_id`,
+    /// for following output as it is written.
+    pub async fn find_after(pool: &Pool<Sqlite>, task_id: &str, after_id: i64, limit: i64) -> Result<Vec<ExecutionLog>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM execution_logs WHERE task_id = ? AND id > ? ORDER BY id ASC LIMIT ?",
+            LOG_COLUMNS
+        ))
+        .bind(task_id)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_by_run(pool: &Pool<Sqlite>, run_id: &str) -> Result<Vec<ExecutionLog>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM execution_logs WHERE run_id = ? ORDER BY id ASC",
+            LOG_COLUMNS
+        ))
+        .bind(run_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn delete_by_task(pool: &Pool<Sqlite>, task_id: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM execution_logs WHERE task_id = ?")
+            .bind(task_id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE execution_logs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL,
+                run_id TEXT,
+                stream TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_append_and_find() {
+        let pool = setup_test_db().await;
+
+        for i in 0..5 {
+            ExecutionLog::append(&pool, "t1", Some("r1"), "stdout", &format!("line {}", i)).await.unwrap();
+        }
+        ExecutionLog::append(&pool, "t1", Some("r2"), "stderr", "oops").await.unwrap();
+        ExecutionLog::append(&pool, "t2", None, "stdout", "other").await.unwrap();
+
+        let latest = ExecutionLog::find_latest_by_task(&pool, "t1", 3).await.unwrap();
+        let lines: Vec<&str> = latest.iter().map(|l| l.content.as_str()).collect();
+        assert_eq!(lines, vec!["line 3", "line 4", "oops"]);
+
+        assert_eq!(ExecutionLog::find_by_run(&pool, "r1").await.unwrap().len(), 5);
+
+        let after = ExecutionLog::find_after(&pool, "t1", latest[0].id, 10).await.unwrap();
+        let lines: Vec<&str> = after.iter().map(|l| l.content.as_str()).collect();
+        assert_eq!(lines, vec!["line 4", "oops"]);
+
+        assert_eq!(ExecutionLog::delete_by_task(&pool, "t1").await.unwrap(), 6);
+        assert_eq!(ExecutionLog::find_latest_by_task(&pool, "t2", 10).await.unwrap().len(), 1);
+    }
+}
@@ -1,4 +1,16 @@
+pub mod attachment;
+pub mod attempt;
 pub mod chat;
+pub mod chat_thread;
+pub mod eval;
+pub mod execution_log;
+pub mod plan_session;
+pub mod project;
+pub mod project_settings;
+pub mod prompt_template;
+pub mod run;
 pub mod task;
+pub mod task_dependency;
+pub mod task_history;
 
 pub use task::{Task, TaskStatus, CreateTask, UpdateTask};
@@ -0,0 +1,220 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+
+/// A planning session as stored in the database.
+///
+/// Questions, answers and pending questions are JSON arrays owned by the
+/// server's plan session types; this crate stores them as text.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PlanSessionRecord {
+    pub id: String,
+    pub project_path: String,
+    pub title: String,
+    pub prompt: String,
+    pub status: String,
+    pub questions: String,
+    pub answers: String,
+    pub pending_questions: String,
+    pub summary: Option<String>,
+    pub plan_content: Option<String>,
+    pub accumulated_output: String,
+    pub ask_questions: bool,
+    /// Existing task the plan will be attached to, if any
+    pub task_id: Option<String>,
+    /// Whether the plan is split into multiple tasks
+    pub breakdown: bool,
+    /// JSON array of planned steps (breakdown mode)
+    pub steps: String,
+    /// Why the breakdown could not be parsed, if it couldn't
+    pub steps_error: Option<String>,
+    /// Claude's own conversation id, used to `--resume` the conversation
+    pub claude_session_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const PLAN_COLUMNS: &str = "id, project_path, title, prompt, status, questions, answers, pending_questions, summary, plan_content, accumulated_output, ask_questions, task_id, breakdown, steps, steps_error, claude_session_id, created_at, updated_at";
+
+impl PlanSessionRecord {
+    /// Insert the session, or overwrite it if it already exists.
+    pub async fn save(pool: &Pool<Sqlite>, record: &PlanSessionRecord) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO plan_sessions (id, project_path, title, prompt, status, questions, answers, pending_questions, summary, plan_content, accumulated_output, ask_questions, task_id, breakdown, steps, steps_error, claude_session_id, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title,
+                prompt = excluded.prompt,
+                status = excluded.status,
+                questions = excluded.questions,
+                answers = excluded.answers,
+                pending_questions = excluded.pending_questions,
+                summary = excluded.summary,
+                plan_content = excluded.plan_content,
+                accumulated_output = excluded.accumulated_output,
+                ask_questions = excluded.ask_questions,
+                task_id = excluded.task_id,
+                breakdown = excluded.breakdown,
+                steps = excluded.steps,
+                steps_error = excluded.steps_error,
+                claude_session_id = excluded.claude_session_id,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&record.id)
+        .bind(&record.project_path)
+        .bind(&record.title)
+        .bind(&record.prompt)
+        .bind(&record.status)
+        .bind(&record.questions)
+        .bind(&record.answers)
+        .bind(&record.pending_questions)
+        .bind(&record.summary)
+        .bind(&record.plan_content)
+        .bind(&record.accumulated_output)
+        .bind(record.ask_questions)
+        .bind(&record.task_id)
+        .bind(record.breakdown)
+        .bind(&record.steps)
+        .bind(&record.steps_error)
+        .bind(&record.claude_session_id)
+        .bind(record.created_at)
+        .bind(record.updated_at)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<PlanSessionRecord>, sqlx::Error> {
+        sqlx::query_as(&format!("SELECT {} FROM plan_sessions WHERE id = ?", PLAN_COLUMNS))
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// All sessions for a project, most recently active first.
+    pub async fn find_all_by_project(pool: &Pool<Sqlite>, project_path: &str) -> Result<Vec<PlanSessionRecord>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM plan_sessions WHERE project_path = ? ORDER BY updated_at DESC",
+            PLAN_COLUMNS
+        ))
+        .bind(project_path)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn delete(pool: &Pool<Sqlite>, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM plan_sessions WHERE id = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE plan_sessions (
+                id TEXT PRIMARY KEY NOT NULL,
+                project_path TEXT NOT NULL,
+                title TEXT NOT NULL,
+                prompt TEXT NOT NULL,
+                status TEXT NOT NULL,
+                questions TEXT NOT NULL DEFAULT '[]',
+                answers TEXT NOT NULL DEFAULT '[]',
+                pending_questions TEXT NOT NULL DEFAULT '[]',
+                summary TEXT,
+                plan_content TEXT,
+                accumulated_output TEXT NOT NULL DEFAULT '',
+                ask_questions BOOLEAN NOT NULL DEFAULT 0,
+                task_id TEXT,
+                breakdown BOOLEAN NOT NULL DEFAULT 0,
+                steps TEXT NOT NULL DEFAULT '[]',
+                steps_error TEXT,
+                claude_session_id TEXT,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    fn record(id: &str, project_path: &str) -> PlanSessionRecord {
+        let now = Utc::now();
+        PlanSessionRecord {
+            id: id.to_string(),
+            project_path: project_path.to_string(),
+            title: "Plan".to_string(),
+            prompt: "Add login".to_string(),
+            status: "processing".to_string(),
+            questions: "[]".to_string(),
+            answers: "[]".to_string(),
+            pending_questions: "[]".to_string(),
+            summary: None,
+            plan_content: None,
+            accumulated_output: String::new(),
+            ask_questions: true,
+            task_id: None,
+            breakdown: false,
+            steps: "[]".to_string(),
+            steps_error: None,
+            claude_session_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_upserts() {
+        let pool = setup_test_db().await;
+
+        let mut session = record("s1", "/project");
+        PlanSessionRecord::save(&pool, &session).await.unwrap();
+
+        session.status = "summary".to_string();
+        session.summary = Some("Do it".to_string());
+        session.claude_session_id = Some("claude-1".to_string());
+        session.updated_at = Utc::now();
+        PlanSessionRecord::save(&pool, &session).await.unwrap();
+
+        let found = PlanSessionRecord::find_by_id(&pool, "s1").await.unwrap().unwrap();
+        assert_eq!(found.status, "summary");
+        assert_eq!(found.summary.as_deref(), Some("Do it"));
+        assert!(found.ask_questions);
+        assert_eq!(found.claude_session_id.as_deref(), Some("claude-1"));
+    }
+
+    #[tokio::test]
+    async fn test_find_all_by_project_and_delete() {
+        let pool = setup_test_db().await;
+
+        PlanSessionRecord::save(&pool, &record("s1", "/project")).await.unwrap();
+        PlanSessionRecord::save(&pool, &record("s2", "/project")).await.unwrap();
+        PlanSessionRecord::save(&pool, &record("s3", "/other")).await.unwrap();
+
+        let sessions = PlanSessionRecord::find_all_by_project(&pool, "/project").await.unwrap();
+        assert_eq!(sessions.len(), 2);
+
+        assert!(PlanSessionRecord::delete(&pool, "s1").await.unwrap());
+        assert!(PlanSessionRecord::find_by_id(&pool, "s1").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+use uuid::Uuid;
+
+/// A repository managed by the board. Tasks, chat threads and settings refer
+/// to it by `path`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub created_at: DateTime<Utc>,
+    /// Last time the project was opened, for the recent projects list
+    pub last_opened_at: DateTime<Utc>,
+}
+
+const PROJECT_COLUMNS: &str = "id, name, path, created_at, last_opened_at";
+
+impl Project {
+    /// The project at `path`, registered if it's new, and marked as opened.
+    pub async fn open(pool: &Pool<Sqlite>, path: &str, name: Option<&str>) -> Result<Project, sqlx::Error> {
+        let now = Utc::now();
+        let name = name
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| default_name(path));
+
+        sqlx::query(
+            r#"
+            INSERT INTO projects (id, name, path, created_at, last_opened_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(path) DO UPDATE SET last_opened_at = excluded.last_opened_at
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&name)
+        .bind(path)
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+        sqlx::query_as(&format!("SELECT {} FROM projects WHERE path = ?", PROJECT_COLUMNS))
+            .bind(path)
+            .fetch_one(pool)
+            .await
+    }
+
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<Project>, sqlx::Error> {
+        sqlx::query_as(&format!("SELECT {} FROM projects WHERE id = ?", PROJECT_COLUMNS))
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn find_by_path(pool: &Pool<Sqlite>, path: &str) -> Result<Option<Project>, sqlx::Error> {
+        sqlx::query_as(&format!("SELECT {} FROM projects WHERE path = ?", PROJECT_COLUMNS))
+            .bind(path)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// All projects, most recently opened first.
+    pub async fn find_recent(pool: &Pool<Sqlite>) -> Result<Vec<Project>, sqlx::Error> {
+        sqlx::query_as(&format!("SELECT {} FROM projects ORDER BY last_opened_at DESC", PROJECT_COLUMNS))
+            .fetch_all(pool)
+            .await
+    }
+
+    pub async fn rename(pool: &Pool<Sqlite>, id: &str, name: &str) -> Result<Option<Project>, sqlx::Error> {
+        sqlx::query("UPDATE projects SET name = ? WHERE id = ?")
+            .bind(name)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Project::find_by_id(pool, id).await
+    }
+
+    /// Remove the project from the list. Its tasks are kept and it comes
+    /// back with them if the path is opened again.
+    pub async fn delete(pool: &Pool<Sqlite>, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM projects WHERE id = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// The last component of the path.
+pub fn default_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE projects (
+                id TEXT PRIMARY KEY NOT NULL,
+                name TEXT NOT NULL,
+                path TEXT NOT NULL UNIQUE,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                last_opened_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_open_and_recent() {
+        let pool = setup_test_db().await;
+
+        let api = Project::open(&pool, "/work/api", None).await.unwrap();
+        assert_eq!(api.name, "api");
+        let web = Project::open(&pool, "/work/web", Some("Website")).await.unwrap();
+        assert_eq!(web.name, "Website");
+
+        // Opening again keeps the id and name, and moves it to the top
+        let reopened = Project::open(&pool, "/work/api", Some("Other")).await.unwrap();
+        assert_eq!(reopened.id, api.id);
+        assert_eq!(reopened.name, "api");
+
+        let recent = Project::find_recent(&pool).await.unwrap();
+        assert_eq!(recent.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), vec![api.id.as_str(), web.id.as_str()]);
+
+        assert_eq!(Project::find_by_path(&pool, "/work/web").await.unwrap().unwrap().id, web.id);
+        assert!(Project::delete(&pool, &web.id).await.unwrap());
+        assert!(Project::find_by_id(&pool, &web.id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,166 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+
+/// Per-project settings stored in the database.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProjectSettings {
+    pub project_path: String,
+    pub budget_usd: Option<f64>,
+    pub budget_tokens: Option<i64>,
+    /// Text placed before every task prompt
+    pub prompt_prefix: Option<String>,
+    /// Text appended after every task prompt
+    pub prompt_suffix: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ProjectSettings {
+    /// Get settings for a project, falling back to empty defaults.
+    pub async fn get(pool: &Pool<Sqlite>, project_path: &str) -> Result<ProjectSettings, sqlx::Error> {
+        let row: Option<ProjectSettings> = sqlx::query_as(
+            "SELECT project_path, budget_usd, budget_tokens, prompt_prefix, prompt_suffix, updated_at FROM project_settings WHERE project_path = ?"
+        )
+        .bind(project_path)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.unwrap_or_else(|| ProjectSettings {
+            project_path: project_path.to_string(),
+            budget_usd: None,
+            budget_tokens: None,
+            prompt_prefix: None,
+            prompt_suffix: None,
+            updated_at: Utc::now(),
+        }))
+    }
+
+    /// Set the project's spending limits. `None` removes a limit.
+    pub async fn set_budget(
+        pool: &Pool<Sqlite>,
+        project_path: &str,
+        budget_usd: Option<f64>,
+        budget_tokens: Option<i64>,
+    ) -> Result<ProjectSettings, sqlx::Error> {
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO project_settings (project_path, budget_usd, budget_tokens, updated_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(project_path) DO UPDATE SET
+                budget_usd = excluded.budget_usd,
+                budget_tokens = excluded.budget_tokens,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(project_path)
+        .bind(budget_usd)
+        .bind(budget_tokens)
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+        Self::get(pool, project_path).await
+    }
+
+    /// Set the text wrapped around every task prompt. `None` removes it.
+    pub async fn set_prompt(
+        pool: &Pool<Sqlite>,
+        project_path: &str,
+        prompt_prefix: Option<&str>,
+        prompt_suffix: Option<&str>,
+    ) -> Result<ProjectSettings, sqlx::Error> {
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO project_settings (project_path, prompt_prefix, prompt_suffix, updated_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(project_path) DO UPDATE SET
+                prompt_prefix = excluded.prompt_prefix,
+                prompt_suffix = excluded.prompt_suffix,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(project_path)
+        .bind(prompt_prefix)
+        .bind(prompt_suffix)
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+        Self::get(pool, project_path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE project_settings (
+                project_path TEXT PRIMARY KEY NOT NULL,
+                budget_usd REAL,
+                budget_tokens INTEGER,
+                prompt_prefix TEXT,
+                prompt_suffix TEXT,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_get_defaults() {
+        let pool = setup_test_db().await;
+
+        let settings = ProjectSettings::get(&pool, "/project/a").await.unwrap();
+        assert_eq!(settings.project_path, "/project/a");
+        assert!(settings.budget_usd.is_none());
+        assert!(settings.budget_tokens.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_set_budget_upserts() {
+        let pool = setup_test_db().await;
+
+        ProjectSettings::set_budget(&pool, "/project/a", Some(10.0), None).await.unwrap();
+        let updated = ProjectSettings::set_budget(&pool, "/project/a", Some(20.0), Some(1_000_000))
+            .await
+            .unwrap();
+
+        assert_eq!(updated.budget_usd, Some(20.0));
+        assert_eq!(updated.budget_tokens, Some(1_000_000));
+
+        let other = ProjectSettings::get(&pool, "/project/b").await.unwrap();
+        assert!(other.budget_usd.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_set_prompt_keeps_budget() {
+        let pool = setup_test_db().await;
+
+        ProjectSettings::set_budget(&pool, "/project/a", Some(5.0), None).await.unwrap();
+        let settings = ProjectSettings::set_prompt(&pool, "/project/a", Some("Be brief."), None)
+            .await
+            .unwrap();
+
+        assert_eq!(settings.prompt_prefix.as_deref(), Some("Be brief."));
+        assert!(settings.prompt_suffix.is_none());
+        assert_eq!(settings.budget_usd, Some(5.0));
+    }
+}
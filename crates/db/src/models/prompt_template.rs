@@ -0,0 +1,205 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+use uuid::Uuid;
+
+/// A stored prompt template. The body may contain `{{variable}}` placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PromptTemplate {
+    pub id: String,
+    pub project_path: String,
+    pub name: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatePromptTemplate {
+    pub project_path: String,
+    pub name: String,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdatePromptTemplate {
+    pub name: Option<String>,
+    pub body: Option<String>,
+}
+
+const TEMPLATE_COLUMNS: &str = "id, project_path, name, body, created_at, updated_at";
+
+impl PromptTemplate {
+    pub async fn create(pool: &Pool<Sqlite>, input: CreatePromptTemplate) -> Result<PromptTemplate, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO prompt_templates (id, project_path, name, body, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&input.project_path)
+        .bind(&input.name)
+        .bind(&input.body)
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+        Ok(PromptTemplate {
+            id,
+            project_path: input.project_path,
+            name: input.name,
+            body: input.body,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<PromptTemplate>, sqlx::Error> {
+        sqlx::query_as(&format!("SELECT {} FROM prompt_templates WHERE id = ?", TEMPLATE_COLUMNS))
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn find_all_by_project(pool: &Pool<Sqlite>, project_path: &str) -> Result<Vec<PromptTemplate>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM prompt_templates WHERE project_path = ? ORDER BY name ASC",
+            TEMPLATE_COLUMNS
+        ))
+        .bind(project_path)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn update(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        input: UpdatePromptTemplate,
+    ) -> Result<Option<PromptTemplate>, sqlx::Error> {
+        let Some(existing) = Self::find_by_id(pool, id).await? else {
+            return Ok(None);
+        };
+
+        let name = input.name.unwrap_or(existing.name);
+        let body = input.body.unwrap_or(existing.body);
+
+        sqlx::query("UPDATE prompt_templates SET name = ?, body = ?, updated_at = ? WHERE id = ?")
+            .bind(&name)
+            .bind(&body)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Self::find_by_id(pool, id).await
+    }
+
+    /// Delete a template and detach it from any tasks that selected it.
+    pub async fn delete(pool: &Pool<Sqlite>, id: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query("UPDATE tasks SET prompt_template_id = NULL WHERE prompt_template_id = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        let result = sqlx::query("DELETE FROM prompt_templates WHERE id = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE prompt_templates (
+                id TEXT PRIMARY KEY NOT NULL,
+                project_path TEXT NOT NULL,
+                name TEXT NOT NULL,
+                body TEXT NOT NULL,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query("CREATE TABLE tasks (id TEXT PRIMARY KEY NOT NULL, prompt_template_id TEXT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        pool
+    }
+
+    fn input(project_path: &str, name: &str) -> CreatePromptTemplate {
+        CreatePromptTemplate {
+            project_path: project_path.to_string(),
+            name: name.to_string(),
+            body: "Implement {{title}}".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_and_list_by_project() {
+        let pool = setup_test_db().await;
+
+        PromptTemplate::create(&pool, input("/project/a", "Review")).await.unwrap();
+        PromptTemplate::create(&pool, input("/project/a", "Feature")).await.unwrap();
+        PromptTemplate::create(&pool, input("/project/b", "Other")).await.unwrap();
+
+        let templates = PromptTemplate::find_all_by_project(&pool, "/project/a").await.unwrap();
+        let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["Feature", "Review"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_and_delete() {
+        let pool = setup_test_db().await;
+
+        let template = PromptTemplate::create(&pool, input("/project/a", "Feature")).await.unwrap();
+        sqlx::query("INSERT INTO tasks (id, prompt_template_id) VALUES ('t1', ?)")
+            .bind(&template.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let updated = PromptTemplate::update(
+            &pool,
+            &template.id,
+            UpdatePromptTemplate { name: None, body: Some("Fix {{title}}".to_string()) },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(updated.name, "Feature");
+        assert_eq!(updated.body, "Fix {{title}}");
+
+        assert!(PromptTemplate::delete(&pool, &template.id).await.unwrap());
+        assert!(PromptTemplate::find_by_id(&pool, &template.id).await.unwrap().is_none());
+
+        let selected: Option<String> = sqlx::query_scalar("SELECT prompt_template_id FROM tasks WHERE id = 't1'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(selected.is_none());
+    }
+}
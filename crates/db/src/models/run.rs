@@ -0,0 +1,483 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    BudgetExceeded,
+}
+
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunStatus::Running => "running",
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+            RunStatus::Cancelled => "cancelled",
+            RunStatus::BudgetExceeded => "budget_exceeded",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "running" => Some(RunStatus::Running),
+            "succeeded" => Some(RunStatus::Succeeded),
+            "failed" => Some(RunStatus::Failed),
+            "cancelled" => Some(RunStatus::Cancelled),
+            "budget_exceeded" => Some(RunStatus::BudgetExceeded),
+            _ => None,
+        }
+    }
+}
+
+/// State of a verification check, or of all of a run's checks together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// Waiting for the checks before it
+    Pending,
+    Running,
+    Passed,
+    Failed,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Pending => "pending",
+            CheckStatus::Running => "running",
+            CheckStatus::Passed => "passed",
+            CheckStatus::Failed => "failed",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(CheckStatus::Pending),
+            "running" => Some(CheckStatus::Running),
+            "passed" => Some(CheckStatus::Passed),
+            "failed" => Some(CheckStatus::Failed),
+            _ => None,
+        }
+    }
+
+    /// Failed if any check failed, passed once all passed, otherwise running.
+    pub fn overall(checks: &[RunCheck]) -> CheckStatus {
+        if checks.iter().any(|c| c.status == CheckStatus::Failed) {
+            CheckStatus::Failed
+        } else if checks.iter().all(|c| c.status == CheckStatus::Passed) {
+            CheckStatus::Passed
+        } else {
+            CheckStatus::Running
+        }
+    }
+}
+
+/// A verification command run in the worktree after the run finished.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunCheck {
+    pub name: String,
+    pub command: String,
+    pub status: CheckStatus,
+    pub exit_code: Option<i32>,
+    /// Why it failed, if not (only) its exit code, e.g. a timeout
+    pub error: Option<String>,
+    pub duration_ms: Option<u64>,
+}
+
+/// Token usage and cost recorded for a run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunUsage {
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_creation_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cost_usd: f64,
+}
+
+impl RunUsage {
+    pub fn total_tokens(&self) -> i64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    }
+}
+
+impl std::ops::AddAssign for RunUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct TaskRunRow {
+    pub id: String,
+    pub task_id: String,
+    pub status: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_creation_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cost_usd: f64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub checks: String,
+    pub attempt_id: Option<String>,
+}
+
+/// A single executor run of a task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRun {
+    pub id: String,
+    pub task_id: String,
+    pub status: RunStatus,
+    pub usage: RunUsage,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Verification checks, in the order they run
+    pub checks: Vec<RunCheck>,
+    /// The parallel attempt the run belongs to, if any
+    pub attempt_id: Option<String>,
+}
+
+impl From<TaskRunRow> for TaskRun {
+    fn from(row: TaskRunRow) -> Self {
+        TaskRun {
+            id: row.id,
+            task_id: row.task_id,
+            status: RunStatus::from_str(&row.status).unwrap_or(RunStatus::Failed),
+            usage: RunUsage {
+                input_tokens: row.input_tokens,
+                output_tokens: row.output_tokens,
+                cache_creation_tokens: row.cache_creation_tokens,
+                cache_read_tokens: row.cache_read_tokens,
+                cost_usd: row.cost_usd,
+            },
+            started_at: row.started_at,
+            finished_at: row.finished_at,
+            checks: serde_json::from_str(&row.checks).unwrap_or_default(),
+            attempt_id: row.attempt_id,
+        }
+    }
+}
+
+const RUN_COLUMNS: &str = "id, task_id, status, input_tokens, output_tokens, cache_creation_tokens, cache_read_tokens, cost_usd, started_at, finished_at, checks, attempt_id";
+
+impl TaskRun {
+    pub async fn create(pool: &Pool<Sqlite>, task_id: &str, attempt_id: Option<&str>) -> Result<TaskRun, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO task_runs (id, task_id, status, started_at, attempt_id)
+            VALUES (?, ?, 'running', ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(task_id)
+        .bind(now)
+        .bind(attempt_id)
+        .execute(pool)
+        .await?;
+
+        Ok(TaskRun {
+            id,
+            task_id: task_id.to_string(),
+            status: RunStatus::Running,
+            usage: RunUsage::default(),
+            started_at: now,
+            finished_at: None,
+            checks: Vec::new(),
+            attempt_id: attempt_id.map(str::to_string),
+        })
+    }
+
+    pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<TaskRun>, sqlx::Error> {
+        let row: Option<TaskRunRow> = sqlx::query_as(
+            &format!("SELECT {} FROM task_runs WHERE id = ?", RUN_COLUMNS)
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(TaskRun::from))
+    }
+
+    pub async fn find_by_task(pool: &Pool<Sqlite>, task_id: &str) -> Result<Vec<TaskRun>, sqlx::Error> {
+        let rows: Vec<TaskRunRow> = sqlx::query_as(
+            &format!("SELECT {} FROM task_runs WHERE task_id = ? ORDER BY started_at ASC", RUN_COLUMNS)
+        )
+        .bind(task_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(TaskRun::from).collect())
+    }
+
+    pub async fn set_usage(pool: &Pool<Sqlite>, id: &str, usage: &RunUsage) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE task_runs
+            SET input_tokens = ?, output_tokens = ?, cache_creation_tokens = ?, cache_read_tokens = ?, cost_usd = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(usage.input_tokens)
+        .bind(usage.output_tokens)
+        .bind(usage.cache_creation_tokens)
+        .bind(usage.cache_read_tokens)
+        .bind(usage.cost_usd)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn finish(pool: &Pool<Sqlite>, id: &str, status: RunStatus) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE task_runs SET status = ?, finished_at = ? WHERE id = ?")
+            .bind(status.as_str())
+            .bind(Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_checks(pool: &Pool<Sqlite>, id: &str, checks: &[RunCheck]) -> Result<(), sqlx::Error> {
+        let checks = serde_json::to_string(checks).unwrap_or_else(|_| "[]".to_string());
+        sqlx::query("UPDATE task_runs SET checks = ? WHERE id = ?")
+            .bind(checks)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Total usage of all runs of a task, optionally excluding one run.
+    pub async fn usage_for_task(
+        pool: &Pool<Sqlite>,
+        task_id: &str,
+        exclude_run_id: Option<&str>,
+    ) -> Result<RunUsage, sqlx::Error> {
+        let row: (i64, i64, i64, i64, f64) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(SUM(input_tokens), 0), COALESCE(SUM(output_tokens), 0),
+                   COALESCE(SUM(cache_creation_tokens), 0), COALESCE(SUM(cache_read_tokens), 0),
+                   COALESCE(SUM(cost_usd), 0.0)
+            FROM task_runs
+            WHERE task_id = ? AND id != COALESCE(?, '')
+            "#,
+        )
+        .bind(task_id)
+        .bind(exclude_run_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(usage_from_sums(row))
+    }
+
+    /// Total usage of all runs of all tasks in a project, optionally excluding one run.
+    pub async fn usage_for_project(
+        pool: &Pool<Sqlite>,
+        project_path: &str,
+        exclude_run_id: Option<&str>,
+    ) -> Result<RunUsage, sqlx::Error> {
+        let row: (i64, i64, i64, i64, f64) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(SUM(r.input_tokens), 0), COALESCE(SUM(r.output_tokens), 0),
+                   COALESCE(SUM(r.cache_creation_tokens), 0), COALESCE(SUM(r.cache_read_tokens), 0),
+                   COALESCE(SUM(r.cost_usd), 0.0)
+            FROM task_runs r
+            JOIN tasks t ON t.id = r.task_id
+            WHERE t.project_path = ? AND r.id != COALESCE(?, '')
+            "#,
+        )
+        .bind(project_path)
+        .bind(exclude_run_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(usage_from_sums(row))
+    }
+}
+
+fn usage_from_sums(row: (i64, i64, i64, i64, f64)) -> RunUsage {
+    RunUsage {
+        input_tokens: row.0,
+        output_tokens: row.1,
+        cache_creation_tokens: row.2,
+        cache_read_tokens: row.3,
+        cost_usd: row.4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE tasks (
+                id TEXT PRIMARY KEY NOT NULL,
+                project_path TEXT
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE task_runs (
+                id TEXT PRIMARY KEY NOT NULL,
+                task_id TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'running',
+                input_tokens INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0,
+                cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+                cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+                cost_usd REAL NOT NULL DEFAULT 0,
+                started_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                finished_at DATETIME,
+                checks TEXT NOT NULL DEFAULT '[]',
+                attempt_id TEXT
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        for (id, project) in [("task-a", "/project/a"), ("task-b", "/project/a"), ("task-c", "/project/b")] {
+            sqlx::query("INSERT INTO tasks (id, project_path) VALUES (?, ?)")
+                .bind(id)
+                .bind(project)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        pool
+    }
+
+    fn usage(tokens: i64, cost_usd: f64) -> RunUsage {
+        RunUsage {
+            input_tokens: tokens,
+            cost_usd,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_and_finish_run() {
+        let pool = setup_test_db().await;
+
+        let run = TaskRun::create(&pool, "task-a", None).await.unwrap();
+        assert_eq!(run.status, RunStatus::Running);
+
+        TaskRun::set_usage(&pool, &run.id, &usage(100, 0.5)).await.unwrap();
+        TaskRun::finish(&pool, &run.id, RunStatus::Succeeded).await.unwrap();
+
+        let found = TaskRun::find_by_id(&pool, &run.id).await.unwrap().unwrap();
+        assert_eq!(found.status, RunStatus::Succeeded);
+        assert_eq!(found.usage.input_tokens, 100);
+        assert!(found.finished_at.is_some());
+
+        let runs = TaskRun::find_by_task(&pool, "task-a").await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].checks.is_empty());
+        assert_eq!(runs[0].attempt_id, None);
+
+        let attempt_run = TaskRun::create(&pool, "task-a", Some("attempt-1")).await.unwrap();
+        let found = TaskRun::find_by_id(&pool, &attempt_run.id).await.unwrap().unwrap();
+        assert_eq!(found.attempt_id.as_deref(), Some("attempt-1"));
+    }
+
+    #[tokio::test]
+    async fn test_set_checks() {
+        let pool = setup_test_db().await;
+        let run = TaskRun::create(&pool, "task-a", None).await.unwrap();
+
+        let check = |name: &str, status| RunCheck {
+            name: name.to_string(),
+            command: format!("make {}", name),
+            status,
+            exit_code: None,
+            error: None,
+            duration_ms: None,
+        };
+        let mut checks = vec![check("build", CheckStatus::Passed), check("test", CheckStatus::Pending)];
+        TaskRun::set_checks(&pool, &run.id, &checks).await.unwrap();
+        assert_eq!(CheckStatus::overall(&checks), CheckStatus::Running);
+
+        checks[1].status = CheckStatus::Failed;
+        checks[1].exit_code = Some(101);
+        TaskRun::set_checks(&pool, &run.id, &checks).await.unwrap();
+
+        let found = TaskRun::find_by_id(&pool, &run.id).await.unwrap().unwrap();
+        assert_eq!(found.checks, checks);
+        assert_eq!(CheckStatus::overall(&found.checks), CheckStatus::Failed);
+        assert_eq!(CheckStatus::overall(&[]), CheckStatus::Passed);
+    }
+
+    #[tokio::test]
+    async fn test_usage_totals() {
+        let pool = setup_test_db().await;
+
+        let first = TaskRun::create(&pool, "task-a", None).await.unwrap();
+        TaskRun::set_usage(&pool, &first.id, &usage(100, 1.0)).await.unwrap();
+        let second = TaskRun::create(&pool, "task-a", None).await.unwrap();
+        TaskRun::set_usage(&pool, &second.id, &usage(50, 0.25)).await.unwrap();
+        let other = TaskRun::create(&pool, "task-b", None).await.unwrap();
+        TaskRun::set_usage(&pool, &other.id, &usage(10, 0.1)).await.unwrap();
+        let elsewhere = TaskRun::create(&pool, "task-c", None).await.unwrap();
+        TaskRun::set_usage(&pool, &elsewhere.id, &usage(1000, 9.0)).await.unwrap();
+
+        let task_total = TaskRun::usage_for_task(&pool, "task-a", None).await.unwrap();
+        assert_eq!(task_total.total_tokens(), 150);
+        assert_eq!(task_total.cost_usd, 1.25);
+
+        let without_second = TaskRun::usage_for_task(&pool, "task-a", Some(&second.id)).await.unwrap();
+        assert_eq!(without_second.total_tokens(), 100);
+
+        let project_total = TaskRun::usage_for_project(&pool, "/project/a", None).await.unwrap();
+        assert_eq!(project_total.total_tokens(), 160);
+    }
+
+    #[test]
+    fn test_usage_add_assign() {
+        let mut usage = RunUsage { input_tokens: 10, cost_usd: 0.5, ..Default::default() };
+        usage += RunUsage { input_tokens: 5, output_tokens: 3, cache_read_tokens: 2, cost_usd: 0.25, ..Default::default() };
+        assert_eq!(usage.total_tokens(), 20);
+        assert_eq!(usage.cost_usd, 0.75);
+    }
+
+    #[test]
+    fn test_run_status_conversion() {
+        assert_eq!(RunStatus::BudgetExceeded.as_str(), "budget_exceeded");
+        assert_eq!(RunStatus::from_str("succeeded"), Some(RunStatus::Succeeded));
+        assert_eq!(RunStatus::from_str("invalid"), None);
+    }
+}
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, Pool, Sqlite};
 use uuid::Uuid;
 
+use super::run::CheckStatus;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
@@ -10,6 +12,10 @@ pub enum TaskStatus {
     InProgress,
     Review,
     Done,
+    /// Stopped because a task or project budget was exceeded
+    BudgetExceeded,
+    /// Kept out of review because verification checks failed
+    ChecksFailed,
 }
 
 impl TaskStatus {
@@ -19,15 +25,20 @@ impl TaskStatus {
             TaskStatus::InProgress => "in_progress",
             TaskStatus::Review => "review",
             TaskStatus::Done => "done",
+            TaskStatus::BudgetExceeded => "budget_exceeded",
+            TaskStatus::ChecksFailed => "checks_failed",
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "todo" => Some(TaskStatus::Todo),
             "in_progress" => Some(TaskStatus::InProgress),
             "review" => Some(TaskStatus::Review),
             "done" => Some(TaskStatus::Done),
+            "budget_exceeded" => Some(TaskStatus::BudgetExceeded),
+            "checks_failed" => Some(TaskStatus::ChecksFailed),
             _ => None,
         }
     }
@@ -49,6 +60,10 @@ pub struct TaskRow {
     pub branch_name: Option<String>,
     pub worktree_path: Option<String>,
     pub project_path: Option<String>,
+    pub budget_usd: Option<f64>,
+    pub budget_tokens: Option<i64>,
+    pub prompt_template_id: Option<String>,
+    pub checks_status: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -63,6 +78,11 @@ pub struct Task {
     pub branch_name: Option<String>,
     pub worktree_path: Option<String>,
     pub project_path: Option<String>,
+    pub budget_usd: Option<f64>,
+    pub budget_tokens: Option<i64>,
+    pub prompt_template_id: Option<String>,
+    /// Verification checks of the latest run, if any were run
+    pub checks_status: Option<CheckStatus>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -78,6 +98,10 @@ impl From<TaskRow> for Task {
             branch_name: row.branch_name,
             worktree_path: row.worktree_path,
             project_path: row.project_path,
+            budget_usd: row.budget_usd,
+            budget_tokens: row.budget_tokens,
+            prompt_template_id: row.prompt_template_id,
+            checks_status: row.checks_status.as_deref().and_then(CheckStatus::from_str),
             created_at: row.created_at,
             updated_at: row.updated_at,
         }
@@ -101,6 +125,8 @@ pub struct UpdateTask {
     pub worktree_path: Option<String>,
 }
 
+const TASK_COLUMNS: &str = "id, title, description, status, error_message, branch_name, worktree_path, project_path, budget_usd, budget_tokens, prompt_template_id, checks_status, created_at, updated_at";
+
 impl Task {
     pub async fn create(pool: &Pool<Sqlite>, input: CreateTask) -> Result<Task, sqlx::Error> {
         let id = Uuid::new_v4().to_string();
@@ -130,6 +156,10 @@ impl Task {
             branch_name: None,
             worktree_path: None,
             project_path: Some(input.project_path),
+            budget_usd: None,
+            budget_tokens: None,
+            prompt_template_id: None,
+            checks_status: None,
             created_at: now,
             updated_at: now,
         })
@@ -137,7 +167,7 @@ impl Task {
 
     pub async fn find_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<Task>, sqlx::Error> {
         let row: Option<TaskRow> = sqlx::query_as(
-            "SELECT id, title, description, status, error_message, branch_name, worktree_path, project_path, created_at, updated_at FROM tasks WHERE id = ?"
+            &format!("SELECT {} FROM tasks WHERE id = ?", TASK_COLUMNS)
         )
         .bind(id)
         .fetch_optional(pool)
@@ -148,7 +178,7 @@ impl Task {
 
     pub async fn find_all(pool: &Pool<Sqlite>) -> Result<Vec<Task>, sqlx::Error> {
         let rows: Vec<TaskRow> = sqlx::query_as(
-            "SELECT id, title, description, status, error_message, branch_name, worktree_path, project_path, created_at, updated_at FROM tasks ORDER BY created_at DESC"
+            &format!("SELECT {} FROM tasks ORDER BY created_at DESC", TASK_COLUMNS)
         )
         .fetch_all(pool)
         .await?;
@@ -156,9 +186,30 @@ impl Task {
         Ok(rows.into_iter().map(Task::from).collect())
     }
 
+    /// The project's task whose id starts with `prefix`, if exactly one does.
+    pub async fn find_by_id_prefix(pool: &Pool<Sqlite>, project_path: &str, prefix: &str) -> Result<Option<Task>, sqlx::Error> {
+        let prefix = prefix.replace(['%', '_'], "");
+        if prefix.is_empty() {
+            return Ok(None);
+        }
+
+        let rows: Vec<TaskRow> = sqlx::query_as(
+            &format!("SELECT {} FROM tasks WHERE project_path = ? AND id LIKE ? || '%' LIMIT 2", TASK_COLUMNS)
+        )
+        .bind(project_path)
+        .bind(&prefix)
+        .fetch_all(pool)
+        .await?;
+
+        if rows.len() != 1 {
+            return Ok(None);
+        }
+        Ok(rows.into_iter().next().map(Task::from))
+    }
+
     pub async fn find_all_by_project(pool: &Pool<Sqlite>, project_path: &str) -> Result<Vec<Task>, sqlx::Error> {
         let rows: Vec<TaskRow> = sqlx::query_as(
-            "SELECT id, title, description, status, error_message, branch_name, worktree_path, project_path, created_at, updated_at FROM tasks WHERE project_path = ? ORDER BY created_at DESC"
+            &format!("SELECT {} FROM tasks WHERE project_path = ? ORDER BY created_at DESC", TASK_COLUMNS)
         )
         .bind(project_path)
         .fetch_all(pool)
@@ -214,6 +265,10 @@ impl Task {
             branch_name,
             worktree_path,
             project_path: existing.project_path,
+            budget_usd: existing.budget_usd,
+            budget_tokens: existing.budget_tokens,
+            prompt_template_id: existing.prompt_template_id,
+            checks_status: existing.checks_status,
             created_at: existing.created_at,
             updated_at: now,
         }))
@@ -288,6 +343,113 @@ impl Task {
         )
         .await
     }
+
+    /// Set the task's spending limits. `None` removes a limit.
+    pub async fn set_budget(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        budget_usd: Option<f64>,
+        budget_tokens: Option<i64>,
+    ) -> Result<Option<Task>, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE tasks SET budget_usd = ?, budget_tokens = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(budget_usd)
+        .bind(budget_tokens)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Self::find_by_id(pool, id).await
+    }
+
+    /// Replace the description, keeping the previous one in `task_description_history`.
+    pub async fn replace_description(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        description: &str,
+        source: &str,
+        plan_session_id: Option<&str>,
+    ) -> Result<Option<Task>, sqlx::Error> {
+        let Some(existing) = Self::find_by_id(pool, id).await? else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO task_description_history (id, task_id, description, source, plan_session_id, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(id)
+        .bind(&existing.description)
+        .bind(source)
+        .bind(plan_session_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE tasks SET description = ?, updated_at = ? WHERE id = ?")
+            .bind(description)
+            .bind(now)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Self::find_by_id(pool, id).await
+    }
+
+    /// Record the state of the latest run's verification checks. `None`
+    /// clears it, e.g. when a new run starts.
+    pub async fn set_checks_status(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        checks_status: Option<CheckStatus>,
+    ) -> Result<Option<Task>, sqlx::Error> {
+        let result = sqlx::query("UPDATE tasks SET checks_status = ?, updated_at = ? WHERE id = ?")
+            .bind(checks_status.map(|s| s.as_str()))
+            .bind(Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Self::find_by_id(pool, id).await
+    }
+
+    /// Select the prompt template used when starting the task. `None` uses the default prompt.
+    pub async fn set_prompt_template(
+        pool: &Pool<Sqlite>,
+        id: &str,
+        template_id: Option<&str>,
+    ) -> Result<Option<Task>, sqlx::Error> {
+        let result = sqlx::query("UPDATE tasks SET prompt_template_id = ?, updated_at = ? WHERE id = ?")
+            .bind(template_id)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Self::find_by_id(pool, id).await
+    }
 }
 
 #[cfg(test)]
@@ -312,6 +474,10 @@ mod tests {
                 branch_name TEXT,
                 worktree_path TEXT,
                 project_path TEXT,
+                budget_usd REAL,
+                budget_tokens INTEGER,
+                prompt_template_id TEXT,
+                checks_status TEXT,
                 created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
                 updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
             )
@@ -535,6 +701,177 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_set_budget() {
+        let pool = setup_test_db().await;
+
+        let created = Task::create(
+            &pool,
+            CreateTask {
+                title: "Budget Test".to_string(),
+                description: None,
+                project_path: "/test/project".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let updated = Task::set_budget(&pool, &created.id, Some(1.5), Some(200_000))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.budget_usd, Some(1.5));
+        assert_eq!(updated.budget_tokens, Some(200_000));
+
+        let cleared = Task::set_budget(&pool, &created.id, None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(cleared.budget_usd.is_none());
+        assert!(cleared.budget_tokens.is_none());
+
+        let missing = Task::set_budget(&pool, "nonexistent", Some(1.0), None).await.unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replace_description_keeps_history() {
+        let pool = setup_test_db().await;
+        sqlx::query(
+            r#"
+            CREATE TABLE task_description_history (
+                id TEXT PRIMARY KEY NOT NULL,
+                task_id TEXT NOT NULL,
+                description TEXT,
+                source TEXT NOT NULL,
+                plan_session_id TEXT,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let created = Task::create(
+            &pool,
+            CreateTask {
+                title: "Plan Me".to_string(),
+                description: Some("Rough idea".to_string()),
+                project_path: "/test/project".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let updated = Task::replace_description(&pool, &created.id, "Detailed plan", "plan", Some("session-1"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.description.as_deref(), Some("Detailed plan"));
+
+        let history = crate::models::task_history::TaskDescriptionHistory::find_by_task(&pool, &created.id)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].description.as_deref(), Some("Rough idea"));
+        assert_eq!(history[0].plan_session_id.as_deref(), Some("session-1"));
+
+        let missing = Task::replace_description(&pool, "nonexistent", "x", "plan", None).await.unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_set_prompt_template() {
+        let pool = setup_test_db().await;
+
+        let created = Task::create(
+            &pool,
+            CreateTask {
+                title: "Template Test".to_string(),
+                description: None,
+                project_path: "/test/project".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        assert!(created.prompt_template_id.is_none());
+
+        let updated = Task::set_prompt_template(&pool, &created.id, Some("template-1"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.prompt_template_id, Some("template-1".to_string()));
+
+        let cleared = Task::set_prompt_template(&pool, &created.id, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(cleared.prompt_template_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_set_checks_status() {
+        let pool = setup_test_db().await;
+
+        let created = Task::create(
+            &pool,
+            CreateTask {
+                title: "Checks Test".to_string(),
+                description: None,
+                project_path: "/test/project".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        assert!(created.checks_status.is_none());
+
+        let failed = Task::set_checks_status(&pool, &created.id, Some(CheckStatus::Failed))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(failed.checks_status, Some(CheckStatus::Failed));
+
+        // Kept when the status changes
+        let moved = Task::set_status(&pool, &created.id, TaskStatus::Review)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(moved.checks_status, Some(CheckStatus::Failed));
+
+        let cleared = Task::set_checks_status(&pool, &created.id, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(cleared.checks_status.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_find_by_id_prefix() {
+        let pool = setup_test_db().await;
+
+        let created = Task::create(
+            &pool,
+            CreateTask {
+                title: "Prefix Test".to_string(),
+                description: None,
+                project_path: "/test/project".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let found = Task::find_by_id_prefix(&pool, "/test/project", &created.id[..8])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, created.id);
+
+        assert!(Task::find_by_id_prefix(&pool, "/other/project", &created.id[..8]).await.unwrap().is_none());
+        // A wildcard would match every task
+        assert!(Task::find_by_id_prefix(&pool, "/test/project", "%").await.unwrap().is_none());
+    }
+
     #[test]
     fn test_task_status_conversion() {
         assert_eq!(TaskStatus::Todo.as_str(), "todo");
@@ -549,6 +886,14 @@ mod tests {
         );
         assert_eq!(TaskStatus::from_str("review"), Some(TaskStatus::Review));
         assert_eq!(TaskStatus::from_str("done"), Some(TaskStatus::Done));
+        assert_eq!(
+            TaskStatus::from_str("budget_exceeded"),
+            Some(TaskStatus::BudgetExceeded)
+        );
+        assert_eq!(
+            TaskStatus::from_str("checks_failed"),
+            Some(TaskStatus::ChecksFailed)
+        );
         assert_eq!(TaskStatus::from_str("invalid"), None);
     }
 }
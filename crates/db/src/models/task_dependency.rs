@@ -0,0 +1,117 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+
+/// A task that has to be done before another one can start.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TaskDependency {
+    pub task_id: String,
+    pub depends_on_task_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TaskDependency {
+    pub async fn create(pool: &Pool<Sqlite>, task_id: &str, depends_on_task_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_task_id, created_at) VALUES (?, ?, ?)",
+        )
+        .bind(task_id)
+        .bind(depends_on_task_id)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Tasks the given task depends on.
+    pub async fn find_by_task(pool: &Pool<Sqlite>, task_id: &str) -> Result<Vec<TaskDependency>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT task_id, depends_on_task_id, created_at
+            FROM task_dependencies
+            WHERE task_id = ?
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(task_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Tasks that depend on the given task.
+    pub async fn find_dependents(pool: &Pool<Sqlite>, task_id: &str) -> Result<Vec<TaskDependency>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT task_id, depends_on_task_id, created_at
+            FROM task_dependencies
+            WHERE depends_on_task_id = ?
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(task_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Remove every link to or from a task.
+    pub async fn delete_for_task(pool: &Pool<Sqlite>, task_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM task_dependencies WHERE task_id = ? OR depends_on_task_id = ?")
+            .bind(task_id)
+            .bind(task_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE task_dependencies (
+                task_id TEXT NOT NULL,
+                depends_on_task_id TEXT NOT NULL,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (task_id, depends_on_task_id)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_create_find_and_delete() {
+        let pool = setup_test_db().await;
+
+        TaskDependency::create(&pool, "b", "a").await.unwrap();
+        TaskDependency::create(&pool, "b", "a").await.unwrap();
+        TaskDependency::create(&pool, "c", "b").await.unwrap();
+
+        let deps = TaskDependency::find_by_task(&pool, "b").await.unwrap();
+        assert_eq!(deps.len(), 1);
+        assert_eq!(deps[0].depends_on_task_id, "a");
+
+        let dependents = TaskDependency::find_dependents(&pool, "b").await.unwrap();
+        assert_eq!(dependents.len(), 1);
+        assert_eq!(dependents[0].task_id, "c");
+
+        TaskDependency::delete_for_task(&pool, "b").await.unwrap();
+        assert!(TaskDependency::find_by_task(&pool, "b").await.unwrap().is_empty());
+        assert!(TaskDependency::find_dependents(&pool, "b").await.unwrap().is_empty());
+    }
+}
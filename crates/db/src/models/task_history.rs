@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+
+/// A task description that was replaced, e.g. by attaching a plan.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TaskDescriptionHistory {
+    pub id: String,
+    pub task_id: String,
+    /// The description before it was replaced
+    pub description: Option<String>,
+    /// What replaced it, e.g. "plan"
+    pub source: String,
+    /// Plan session that produced the replacement, if any
+    pub plan_session_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TaskDescriptionHistory {
+    /// Previous descriptions of a task, newest first.
+    pub async fn find_by_task(pool: &Pool<Sqlite>, task_id: &str) -> Result<Vec<TaskDescriptionHistory>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, task_id, description, source, plan_session_id, created_at
+            FROM task_description_history
+            WHERE task_id = ?
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(task_id)
+        .fetch_all(pool)
+        .await
+    }
+}
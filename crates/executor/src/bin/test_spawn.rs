@@ -106,6 +106,7 @@ async fn test_spawn_with_question_detection() {
 }
 
 #[derive(Clone)]
+#[allow(dead_code)]
 enum StdinMode {
     Null,
     Piped,
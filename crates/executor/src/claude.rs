@@ -1,9 +1,12 @@
 use std::path::PathBuf;
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
 use tokio::sync::mpsc;
 
+use crate::stream_json;
+use crate::usage::{Usage, UsageTracker};
+
 #[derive(Debug, thiserror::Error)]
 pub enum ExecutorError {
     #[error("IO error: {0}")]
@@ -23,15 +26,46 @@ pub enum ExecutorEvent {
     Started,
     Completed { success: bool },
     Error(String),
+    /// Updated usage totals for the run, parsed from stream-json output.
+    Usage(Usage),
 }
 
 pub struct ClaudeExecutor {
     working_dir: PathBuf,
+    /// Model passed to task and chat runs with `--model`, or the CLI's default
+    model: Option<String>,
+}
+
+/// Tools a read-only chat may use.
+pub const READ_ONLY_TOOLS: &[&str] = &["Read", "Glob", "Grep", "LS", "WebFetch", "WebSearch"];
+
+/// Tools that change files or run commands, denied to a read-only chat even
+/// if a setting elsewhere would allow them.
+pub const WRITE_TOOLS: &[&str] = &["Bash", "Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// How the Claude process is started and fed its prompt.
+enum SpawnMode<'a> {
+    /// Autonomous task execution, prompt as argument
+    Task,
+    /// Plan mode, prompt as argument, stdin closed
+    Plan,
+    /// Plan mode with stream-json stdin kept open for later turns
+    PlanConversation,
+    /// Plan mode continuing an earlier conversation by its session id
+    PlanResume(&'a str),
+    /// Read-only chat: only READ_ONLY_TOOLS are allowed
+    Chat,
 }
 
 impl ClaudeExecutor {
     pub fn new(working_dir: PathBuf) -> Self {
-        Self { working_dir }
+        Self { working_dir, model: None }
+    }
+
+    /// Run tasks with the given model (an alias like `sonnet` or a full name).
+    pub fn with_model(mut self, model: Option<String>) -> Self {
+        self.model = model;
+        self
     }
 
     #[cfg(windows)]
@@ -60,6 +94,19 @@ impl ClaudeExecutor {
             .unwrap_or(false)
     }
 
+    /// What `claude --version` prints, or None if it can't be run.
+    pub async fn cli_version() -> Option<String> {
+        let output = Self::create_claude_command()
+            .arg("--version")
+            .output()
+            .await
+            .ok()
+            .filter(|o| o.status.success())?;
+
+        let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (!version.is_empty()).then_some(version)
+    }
+
     #[cfg(windows)]
     fn get_node_path() -> String {
         // Try common Node.js installation paths
@@ -111,7 +158,7 @@ impl ClaudeExecutor {
         &self,
         prompt: &str,
     ) -> Result<(mpsc::Receiver<ExecutorEvent>, ClaudeProcess), ExecutorError> {
-        let (rx, process, _stdin_tx) = self.spawn_internal(prompt, false).await?;
+        let (rx, process, _stdin_tx) = self.spawn_internal(prompt, SpawnMode::Task).await?;
         Ok((rx, process))
     }
 
@@ -121,7 +168,44 @@ impl ClaudeExecutor {
         &self,
         prompt: &str,
     ) -> Result<(mpsc::Receiver<ExecutorEvent>, ClaudeProcess, mpsc::Sender<String>), ExecutorError> {
-        self.spawn_internal(prompt, true).await
+        self.spawn_internal(prompt, SpawnMode::Plan).await
+    }
+
+    /// Whether plan conversations can be kept alive over stdin. Piped stdin
+    /// blocks the CLI on Windows, so there plans fall back to re-spawning.
+    pub fn supports_conversation() -> bool {
+        cfg!(not(windows))
+    }
+
+    /// Spawn Claude in plan mode with a live stream-json conversation. The
+    /// prompt is sent as the first user turn; further turns (e.g. tool results
+    /// with the user's answers) are lines sent through the returned sender.
+    /// Dropping the sender closes stdin, which ends the conversation.
+    pub async fn spawn_conversation(
+        &self,
+        prompt: &str,
+    ) -> Result<(mpsc::Receiver<ExecutorEvent>, ClaudeProcess, mpsc::Sender<String>), ExecutorError> {
+        self.spawn_internal(prompt, SpawnMode::PlanConversation).await
+    }
+
+    /// Continue an earlier plan conversation with a new prompt, keeping
+    /// everything Claude explored so far.
+    pub async fn spawn_resume(
+        &self,
+        session_id: &str,
+        prompt: &str,
+    ) -> Result<(mpsc::Receiver<ExecutorEvent>, ClaudeProcess, mpsc::Sender<String>), ExecutorError> {
+        self.spawn_internal(prompt, SpawnMode::PlanResume(session_id)).await
+    }
+
+    /// Spawn Claude for a read-only chat answer. Permissions are enforced by
+    /// the CLI: only `READ_ONLY_TOOLS` are allowed and `WRITE_TOOLS` are denied.
+    pub async fn spawn_chat(
+        &self,
+        prompt: &str,
+    ) -> Result<(mpsc::Receiver<ExecutorEvent>, ClaudeProcess), ExecutorError> {
+        let (rx, process, _stdin_tx) = self.spawn_internal(prompt, SpawnMode::Chat).await?;
+        Ok((rx, process))
     }
 
     /// Run Claude in plan mode and capture all output synchronously.
@@ -147,7 +231,7 @@ impl ClaudeExecutor {
         tracing::debug!("[Claude Plan] Full prompt: {}", prompt);
 
         let output = cmd
-            .args(&args)
+            .args(args)
             .arg(prompt)
             .current_dir(&self.working_dir)
             .output()
@@ -186,42 +270,80 @@ impl ClaudeExecutor {
         Ok(stdout)
     }
 
+    /// Arguments for a spawn, with the prompt right after `--print` unless it
+    /// goes through stdin. `--allowedTools` and `--disallowedTools` take any
+    /// number of values, so a prompt after them would be read as a tool name.
+    ///
+    /// - Interactive (plan mode): needs stream-json for tool_use parsing
+    ///   NOTE: We use stdin null because piped stdin blocks the process on Windows
+    ///   Responses will be sent by re-spawning with context
+    /// - Conversation (plan mode, not on Windows): stream-json on stdin too,
+    ///   so answers go to the same process
+    /// - Chat: default permissions with an allow-list, so anything outside
+    ///   READ_ONLY_TOOLS is refused rather than prompted for
+    /// - Non-interactive: use --dangerously-skip-permissions for autonomous execution,
+    ///   with stream-json so token usage and cost can be tracked live
+    fn spawn_args(&self, prompt: &str, mode: &SpawnMode<'_>) -> Vec<String> {
+        let mut args = vec!["--print".to_string()];
+        if !matches!(mode, SpawnMode::PlanConversation) {
+            args.push(prompt.to_string());
+        }
+        args.extend(["--output-format", "stream-json", "--verbose"].map(String::from));
+
+        let plan = ["--permission-mode", "plan"].map(String::from);
+        match mode {
+            SpawnMode::Task => args.push("--dangerously-skip-permissions".to_string()),
+            SpawnMode::Chat => args.extend([
+                "--permission-mode".to_string(), "default".to_string(),
+                "--allowedTools".to_string(), READ_ONLY_TOOLS.join(","),
+                "--disallowedTools".to_string(), WRITE_TOOLS.join(","),
+            ]),
+            SpawnMode::Plan => args.extend(plan),
+            // NOTE: --input-format stream-json requires stdin, which blocks on Windows
+            SpawnMode::PlanConversation => {
+                args.extend(plan);
+                args.extend(["--input-format", "stream-json"].map(String::from));
+            }
+            SpawnMode::PlanResume(session_id) => {
+                args.extend(plan);
+                args.extend(["--resume".to_string(), session_id.to_string()]);
+            }
+        }
+
+        if matches!(mode, SpawnMode::Task | SpawnMode::Chat) {
+            if let Some(model) = &self.model {
+                args.extend(["--model".to_string(), model.clone()]);
+            }
+        }
+        args
+    }
+
     async fn spawn_internal(
         &self,
         prompt: &str,
-        interactive: bool,
+        mode: SpawnMode<'_>,
     ) -> Result<(mpsc::Receiver<ExecutorEvent>, ClaudeProcess, mpsc::Sender<String>), ExecutorError> {
         let (tx, rx) = mpsc::channel(100);
-        // NOTE: stdin_rx is unused now that we use stdin null
-        // Kept for API compatibility but will be replaced with re-spawn mechanism
-        let (stdin_tx, _stdin_rx) = mpsc::channel::<String>(32);
+        // Only read in conversation mode, otherwise stdin is null
+        let (stdin_tx, mut stdin_rx) = mpsc::channel::<String>(32);
+        let interactive = !matches!(mode, SpawnMode::Task | SpawnMode::Chat);
+        let conversation = matches!(mode, SpawnMode::PlanConversation);
 
         let mut cmd = Self::create_claude_command();
 
-        // Build args based on mode
-        // - Interactive (plan mode): needs stream-json for tool_use parsing
-        //   NOTE: We use stdin null because piped stdin blocks the process on Windows
-        //   Responses will be sent by re-spawning with context
-        // - Non-interactive: use --dangerously-skip-permissions for autonomous execution
-        let args: Vec<&str> = if interactive {
-            vec![
-                "--print",
-                "--output-format", "stream-json",
-                // NOTE: Removed --input-format stream-json - it requires stdin which blocks
-                "--verbose",
-                "--permission-mode", "plan",
-            ]
-        } else {
-            vec!["--print", "--dangerously-skip-permissions"]
-        };
+        let args = self.spawn_args(prompt, &mode);
 
-        // CRITICAL: Always use Stdio::null() for stdin
+        // CRITICAL: Use Stdio::null() for stdin unless it carries the conversation
         // When stdin is piped but empty, the Claude CLI blocks waiting for input
         // This was confirmed via diagnostic testing - only stdin null allows output
-        cmd.args(&args)
-        .arg(prompt)
-        .current_dir(&self.working_dir)
-        .stdin(Stdio::null())
+        // In conversation mode the prompt is written to stdin right away instead
+        cmd.args(&args);
+        if conversation {
+            cmd.stdin(Stdio::piped());
+        } else {
+            cmd.stdin(Stdio::null());
+        }
+        cmd.current_dir(&self.working_dir)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .kill_on_drop(true);
@@ -229,7 +351,7 @@ impl ClaudeExecutor {
         // Log the command being executed
         tracing::info!(
             "[Claude] Spawning claude with args: {:?}, prompt length: {}, interactive: {}, working_dir: {:?}",
-            args,
+            args.iter().filter(|arg| arg.as_str() != prompt).collect::<Vec<_>>(),
             prompt.len(),
             interactive,
             &self.working_dir
@@ -247,10 +369,29 @@ impl ClaudeExecutor {
 
         tracing::info!("[Claude] Process spawned successfully, pid: {:?}", child.id());
 
-        // NOTE: stdin writer removed - we now use stdin null to avoid blocking
-        // Interactive responses will be handled by re-spawning with context
-        // The stdin_tx channel is still returned for API compatibility but won't be used
-        if interactive {
+        // In conversation mode, lines sent through stdin_tx are written to stdin.
+        // Otherwise stdin is null and responses are handled by re-spawning.
+        if conversation {
+            let mut stdin = child.stdin.take().ok_or_else(|| {
+                ExecutorError::Process("Failed to capture stdin".to_string())
+            })?;
+            let first_turn = stream_json::user_text_message(prompt);
+            tokio::spawn(async move {
+                let mut next = Some(first_turn);
+                while let Some(line) = next {
+                    if stdin.write_all(line.as_bytes()).await.is_err()
+                        || stdin.write_all(b"\n").await.is_err()
+                        || stdin.flush().await.is_err()
+                    {
+                        tracing::warn!("[Claude] Failed to write to stdin, conversation ended");
+                        break;
+                    }
+                    next = stdin_rx.recv().await;
+                }
+                // Dropping stdin closes it, which lets Claude exit
+            });
+            tracing::info!("[Claude] Conversation mode enabled (stream-json stdin)");
+        } else if interactive {
             tracing::info!("[Claude] Interactive mode enabled (stdin null, responses via re-spawn)");
         }
 
@@ -266,10 +407,17 @@ impl ClaudeExecutor {
         let stdout_reader = BufReader::new(stdout);
         tokio::spawn(async move {
             let mut lines = stdout_reader.lines();
+            let mut usage = UsageTracker::new();
             while let Ok(Some(line)) = lines.next_line().await {
+                let usage_changed = usage.observe(&line);
                 if tx_stdout.send(ExecutorEvent::Stdout(line)).await.is_err() {
                     break;
                 }
+                if usage_changed
+                    && tx_stdout.send(ExecutorEvent::Usage(usage.current())).await.is_err()
+                {
+                    break;
+                }
             }
         });
 
@@ -307,6 +455,21 @@ impl ClaudeProcess {
         Ok(success)
     }
 
+    /// Wait for the process to exit, killing it early if `stop_rx` fires.
+    pub async fn wait_or_stop(mut self, mut stop_rx: mpsc::Receiver<()>) -> Result<bool, ExecutorError> {
+        tokio::select! {
+            status = self.child.wait() => {
+                let success = status?.success();
+                let _ = self.tx.send(ExecutorEvent::Completed { success }).await;
+                Ok(success)
+            }
+            _ = stop_rx.recv() => {
+                self.kill().await?;
+                Ok(false)
+            }
+        }
+    }
+
     pub async fn kill(mut self) -> Result<(), ExecutorError> {
         self.child.kill().await?;
         let _ = self.tx.send(ExecutorEvent::Completed { success: false }).await;
@@ -327,6 +490,32 @@ mod tests {
     fn test_executor_creation() {
         let executor = ClaudeExecutor::new(PathBuf::from("/tmp"));
         assert_eq!(executor.working_dir, PathBuf::from("/tmp"));
+        assert_eq!(executor.model, None);
+
+        let executor = executor.with_model(Some("sonnet".to_string()));
+        assert_eq!(executor.model.as_deref(), Some("sonnet"));
+    }
+
+    #[test]
+    fn test_chat_args_put_prompt_before_tool_lists() {
+        let executor = ClaudeExecutor::new(PathBuf::from("/tmp")).with_model(Some("sonnet".to_string()));
+        let args = executor.spawn_args("What does main do?", &SpawnMode::Chat);
+
+        assert_eq!(args[..2], ["--print", "What does main do?"]);
+        let allowed = args.iter().position(|a| a == "--allowedTools").unwrap();
+        let disallowed = args.iter().position(|a| a == "--disallowedTools").unwrap();
+        assert_eq!(args[allowed + 1], READ_ONLY_TOOLS.join(","));
+        assert_eq!(args[disallowed + 1], WRITE_TOOLS.join(","));
+        assert_eq!(args.windows(2).find(|w| w[0] == "--model").map(|w| w[1].as_str()), Some("sonnet"));
+    }
+
+    #[test]
+    fn test_conversation_args_leave_prompt_for_stdin() {
+        let executor = ClaudeExecutor::new(PathBuf::from("/tmp"));
+        let args = executor.spawn_args("Plan it", &SpawnMode::PlanConversation);
+
+        assert!(!args.iter().any(|a| a == "Plan it"));
+        assert!(args.windows(2).any(|w| w == ["--input-format", "stream-json"]));
     }
 
     #[test]
@@ -1,3 +1,6 @@
 pub mod claude;
+pub mod stream_json;
+pub mod usage;
 
 pub use claude::{ClaudeExecutor, ExecutorEvent, ExecutorError};
+pub use usage::{Usage, UsageTracker};
@@ -0,0 +1,188 @@
+//! Messages for Claude's `--input-format stream-json` stdin protocol, and
+//! helpers for reading its stream-json output.
+
+use serde_json::json;
+
+/// A user turn with plain text, as one stdin line.
+pub fn user_text_message(text: &str) -> String {
+    json!({
+        "type": "user",
+        "message": {
+            "role": "user",
+            "content": [{ "type": "text", "text": text }],
+        },
+    })
+    .to_string()
+}
+
+/// A user turn answering tool calls, as one stdin line. Each result is a
+/// `(tool_use_id, content)` pair.
+pub fn tool_result_message(results: &[(String, String)]) -> String {
+    let content: Vec<serde_json::Value> = results
+        .iter()
+        .map(|(tool_use_id, content)| {
+            json!({
+                "type": "tool_result",
+                "tool_use_id": tool_use_id,
+                "content": content,
+            })
+        })
+        .collect();
+
+    json!({
+        "type": "user",
+        "message": {
+            "role": "user",
+            "content": content,
+        },
+    })
+    .to_string()
+}
+
+/// The conversation id from the `{"type":"system","subtype":"init"}` event,
+/// which `--resume` accepts.
+pub fn session_id(line: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(line).ok()?;
+    if json.get("type").and_then(|t| t.as_str()) != Some("system")
+        || json.get("subtype").and_then(|t| t.as_str()) != Some("init")
+    {
+        return None;
+    }
+    json.get("session_id").and_then(|s| s.as_str()).map(str::to_string)
+}
+
+/// A piece of a message in stream-json output.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentBlock {
+    /// Text Claude wrote
+    Text(String),
+    /// A tool Claude called
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    /// The outcome of a tool call, reported back to Claude
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+        is_error: bool,
+    },
+}
+
+/// Content blocks of an `assistant` or `user` event. Other events have none.
+pub fn content_blocks(line: &str) -> Vec<ContentBlock> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(line) else {
+        return Vec::new();
+    };
+    if !matches!(json.get("type").and_then(|t| t.as_str()), Some("assistant" | "user")) {
+        return Vec::new();
+    }
+    let Some(content) = json.get("message").and_then(|m| m.get("content")).and_then(|c| c.as_array()) else {
+        return Vec::new();
+    };
+
+    let str_field = |item: &serde_json::Value, name: &str| {
+        item.get(name).and_then(|v| v.as_str()).unwrap_or_default().to_string()
+    };
+
+    content
+        .iter()
+        .filter_map(|item| match item.get("type").and_then(|t| t.as_str()) {
+            Some("text") => Some(ContentBlock::Text(str_field(item, "text"))),
+            Some("tool_use") => Some(ContentBlock::ToolUse {
+                id: str_field(item, "id"),
+                name: str_field(item, "name"),
+                input: item.get("input").cloned().unwrap_or(serde_json::Value::Null),
+            }),
+            Some("tool_result") => Some(ContentBlock::ToolResult {
+                tool_use_id: str_field(item, "tool_use_id"),
+                content: tool_result_text(item.get("content")),
+                is_error: item.get("is_error").and_then(|e| e.as_bool()).unwrap_or(false),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Tool result content is either a string or a list of text blocks.
+fn tool_result_text(content: Option<&serde_json::Value>) -> String {
+    match content {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Whether the line is the `result` event that ends a turn.
+pub fn is_result(line: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(line)
+        .ok()
+        .is_some_and(|json| json.get("type").and_then(|t| t.as_str()) == Some("result"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_text_message() {
+        let line = user_text_message("Plan \"this\"\nplease");
+        assert!(!line.contains('\n'));
+
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["type"], "user");
+        assert_eq!(json["message"]["content"][0]["text"], "Plan \"this\"\nplease");
+    }
+
+    #[test]
+    fn test_tool_result_message() {
+        let line = tool_result_message(&[("toolu_1".to_string(), "SQLite".to_string())]);
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        let block = &json["message"]["content"][0];
+        assert_eq!(block["type"], "tool_result");
+        assert_eq!(block["tool_use_id"], "toolu_1");
+        assert_eq!(block["content"], "SQLite");
+    }
+
+    #[test]
+    fn test_session_id() {
+        let init = r#"{"type":"system","subtype":"init","session_id":"abc-123","tools":[]}"#;
+        assert_eq!(session_id(init).as_deref(), Some("abc-123"));
+        assert_eq!(session_id(r#"{"type":"result","session_id":"abc-123"}"#), None);
+        assert_eq!(session_id("not json"), None);
+    }
+
+    #[test]
+    fn test_content_blocks() {
+        let assistant = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Let me look"},{"type":"tool_use","id":"toolu_1","name":"Read","input":{"file_path":"a.rs"}}]}}"#;
+        assert_eq!(content_blocks(assistant), vec![
+            ContentBlock::Text("Let me look".to_string()),
+            ContentBlock::ToolUse {
+                id: "toolu_1".to_string(),
+                name: "Read".to_string(),
+                input: json!({ "file_path": "a.rs" }),
+            },
+        ]);
+
+        let user = r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"toolu_1","content":[{"type":"text","text":"denied"}],"is_error":true}]}}"#;
+        assert_eq!(content_blocks(user), vec![ContentBlock::ToolResult {
+            tool_use_id: "toolu_1".to_string(),
+            content: "denied".to_string(),
+            is_error: true,
+        }]);
+
+        assert!(content_blocks(r#"{"type":"result","result":"Done"}"#).is_empty());
+        assert!(content_blocks("plain text").is_empty());
+    }
+
+    #[test]
+    fn test_is_result() {
+        assert!(is_result(r#"{"type":"result","subtype":"success","result":"Done"}"#));
+        assert!(!is_result(r#"{"type":"assistant"}"#));
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use serde::Serialize;
+
+/// Token and cost usage of a single Claude run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl Usage {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+
+    fn from_json(usage: &serde_json::Value) -> Self {
+        let field = |name: &str| usage.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+        Self {
+            input_tokens: field("input_tokens"),
+            output_tokens: field("output_tokens"),
+            cache_creation_input_tokens: field("cache_creation_input_tokens"),
+            cache_read_input_tokens: field("cache_read_input_tokens"),
+            cost_usd: 0.0,
+        }
+    }
+
+    fn add(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+/// Approximate list prices in USD per million tokens: (input, output).
+/// Only used to estimate cost while a run is in progress; the final
+/// `result` event carries the authoritative `total_cost_usd`.
+fn price_per_mtok(model: &str) -> (f64, f64) {
+    let model = model.to_lowercase();
+    if model.contains("opus") {
+        (15.0, 75.0)
+    } else if model.contains("haiku") {
+        (0.8, 4.0)
+    } else {
+        (3.0, 15.0)
+    }
+}
+
+fn estimate_cost(model: &str, usage: &Usage) -> f64 {
+    let (input, output) = price_per_mtok(model);
+    let mtok = |tokens: u64| tokens as f64 / 1_000_000.0;
+
+    mtok(usage.input_tokens) * input
+        + mtok(usage.output_tokens) * output
+        + mtok(usage.cache_creation_input_tokens) * input * 1.25
+        + mtok(usage.cache_read_input_tokens) * input * 0.1
+}
+
+/// Accumulates usage from Claude's stream-json output.
+///
+/// Assistant events are keyed by message id because the CLI emits one event
+/// per content block, each repeating the usage of the whole message.
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    messages: HashMap<String, Usage>,
+    final_usage: Option<Usage>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one stdout line. Returns true if the totals changed.
+    pub fn observe(&mut self, line: &str) -> bool {
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(line) else {
+            return false;
+        };
+
+        match json.get("type").and_then(|t| t.as_str()) {
+            Some("assistant") => {
+                let Some(message) = json.get("message") else {
+                    return false;
+                };
+                let Some(usage_json) = message.get("usage") else {
+                    return false;
+                };
+                let id = message.get("id").and_then(|i| i.as_str()).unwrap_or("").to_string();
+                let model = message.get("model").and_then(|m| m.as_str()).unwrap_or("");
+
+                let mut usage = Usage::from_json(usage_json);
+                usage.cost_usd = estimate_cost(model, &usage);
+
+                if self.messages.get(&id) == Some(&usage) {
+                    return false;
+                }
+                self.messages.insert(id, usage);
+                true
+            }
+            Some("result") => {
+                let mut usage = json.get("usage").map(Usage::from_json).unwrap_or_default();
+                usage.cost_usd = json.get("total_cost_usd")
+                    .or_else(|| json.get("cost_usd"))
+                    .and_then(|c| c.as_f64())
+                    .unwrap_or_else(|| self.estimated().cost_usd);
+                self.final_usage = Some(usage);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Current totals: the final result if the run finished, otherwise the
+    /// running estimate from assistant messages seen so far.
+    pub fn current(&self) -> Usage {
+        self.final_usage.unwrap_or_else(|| self.estimated())
+    }
+
+    fn estimated(&self) -> Usage {
+        let mut total = Usage::default();
+        for usage in self.messages.values() {
+            total.add(usage);
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_dedupes_assistant_messages() {
+        let mut tracker = UsageTracker::new();
+        let line = r#"{"type":"assistant","message":{"id":"msg_1","model":"claude-sonnet-4","usage":{"input_tokens":1000,"output_tokens":500}}}"#;
+
+        assert!(tracker.observe(line));
+        assert!(!tracker.observe(line));
+
+        let usage = tracker.current();
+        assert_eq!(usage.input_tokens, 1000);
+        assert_eq!(usage.output_tokens, 500);
+        assert_eq!(usage.total_tokens(), 1500);
+        assert!(usage.cost_usd > 0.0);
+    }
+
+    #[test]
+    fn test_tracker_prefers_result_cost() {
+        let mut tracker = UsageTracker::new();
+        tracker.observe(r#"{"type":"assistant","message":{"id":"msg_1","usage":{"input_tokens":10,"output_tokens":5}}}"#);
+        tracker.observe(r#"{"type":"result","subtype":"success","total_cost_usd":0.42,"usage":{"input_tokens":20,"output_tokens":8}}"#);
+
+        let usage = tracker.current();
+        assert_eq!(usage.cost_usd, 0.42);
+        assert_eq!(usage.input_tokens, 20);
+    }
+
+    #[test]
+    fn test_tracker_ignores_plain_text() {
+        let mut tracker = UsageTracker::new();
+        assert!(!tracker.observe("Hello world"));
+        assert_eq!(tracker.current(), Usage::default());
+    }
+}
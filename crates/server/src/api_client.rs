@@ -0,0 +1,170 @@
+//! A minimal HTTP/1.1 client for the server's own JSON API, used by the
+//! `task` subcommands. Only plain `http://` URLs are supported.
+
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("Invalid server URL '{0}' (expected http://host:port)")]
+    InvalidUrl(String),
+    #[error("Could not reach the server at {0}: {1}")]
+    Unreachable(String, std::io::Error),
+    #[error("Invalid response from the server: {0}")]
+    InvalidResponse(String),
+    /// The server answered with an error status
+    #[error("{message} (HTTP {status})")]
+    Status { status: u16, message: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    /// `host:port`
+    authority: String,
+    /// Sent as a bearer token
+    token: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(url: &str) -> Result<Self, ApiError> {
+        let authority = url
+            .trim_end_matches('/')
+            .strip_prefix("http://")
+            .filter(|a| !a.is_empty() && !a.contains('/'))
+            .ok_or_else(|| ApiError::InvalidUrl(url.to_string()))?;
+
+        let authority = if authority.contains(':') {
+            authority.to_string()
+        } else {
+            format!("{}:80", authority)
+        };
+
+        Ok(Self { authority, token: None })
+    }
+
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.authority)
+    }
+
+    pub async fn get(&self, path: &str) -> Result<Value, ApiError> {
+        self.request("GET", path, None).await
+    }
+
+    pub async fn post(&self, path: &str, body: Option<&Value>) -> Result<Value, ApiError> {
+        self.request("POST", path, body).await
+    }
+
+    async fn request(&self, method: &str, path: &str, body: Option<&Value>) -> Result<Value, ApiError> {
+        let mut stream = TcpStream::connect(&self.authority)
+            .await
+            .map_err(|e| ApiError::Unreachable(self.url(), e))?;
+
+        let body = body.map(Value::to_string).unwrap_or_default();
+        let authorization = self.token
+            .as_ref()
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        let request = format!(
+            "{method} {path} HTTP/1.1\r\nHost: {host}\r\n{authorization}Accept: application/json\r\nContent-Type: application/json\r\nContent-Length: {length}\r\nConnection: close\r\n\r\n{body}",
+            host = self.authority,
+            length = body.len(),
+        );
+
+        let io_error = |e: std::io::Error| ApiError::Unreachable(self.url(), e);
+        stream.write_all(request.as_bytes()).await.map_err(io_error)?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.map_err(io_error)?;
+
+        let (status, body) = parse_response(&response)?;
+        let json: Value = if body.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&body).map_err(|e| ApiError::InvalidResponse(e.to_string()))?
+        };
+
+        if !(200..300).contains(&status) {
+            let message = json.get("error")
+                .and_then(|e| e.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| "Request failed".to_string());
+            return Err(ApiError::Status { status, message });
+        }
+
+        Ok(json)
+    }
+}
+
+/// Status code and body of a complete response.
+fn parse_response(response: &[u8]) -> Result<(u16, String), ApiError> {
+    let text = String::from_utf8_lossy(response);
+    let (head, body) = text
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| ApiError::InvalidResponse("incomplete response".to_string()))?;
+
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| ApiError::InvalidResponse("missing status line".to_string()))?;
+
+    let chunked = head.lines().any(|line| {
+        let line = line.to_ascii_lowercase();
+        line.starts_with("transfer-encoding:") && line.contains("chunked")
+    });
+
+    let body = if chunked { decode_chunked(body)? } else { body.to_string() };
+    Ok((status, body))
+}
+
+fn decode_chunked(mut body: &str) -> Result<String, ApiError> {
+    let mut decoded = String::new();
+
+    loop {
+        let (size_line, rest) = body
+            .split_once("\r\n")
+            .ok_or_else(|| ApiError::InvalidResponse("truncated chunk".to_string()))?;
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| ApiError::InvalidResponse(format!("bad chunk size '{}'", size_hex)))?;
+        if size == 0 {
+            return Ok(decoded);
+        }
+
+        let chunk = rest
+            .get(..size)
+            .ok_or_else(|| ApiError::InvalidResponse("truncated chunk".to_string()))?;
+        decoded.push_str(chunk);
+        body = rest[size..].trim_start_matches("\r\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        assert_eq!(ApiClient::new("http://127.0.0.1:9847/").unwrap().url(), "http://127.0.0.1:9847");
+        assert_eq!(ApiClient::new("http://box").unwrap().url(), "http://box:80");
+        assert!(ApiClient::new("https://box:443").is_err());
+        assert!(ApiClient::new("http://box:1/api").is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let plain = b"HTTP/1.1 404 Not Found\r\ncontent-type: application/json\r\ncontent-length: 15\r\n\r\n{\"error\":\"no\"}";
+        assert_eq!(parse_response(plain).unwrap(), (404, "{\"error\":\"no\"}".to_string()));
+
+        let chunked = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n{\"a\"\r\n3\r\n:1}\r\n0\r\n\r\n";
+        assert_eq!(parse_response(chunked).unwrap(), (200, "{\"a\":1}".to_string()));
+
+        assert!(parse_response(b"garbage").is_err());
+    }
+}
@@ -0,0 +1,289 @@
+//! Attachment blob storage.
+//!
+//! Uploaded files are stored under `~/.eval-kanban/attachments/<id>/` and
+//! referenced by absolute path in the prompt, so Claude can open them with its
+//! Read tool (which also handles images).
+
+use std::path::PathBuf;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use eval_kanban_db::{Attachment, AttachmentOwner, CreateAttachment};
+use serde::Deserialize;
+use sqlx::{Pool, Sqlite};
+
+/// Largest accepted attachment, after decoding.
+pub const MAX_ATTACHMENT_BYTES: usize = 10 * 1024 * 1024;
+
+/// A file upload. `data` is base64, optionally as a `data:` URL.
+#[derive(Debug, Deserialize)]
+pub struct Upload {
+    pub filename: String,
+    pub mime_type: Option<String>,
+    pub data: String,
+}
+
+#[derive(Debug)]
+pub enum AttachmentError {
+    Invalid(String),
+    Io(std::io::Error),
+    Db(sqlx::Error),
+}
+
+impl std::fmt::Display for AttachmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachmentError::Invalid(msg) => write!(f, "{}", msg),
+            AttachmentError::Io(e) => write!(f, "Failed to store attachment: {}", e),
+            AttachmentError::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for AttachmentError {
+    fn from(e: std::io::Error) -> Self {
+        AttachmentError::Io(e)
+    }
+}
+
+impl From<sqlx::Error> for AttachmentError {
+    fn from(e: sqlx::Error) -> Self {
+        AttachmentError::Db(e)
+    }
+}
+
+/// Decode upload data, returning the bytes and the mime type from a `data:` URL if present.
+pub fn decode_data(data: &str) -> Result<(Vec<u8>, Option<String>), AttachmentError> {
+    let (mime_type, encoded) = match data.strip_prefix("data:").and_then(|rest| rest.split_once(',')) {
+        Some((meta, encoded)) => (meta.strip_suffix(";base64").map(String::from), encoded),
+        None => (None, data),
+    };
+
+    let bytes = STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| AttachmentError::Invalid("Attachment data is not valid base64".to_string()))?;
+
+    if bytes.is_empty() {
+        return Err(AttachmentError::Invalid("Attachment is empty".to_string()));
+    }
+    if bytes.len() > MAX_ATTACHMENT_BYTES {
+        return Err(AttachmentError::Invalid(format!(
+            "Attachment exceeds the {} MB limit",
+            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+        )));
+    }
+
+    Ok((bytes, mime_type.filter(|m| !m.is_empty())))
+}
+
+/// Keep only the final path component and characters that are safe in file names.
+pub fn sanitize_filename(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.');
+
+    if cleaned.is_empty() {
+        "attachment".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+pub fn guess_mime_type(filename: &str) -> &'static str {
+    let ext = filename.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "txt" | "log" | "md" | "csv" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Types served inline, for the board to show. Anything else is only offered
+/// as a download: HTML or SVG shown inline would run on the board's origin.
+const INLINE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+pub fn is_inline(mime_type: &str) -> bool {
+    INLINE_MIME_TYPES.contains(&mime_type.trim().to_ascii_lowercase().as_str())
+}
+
+/// `Content-Disposition` for serving an attachment, with an ASCII `filename`
+/// and the exact name RFC 5987-encoded in `filename*`.
+pub fn content_disposition(filename: &str, inline: bool) -> String {
+    let safe = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-' | b'_');
+    let fallback: String = filename.bytes().map(|b| if safe(b) { b as char } else { '_' }).collect();
+    let encoded: String = filename
+        .bytes()
+        .map(|b| if safe(b) { (b as char).to_string() } else { format!("%{:02X}", b) })
+        .collect();
+
+    format!(
+        "{}; filename=\"{}\"; filename*=UTF-8''{}",
+        if inline { "inline" } else { "attachment" },
+        fallback,
+        encoded
+    )
+}
+
+/// Decode an upload, write it to disk and record it.
+pub async fn store(
+    db: &Pool<Sqlite>,
+    project_path: &str,
+    owner_type: AttachmentOwner,
+    owner_id: Option<String>,
+    upload: Upload,
+) -> Result<Attachment, AttachmentError> {
+    let (bytes, data_mime) = decode_data(&upload.data)?;
+    let filename = sanitize_filename(&upload.filename);
+    let mime_type = upload.mime_type
+        .filter(|m| !m.is_empty())
+        .or(data_mime)
+        .unwrap_or_else(|| guess_mime_type(&filename).to_string());
+
+    let id = CreateAttachment::new_id();
+    let dir = eval_kanban_db::get_attachments_dir().join(&id);
+    tokio::fs::create_dir_all(&dir).await?;
+    let storage_path = dir.join(&filename);
+    tokio::fs::write(&storage_path, &bytes).await?;
+
+    let result = Attachment::create(
+        db,
+        CreateAttachment {
+            id,
+            project_path: project_path.to_string(),
+            owner_type,
+            owner_id,
+            filename,
+            mime_type,
+            size_bytes: bytes.len() as i64,
+            storage_path: storage_path.to_string_lossy().to_string(),
+        },
+    )
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    Ok(result?)
+}
+
+/// Delete an attachment and its blob.
+pub async fn remove(db: &Pool<Sqlite>, attachment: &Attachment) -> Result<bool, sqlx::Error> {
+    let deleted = Attachment::delete(db, &attachment.id).await?;
+
+    if let Some(dir) = PathBuf::from(&attachment.storage_path).parent() {
+        if let Err(e) = tokio::fs::remove_dir_all(dir).await {
+            tracing::warn!("Failed to remove attachment blob {}: {}", attachment.storage_path, e);
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Delete every attachment in the list, logging failures.
+pub async fn remove_all(db: &Pool<Sqlite>, attachments: &[Attachment]) {
+    for attachment in attachments {
+        if let Err(e) = remove(db, attachment).await {
+            tracing::warn!("Failed to delete attachment {}: {}", attachment.id, e);
+        }
+    }
+}
+
+/// Prompt text telling Claude where the attached files are.
+pub fn prompt_section(attachments: &[Attachment]) -> Option<String> {
+    if attachments.is_empty() {
+        return None;
+    }
+
+    let mut section = String::from("Attached files (open them with the Read tool):");
+    for attachment in attachments {
+        section.push_str(&format!(
+            "\n- {} ({}, {})",
+            attachment.storage_path, attachment.filename, attachment.mime_type
+        ));
+    }
+
+    Some(section)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_decode_plain_and_data_url() {
+        let (bytes, mime) = decode_data("aGVsbG8=").unwrap();
+        assert_eq!(bytes, b"hello");
+        assert!(mime.is_none());
+
+        let (bytes, mime) = decode_data("data:image/png;base64,aGVsbG8=").unwrap();
+        assert_eq!(bytes, b"hello");
+        assert_eq!(mime.as_deref(), Some("image/png"));
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_and_empty() {
+        assert!(matches!(decode_data("not base64!"), Err(AttachmentError::Invalid(_))));
+        assert!(matches!(decode_data(""), Err(AttachmentError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_filename("C:\\Users\\me\\bug shot.png"), "bug_shot.png");
+        assert_eq!(sanitize_filename(".hidden"), "hidden");
+        assert_eq!(sanitize_filename(""), "attachment");
+    }
+
+    #[test]
+    fn test_guess_mime_type() {
+        assert_eq!(guess_mime_type("shot.PNG"), "image/png");
+        assert_eq!(guess_mime_type("notes.md"), "text/plain");
+        assert_eq!(guess_mime_type("blob"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_inline_and_content_disposition() {
+        assert!(is_inline("image/PNG"));
+        assert!(!is_inline("image/svg+xml"));
+        assert!(!is_inline("text/html"));
+
+        assert_eq!(
+            content_disposition("shot.png", true),
+            "inline; filename=\"shot.png\"; filename*=UTF-8''shot.png"
+        );
+        assert_eq!(
+            content_disposition("r\"é\r\n.html", false),
+            "attachment; filename=\"r_____.html\"; filename*=UTF-8''r%22%C3%A9%0D%0A.html"
+        );
+    }
+
+    #[test]
+    fn test_prompt_section() {
+        assert!(prompt_section(&[]).is_none());
+
+        let attachment = Attachment {
+            id: "a1".to_string(),
+            project_path: "/project".to_string(),
+            owner_type: AttachmentOwner::Task,
+            owner_id: Some("t1".to_string()),
+            filename: "bug.png".to_string(),
+            mime_type: "image/png".to_string(),
+            size_bytes: 5,
+            storage_path: "/data/attachments/a1/bug.png".to_string(),
+            created_at: Utc::now(),
+        };
+        let section = prompt_section(&[attachment]).unwrap();
+        assert!(section.contains("- /data/attachments/a1/bug.png (bug.png, image/png)"));
+    }
+}
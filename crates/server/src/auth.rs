@@ -0,0 +1,232 @@
+//! API token authentication.
+//!
+//! A random token is generated on first start and kept in
+//! `~/.eval-kanban/token`. Every `/api` and `/preview` request must carry it,
+//! as a bearer token, a `token` query parameter (for WebSocket and
+//! EventSource, which can't set headers) or the cookie set when the browser
+//! first opens the URL printed at startup.
+//!
+//! Previews are proxied on a port of their own. Browsers send the cookie there
+//! too (cookies don't tell ports apart), but a preview page's requests to the
+//! API carry the preview's origin, which isn't accepted.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+pub const TOKEN_COOKIE: &str = "eval_kanban_token";
+/// Overrides the token file for the `task` commands
+pub const TOKEN_ENV: &str = "EVAL_KANBAN_TOKEN";
+/// A year, so the browser stays signed in across restarts
+const COOKIE_MAX_AGE_SECS: u64 = 365 * 24 * 60 * 60;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+pub struct Auth {
+    /// None when started with `--no-auth`
+    token: Option<String>,
+    /// Origins besides the server's own whose browser requests are accepted
+    allowed_origins: Vec<String>,
+}
+
+impl Auth {
+    pub fn new(token: Option<String>, allowed_origins: Vec<String>) -> Arc<Self> {
+        Arc::new(Self { token, allowed_origins })
+    }
+
+    fn is_valid(&self, candidate: &str) -> bool {
+        self.token.as_deref().is_some_and(|token| constant_time_eq(token, candidate))
+    }
+
+    /// Whether the request is authorized. A cookie is sent by the browser on
+    /// its own, so it only counts for requests from our own pages.
+    fn authorizes(&self, headers: &HeaderMap, query: Option<&str>) -> bool {
+        if self.token.is_none() {
+            return true;
+        }
+
+        if bearer_token(headers).is_some_and(|t| self.is_valid(t))
+            || query_token(query).is_some_and(|t| self.is_valid(t))
+        {
+            return true;
+        }
+
+        cookie_token(headers).is_some_and(|t| self.is_valid(t)) && self.origin_allowed(headers)
+    }
+
+    /// Requests without an `Origin` header don't come from another site's
+    /// scripts; the rest must come from our own origin or an allowed one.
+    fn origin_allowed(&self, headers: &HeaderMap) -> bool {
+        let Some(origin) = headers.get(header::ORIGIN).and_then(|o| o.to_str().ok()) else {
+            return true;
+        };
+        let host = headers.get(header::HOST).and_then(|h| h.to_str().ok());
+
+        let same_origin = origin
+            .split_once("://")
+            .is_some_and(|(_, authority)| Some(authority) == host);
+
+        same_origin || self.allowed_origins.iter().any(|o| o == origin)
+    }
+}
+
+/// Rejects `/api` and `/preview` requests without a valid token.
+pub async fn require_token(State(auth): State<Arc<Auth>>, req: Request, next: Next) -> Response {
+    if auth.authorizes(req.headers(), req.uri().query()) {
+        return next.run(req).await;
+    }
+
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(ErrorResponse {
+            error: "Missing or invalid API token (see ~/.eval-kanban/token)".to_string(),
+        }),
+    )
+        .into_response()
+}
+
+/// Turns a valid `?token=` into a cookie, so the web UI opened from the
+/// printed URL keeps working without it.
+pub async fn remember_token(State(auth): State<Arc<Auth>>, req: Request, next: Next) -> Response {
+    let token = query_token(req.uri().query())
+        .filter(|t| auth.is_valid(t))
+        .map(str::to_string);
+
+    let mut response = next.run(req).await;
+
+    if let Some(token) = token {
+        let cookie = format!(
+            "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Strict",
+            TOKEN_COOKIE, token, COOKIE_MAX_AGE_SECS
+        );
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+
+    response
+}
+
+pub fn token_path() -> PathBuf {
+    eval_kanban_db::get_data_dir().join("token")
+}
+
+/// The stored token, generated on first use.
+pub fn load_or_create_token() -> std::io::Result<String> {
+    if let Some(token) = read_token_file() {
+        return Ok(token);
+    }
+
+    let token = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let path = token_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, &token)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(token)
+}
+
+/// The token for talking to a server: `EVAL_KANBAN_TOKEN`, or the stored one.
+pub fn client_token() -> Option<String> {
+    std::env::var(TOKEN_ENV)
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| t.trim().to_string())
+        .or_else(read_token_file)
+}
+
+fn read_token_file() -> Option<String> {
+    std::fs::read_to_string(token_path())
+        .ok()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+fn query_token(query: Option<&str>) -> Option<&str> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+}
+
+fn cookie_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(TOKEN_COOKIE)?.strip_prefix('='))
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_authorizes() {
+        let auth = Auth::new(Some("secret".to_string()), vec!["http://localhost:5173".to_string()]);
+
+        assert!(!auth.authorizes(&HeaderMap::new(), None));
+        assert!(auth.authorizes(&headers(&[(header::AUTHORIZATION, "Bearer secret")]), None));
+        assert!(!auth.authorizes(&headers(&[(header::AUTHORIZATION, "Bearer wrong")]), None));
+        assert!(auth.authorizes(&HeaderMap::new(), Some("last=3&token=secret")));
+        assert!(!auth.authorizes(&HeaderMap::new(), Some("token=secre")));
+
+        let cookie = (header::COOKIE, "theme=dark; eval_kanban_token=secret");
+        let host = (header::HOST, "127.0.0.1:9847");
+        assert!(auth.authorizes(&headers(std::slice::from_ref(&cookie)), None));
+        assert!(auth.authorizes(&headers(&[cookie.clone(), host.clone(), (header::ORIGIN, "http://127.0.0.1:9847")]), None));
+        assert!(auth.authorizes(&headers(&[cookie.clone(), host.clone(), (header::ORIGIN, "http://localhost:5173")]), None));
+        assert!(!auth.authorizes(&headers(&[cookie, host, (header::ORIGIN, "http://127.0.0.1:3000")]), None));
+
+        // A preview page, on the preview port, may load the preview with the
+        // cookie but not call the API
+        let preview_host = (header::HOST, "127.0.0.1:9848");
+        let preview_origin = (header::ORIGIN, "http://127.0.0.1:9848");
+        let cookie = (header::COOKIE, "eval_kanban_token=secret");
+        assert!(auth.authorizes(&headers(&[cookie.clone(), preview_host, preview_origin.clone()]), None));
+        assert!(!auth.authorizes(&headers(&[cookie.clone(), (header::HOST, "127.0.0.1:9847"), preview_origin.clone()]), None));
+        assert!(auth.authorizes(&headers(&[cookie, (header::HOST, "127.0.0.1:9847"), preview_origin]), Some("token=secret")));
+
+        let open = Auth::new(None, Vec::new());
+        assert!(open.authorizes(&HeaderMap::new(), None));
+    }
+}
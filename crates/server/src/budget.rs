@@ -0,0 +1,169 @@
+//! Budget limits for task runs.
+//!
+//! Limits can be set per task and per project, in USD and in tokens. They are
+//! checked against the accumulated usage of all runs every time the executor
+//! reports new usage.
+
+use eval_kanban_db::{ProjectSettings, RunUsage, Task, TaskRun};
+use eval_kanban_executor::Usage;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct BudgetLimits {
+    pub max_cost_usd: Option<f64>,
+    pub max_tokens: Option<i64>,
+}
+
+impl BudgetLimits {
+    pub fn for_task(task: &Task) -> Self {
+        Self {
+            max_cost_usd: task.budget_usd,
+            max_tokens: task.budget_tokens,
+        }
+    }
+
+    pub fn for_project(settings: &ProjectSettings) -> Self {
+        Self {
+            max_cost_usd: settings.budget_usd,
+            max_tokens: settings.budget_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetScope {
+    Task,
+    Project,
+}
+
+impl BudgetScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BudgetScope::Task => "task",
+            BudgetScope::Project => "project",
+        }
+    }
+}
+
+/// A limit that has been reached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetBreach {
+    pub scope: BudgetScope,
+    pub message: String,
+}
+
+/// Check accumulated usage against limits. Reaching a limit counts as a breach.
+pub fn check_budget(scope: BudgetScope, limits: &BudgetLimits, spent: &RunUsage) -> Option<BudgetBreach> {
+    if let Some(max) = limits.max_cost_usd {
+        if spent.cost_usd >= max {
+            return Some(BudgetBreach {
+                scope,
+                message: format!(
+                    "{} budget exceeded: spent ${:.2} of ${:.2}",
+                    capitalize(scope.as_str()), spent.cost_usd, max
+                ),
+            });
+        }
+    }
+
+    if let Some(max) = limits.max_tokens {
+        if spent.total_tokens() >= max {
+            return Some(BudgetBreach {
+                scope,
+                message: format!(
+                    "{} budget exceeded: used {} of {} tokens",
+                    capitalize(scope.as_str()), spent.total_tokens(), max
+                ),
+            });
+        }
+    }
+
+    None
+}
+
+/// Check a task's accumulated spend, and then its project's, against their limits.
+pub async fn check_task_spend(db: &Pool<Sqlite>, task: &Task) -> Result<Option<BudgetBreach>, sqlx::Error> {
+    let task_spent = TaskRun::usage_for_task(db, &task.id, None).await?;
+    if let Some(breach) = check_budget(BudgetScope::Task, &BudgetLimits::for_task(task), &task_spent) {
+        return Ok(Some(breach));
+    }
+
+    let Some(project_path) = task.project_path.as_deref() else {
+        return Ok(None);
+    };
+    let settings = ProjectSettings::get(db, project_path).await?;
+    let project_spent = TaskRun::usage_for_project(db, project_path, None).await?;
+
+    Ok(check_budget(BudgetScope::Project, &BudgetLimits::for_project(&settings), &project_spent))
+}
+
+pub fn to_run_usage(usage: &Usage) -> RunUsage {
+    RunUsage {
+        input_tokens: usage.input_tokens as i64,
+        output_tokens: usage.output_tokens as i64,
+        cache_creation_tokens: usage.cache_creation_input_tokens as i64,
+        cache_read_tokens: usage.cache_read_input_tokens as i64,
+        cost_usd: usage.cost_usd,
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spent(tokens: i64, cost_usd: f64) -> RunUsage {
+        RunUsage {
+            input_tokens: tokens,
+            cost_usd,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_no_limits_never_breach() {
+        let limits = BudgetLimits::default();
+        assert!(check_budget(BudgetScope::Task, &limits, &spent(1_000_000, 100.0)).is_none());
+    }
+
+    #[test]
+    fn test_cost_limit() {
+        let limits = BudgetLimits { max_cost_usd: Some(1.0), max_tokens: None };
+        assert!(check_budget(BudgetScope::Task, &limits, &spent(0, 0.99)).is_none());
+
+        let breach = check_budget(BudgetScope::Task, &limits, &spent(0, 1.0)).unwrap();
+        assert_eq!(breach.scope, BudgetScope::Task);
+        assert_eq!(breach.message, "Task budget exceeded: spent $1.00 of $1.00");
+    }
+
+    #[test]
+    fn test_token_limit() {
+        let limits = BudgetLimits { max_cost_usd: None, max_tokens: Some(500) };
+        let breach = check_budget(BudgetScope::Project, &limits, &spent(600, 0.0)).unwrap();
+        assert_eq!(breach.scope, BudgetScope::Project);
+        assert!(breach.message.starts_with("Project budget exceeded"));
+    }
+
+    #[test]
+    fn test_to_run_usage() {
+        let usage = Usage {
+            input_tokens: 1,
+            output_tokens: 2,
+            cache_creation_input_tokens: 3,
+            cache_read_input_tokens: 4,
+            cost_usd: 0.5,
+        };
+        let run_usage = to_run_usage(&usage);
+        assert_eq!(run_usage.total_tokens(), 10);
+        assert_eq!(run_usage.cost_usd, 0.5);
+    }
+}
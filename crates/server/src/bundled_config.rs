@@ -67,6 +67,7 @@ pub fn get_file(path: &str) -> Option<String> {
 }
 
 /// List all bundled files.
+#[allow(dead_code)]
 pub fn list_files() -> Vec<String> {
     BundledClaudeConfig::iter()
         .map(|f| f.to_string())
@@ -0,0 +1,346 @@
+//! Bridges between the chat and the board.
+//!
+//! Chat messages can reference tasks as `#<id prefix>` (e.g. `#1a2b3c4d`);
+//! the referenced task's description, latest diff and recent log output are
+//! added to the chat prompt. In the other direction, a chat message can be
+//! turned into tasks: a Markdown list becomes one task per item, anything
+//! else a single task.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use eval_kanban_db::{ExecutionLog, Task};
+use eval_kanban_executor::stream_json::{self, ContentBlock};
+use eval_kanban_worktree::get_worktree_diff;
+
+use crate::state::AppState;
+
+/// Shortest id prefix accepted as a task reference
+const MIN_REFERENCE_LEN: usize = 6;
+/// Most tasks pulled into a single prompt
+const MAX_REFERENCES: usize = 5;
+/// Limits for each referenced task's context
+const MAX_DIFF_CHARS: usize = 6000;
+const MAX_LOG_LINES: i64 = 200;
+const MAX_LOG_CHARS: usize = 3000;
+/// Longest task title taken from a message
+const MAX_TITLE_CHARS: usize = 80;
+
+/// A task suggested by a chat message, before it is created.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposedTask {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Task id prefixes referenced as `#<prefix>`, in order of appearance.
+pub fn task_references(content: &str) -> Vec<String> {
+    let mut references: Vec<String> = Vec::new();
+
+    for (index, _) in content.match_indices('#') {
+        // `#` must start a word, so `issue#abc123` is not a reference
+        let preceded_by_word = content[..index]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        if preceded_by_word {
+            continue;
+        }
+
+        let reference: String = content[index + 1..]
+            .chars()
+            .take_while(|c| c.is_ascii_hexdigit() || *c == '-')
+            .collect::<String>()
+            .to_lowercase();
+        let ends_word = content[index + 1 + reference.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| !c.is_alphanumeric() && c != '_');
+
+        if reference.len() >= MIN_REFERENCE_LEN && ends_word && !references.contains(&reference) {
+            references.push(reference);
+        }
+        if references.len() == MAX_REFERENCES {
+            break;
+        }
+    }
+
+    references
+}
+
+/// Prompt section describing the tasks referenced in a message, if any resolve.
+pub async fn referenced_tasks_section(state: &AppState, project_path: &str, content: &str) -> Option<String> {
+    let mut sections = Vec::new();
+
+    for reference in task_references(content) {
+        match Task::find_by_id_prefix(&state.db, project_path, &reference).await {
+            Ok(Some(task)) => sections.push(task_section(state, &reference, &task).await),
+            Ok(None) => tracing::debug!("[Chat] No unique task for reference #{}", reference),
+            Err(e) => tracing::warn!("[Chat] Failed to resolve task reference #{}: {}", reference, e),
+        }
+    }
+
+    if sections.is_empty() {
+        return None;
+    }
+
+    Some(format!("## Referenced tasks\n\n{}", sections.join("\n\n")))
+}
+
+async fn task_section(state: &AppState, reference: &str, task: &Task) -> String {
+    let mut section = format!("### #{} {} ({})\n\n", reference, task.title, task.status.as_str());
+
+    match task.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        Some(description) => section.push_str(description.trim()),
+        None => section.push_str("(no description)"),
+    }
+
+    if let Some(path) = &task.worktree_path {
+        if let Some(diff) = diff_excerpt(PathBuf::from(path)).await {
+            section.push_str("\n\n#### Latest diff\n\n```diff\n");
+            section.push_str(&diff);
+            section.push_str("\n```");
+        }
+    }
+
+    match ExecutionLog::find_latest_by_task(&state.db, &task.id, MAX_LOG_LINES).await {
+        Ok(logs) => {
+            let excerpt = log_excerpt(&logs);
+            if !excerpt.is_empty() {
+                section.push_str("\n\n#### Recent log output\n\n");
+                section.push_str(&excerpt);
+            }
+        }
+        Err(e) => tracing::warn!("[Chat] Failed to load logs for task {}: {}", task.id, e),
+    }
+
+    section
+}
+
+async fn diff_excerpt(worktree_path: PathBuf) -> Option<String> {
+    if !worktree_path.exists() {
+        return None;
+    }
+
+    let diff = tokio::task::spawn_blocking(move || get_worktree_diff(&worktree_path)).await;
+    let Ok(Ok(diff)) = diff else {
+        return None;
+    };
+    if diff.files.is_empty() {
+        return None;
+    }
+
+    let patch: String = diff.files.iter()
+        .map(|f| f.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(truncate(&patch, MAX_DIFF_CHARS, false))
+}
+
+/// What Claude said, its final result and any errors, from the most recent
+/// log lines. Tool calls and results are left out.
+pub fn log_excerpt(logs: &[ExecutionLog]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+
+    for log in logs {
+        if log.stream == "stderr" {
+            lines.push(format!("[stderr] {}", log.content));
+            continue;
+        }
+
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(&log.content) else {
+            // Plain text output
+            lines.push(log.content.clone());
+            continue;
+        };
+        if stream_json::is_result(&log.content) {
+            if let Some(result) = json.get("result").and_then(|r| r.as_str()) {
+                lines.push(format!("[result] {}", result));
+            }
+        }
+        for block in stream_json::content_blocks(&log.content) {
+            if let ContentBlock::Text(text) = block {
+                lines.push(text);
+            }
+        }
+    }
+
+    truncate(lines.join("\n").trim(), MAX_LOG_CHARS, true)
+}
+
+/// Cut text to `max_chars`, keeping the start or (for logs) the end.
+fn truncate(text: &str, max_chars: usize, keep_end: bool) -> String {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.to_string();
+    }
+
+    if keep_end {
+        let tail: String = text.chars().skip(count - max_chars).collect();
+        format!("[...]\n{}", tail)
+    } else {
+        let head: String = text.chars().take(max_chars).collect();
+        format!("{}\n[...]", head)
+    }
+}
+
+/// Tasks suggested by a message: one per top-level Markdown list item, or the
+/// whole message as a single task.
+pub fn proposed_tasks(content: &str) -> Vec<ProposedTask> {
+    let mut tasks: Vec<ProposedTask> = Vec::new();
+    let mut current: Option<(String, Vec<String>)> = None;
+
+    for line in content.lines() {
+        if let Some(item) = list_item(line) {
+            if let Some(task) = current.take() {
+                tasks.push(item_to_task(task));
+            }
+            current = Some((item.to_string(), Vec::new()));
+        } else if let Some((_, details)) = current.as_mut() {
+            // Indented lines belong to the item above; anything else ends the list
+            if line.starts_with(' ') || line.starts_with('\t') {
+                details.push(line.trim().to_string());
+            } else if !line.trim().is_empty() {
+                if let Some(task) = current.take() {
+                    tasks.push(item_to_task(task));
+                }
+            }
+        }
+    }
+    if let Some(task) = current.take() {
+        tasks.push(item_to_task(task));
+    }
+
+    tasks.retain(|t| !t.title.is_empty());
+    if !tasks.is_empty() {
+        return tasks;
+    }
+
+    let content = content.trim();
+    let first_line = content.lines().next().unwrap_or_default();
+    let title = clean_title(first_line);
+    if title.is_empty() {
+        return Vec::new();
+    }
+
+    vec![ProposedTask {
+        title,
+        description: (content != first_line).then(|| content.to_string()),
+    }]
+}
+
+/// The text of a top-level `- `, `* ` or `1. ` list item.
+fn list_item(line: &str) -> Option<&str> {
+    if let Some(rest) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+        return Some(rest.trim());
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &line[digits..];
+        if let Some(rest) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some(rest.trim());
+        }
+    }
+
+    None
+}
+
+/// `**Title** - details` or `Title: details` become a title and description.
+fn item_to_task((item, details): (String, Vec<String>)) -> ProposedTask {
+    let item = item.replace("[ ] ", "");
+    let (title, rest) = match item.strip_prefix("**").and_then(|s| s.split_once("**")) {
+        Some((title, rest)) => (title.to_string(), rest.trim_start_matches([':', '-', ' ']).to_string()),
+        None => match item.split_once(": ") {
+            Some((title, rest)) if title.chars().count() <= MAX_TITLE_CHARS => (title.to_string(), rest.to_string()),
+            _ => (item.clone(), String::new()),
+        },
+    };
+
+    let mut description: Vec<String> = Vec::new();
+    if !rest.trim().is_empty() {
+        description.push(rest.trim().to_string());
+    }
+    description.extend(details);
+
+    ProposedTask {
+        title: clean_title(&title),
+        description: (!description.is_empty()).then(|| description.join("\n")),
+    }
+}
+
+fn clean_title(text: &str) -> String {
+    let text = text.trim().trim_start_matches('#').replace("**", "").replace('`', "");
+    let text = text.trim().trim_end_matches(['.', ':']);
+    text.chars().take(MAX_TITLE_CHARS).collect::<String>().trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_task_references() {
+        assert_eq!(
+            task_references("Why did #1A2B3C4D fail? See #1a2b3c4d and #deadbeef-12."),
+            vec!["1a2b3c4d".to_string(), "deadbeef-12".to_string()]
+        );
+        // Too short, not hex, or part of a word
+        assert!(task_references("#abc #zzzzzzzz issue#abcdef12 #abcdef12x").is_empty());
+    }
+
+    #[test]
+    fn test_proposed_tasks_from_list() {
+        let content = "Sounds good. Here's the split:\n\n1. **Add schema** - create the users table\n2. API routes: add login and logout\n   Use the session middleware\n3. `Login form`\n\nLet me know!";
+        assert_eq!(proposed_tasks(content), vec![
+            ProposedTask { title: "Add schema".to_string(), description: Some("create the users table".to_string()) },
+            ProposedTask {
+                title: "API routes".to_string(),
+                description: Some("add login and logout\nUse the session middleware".to_string()),
+            },
+            ProposedTask { title: "Login form".to_string(), description: None },
+        ]);
+    }
+
+    #[test]
+    fn test_proposed_task_from_plain_message() {
+        assert_eq!(proposed_tasks("Fix the flaky login test.\n\nIt fails on CI only."), vec![ProposedTask {
+            title: "Fix the flaky login test".to_string(),
+            description: Some("Fix the flaky login test.\n\nIt fails on CI only.".to_string()),
+        }]);
+        assert!(proposed_tasks("   ").is_empty());
+    }
+
+    fn log(stream: &str, content: &str) -> ExecutionLog {
+        ExecutionLog {
+            id: 0,
+            task_id: "t1".to_string(),
+            run_id: None,
+            stream: stream.to_string(),
+            content: content.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_log_excerpt() {
+        let logs = vec![
+            log("stdout", r#"{"type":"system","subtype":"init"}"#),
+            log("stdout", r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Running tests"},{"type":"tool_use","id":"t","name":"Bash","input":{}}]}}"#),
+            log("stderr", "warning: unused"),
+            log("stdout", "plain output"),
+            log("stdout", r#"{"type":"result","subtype":"success","result":"All green"}"#),
+        ];
+        assert_eq!(log_excerpt(&logs), "Running tests\n[stderr] warning: unused\nplain output\n[result] All green");
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("abcdef", 10, false), "abcdef");
+        assert_eq!(truncate("abcdef", 3, false), "abc\n[...]");
+        assert_eq!(truncate("abcdef", 3, true), "[...]\ndef");
+    }
+}
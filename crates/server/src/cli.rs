@@ -0,0 +1,301 @@
+//! Command-line arguments.
+//!
+//! Without a subcommand the binary serves the web UI, as before. The `task`
+//! subcommands drive the board from a terminal, see `task_commands`.
+
+use std::net::IpAddr;
+
+pub const USAGE: &str = "\
+Usage: eval-kanban [serve] [--host <addr>] [--port <port>] [--preview-port <port>] [--no-open] [--no-auth]
+       eval-kanban [--server <url>] task <command>
+
+Commands:
+  serve                        Start the server and web UI (default)
+      --host <addr>            Address to listen on (default: 127.0.0.1)
+      --port <port>            Listen on this port instead of the first free one from
+                               server.port in the settings (9847)
+      --preview-port <port>    Serve task previews on this port instead of the first
+                               free one after the board's
+      --no-open                Don't open a browser
+      --no-auth                Don't require the API token from ~/.eval-kanban/token
+      --allow-origin <origin>  Also accept browser requests from this origin (repeatable)
+  task add <title> [-d <description>]
+  task list
+  task start <id> [--feedback <text>]
+  task logs <id> [--follow] [--raw] [-n <lines>]
+  task diff <id> [--stat]
+  task merge <id>
+
+Task ids can be shortened to any unique prefix (at least 4 characters).
+Task commands use the server running for the current directory, or the
+database directly if there is none; `start` and `merge` need the server.
+They authenticate with ~/.eval-kanban/token, or $EVAL_KANBAN_TOKEN.
+
+Options:
+  --server <url>               Server to use (default: http://127.0.0.1:9847)
+  -h, --help                   Show this help";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Serve(ServeOptions),
+    Task {
+        /// Explicit server URL; the server is then required
+        server: Option<String>,
+        command: TaskCommand,
+    },
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ServeOptions {
+    pub host: Option<IpAddr>,
+    pub port: Option<u16>,
+    /// Port previews are served on, a separate origin from the board
+    pub preview_port: Option<u16>,
+    pub no_open: bool,
+    pub no_auth: bool,
+    /// Extra origins allowed to call the API from a browser
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskCommand {
+    Add {
+        title: String,
+        description: Option<String>,
+    },
+    List,
+    Start {
+        id: String,
+        feedback: Option<String>,
+    },
+    Logs {
+        id: String,
+        follow: bool,
+        raw: bool,
+        lines: i64,
+    },
+    Diff {
+        id: String,
+        stat: bool,
+    },
+    Merge {
+        id: String,
+    },
+}
+
+/// Lines shown by `task logs` without `-n`
+const DEFAULT_LOG_LINES: i64 = 200;
+
+/// Parse the arguments after the program name.
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let mut args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    if args.first() == Some(&"help") || args.iter().any(|a| *a == "-h" || *a == "--help") {
+        return Ok(Command::Help);
+    }
+
+    let server = take_value(&mut args, "--server")?;
+
+    match args.first().copied() {
+        None | Some("serve") => {
+            if !args.is_empty() {
+                args.remove(0);
+            }
+            if server.is_some() {
+                return Err("--server is only used with task commands".to_string());
+            }
+            parse_serve(args).map(Command::Serve)
+        }
+        // `eval-kanban --port 8080` without `serve`
+        Some(flag) if flag.starts_with('-') => parse_serve(args).map(Command::Serve),
+        Some("task") => {
+            args.remove(0);
+            let command = parse_task(args)?;
+            Ok(Command::Task { server, command })
+        }
+        Some(other) => Err(format!("Unknown command '{}'", other)),
+    }
+}
+
+fn parse_serve(mut args: Vec<&str>) -> Result<ServeOptions, String> {
+    let host = take_value(&mut args, "--host")?
+        .map(|h| h.parse::<IpAddr>().map_err(|_| format!("Invalid host '{}' (expected an IP address)", h)))
+        .transpose()?;
+    let port = take_value(&mut args, "--port")?
+        .map(|p| p.parse::<u16>().map_err(|_| format!("Invalid port '{}'", p)))
+        .transpose()?;
+    let preview_port = take_value(&mut args, "--preview-port")?
+        .map(|p| p.parse::<u16>().map_err(|_| format!("Invalid port '{}'", p)))
+        .transpose()?;
+    let no_open = take_flag(&mut args, "--no-open");
+    let no_auth = take_flag(&mut args, "--no-auth");
+
+    let mut allowed_origins = Vec::new();
+    while let Some(origin) = take_value(&mut args, "--allow-origin")? {
+        allowed_origins.push(origin.trim_end_matches('/').to_string());
+    }
+    no_extra_args(&args)?;
+
+    Ok(ServeOptions { host, port, preview_port, no_open, no_auth, allowed_origins })
+}
+
+fn parse_task(mut args: Vec<&str>) -> Result<TaskCommand, String> {
+    if args.is_empty() {
+        return Err("Missing task command".to_string());
+    }
+    let name = args.remove(0);
+
+    let command = match name {
+        "add" => {
+            let description = take_value(&mut args, "-d")?.or(take_value(&mut args, "--description")?);
+            let title = args.join(" ");
+            args.clear();
+            if title.trim().is_empty() {
+                return Err("Missing task title".to_string());
+            }
+            TaskCommand::Add { title, description }
+        }
+        "list" | "ls" => TaskCommand::List,
+        "start" => {
+            let feedback = take_value(&mut args, "--feedback")?;
+            TaskCommand::Start { id: take_id(&mut args)?, feedback }
+        }
+        "logs" => {
+            let follow = take_flag(&mut args, "--follow") | take_flag(&mut args, "-f");
+            let raw = take_flag(&mut args, "--raw");
+            let lines = take_value(&mut args, "-n")?
+                .map(|n| n.parse::<i64>().ok().filter(|n| *n > 0).ok_or(format!("Invalid line count '{}'", n)))
+                .transpose()?
+                .unwrap_or(DEFAULT_LOG_LINES);
+            TaskCommand::Logs { id: take_id(&mut args)?, follow, raw, lines }
+        }
+        "diff" => {
+            let stat = take_flag(&mut args, "--stat");
+            TaskCommand::Diff { id: take_id(&mut args)?, stat }
+        }
+        "merge" => TaskCommand::Merge { id: take_id(&mut args)? },
+        other => return Err(format!("Unknown task command '{}'", other)),
+    };
+
+    no_extra_args(&args)?;
+    Ok(command)
+}
+
+fn take_flag(args: &mut Vec<&str>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|a| *a != flag);
+    args.len() != before
+}
+
+/// Remove the first `--name value` (or `--name=value`) and return the value.
+fn take_value(args: &mut Vec<&str>, name: &str) -> Result<Option<String>, String> {
+    let prefix = format!("{}=", name);
+    let Some(pos) = args.iter().position(|a| *a == name || a.starts_with(&prefix)) else {
+        return Ok(None);
+    };
+
+    if let Some(value) = args[pos].strip_prefix(&prefix) {
+        let value = value.to_string();
+        args.remove(pos);
+        return Ok(Some(value));
+    }
+
+    if pos + 1 >= args.len() {
+        return Err(format!("{} needs a value", name));
+    }
+    let value = args.remove(pos + 1).to_string();
+    args.remove(pos);
+    Ok(Some(value))
+}
+
+fn take_id(args: &mut Vec<&str>) -> Result<String, String> {
+    if args.is_empty() || args[0].starts_with('-') {
+        return Err("Missing task id".to_string());
+    }
+    Ok(args.remove(0).to_string())
+}
+
+fn no_extra_args(args: &[&str]) -> Result<(), String> {
+    match args.first() {
+        Some(arg) => Err(format!("Unexpected argument '{}'", arg)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(line: &str) -> Result<Command, String> {
+        let args: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+        parse(&args)
+    }
+
+    fn task(line: &str) -> TaskCommand {
+        match parse_str(line).unwrap() {
+            Command::Task { command, .. } => command,
+            other => panic!("expected a task command, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_serve() {
+        assert_eq!(parse_str("").unwrap(), Command::Serve(ServeOptions::default()));
+        assert_eq!(
+            parse_str("serve --no-open --port 8080").unwrap(),
+            Command::Serve(ServeOptions { port: Some(8080), no_open: true, ..Default::default() })
+        );
+        assert_eq!(
+            parse_str("--port=9000").unwrap(),
+            Command::Serve(ServeOptions { port: Some(9000), ..Default::default() })
+        );
+        assert_eq!(
+            parse_str("--port 9000 --preview-port 9100").unwrap(),
+            Command::Serve(ServeOptions { port: Some(9000), preview_port: Some(9100), ..Default::default() })
+        );
+        assert_eq!(
+            parse_str("serve --host 0.0.0.0 --no-auth --allow-origin http://a:1/ --allow-origin=http://b:2").unwrap(),
+            Command::Serve(ServeOptions {
+                host: Some("0.0.0.0".parse().unwrap()),
+                no_auth: true,
+                allowed_origins: vec!["http://a:1".to_string(), "http://b:2".to_string()],
+                ..Default::default()
+            })
+        );
+        assert!(parse_str("serve --host localhost").is_err());
+        assert!(parse_str("serve --port nope").is_err());
+        assert!(parse_str("serve --port").is_err());
+        assert_eq!(parse_str("task list --help").unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn test_parse_task_commands() {
+        assert_eq!(task("task add Fix the login -d Broken"), TaskCommand::Add {
+            title: "Fix the login".to_string(),
+            description: Some("Broken".to_string()),
+        });
+        assert_eq!(task("task ls"), TaskCommand::List);
+        assert_eq!(task("task logs 1a2b -f -n 50"), TaskCommand::Logs {
+            id: "1a2b".to_string(),
+            follow: true,
+            raw: false,
+            lines: 50,
+        });
+        assert_eq!(task("task diff 1a2b --stat"), TaskCommand::Diff { id: "1a2b".to_string(), stat: true });
+
+        match parse_str("--server http://box:9000 task merge 1a2b").unwrap() {
+            Command::Task { server, command } => {
+                assert_eq!(server.as_deref(), Some("http://box:9000"));
+                assert_eq!(command, TaskCommand::Merge { id: "1a2b".to_string() });
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert!(parse_str("task").is_err());
+        assert!(parse_str("task start").is_err());
+        assert!(parse_str("task merge 1a2b extra").is_err());
+        assert!(parse_str("task logs 1a2b -n 0").is_err());
+        assert!(parse_str("frobnicate").is_err());
+    }
+}
@@ -0,0 +1,293 @@
+//! Eval suites: task prompts replayed against a pinned commit, each scored by
+//! the suite's command, so runs with different prompt templates, models or
+//! CLI versions can be compared.
+
+use serde::Serialize;
+
+use eval_kanban_db::{EvalCase, EvalResult, EvalResultStatus, EvalRun, EvalSuite, ProjectSettings, PromptTemplate, RunUsage};
+
+use crate::prompt::{self, PromptContext};
+
+/// The prompt a case is run with: the template (or the default prompt) with
+/// the case's name as title and its prompt as description, wrapped in the
+/// project's prefix and suffix.
+pub fn case_prompt(
+    suite: &EvalSuite,
+    case: &EvalCase,
+    template: Option<&PromptTemplate>,
+    settings: &ProjectSettings,
+) -> String {
+    let ctx = PromptContext {
+        title: case.name.clone(),
+        description: case.prompt.clone(),
+        base_branch: suite.base_commit.clone(),
+        ..Default::default()
+    };
+
+    let body = match template {
+        Some(template) => prompt::render_template(&template.body, &ctx),
+        None => prompt::default_body(&ctx),
+    };
+
+    prompt::compose(settings.prompt_prefix.as_deref(), &body, settings.prompt_suffix.as_deref())
+}
+
+/// The last line of the score command's output if it is a number, otherwise
+/// 1 for a pass and 0 for a failure.
+pub fn parse_score(output: &str, passed: bool) -> f64 {
+    output
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .and_then(|line| line.parse::<f64>().ok())
+        .filter(|score| score.is_finite())
+        .unwrap_or(if passed { 1.0 } else { 0.0 })
+}
+
+/// Totals of one run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunSummary {
+    pub cases: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub errors: usize,
+    pub skipped: usize,
+    /// Passed out of the cases that were run; errors count as not passed
+    pub pass_rate: Option<f64>,
+    pub mean_score: Option<f64>,
+    pub usage: RunUsage,
+    pub duration_ms: i64,
+}
+
+impl RunSummary {
+    pub fn from_results(results: &[EvalResult]) -> Self {
+        let count = |status| results.iter().filter(|r| r.status == status).count();
+        let passed = count(EvalResultStatus::Passed);
+        let failed = count(EvalResultStatus::Failed);
+        let errors = count(EvalResultStatus::Error);
+
+        let scores: Vec<f64> = results.iter().filter_map(|r| r.score).collect();
+        let run = passed + failed + errors;
+
+        let mut usage = RunUsage::default();
+        for result in results {
+            usage += result.usage;
+        }
+
+        RunSummary {
+            cases: results.len(),
+            passed,
+            failed,
+            errors,
+            skipped: count(EvalResultStatus::Skipped),
+            pass_rate: (run > 0).then(|| passed as f64 / run as f64),
+            mean_score: (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64),
+            usage,
+            duration_ms: results.iter().filter_map(|r| r.duration_ms).sum(),
+        }
+    }
+}
+
+/// A run of a suite with its totals.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    #[serde(flatten)]
+    pub run: EvalRun,
+    pub summary: RunSummary,
+}
+
+/// How a case went in one run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaseOutcome {
+    pub status: EvalResultStatus,
+    pub score: Option<f64>,
+    pub cost_usd: f64,
+    pub duration_ms: Option<i64>,
+}
+
+/// A case of the suite across runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseReport {
+    pub name: String,
+    /// One entry per run, in the order of the report's runs; None if the
+    /// run has no result for the case
+    pub outcomes: Vec<Option<CaseOutcome>>,
+}
+
+/// Every run of a suite, oldest first, and each case across them.
+#[derive(Debug, Clone, Serialize)]
+pub struct SuiteReport {
+    pub suite: EvalSuite,
+    pub runs: Vec<RunReport>,
+    pub cases: Vec<CaseReport>,
+}
+
+impl SuiteReport {
+    pub fn new(suite: EvalSuite, runs: Vec<(EvalRun, Vec<EvalResult>)>) -> Self {
+        let cases = suite.cases.iter()
+            .map(|case| CaseReport {
+                name: case.name.clone(),
+                outcomes: runs.iter()
+                    .map(|(_, results)| {
+                        results.iter().find(|r| r.case_name == case.name).map(|r| CaseOutcome {
+                            status: r.status,
+                            score: r.score,
+                            cost_usd: r.usage.cost_usd,
+                            duration_ms: r.duration_ms,
+                        })
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let runs = runs.into_iter()
+            .map(|(run, results)| RunReport {
+                summary: RunSummary::from_results(&results),
+                run,
+            })
+            .collect();
+
+        SuiteReport { suite, runs, cases }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use eval_kanban_db::EvalRunStatus;
+
+    fn suite() -> EvalSuite {
+        EvalSuite {
+            id: "suite-1".to_string(),
+            project_path: "/project".to_string(),
+            name: "Smoke".to_string(),
+            base_commit: "abc123".to_string(),
+            score_command: "make test".to_string(),
+            score_timeout_secs: 600,
+            cases: vec![
+                EvalCase { name: "login".to_string(), prompt: "Add a login form".to_string() },
+                EvalCase { name: "logout".to_string(), prompt: "Add a logout button".to_string() },
+            ],
+            created_at: Utc::now(),
+        }
+    }
+
+    fn run(id: &str) -> EvalRun {
+        EvalRun {
+            id: id.to_string(),
+            suite_id: "suite-1".to_string(),
+            status: EvalRunStatus::Completed,
+            label: None,
+            prompt_template_id: None,
+            model: None,
+            cli_version: None,
+            app_version: "0.1.0".to_string(),
+            created_at: Utc::now(),
+            finished_at: None,
+        }
+    }
+
+    fn result(case_name: &str, status: EvalResultStatus, score: Option<f64>, cost_usd: f64) -> EvalResult {
+        EvalResult {
+            id: format!("result-{}", case_name),
+            eval_run_id: "run-1".to_string(),
+            position: 0,
+            case_name: case_name.to_string(),
+            status,
+            score,
+            exit_code: None,
+            usage: RunUsage { input_tokens: 10, cost_usd, ..Default::default() },
+            duration_ms: Some(1000),
+            error: None,
+            output: None,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    fn settings(prompt_prefix: Option<&str>) -> ProjectSettings {
+        ProjectSettings {
+            project_path: "/project".to_string(),
+            budget_usd: None,
+            budget_tokens: None,
+            prompt_prefix: prompt_prefix.map(str::to_string),
+            prompt_suffix: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_case_prompt() {
+        let suite = suite();
+        assert_eq!(
+            case_prompt(&suite, &suite.cases[0], None, &settings(Some("Be brief."))),
+            "Be brief.\n\nAdd a login form"
+        );
+
+        let template = PromptTemplate {
+            id: "t".to_string(),
+            project_path: "/project".to_string(),
+            name: "Titled".to_string(),
+            body: "{{title}}: {{description}} (from {{base_branch}})".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert_eq!(
+            case_prompt(&suite, &suite.cases[1], Some(&template), &settings(None)),
+            "logout: Add a logout button (from abc123)"
+        );
+    }
+
+    #[test]
+    fn test_parse_score() {
+        assert_eq!(parse_score("running tests\n0.75\n\n", true), 0.75);
+        assert_eq!(parse_score("3 of 4 passed", true), 1.0);
+        assert_eq!(parse_score("", false), 0.0);
+        assert_eq!(parse_score("NaN", false), 0.0);
+    }
+
+    #[test]
+    fn test_run_summary() {
+        let results = [
+            result("a", EvalResultStatus::Passed, Some(1.0), 0.5),
+            result("b", EvalResultStatus::Failed, Some(0.0), 0.25),
+            result("c", EvalResultStatus::Error, None, 0.0),
+            result("d", EvalResultStatus::Skipped, None, 0.0),
+        ];
+        let summary = RunSummary::from_results(&results);
+        assert_eq!((summary.passed, summary.failed, summary.errors, summary.skipped), (1, 1, 1, 1));
+        assert_eq!(summary.pass_rate, Some(1.0 / 3.0));
+        assert_eq!(summary.mean_score, Some(0.5));
+        assert_eq!(summary.usage.cost_usd, 0.75);
+        assert_eq!(summary.usage.input_tokens, 40);
+        assert_eq!(summary.duration_ms, 4000);
+
+        let empty = RunSummary::from_results(&[]);
+        assert_eq!((empty.pass_rate, empty.mean_score), (None, None));
+    }
+
+    #[test]
+    fn test_suite_report() {
+        let report = SuiteReport::new(
+            suite(),
+            vec![
+                (run("run-1"), vec![result("login", EvalResultStatus::Passed, Some(1.0), 0.5)]),
+                (run("run-2"), vec![
+                    result("login", EvalResultStatus::Failed, Some(0.0), 0.25),
+                    result("logout", EvalResultStatus::Passed, Some(1.0), 0.25),
+                ]),
+            ],
+        );
+
+        assert_eq!(report.runs.len(), 2);
+        assert_eq!(report.runs[1].summary.passed, 1);
+        assert_eq!(report.cases[0].name, "login");
+        assert_eq!(
+            report.cases[0].outcomes.iter().map(|o| o.as_ref().map(|o| o.status)).collect::<Vec<_>>(),
+            vec![Some(EvalResultStatus::Passed), Some(EvalResultStatus::Failed)]
+        );
+        assert_eq!(report.cases[1].outcomes[0], None);
+    }
+}
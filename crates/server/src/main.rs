@@ -1,28 +1,68 @@
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use axum::{Router, routing::get};
+use axum::{Router, ServiceExt, middleware, routing::get};
 use sqlx::{Pool, Sqlite};
-use tower_http::cors::{Any, CorsLayer};
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use tower::Layer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::services::ServeDir;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use eval_kanban_db::Task;
+use eval_kanban_db::{EvalRun, Project, Task, TaskAttempt};
 
+mod api_client;
+mod attachments;
+mod auth;
+mod budget;
+mod chat_context;
+mod cli;
+mod eval;
+mod prompt;
 mod routes;
+mod run_events;
 mod state;
 mod plan_session;
+mod preview;
+mod project;
+mod settings;
 mod bundled_config;
 mod config_setup;
+mod task_commands;
+mod verify;
 
-use routes::{chat_router, tasks_router, ws_handler, review_router, preview_router, plan_router, server_router};
+use routes::{attachments_router, attempts_router, budget_router, chat_router, evals_router, events_handler, tasks_router, ws_handler, review_router, preview_router, preview_proxy_router, plan_router, projects_router, prompts_router, server_router, settings_router, task_plan_router};
+use cli::{Command, ServeOptions};
 use state::AppState;
 
-const DEFAULT_PORT: u16 = 9847;
+/// Tries for an explicit `--port` that is in use, half a second apart
+const BIND_ATTEMPTS: u32 = 8;
 
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::parse(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match command {
+        Command::Help => println!("{}", cli::USAGE),
+        Command::Task { server, command } => {
+            if let Err(e) = task_commands::run(server, command).await {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Command::Serve(options) => serve(options).await,
+    }
+}
+
+async fn serve(options: ServeOptions) {
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
@@ -63,45 +103,166 @@ async fn main() {
         }
     };
 
-    let state = AppState::new(db.clone(), working_dir);
+    let default_project = match Project::open(&db, &working_dir.to_string_lossy(), None).await {
+        Ok(project) => project,
+        Err(e) => {
+            tracing::error!("Failed to register project: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Eval runs don't survive a restart
+    match EvalRun::fail_interrupted(&db).await {
+        Ok(0) => {}
+        Ok(count) => tracing::warn!("Marked {} eval runs interrupted by the last shutdown as failed", count),
+        Err(e) => tracing::warn!("Failed to mark interrupted eval runs: {}", e),
+    }
+
+    let state = AppState::new(db.clone(), default_project);
 
     // Cleanup orphan worktrees on startup
     tokio::spawn(cleanup_orphan_worktrees(db.clone(), state.clone()));
+    tokio::spawn(routes::preview::reap_idle_previews(state.clone()));
 
+    let token = if options.no_auth {
+        None
+    } else {
+        match auth::load_or_create_token() {
+            Ok(token) => Some(token),
+            Err(e) => {
+                tracing::error!("Failed to create API token at {}: {}", auth::token_path().display(), e);
+                std::process::exit(1);
+            }
+        }
+    };
+    let auth = auth::Auth::new(token.clone(), options.allowed_origins.clone());
+
+    // The web UI is served from our own origin and needs no CORS; other
+    // origins must be allowed explicitly
+    let allowed_origins: Vec<HeaderValue> = options.allowed_origins
+        .iter()
+        .filter_map(|o| HeaderValue::from_str(o).ok())
+        .collect();
     let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_origin(AllowOrigin::list(allowed_origins))
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, HeaderName::from_static("last-event-id")])
+        .allow_credentials(true);
 
     let api_routes = Router::new()
         .nest("/tasks", tasks_router())
         .nest("/plan", plan_router())
         .nest("/chat", chat_router())
         .nest("/server", server_router())
+        .nest("/projects", projects_router())
         .merge(review_router())
+        .merge(settings_router())
         .merge(preview_router())
-        .route("/ws", get(ws_handler));
+        .merge(budget_router())
+        .merge(prompts_router())
+        .merge(attachments_router())
+        .merge(attempts_router())
+        .merge(evals_router())
+        .merge(task_plan_router())
+        .route("/ws", get(ws_handler))
+        .route("/events", get(events_handler))
+        .layer(middleware::from_fn_with_state(auth.clone(), auth::require_token));
 
     let frontend_dir = get_frontend_dir();
     tracing::info!("Frontend directory: {}", frontend_dir.display());
 
+    let default_port = state.settings.get(&state.default_project.path).server.port;
+    let shutdown_state = state.clone();
+
+    // Previews get their own port, so their pages are another origin than
+    // the board's and can't use its API with the browser's cookie
+    let preview_app = preview_proxy_router()
+        .layer(middleware::from_fn_with_state(auth.clone(), auth::require_token))
+        .with_state(state.clone());
+
     let app = Router::new()
         .nest("/api", api_routes)
         .fallback_service(ServeDir::new(&frontend_dir).append_index_html_on_directories(true))
+        .layer(middleware::from_fn_with_state(auth, auth::remember_token))
         .layer(cors)
         .with_state(state);
 
-    let port = find_available_port(DEFAULT_PORT).await;
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let host = options.host.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    let port = match options.port {
+        Some(port) => port,
+        None => find_available_port(host, default_port).await,
+    };
+    let addr = SocketAddr::new(host, port);
+
+    if !host.is_loopback() && token.is_none() {
+        tracing::warn!("Listening on {} without authentication: anyone on the network can run agents and merge branches", host);
+    }
+
+    let listener = match bind(addr, options.port.is_some()).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to listen on {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    };
+
+    let preview_port = match options.preview_port {
+        Some(port) => port,
+        None => find_available_port(host, port.saturating_add(1)).await,
+    };
+    let preview_addr = SocketAddr::new(host, preview_port);
+    let preview_listener = match bind(preview_addr, options.preview_port.is_some()).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to listen for previews on {}: {}", preview_addr, e);
+            std::process::exit(1);
+        }
+    };
+    let _ = shutdown_state.preview_proxy_port.set(preview_port);
+
+    // Browsers can't open the unspecified address
+    let browser_host = if host.is_unspecified() { IpAddr::V4(Ipv4Addr::LOCALHOST) } else { host };
+    let url = match &token {
+        Some(token) => format!("http://{}/?token={}", SocketAddr::new(browser_host, port), token),
+        None => format!("http://{}", SocketAddr::new(browser_host, port)),
+    };
 
     tracing::info!("Server starting at http://{}", addr);
+    tracing::info!("Previews are served at http://{}", preview_addr);
+    tracing::info!("Open {}", url);
 
-    if open_browser(&format!("http://{}", addr)).is_err() {
+    if !options.no_open && open_browser(&url).is_err() {
         tracing::warn!("Failed to open browser automatically");
     }
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // `/api/projects/:id/...` is rewritten before routing
+    let app = middleware::map_request(project::scope_request).layer(app);
+    let server = axum::serve(listener, ServiceExt::<axum::extract::Request>::into_make_service(app));
+    let preview_server = axum::serve(preview_listener, preview_app);
+    tokio::select! {
+        result = server => result.unwrap(),
+        result = preview_server => result.unwrap(),
+        _ = shutdown_signal() => tracing::info!("Shutting down"),
+    }
+
+    // Preview services don't get the terminal's signals
+    shutdown_state.cleanup_all_previews().await;
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let Ok(mut terminate) = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) else {
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
 }
 
 fn get_frontend_dir() -> PathBuf {
@@ -125,9 +286,25 @@ fn get_frontend_dir() -> PathBuf {
     PathBuf::from("./frontend/dist")
 }
 
-async fn find_available_port(start_port: u16) -> u16 {
+/// An explicit port must be the one we get. After a restart the old instance
+/// holds it for a moment, so keep trying briefly.
+async fn bind(addr: SocketAddr, explicit_port: bool) -> std::io::Result<tokio::net::TcpListener> {
+    let attempts = if explicit_port { BIND_ATTEMPTS } else { 1 };
+    let mut attempt = 1;
+    loop {
+        match tokio::net::TcpListener::bind(addr).await {
+            Err(e) if attempt < attempts && e.kind() == std::io::ErrorKind::AddrInUse => {
+                attempt += 1;
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn find_available_port(host: IpAddr, start_port: u16) -> u16 {
     for port in start_port..start_port + 10 {
-        if tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port)))
+        if tokio::net::TcpListener::bind(SocketAddr::new(host, port))
             .await
             .is_ok()
         {
@@ -162,7 +339,7 @@ async fn cleanup_orphan_worktrees(db: Pool<Sqlite>, state: Arc<AppState>) {
     tracing::info!("Starting orphan worktree cleanup");
 
     // Get all valid worktree paths from tasks
-    let valid_paths: Vec<String> = match Task::find_all(&db).await {
+    let mut valid_paths: Vec<String> = match Task::find_all(&db).await {
         Ok(tasks) => tasks
             .into_iter()
             .filter_map(|t| t.worktree_path)
@@ -173,15 +350,38 @@ async fn cleanup_orphan_worktrees(db: Pool<Sqlite>, state: Arc<AppState>) {
         }
     };
 
-    // Cleanup orphans
-    match state.worktree_manager.cleanup_orphans(&valid_paths).await {
-        Ok(removed) => {
-            if !removed.is_empty() {
-                tracing::info!("Removed {} orphan worktrees", removed.len());
-            }
+    // and from attempts still to be compared
+    match TaskAttempt::worktree_paths(&db).await {
+        Ok(paths) => valid_paths.extend(paths),
+        Err(e) => {
+            tracing::error!("Failed to fetch attempts for cleanup: {}", e);
+            return;
         }
+    }
+
+    let projects = match Project::find_recent(&db).await {
+        Ok(projects) => projects,
         Err(e) => {
-            tracing::error!("Failed to cleanup orphan worktrees: {}", e);
+            tracing::error!("Failed to fetch projects for cleanup: {}", e);
+            return;
+        }
+    };
+
+    // Cleanup orphans of every project whose repo is still there
+    for project in projects.into_iter().filter(|p| std::path::Path::new(&p.path).is_dir()) {
+        let Ok(Some(project)) = state.project(&project.id).await else {
+            continue;
+        };
+
+        match project.worktree_manager.cleanup_orphans(&valid_paths).await {
+            Ok(removed) => {
+                if !removed.is_empty() {
+                    tracing::info!("Removed {} orphan worktrees of {}", removed.len(), project.project.name);
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to cleanup orphan worktrees of {}: {}", project.project.name, e);
+            }
         }
     }
 }
@@ -1,5 +1,10 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
+use tokio::sync::mpsc;
+
+use eval_kanban_db::PlanSessionRecord;
+use eval_kanban_executor::stream_json;
 
 /// Status of a planning session
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -19,6 +24,32 @@ pub enum PlanStatus {
     Error,
 }
 
+impl PlanStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanStatus::Processing => "processing",
+            PlanStatus::WaitingForAnswer => "waiting_for_answer",
+            PlanStatus::Summary => "summary",
+            PlanStatus::Completed => "completed",
+            PlanStatus::Cancelled => "cancelled",
+            PlanStatus::Error => "error",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "processing" => Some(PlanStatus::Processing),
+            "waiting_for_answer" => Some(PlanStatus::WaitingForAnswer),
+            "summary" => Some(PlanStatus::Summary),
+            "completed" => Some(PlanStatus::Completed),
+            "cancelled" => Some(PlanStatus::Cancelled),
+            "error" => Some(PlanStatus::Error),
+            _ => None,
+        }
+    }
+}
+
 /// A single option in a question
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuestionOption {
@@ -44,6 +75,16 @@ pub struct PlanAnswer {
     pub answers: Vec<String>,
 }
 
+/// One step of a plan broken down into separate tasks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub title: String,
+    pub description: String,
+    /// Indices of earlier steps that must be done first
+    #[serde(default)]
+    pub depends_on: Vec<usize>,
+}
+
 /// Serializable info about a planning session (for API responses)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanSessionInfo {
@@ -56,19 +97,30 @@ pub struct PlanSessionInfo {
     pub summary: Option<String>,
     /// Whether Claude should ask clarifying questions (uses PLAN_MODE_SUFFIX)
     pub ask_questions: bool,
+    /// Existing task the plan will be attached to, if any
+    pub task_id: Option<String>,
+    /// Whether the plan is split into one task per step
+    pub breakdown: bool,
+    pub steps: Vec<PlanStep>,
+    /// Why the breakdown could not be used, if it couldn't
+    pub steps_error: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
 
+/// Fields of a session compared by `PlanSession::checkpoint`
+pub type PlanCheckpoint = (PlanStatus, usize, usize, usize, bool, bool, Vec<PlanStep>, Option<String>);
+
 /// A planning session that tracks Q&A state
 pub struct PlanSession {
     pub id: String,
+    pub project_path: String,
     pub title: String,
     pub prompt: String,
     pub questions: Vec<PlanQuestion>,
     pub answers: Vec<PlanAnswer>,
     pub status: PlanStatus,
     pub summary: Option<String>,
-    #[allow(dead_code)]
-    pub created_at: Instant,
+    pub created_at: DateTime<Utc>,
     pub last_activity: Instant,
     /// Accumulated output from Claude (used for context in re-spawn)
     pub accumulated_output: String,
@@ -78,25 +130,48 @@ pub struct PlanSession {
     pub ask_questions: bool,
     /// Content of the plan file written by Claude (extracted from Write tool)
     pub plan_content: Option<String>,
+    /// Existing task the plan will be attached to, if any
+    pub task_id: Option<String>,
+    /// Whether Claude is asked for a breakdown into dependent steps (uses BREAKDOWN_SUFFIX)
+    pub breakdown: bool,
+    /// Steps parsed from the summary, or edited by the user
+    pub steps: Vec<PlanStep>,
+    /// Why the summary could not be parsed into steps
+    pub steps_error: Option<String>,
+    /// Claude's conversation id from the init event, used to `--resume`
+    pub claude_session_id: Option<String>,
+    /// Stdin of the live Claude conversation, while its process is running
+    pub input: Option<mpsc::Sender<String>>,
+    /// Claude finished its turn while questions were pending, so answers are
+    /// sent as a new message rather than as tool results
+    pub turn_ended: bool,
 }
 
 impl PlanSession {
-    pub fn new(id: String, title: String, prompt: String, ask_questions: bool) -> Self {
+    pub fn new(id: String, project_path: String, title: String, prompt: String, ask_questions: bool) -> Self {
         let now = Instant::now();
         Self {
             id,
+            project_path,
             title,
             prompt,
             questions: Vec::new(),
             answers: Vec::new(),
             status: PlanStatus::Processing,
             summary: None,
-            created_at: now,
+            created_at: Utc::now(),
             last_activity: now,
             accumulated_output: String::new(),
             pending_questions: Vec::new(),
             ask_questions,
             plan_content: None,
+            task_id: None,
+            breakdown: false,
+            steps: Vec::new(),
+            steps_error: None,
+            claude_session_id: None,
+            input: None,
+            turn_ended: false,
         }
     }
 
@@ -135,11 +210,38 @@ impl PlanSession {
     }
 
     pub fn set_summary(&mut self, summary: String) {
+        if self.breakdown {
+            self.apply_breakdown(&summary);
+        }
         self.summary = Some(summary);
         self.status = PlanStatus::Summary;
         self.last_activity = Instant::now();
     }
 
+    /// Take the steps from Claude's breakdown, or record why that failed.
+    pub fn apply_breakdown(&mut self, text: &str) {
+        match parse_breakdown(text) {
+            Ok(steps) => {
+                self.steps = steps;
+                self.steps_error = None;
+            }
+            Err(e) => {
+                self.steps.clear();
+                self.steps_error = Some(e);
+            }
+        }
+    }
+
+    /// Replace the breakdown with user-edited steps.
+    pub fn set_steps(&mut self, steps: Vec<PlanStep>) -> Result<(), String> {
+        validate_steps(&steps)?;
+        self.steps = steps;
+        self.steps_error = None;
+        self.breakdown = true;
+        self.last_activity = Instant::now();
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub fn is_expired(&self, timeout_secs: u64) -> bool {
         self.last_activity.elapsed().as_secs() > timeout_secs
@@ -169,6 +271,87 @@ impl PlanSession {
         prompt
     }
 
+    /// The answers as text, one question and answer per paragraph.
+    pub fn format_answers(&self, answers: &[PlanAnswer]) -> String {
+        answers.iter()
+            .map(|a| {
+                let question = self.questions.iter().find(|q| q.index == a.question_index);
+                match question {
+                    Some(q) => format!("**Question ({})**: {}\n**User's answer**: {}", q.header, q.question, a.answers.join(", ")),
+                    None => format!("**User's answer**: {}", a.answers.join(", ")),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Stdin line delivering answers to the pending questions of the live
+    /// conversation. Must be built before the answers are added.
+    pub fn answer_message(&self, answers: &[PlanAnswer]) -> String {
+        if self.turn_ended {
+            return stream_json::user_text_message(&self.format_answers(answers));
+        }
+
+        // Questions asked in one AskUserQuestion call share its tool_use_id
+        let mut results: Vec<(String, String)> = Vec::new();
+        for question in &self.pending_questions {
+            let batch: Vec<PlanAnswer> = answers.iter()
+                .filter(|a| a.question_index == question.index)
+                .cloned()
+                .collect();
+            let text = self.format_answers(&batch);
+            match results.iter_mut().find(|(id, _)| *id == question.tool_use_id) {
+                Some((_, content)) => {
+                    content.push_str("\n\n");
+                    content.push_str(&text);
+                }
+                None => results.push((question.tool_use_id.clone(), text)),
+            }
+        }
+
+        stream_json::tool_result_message(&results)
+    }
+
+    /// Task description built from the prompt, the interview and the plan.
+    pub fn build_task_description(&self) -> String {
+        let mut description = self.prompt.clone();
+
+        if !self.questions.is_empty() {
+            description.push_str("\n\n## Interview Answers\n\n");
+            for (q, a) in self.questions.iter().zip(self.answers.iter()) {
+                description.push_str(&format!("**{}**: {}\n", q.header, q.question));
+                description.push_str(&format!("Answer: {}\n\n", a.answers.join(", ")));
+            }
+        }
+
+        if let Some(summary) = &self.summary {
+            description.push_str("\n\n## Implementation Plan\n\n");
+            description.push_str(summary);
+        }
+
+        description
+    }
+
+    /// Task description for one step of the breakdown. The other steps are
+    /// listed so the step can be implemented without reading their cards.
+    pub fn build_step_description(&self, index: usize) -> String {
+        let step = &self.steps[index];
+        let mut description = step.description.clone();
+
+        description.push_str(&format!(
+            "\n\n## Context\n\nThis is step {} of {} of the plan \"{}\":\n\n",
+            index + 1,
+            self.steps.len(),
+            self.title
+        ));
+        for (i, other) in self.steps.iter().enumerate() {
+            let marker = if i == index { " (this task)" } else { "" };
+            description.push_str(&format!("{}. {}{}\n", i + 1, other.title, marker));
+        }
+
+        description
+    }
+
     pub fn to_info(&self) -> PlanSessionInfo {
         PlanSessionInfo {
             id: self.id.clone(),
@@ -179,8 +362,93 @@ impl PlanSession {
             status: self.status.clone(),
             summary: self.summary.clone(),
             ask_questions: self.ask_questions,
+            task_id: self.task_id.clone(),
+            breakdown: self.breakdown,
+            steps: self.steps.clone(),
+            steps_error: self.steps_error.clone(),
+            created_at: self.created_at,
         }
     }
+
+    /// The parts of the session whose change should be written to the
+    /// database. Streamed output alone does not trigger a write.
+    pub fn checkpoint(&self) -> PlanCheckpoint {
+        (
+            self.status.clone(),
+            self.questions.len(),
+            self.answers.len(),
+            self.pending_questions.len(),
+            self.summary.is_some(),
+            self.plan_content.is_some(),
+            self.steps.clone(),
+            self.claude_session_id.clone(),
+        )
+    }
+
+    pub fn to_record(&self) -> PlanSessionRecord {
+        PlanSessionRecord {
+            id: self.id.clone(),
+            project_path: self.project_path.clone(),
+            title: self.title.clone(),
+            prompt: self.prompt.clone(),
+            status: self.status.as_str().to_string(),
+            questions: serde_json::to_string(&self.questions).unwrap_or_else(|_| "[]".to_string()),
+            answers: serde_json::to_string(&self.answers).unwrap_or_else(|_| "[]".to_string()),
+            pending_questions: serde_json::to_string(&self.pending_questions).unwrap_or_else(|_| "[]".to_string()),
+            summary: self.summary.clone(),
+            plan_content: self.plan_content.clone(),
+            accumulated_output: self.accumulated_output.clone(),
+            ask_questions: self.ask_questions,
+            task_id: self.task_id.clone(),
+            breakdown: self.breakdown,
+            steps: serde_json::to_string(&self.steps).unwrap_or_else(|_| "[]".to_string()),
+            steps_error: self.steps_error.clone(),
+            claude_session_id: self.claude_session_id.clone(),
+            created_at: self.created_at,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Restore a stored session. A session that was processing when the
+    /// server stopped has no Claude process anymore, so it is marked as
+    /// errored and can be resumed.
+    pub fn from_record(record: PlanSessionRecord) -> Self {
+        let status = match PlanStatus::from_str(&record.status).unwrap_or(PlanStatus::Error) {
+            PlanStatus::Processing => PlanStatus::Error,
+            status => status,
+        };
+
+        Self {
+            id: record.id,
+            project_path: record.project_path,
+            title: record.title,
+            prompt: record.prompt,
+            questions: serde_json::from_str(&record.questions).unwrap_or_default(),
+            answers: serde_json::from_str(&record.answers).unwrap_or_default(),
+            status,
+            summary: record.summary,
+            created_at: record.created_at,
+            last_activity: Instant::now(),
+            accumulated_output: record.accumulated_output,
+            pending_questions: serde_json::from_str(&record.pending_questions).unwrap_or_default(),
+            ask_questions: record.ask_questions,
+            plan_content: record.plan_content,
+            task_id: record.task_id,
+            breakdown: record.breakdown,
+            steps: serde_json::from_str(&record.steps).unwrap_or_default(),
+            steps_error: record.steps_error,
+            claude_session_id: record.claude_session_id,
+            input: None,
+            turn_ended: false,
+        }
+    }
+}
+
+impl PlanSessionInfo {
+    /// Info for a stored session, without restoring it.
+    pub fn from_record(record: PlanSessionRecord) -> Self {
+        PlanSession::from_record(record).to_info()
+    }
 }
 
 /// Suffix to append to prompts when Plan Mode is enabled
@@ -188,3 +456,267 @@ pub const PLAN_MODE_SUFFIX: &str = r#"
 
 Interview me in detail using the AskUserQuestionTool about literally anything: technical implementation, UI & UX, concerns, tradeoffs, etc. but make sure the questions are not obvious.
 Be very in-depth and continue interviewing me continually until it's complete. After gathering all information, provide a summary of the implementation plan."#;
+
+/// Suffix appended to the prompt when the plan should be split into tasks
+pub const BREAKDOWN_SUFFIX: &str = r#"
+
+When the plan is final, break it down into small tasks that can each be implemented and reviewed on their own. End your final answer with a single ```json code block of the form:
+{"steps": [{"title": "Short task title", "description": "What to implement and how", "depends_on": []}]}
+List the steps in implementation order. "depends_on" holds the zero-based indices of earlier steps that must be finished first."#;
+
+/// Most steps a breakdown may create
+pub const MAX_PLAN_STEPS: usize = 20;
+
+#[derive(Deserialize)]
+struct Breakdown {
+    steps: Vec<PlanStep>,
+}
+
+/// Parse and validate the JSON breakdown at the end of a plan summary.
+pub fn parse_breakdown(summary: &str) -> Result<Vec<PlanStep>, String> {
+    let json = extract_json_block(summary).ok_or("No JSON breakdown found in the plan")?;
+    let breakdown: Breakdown = serde_json::from_str(json)
+        .map_err(|e| format!("Invalid breakdown JSON: {}", e))?;
+    validate_steps(&breakdown.steps)?;
+    Ok(breakdown.steps)
+}
+
+/// The last ```json fenced block, or else the outermost `{...}` in the text.
+fn extract_json_block(text: &str) -> Option<&str> {
+    if let Some(start) = text.rfind("```json") {
+        let body = &text[start + "```json".len()..];
+        if let Some(end) = body.find("```") {
+            return Some(body[..end].trim());
+        }
+    }
+
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    (start < end).then(|| &text[start..=end])
+}
+
+/// Steps must have titles and may only depend on earlier steps, which also
+/// rules out cycles.
+pub fn validate_steps(steps: &[PlanStep]) -> Result<(), String> {
+    if steps.is_empty() {
+        return Err("The breakdown has no steps".to_string());
+    }
+    if steps.len() > MAX_PLAN_STEPS {
+        return Err(format!("The breakdown has {} steps (at most {})", steps.len(), MAX_PLAN_STEPS));
+    }
+
+    for (index, step) in steps.iter().enumerate() {
+        if step.title.trim().is_empty() {
+            return Err(format!("Step {} has no title", index + 1));
+        }
+        if let Some(dep) = step.depends_on.iter().find(|&&dep| dep >= index) {
+            return Err(format!(
+                "Step {} depends on step {}, but steps may only depend on earlier steps",
+                index + 1,
+                dep + 1
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question(index: usize) -> PlanQuestion {
+        PlanQuestion {
+            index,
+            question: "Which database?".to_string(),
+            header: "Storage".to_string(),
+            options: vec![QuestionOption {
+                label: "SQLite".to_string(),
+                description: "Embedded".to_string(),
+            }],
+            multi_select: false,
+            tool_use_id: "toolu_1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_status_roundtrip() {
+        for status in [
+            PlanStatus::Processing,
+            PlanStatus::WaitingForAnswer,
+            PlanStatus::Summary,
+            PlanStatus::Completed,
+            PlanStatus::Cancelled,
+            PlanStatus::Error,
+        ] {
+            assert_eq!(PlanStatus::from_str(status.as_str()), Some(status));
+        }
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let mut session = PlanSession::new(
+            "s1".to_string(),
+            "/project".to_string(),
+            "Plan".to_string(),
+            "Add storage".to_string(),
+            true,
+        );
+        session.add_questions(vec![question(0)]);
+        session.append_output("{\"type\":\"assistant\"}");
+
+        let restored = PlanSession::from_record(session.to_record());
+        assert_eq!(restored.status, PlanStatus::WaitingForAnswer);
+        assert_eq!(restored.questions.len(), 1);
+        assert_eq!(restored.pending_questions[0].tool_use_id, "toolu_1");
+        assert_eq!(restored.accumulated_output, session.accumulated_output);
+        assert!(restored.ask_questions);
+    }
+
+    #[test]
+    fn test_processing_session_restores_as_error() {
+        let session = PlanSession::new(
+            "s1".to_string(),
+            "/project".to_string(),
+            "Plan".to_string(),
+            "Add storage".to_string(),
+            false,
+        );
+
+        let restored = PlanSession::from_record(session.to_record());
+        assert_eq!(restored.status, PlanStatus::Error);
+    }
+
+    #[test]
+    fn test_build_task_description() {
+        let mut session = PlanSession::new(
+            "s1".to_string(),
+            "/project".to_string(),
+            "Plan".to_string(),
+            "Add storage".to_string(),
+            true,
+        );
+        session.add_questions(vec![question(0)]);
+        session.add_answers(vec![PlanAnswer { question_index: 0, answers: vec!["SQLite".to_string()] }]);
+        session.set_summary("1. Add table".to_string());
+
+        let description = session.build_task_description();
+        assert!(description.starts_with("Add storage\n\n## Interview Answers"));
+        assert!(description.contains("**Storage**: Which database?\nAnswer: SQLite"));
+        assert!(description.ends_with("## Implementation Plan\n\n1. Add table"));
+    }
+
+    fn step(title: &str, depends_on: Vec<usize>) -> PlanStep {
+        PlanStep {
+            title: title.to_string(),
+            description: format!("Implement {}", title),
+            depends_on,
+        }
+    }
+
+    #[test]
+    fn test_parse_breakdown() {
+        let summary = "Plan:\n1. Schema\n2. API\n\n```json\n{\"steps\": [\n  {\"title\": \"Schema\", \"description\": \"Add table\"},\n  {\"title\": \"API\", \"description\": \"Add routes\", \"depends_on\": [0]}\n]}\n```";
+        let steps = parse_breakdown(summary).unwrap();
+        assert_eq!(steps, vec![
+            PlanStep { title: "Schema".to_string(), description: "Add table".to_string(), depends_on: vec![] },
+            PlanStep { title: "API".to_string(), description: "Add routes".to_string(), depends_on: vec![0] },
+        ]);
+
+        let unfenced = r#"Here you go: {"steps": [{"title": "Only", "description": "All of it"}]}"#;
+        assert_eq!(parse_breakdown(unfenced).unwrap().len(), 1);
+
+        assert!(parse_breakdown("Just prose").is_err());
+        assert!(parse_breakdown("```json\n{\"steps\": \"nope\"}\n```").is_err());
+    }
+
+    #[test]
+    fn test_validate_steps() {
+        assert!(validate_steps(&[step("A", vec![]), step("B", vec![0]), step("C", vec![0, 1])]).is_ok());
+        assert!(validate_steps(&[]).is_err());
+        assert!(validate_steps(&[step(" ", vec![])]).is_err());
+        // Self and forward references would allow cycles
+        assert!(validate_steps(&[step("A", vec![0])]).is_err());
+        assert!(validate_steps(&[step("A", vec![1]), step("B", vec![0])]).is_err());
+
+        let too_many: Vec<PlanStep> = (0..=MAX_PLAN_STEPS).map(|i| step(&i.to_string(), vec![])).collect();
+        assert!(validate_steps(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_breakdown_summary_sets_steps() {
+        let mut session = PlanSession::new(
+            "s1".to_string(),
+            "/project".to_string(),
+            "Auth".to_string(),
+            "Add auth".to_string(),
+            false,
+        );
+        session.breakdown = true;
+
+        session.set_summary("No JSON here".to_string());
+        assert!(session.steps.is_empty());
+        assert!(session.steps_error.is_some());
+
+        session.set_steps(vec![step("Schema", vec![]), step("API", vec![0])]).unwrap();
+        assert!(session.steps_error.is_none());
+        assert!(session.set_steps(vec![step("API", vec![1])]).is_err());
+        assert_eq!(session.steps.len(), 2);
+
+        let description = session.build_step_description(1);
+        assert!(description.starts_with("Implement API\n\n## Context"));
+        assert!(description.contains("step 2 of 2 of the plan \"Auth\""));
+        assert!(description.contains("2. API (this task)"));
+
+        let restored = PlanSession::from_record(session.to_record());
+        assert!(restored.breakdown);
+        assert_eq!(restored.steps, session.steps);
+    }
+
+    #[test]
+    fn test_answer_message() {
+        let mut session = PlanSession::new(
+            "s1".to_string(),
+            "/project".to_string(),
+            "Plan".to_string(),
+            "Add storage".to_string(),
+            true,
+        );
+        session.add_questions(vec![question(0), question(1)]);
+        let answers = vec![
+            PlanAnswer { question_index: 0, answers: vec!["SQLite".to_string()] },
+            PlanAnswer { question_index: 1, answers: vec!["Postgres".to_string()] },
+        ];
+
+        // Both questions came from the same tool call, so one tool result
+        let json: serde_json::Value = serde_json::from_str(&session.answer_message(&answers)).unwrap();
+        let content = json["message"]["content"].as_array().unwrap();
+        assert_eq!(content.len(), 1);
+        assert_eq!(content[0]["tool_use_id"], "toolu_1");
+        let text = content[0]["content"].as_str().unwrap();
+        assert!(text.contains("**User's answer**: SQLite"));
+        assert!(text.contains("**User's answer**: Postgres"));
+
+        session.turn_ended = true;
+        let json: serde_json::Value = serde_json::from_str(&session.answer_message(&answers)).unwrap();
+        assert_eq!(json["message"]["content"][0]["type"], "text");
+    }
+
+    #[test]
+    fn test_checkpoint_ignores_output() {
+        let mut session = PlanSession::new(
+            "s1".to_string(),
+            "/project".to_string(),
+            "Plan".to_string(),
+            "Add storage".to_string(),
+            false,
+        );
+        let before = session.checkpoint();
+
+        session.append_output("line");
+        assert_eq!(session.checkpoint(), before);
+
+        session.set_summary("Done".to_string());
+        assert_ne!(session.checkpoint(), before);
+    }
+}
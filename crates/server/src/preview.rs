@@ -0,0 +1,416 @@
+//! Preview services: the processes started from a task's worktree so it can
+//! be tried out, as configured in `preview.services`.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::process::{Child, Command};
+
+use crate::settings::{PreviewService, ReadyCheck};
+
+/// Time between readiness checks
+pub const READY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Output lines kept per preview, across its services
+pub const LOG_BUFFER_SIZE: usize = 2000;
+
+/// A line of output from a preview service or its setup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewLogLine {
+    /// Increases by one per line of the preview
+    pub id: u64,
+    pub service: String,
+    pub stream: String,
+    pub content: String,
+}
+
+/// The most recent output of a preview, oldest first.
+pub struct LogBuffer {
+    next_id: u64,
+    lines: VecDeque<PreviewLogLine>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            next_id: 1,
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, service: &str, stream: &str, content: String) -> PreviewLogLine {
+        let line = PreviewLogLine {
+            id: self.next_id,
+            service: service.to_string(),
+            stream: stream.to_string(),
+            content,
+        };
+        self.next_id += 1;
+
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.clone());
+
+        line
+    }
+
+    /// The last `limit` lines after `after`, of one service or all of them.
+    pub fn since(&self, after: u64, service: Option<&str>, limit: usize) -> Vec<PreviewLogLine> {
+        let mut lines: Vec<PreviewLogLine> = self
+            .lines
+            .iter()
+            .rev()
+            .filter(|l| l.id > after && service.is_none_or(|s| l.service == s))
+            .take(limit)
+            .cloned()
+            .collect();
+        lines.reverse();
+        lines
+    }
+}
+
+/// Indices of `services` so that each comes after what it depends on,
+/// otherwise keeping the configured order.
+pub fn start_order(services: &[PreviewService]) -> Result<Vec<usize>, String> {
+    let index_of = |name: &str| services.iter().position(|s| s.name == name);
+    let mut order = Vec::with_capacity(services.len());
+    let mut started = vec![false; services.len()];
+
+    while order.len() < services.len() {
+        let next = (0..services.len()).find(|&i| {
+            !started[i]
+                && services[i]
+                    .depends_on
+                    .iter()
+                    .filter_map(|d| index_of(d))
+                    .all(|d| started[d])
+        });
+
+        let Some(next) = next else {
+            let waiting: Vec<&str> = (0..services.len())
+                .filter(|&i| !started[i])
+                .map(|i| services[i].name.as_str())
+                .collect();
+            return Err(format!("dependency cycle between {}", waiting.join(", ")));
+        };
+        started[next] = true;
+        order.push(next);
+    }
+
+    Ok(order)
+}
+
+/// Services named by `{port:<name>}` placeholders in `template`.
+pub fn port_references(template: &str) -> Vec<&str> {
+    template
+        .split("{port:")
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+/// Replace `{port}` with `own` and `{port:<name>}` with that service's port.
+pub fn expand(template: &str, own: u16, ports: &HashMap<String, u16>) -> String {
+    let mut expanded = template.replace("{port}", &own.to_string());
+    for (name, port) in ports {
+        expanded = expanded.replace(&format!("{{port:{}}}", name), &port.to_string());
+    }
+    expanded
+}
+
+/// `count` free ports from `start`, leaving out `taken`.
+pub fn allocate_ports(count: usize, start: u16, range: u16, taken: &[u16]) -> Option<Vec<u16>> {
+    let end = start.saturating_add(range);
+    let mut ports = Vec::with_capacity(count);
+    for port in start..end {
+        if ports.len() == count {
+            break;
+        }
+        if !taken.contains(&port) && TcpListener::bind(("127.0.0.1", port)).is_ok() {
+            ports.push(port);
+        }
+    }
+    (ports.len() == count).then_some(ports)
+}
+
+/// Path the server proxies a service under, with a trailing slash.
+pub fn proxy_base(task_id: &str, service: &str) -> String {
+    format!("/preview/{}/{}/", task_id, service)
+}
+
+/// Ports reserved by running previews, so that previews started at the same
+/// time don't both pick a port that is still free.
+#[derive(Default)]
+pub struct PortRegistry {
+    /// Port to the run id of the preview holding it
+    reserved: HashMap<u16, String>,
+}
+
+impl PortRegistry {
+    /// Reserve `count` free ports from `start` for `owner`.
+    pub fn reserve(&mut self, owner: &str, count: usize, start: u16, range: u16) -> Option<Vec<u16>> {
+        let taken: Vec<u16> = self.reserved.keys().copied().collect();
+        let ports = allocate_ports(count, start, range, &taken)?;
+        for &port in &ports {
+            self.reserved.insert(port, owner.to_string());
+        }
+        Some(ports)
+    }
+
+    /// `port`, held by `owner`, if it can be bound again; otherwise another
+    /// port in its place.
+    pub fn renew(&mut self, owner: &str, port: u16, start: u16, range: u16) -> Option<u16> {
+        if allocate_ports(1, port, 1, &[]).is_some() {
+            return Some(port);
+        }
+        let renewed = self.reserve(owner, 1, start, range)?[0];
+        self.reserved.remove(&port);
+        Some(renewed)
+    }
+
+    pub fn release(&mut self, owner: &str) {
+        self.reserved.retain(|_, o| o != owner);
+    }
+}
+
+/// Directory a service runs in.
+pub fn service_dir(service: &PreviewService, worktree: &Path, port: u16, ports: &HashMap<String, u16>) -> PathBuf {
+    worktree.join(expand(&service.cwd, port, ports))
+}
+
+/// Start the service's setup command, unless it has none or what it creates
+/// already exists. Output is piped.
+pub fn spawn_setup(
+    service: &PreviewService,
+    dir: &Path,
+    port: u16,
+    ports: &HashMap<String, u16>,
+    base: &str,
+) -> Option<std::io::Result<Child>> {
+    let setup = service.setup.as_ref()?;
+    if service.creates.as_ref().is_some_and(|path| dir.join(path).exists()) {
+        return None;
+    }
+
+    tracing::info!("[Preview {}] Running setup: {}", service.name, setup);
+    Some(spawn_piped(service, setup, dir, port, ports, base))
+}
+
+/// Start the service. Output is piped.
+pub fn spawn_service(
+    service: &PreviewService,
+    worktree: &Path,
+    port: u16,
+    ports: &HashMap<String, u16>,
+    base: &str,
+) -> std::io::Result<Child> {
+    let dir = service_dir(service, worktree, port, ports);
+    spawn_piped(service, &service.command, &dir, port, ports, base)
+}
+
+fn spawn_piped(
+    service: &PreviewService,
+    command_line: &str,
+    dir: &Path,
+    port: u16,
+    ports: &HashMap<String, u16>,
+    base: &str,
+) -> std::io::Result<Child> {
+    let expand = |template: &str| expand(template, port, ports).replace("{base}", base);
+
+    let mut command = shell_command(&expand(command_line), dir);
+    command.env("PORT", port.to_string());
+    for (key, value) in &service.env {
+        command.env(key, expand(value));
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+}
+
+/// Wait until a service on `port` passes the readiness check, or fail once
+/// it times out.
+pub async fn wait_ready(check: &ReadyCheck, port: u16) -> Result<(), String> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(check.timeout_secs);
+
+    loop {
+        if probe(check, port).await {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(format!("not ready after {}s", check.timeout_secs));
+        }
+        tokio::time::sleep(READY_POLL_INTERVAL).await;
+    }
+}
+
+/// Kill the process and, on Unix, whatever it started.
+pub async fn kill(process: &mut Child) {
+    if let Some(pid) = process.id() {
+        kill_group(pid);
+    }
+    let _ = process.kill().await;
+}
+
+/// Kill what a service started, on Unix. Services lead their own process
+/// group, so they don't get the terminal's signals with the server.
+pub fn kill_group(pid: u32) {
+    #[cfg(unix)]
+    // SAFETY: only sends a signal, to a group the server isn't in
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+/// One readiness check: the port accepts connections and, for HTTP checks,
+/// the path answers with a status below 500.
+async fn probe(check: &ReadyCheck, port: u16) -> bool {
+    let Ok(Ok(mut stream)) = tokio::time::timeout(READY_POLL_INTERVAL, TcpStream::connect(("127.0.0.1", port))).await else {
+        return false;
+    };
+    let Some(path) = &check.http else {
+        return true;
+    };
+
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost:{}\r\nConnection: close\r\n\r\n", path, port);
+    if stream.write_all(request.as_bytes()).await.is_err() {
+        return false;
+    }
+
+    let mut head = [0u8; 32];
+    let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut head)).await;
+    let Ok(Ok(len)) = read else {
+        return false;
+    };
+    let ready = String::from_utf8_lossy(&head[..len])
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .is_some_and(|code| code < 500);
+
+    // Read the rest, as closing with unread data resets the connection
+    let _ = tokio::time::timeout(Duration::from_secs(1), tokio::io::copy(&mut stream, &mut tokio::io::sink())).await;
+    ready
+}
+
+/// A command line run through the shell, leading its own process group on
+/// Unix. Cargo's bin directory is added to `PATH`, as it often isn't there
+/// for processes not started from a login shell.
+pub fn shell_command(command_line: &str, dir: &Path) -> Command {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", command_line]);
+        command
+    } else {
+        // exec, so that killing the process stops the command and not just
+        // the shell. Not possible for lists and pipelines.
+        let simple = !command_line.contains([';', '&', '|', '\n', '(']);
+        let command_line = if simple { format!("exec {}", command_line) } else { command_line.to_string() };
+        let mut command = Command::new("sh");
+        command.args(["-c", &command_line]);
+        #[cfg(unix)]
+        command.process_group(0);
+        command
+    };
+
+    let mut paths = vec![get_cargo_bin_dir()];
+    if let Some(path) = std::env::var_os("PATH") {
+        paths.extend(std::env::split_paths(&path));
+    }
+    if let Ok(path) = std::env::join_paths(paths) {
+        command.env("PATH", path);
+    }
+
+    command.current_dir(dir);
+    command
+}
+
+fn get_cargo_bin_dir() -> PathBuf {
+    std::env::var("CARGO_HOME")
+        .map(|home| PathBuf::from(home).join("bin"))
+        .unwrap_or_else(|_| {
+            let home = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+            std::env::var(home)
+                .map(|home| PathBuf::from(home).join(".cargo").join("bin"))
+                .unwrap_or_default()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(name: &str, depends_on: &[&str]) -> PreviewService {
+        PreviewService {
+            name: name.to_string(),
+            command: "true".to_string(),
+            cwd: String::new(),
+            env: Default::default(),
+            setup: None,
+            creates: None,
+            ready: None,
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            keep_base: false,
+        }
+    }
+
+    #[test]
+    fn test_start_order() {
+        let services = [service("web", &["api"]), service("api", &["db"]), service("db", &[]), service("docs", &[])];
+        assert_eq!(start_order(&services).unwrap(), vec![2, 1, 0, 3]);
+
+        let cycle = [service("a", &["b"]), service("b", &["a"]), service("c", &[])];
+        assert_eq!(start_order(&cycle).unwrap_err(), "dependency cycle between a, b");
+    }
+
+    #[test]
+    fn test_expand() {
+        let ports = HashMap::from([("api".to_string(), 5201), ("db".to_string(), 5202)]);
+        let template = "--port {port} --api http://localhost:{port:api} --db {port:db}";
+        assert_eq!(port_references(template), vec!["api", "db"]);
+        assert_eq!(expand(template, 5200, &ports), "--port 5200 --api http://localhost:5201 --db 5202");
+    }
+
+    #[test]
+    fn test_port_registry() {
+        let mut registry = PortRegistry::default();
+        let first = registry.reserve("a", 2, 47300, 50).unwrap();
+        let second = registry.reserve("b", 2, 47300, 50).unwrap();
+        assert!(first.iter().all(|p| !second.contains(p)));
+        assert_eq!(registry.renew("a", first[0], 47300, 50), Some(first[0]));
+
+        registry.release("a");
+        let third = registry.reserve("c", 2, 47300, 50).unwrap();
+        assert!(third.iter().all(|p| !second.contains(p)));
+        assert!(registry.reserve("d", 100, 47300, 50).is_none());
+    }
+
+    #[test]
+    fn test_log_buffer() {
+        let mut logs = LogBuffer::new(3);
+        for i in 1..=4 {
+            let service = if i % 2 == 0 { "web" } else { "api" };
+            logs.push(service, "stdout", format!("line {}", i));
+        }
+
+        let ids = |lines: Vec<PreviewLogLine>| lines.iter().map(|l| l.id).collect::<Vec<_>>();
+        assert_eq!(ids(logs.since(0, None, 10)), vec![2, 3, 4]);
+        assert_eq!(ids(logs.since(2, None, 10)), vec![3, 4]);
+        assert_eq!(ids(logs.since(0, Some("web"), 10)), vec![2, 4]);
+        assert_eq!(ids(logs.since(0, None, 1)), vec![4]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Projects served by this instance.
+//!
+//! Every route is available unscoped, for the project the server was started
+//! in, and under `/api/projects/:project_id/`, for any registered project.
+//! Scoped requests are rewritten to the unscoped path before routing, with
+//! the project id kept in the request; handlers that list or create things
+//! take a `CurrentProject`. Routes about a single task use that task's own
+//! project instead, whatever the URL.
+
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use eval_kanban_db::Project;
+use eval_kanban_worktree::WorktreeManager;
+
+use crate::state::AppState;
+
+const SCOPE_PREFIX: &str = "/api/projects/";
+
+pub struct ProjectContext {
+    pub project: Project,
+    pub path: PathBuf,
+    pub worktree_manager: WorktreeManager,
+}
+
+impl ProjectContext {
+    pub fn new(project: Project) -> Self {
+        let path = PathBuf::from(&project.path);
+        let worktree_manager = WorktreeManager::new(path.clone(), eval_kanban_db::get_worktrees_dir());
+        Self { project, path, worktree_manager }
+    }
+
+    /// The path as stored in `project_path` columns
+    pub fn path_string(&self) -> String {
+        self.project.path.clone()
+    }
+}
+
+/// Project id of a `/api/projects/:project_id/...` request.
+#[derive(Debug, Clone)]
+struct ProjectScope(String);
+
+/// Rewrite `/api/projects/:project_id/<rest>` to `/api/<rest>`, remembering
+/// the project. Must run before routing.
+pub async fn scope_request(mut req: Request) -> Request {
+    let Some((project_id, rest)) = split_scoped_path(req.uri().path()) else {
+        return req;
+    };
+    let project_id = project_id.to_string();
+
+    let path_and_query = match req.uri().query() {
+        Some(query) => format!("/api/{}?{}", rest, query),
+        None => format!("/api/{}", rest),
+    };
+    let mut parts = req.uri().clone().into_parts();
+    let Ok(path_and_query) = path_and_query.parse() else {
+        return req;
+    };
+    parts.path_and_query = Some(path_and_query);
+
+    if let Ok(uri) = Uri::from_parts(parts) {
+        *req.uri_mut() = uri;
+        req.extensions_mut().insert(ProjectScope(project_id));
+    }
+    req
+}
+
+/// `(project_id, rest)` for a scoped path. `/api/projects/:id` itself is a
+/// project route, not a scoped one.
+fn split_scoped_path(path: &str) -> Option<(&str, &str)> {
+    let (project_id, rest) = path.strip_prefix(SCOPE_PREFIX)?.split_once('/')?;
+    if project_id.is_empty() || rest.is_empty() {
+        return None;
+    }
+    Some((project_id, rest))
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// The project a request is about: the one in the URL, or the one the
+/// server was started in.
+pub struct CurrentProject(pub Arc<ProjectContext>);
+
+impl Deref for CurrentProject {
+    type Target = ProjectContext;
+
+    fn deref(&self) -> &ProjectContext {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for CurrentProject {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let Some(ProjectScope(project_id)) = parts.extensions.get::<ProjectScope>() else {
+            return Ok(CurrentProject(state.default_project.clone()));
+        };
+
+        match state.project(project_id).await {
+            Ok(Some(project)) => Ok(CurrentProject(project)),
+            Ok(None) => Err((
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "Project not found".to_string(),
+                }),
+            )
+                .into_response()),
+            Err(e) => Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+                .into_response()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope_request() {
+        let req = Request::builder()
+            .uri("/api/projects/p1/tasks/t1/logs?limit=5")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let req = scope_request(req).await;
+        assert_eq!(req.uri(), "/api/tasks/t1/logs?limit=5");
+        assert_eq!(req.extensions().get::<ProjectScope>().unwrap().0, "p1");
+
+        for path in ["/api/projects/p1", "/api/projects/p1/", "/api/projects", "/api/tasks"] {
+            let req = Request::builder().uri(path).body(axum::body::Body::empty()).unwrap();
+            let req = scope_request(req).await;
+            assert_eq!(req.uri(), path);
+            assert!(req.extensions().get::<ProjectScope>().is_none());
+        }
+    }
+}
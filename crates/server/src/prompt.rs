@@ -0,0 +1,272 @@
+//! Building the prompt sent to the executor when a task starts.
+//!
+//! A task either uses its description (falling back to the title) or a stored
+//! prompt template with `{{variable}}` placeholders. Attached files are listed
+//! after the body, and the project's prefix and suffix are wrapped around the
+//! result. Runs that fix failing checks get their output appended.
+
+use std::path::PathBuf;
+
+use eval_kanban_db::{Attachment, AttachmentOwner, CheckStatus, ProjectSettings, PromptTemplate, RunCheck, Task};
+use eval_kanban_worktree::{BASE_BRANCH, generate_branch_name, get_worktree_diff};
+
+use crate::attachments;
+use crate::state::AppState;
+use crate::verify;
+
+/// Variables available to templates.
+pub const TEMPLATE_VARIABLES: &[&str] = &[
+    "title",
+    "description",
+    "branch",
+    "base_branch",
+    "diff_summary",
+    "feedback",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct PromptContext {
+    pub title: String,
+    pub description: String,
+    pub branch: String,
+    pub base_branch: String,
+    pub diff_summary: String,
+    pub feedback: String,
+}
+
+impl PromptContext {
+    fn get(&self, name: &str) -> Option<&str> {
+        match name {
+            "title" => Some(&self.title),
+            "description" => Some(&self.description),
+            "branch" => Some(&self.branch),
+            "base_branch" => Some(&self.base_branch),
+            "diff_summary" => Some(&self.diff_summary),
+            "feedback" => Some(&self.feedback),
+            _ => None,
+        }
+    }
+}
+
+/// Replace `{{variable}}` placeholders. Unknown placeholders are left as they are.
+pub fn render_template(body: &str, ctx: &PromptContext) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+
+        match ctx.get(after[..end].trim()) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Prompt used when the task has no template selected.
+pub fn default_body(ctx: &PromptContext) -> String {
+    let mut body = if ctx.description.trim().is_empty() {
+        ctx.title.clone()
+    } else {
+        ctx.description.clone()
+    };
+
+    if !ctx.feedback.trim().is_empty() {
+        body.push_str("\n\nFeedback on the previous attempt:\n");
+        body.push_str(&ctx.feedback);
+    }
+
+    body
+}
+
+/// Appended to the prompt when Claude is run again to fix failing
+/// verification checks, with the end of each failing check's output.
+pub fn check_failures_section(checks: &[RunCheck], outputs: &[String]) -> String {
+    let mut section = String::from("These checks fail after your changes. Fix the code so that they pass:");
+    for (check, output) in checks.iter().zip(outputs) {
+        if check.status != CheckStatus::Failed {
+            continue;
+        }
+        section.push_str(&format!(
+            "\n\n{} (`{}`) {}:\n```\n{}\n```",
+            check.name,
+            check.command,
+            verify::summary(check),
+            output.trim_end()
+        ));
+    }
+    section
+}
+
+/// Join the project prefix, the task body and the project suffix.
+pub fn compose(prefix: Option<&str>, body: &str, suffix: Option<&str>) -> String {
+    [prefix, Some(body), suffix]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Render the full prompt for a task exactly as it will be sent to the executor.
+pub async fn build_task_prompt(
+    state: &AppState,
+    task: &Task,
+    feedback: Option<&str>,
+) -> Result<String, sqlx::Error> {
+    let project_path = task.project_path.clone()
+        .unwrap_or_else(|| state.default_project.path_string());
+    let settings = ProjectSettings::get(&state.db, &project_path).await?;
+
+    let template = match task.prompt_template_id.as_deref() {
+        Some(template_id) => PromptTemplate::find_by_id(&state.db, template_id).await?,
+        None => None,
+    };
+
+    // The branch is not stored until the first run creates the worktree, but
+    // its name is deterministic
+    let branch = match &task.branch_name {
+        Some(branch) => branch.clone(),
+        None => {
+            let project = state.project_for_task(task).await?;
+            if project.worktree_manager.is_git_repo() {
+                let settings = state.settings.get(&project.path);
+                generate_branch_name(&settings.worktree.branch_prefix, &task.title, &task.id)
+            } else {
+                String::new()
+            }
+        }
+    };
+
+    let needs_diff = template.as_ref().is_some_and(|t| t.body.contains("diff_summary"));
+    let diff_summary = match (&task.worktree_path, needs_diff) {
+        (Some(path), true) => diff_summary(PathBuf::from(path)).await,
+        _ => String::new(),
+    };
+
+    let ctx = PromptContext {
+        title: task.title.clone(),
+        description: task.description.clone().unwrap_or_default(),
+        branch,
+        base_branch: BASE_BRANCH.to_string(),
+        diff_summary,
+        feedback: feedback.unwrap_or_default().to_string(),
+    };
+
+    let mut body = match &template {
+        Some(template) => render_template(&template.body, &ctx),
+        None => default_body(&ctx),
+    };
+
+    let task_attachments = Attachment::find_by_owner(&state.db, AttachmentOwner::Task, &task.id).await?;
+    if let Some(section) = attachments::prompt_section(&task_attachments) {
+        body.push_str("\n\n");
+        body.push_str(&section);
+    }
+
+    Ok(compose(settings.prompt_prefix.as_deref(), &body, settings.prompt_suffix.as_deref()))
+}
+
+/// One line per changed file plus a total, or empty if there are no changes.
+async fn diff_summary(worktree_path: PathBuf) -> String {
+    if !worktree_path.exists() {
+        return String::new();
+    }
+
+    let diff = tokio::task::spawn_blocking(move || get_worktree_diff(&worktree_path)).await;
+    let Ok(Ok(diff)) = diff else {
+        return String::new();
+    };
+
+    if diff.files.is_empty() {
+        return String::new();
+    }
+
+    let mut lines: Vec<String> = diff.files.iter()
+        .map(|f| format!("{} (+{} -{})", f.path, f.additions, f.deletions))
+        .collect();
+    lines.push(format!(
+        "{} files changed, {} insertions(+), {} deletions(-)",
+        diff.files.len(), diff.total_additions, diff.total_deletions
+    ));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> PromptContext {
+        PromptContext {
+            title: "Add login".to_string(),
+            description: "Add a login form".to_string(),
+            branch: "ek/add-login-1234abcd".to_string(),
+            base_branch: "main".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let rendered = render_template("{{title}} on {{ branch }} from {{base_branch}}", &ctx());
+        assert_eq!(rendered, "Add login on ek/add-login-1234abcd from main");
+    }
+
+    #[test]
+    fn test_render_keeps_unknown_and_unclosed_placeholders() {
+        assert_eq!(render_template("{{unknown}} {{title}}", &ctx()), "{{unknown}} Add login");
+        assert_eq!(render_template("{{title}} {{oops", &ctx()), "Add login {{oops");
+    }
+
+    #[test]
+    fn test_default_body() {
+        assert_eq!(default_body(&ctx()), "Add a login form");
+
+        let no_description = PromptContext { description: String::new(), ..ctx() };
+        assert_eq!(default_body(&no_description), "Add login");
+
+        let with_feedback = PromptContext { feedback: "Use OAuth".to_string(), ..ctx() };
+        assert_eq!(
+            default_body(&with_feedback),
+            "Add a login form\n\nFeedback on the previous attempt:\nUse OAuth"
+        );
+    }
+
+    #[test]
+    fn test_check_failures_section() {
+        let check = |name: &str, status, exit_code| RunCheck {
+            name: name.to_string(),
+            command: format!("make {}", name),
+            status,
+            exit_code,
+            error: None,
+            duration_ms: None,
+        };
+        let checks = [check("build", CheckStatus::Passed, Some(0)), check("test", CheckStatus::Failed, Some(2))];
+        let outputs = ["Compiling".to_string(), "1 failed\n".to_string()];
+
+        assert_eq!(
+            check_failures_section(&checks, &outputs),
+            "These checks fail after your changes. Fix the code so that they pass:\n\n\
+             test (`make test`) failed with exit code 2:\n```\n1 failed\n```"
+        );
+    }
+
+    #[test]
+    fn test_compose() {
+        assert_eq!(compose(None, "Body", None), "Body");
+        assert_eq!(compose(Some("Prefix\n"), "Body", Some("  ")), "Prefix\n\nBody");
+        assert_eq!(compose(Some("Prefix"), "Body", Some("Suffix")), "Prefix\n\nBody\n\nSuffix");
+    }
+}
@@ -0,0 +1,170 @@
+use std::sync::Arc;
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{DefaultBodyLimit, Path, State},
+    http::{StatusCode, header},
+    response::Response,
+    routing::{get, post},
+};
+use serde::Serialize;
+
+use eval_kanban_db::{Attachment, AttachmentOwner, Task};
+
+use crate::attachments::{self, AttachmentError, MAX_ATTACHMENT_BYTES, Upload};
+use crate::project::CurrentProject;
+use crate::state::AppState;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Serialize)]
+struct AttachmentsResponse {
+    attachments: Vec<Attachment>,
+}
+
+pub fn attachments_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/tasks/:id/attachments", get(list_task_attachments).post(upload_task_attachment))
+        .route("/chat/attachments", post(upload_chat_attachment))
+        .route("/attachments/:id", get(get_attachment).delete(delete_attachment))
+        .route("/attachments/:id/content", get(get_attachment_content))
+        // Base64 adds a third on top of the decoded size
+        .layer(DefaultBodyLimit::max(MAX_ATTACHMENT_BYTES / 3 * 4 + 64 * 1024))
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: e.to_string(),
+        }),
+    )
+}
+
+fn upload_error(e: AttachmentError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match e {
+        AttachmentError::Invalid(_) => StatusCode::BAD_REQUEST,
+        AttachmentError::Io(_) | AttachmentError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (status, Json(ErrorResponse { error: e.to_string() }))
+}
+
+fn not_found(what: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: format!("{} not found", what),
+        }),
+    )
+}
+
+async fn list_task_attachments(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<AttachmentsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let attachments = Attachment::find_by_owner(&state.db, AttachmentOwner::Task, &id)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(AttachmentsResponse { attachments }))
+}
+
+async fn upload_task_attachment(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(upload): Json<Upload>,
+) -> Result<(StatusCode, Json<Attachment>), (StatusCode, Json<ErrorResponse>)> {
+    let task = Task::find_by_id(&state.db, &id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| not_found("Task"))?;
+
+    let project_path = task.project_path
+        .unwrap_or_else(|| state.default_project.path_string());
+
+    let attachment = attachments::store(&state.db, &project_path, AttachmentOwner::Task, Some(task.id), upload)
+        .await
+        .map_err(upload_error)?;
+
+    tracing::info!("Attached {} ({} bytes) to task {}", attachment.filename, attachment.size_bytes, id);
+
+    Ok((StatusCode::CREATED, Json(attachment)))
+}
+
+/// Upload a file to send with the next chat message (see `attachment_ids`).
+async fn upload_chat_attachment(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Json(upload): Json<Upload>,
+) -> Result<(StatusCode, Json<Attachment>), (StatusCode, Json<ErrorResponse>)> {
+    let project_path = project.path_string();
+
+    let attachment = attachments::store(&state.db, &project_path, AttachmentOwner::ChatMessage, None, upload)
+        .await
+        .map_err(upload_error)?;
+
+    Ok((StatusCode::CREATED, Json(attachment)))
+}
+
+async fn get_attachment(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Attachment>, (StatusCode, Json<ErrorResponse>)> {
+    let attachment = Attachment::find_by_id(&state.db, &id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| not_found("Attachment"))?;
+
+    Ok(Json(attachment))
+}
+
+async fn get_attachment_content(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let attachment = Attachment::find_by_id(&state.db, &id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| not_found("Attachment"))?;
+
+    let bytes = tokio::fs::read(&attachment.storage_path)
+        .await
+        .map_err(|_| not_found("Attachment file"))?;
+
+    // The mime type comes from the uploader, so only known images are shown
+    let inline = attachments::is_inline(&attachment.mime_type);
+    let content_type = if inline { attachment.mime_type.as_str() } else { "application/octet-stream" };
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_DISPOSITION, attachments::content_disposition(&attachment.filename, inline))
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(header::CONTENT_SECURITY_POLICY, "sandbox")
+        .body(Body::from(bytes))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })
+}
+
+async fn delete_attachment(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let attachment = Attachment::find_by_id(&state.db, &id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| not_found("Attachment"))?;
+
+    attachments::remove(&state.db, &attachment).await.map_err(internal_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,525 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use eval_kanban_db::{
+    AttemptStatus, CheckStatus, CreateTaskAttempt, PromptTemplate, RunCheck, RunUsage, Task, TaskAttempt, TaskRun,
+    TaskStatus,
+};
+use eval_kanban_worktree::{DiffResponse, get_worktree_diff};
+
+use crate::budget::{self, BudgetBreach};
+use crate::project::ProjectContext;
+use crate::prompt;
+use crate::routes::tasks::{self, RunEnd, RunSpec};
+use crate::routes::ws::WsMessage;
+use crate::settings::OnCheckFailure;
+use crate::state::AppState;
+
+/// Most attempts a task can be started with at once
+const MAX_ATTEMPTS: usize = 8;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// Start a task as several attempts. Either `count` attempts with the task's
+/// own settings, or one per entry of `variants`.
+#[derive(Deserialize)]
+struct StartAttemptsRequest {
+    count: Option<usize>,
+    variants: Option<Vec<AttemptVariant>>,
+    /// Feedback on a previous attempt, available to templates as `{{feedback}}`
+    feedback: Option<String>,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+struct AttemptVariant {
+    /// Model to run with, e.g. `sonnet` or `opus`
+    model: Option<String>,
+    /// Prompt template to use instead of the task's
+    prompt_template_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AttemptsResponse {
+    attempts: Vec<AttemptComparison>,
+}
+
+/// An attempt with what is needed to compare it to the others.
+#[derive(Serialize)]
+struct AttemptComparison {
+    #[serde(flatten)]
+    attempt: TaskAttempt,
+    /// Runs of the attempt, more than one if checks were auto-fixed
+    runs: usize,
+    usage: RunUsage,
+    /// From start until the last run (and its checks) finished
+    duration_secs: Option<f64>,
+    /// Checks of the latest run
+    checks: Vec<RunCheck>,
+    /// Changes in the attempt's worktree, while it still exists
+    diff: Option<DiffStat>,
+}
+
+#[derive(Serialize)]
+struct DiffStat {
+    files_changed: usize,
+    additions: usize,
+    deletions: usize,
+}
+
+#[derive(Serialize)]
+struct TaskResponse {
+    #[serde(flatten)]
+    task: Task,
+}
+
+pub fn attempts_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/tasks/:id/attempts", get(list_attempts).post(start_attempts))
+        .route("/tasks/:id/attempts/:attempt_id/diff", get(get_attempt_diff))
+        .route("/tasks/:id/attempts/:attempt_id/pick", post(pick_attempt))
+}
+
+fn error_response(status: StatusCode, error: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse { error: error.into() }))
+}
+
+fn internal_error(e: impl ToString) -> (StatusCode, Json<ErrorResponse>) {
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+async fn find_task(state: &AppState, id: &str) -> Result<Task, (StatusCode, Json<ErrorResponse>)> {
+    Task::find_by_id(&state.db, id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "Task not found"))
+}
+
+async fn find_attempt(state: &AppState, task_id: &str, attempt_id: &str) -> Result<TaskAttempt, (StatusCode, Json<ErrorResponse>)> {
+    TaskAttempt::find_by_id(&state.db, attempt_id)
+        .await
+        .map_err(internal_error)?
+        .filter(|attempt| attempt.task_id == task_id)
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "Attempt not found"))
+}
+
+async fn start_attempts(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<StartAttemptsRequest>,
+) -> Result<Json<AttemptsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let variants = match (req.variants, req.count) {
+        (Some(variants), _) => variants,
+        (None, Some(count)) => vec![AttemptVariant::default(); count],
+        (None, None) => return Err(error_response(StatusCode::BAD_REQUEST, "Give a count or variants")),
+    };
+    if variants.len() < 2 || variants.len() > MAX_ATTEMPTS {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            format!("Start between 2 and {} attempts", MAX_ATTEMPTS),
+        ));
+    }
+    for variant in &variants {
+        if variant.model.as_deref().is_some_and(|m| m.trim().is_empty()) {
+            return Err(error_response(StatusCode::BAD_REQUEST, "Model must not be empty"));
+        }
+        if let Some(template_id) = variant.prompt_template_id.as_deref() {
+            let template = PromptTemplate::find_by_id(&state.db, template_id).await.map_err(internal_error)?;
+            if template.is_none() {
+                return Err(error_response(StatusCode::BAD_REQUEST, "Prompt template not found"));
+            }
+        }
+    }
+
+    // Reserved before any worktree is created and released if starting fails
+    let (cancel_tx, cancel_rx) = mpsc::channel::<()>(1);
+    if !state.try_add_running_task(id.clone(), cancel_tx).await {
+        return Err(error_response(StatusCode::CONFLICT, "Task is already running"));
+    }
+
+    let attempts = match create_attempts(&state, &id, variants, req.feedback.as_deref()).await {
+        Ok((attempts, specs)) => {
+            tokio::spawn(run_attempts(state.clone(), id.clone(), specs, cancel_rx));
+            attempts
+        }
+        Err(e) => {
+            state.remove_running_task(&id).await;
+            return Err(e);
+        }
+    };
+
+    let attempts = compare(&state, &id, attempts).await.map_err(internal_error)?;
+    Ok(Json(AttemptsResponse { attempts }))
+}
+
+/// Create the attempts of a new round, each with its own worktree, and mark
+/// the task in progress. The caller has reserved the task as running.
+async fn create_attempts(
+    state: &Arc<AppState>,
+    id: &str,
+    variants: Vec<AttemptVariant>,
+    feedback: Option<&str>,
+) -> Result<(Vec<TaskAttempt>, Vec<RunSpec>), (StatusCode, Json<ErrorResponse>)> {
+    let task = find_task(state, id).await?;
+
+    if task.worktree_path.is_some() {
+        return Err(error_response(
+            StatusCode::CONFLICT,
+            "Task already has a worktree; merge or delete it before starting attempts",
+        ));
+    }
+
+    let blocking = tasks::unfinished_dependencies(state, id).await.map_err(internal_error)?;
+    if !blocking.is_empty() {
+        let titles: Vec<&str> = blocking.iter().map(|t| t.title.as_str()).collect();
+        return Err(error_response(
+            StatusCode::CONFLICT,
+            format!("Task is waiting for: {}", titles.join(", ")),
+        ));
+    }
+
+    if let Some(breach) = budget::check_task_spend(&state.db, &task).await.map_err(internal_error)? {
+        return Err(error_response(StatusCode::CONFLICT, breach.message));
+    }
+
+    let project = state.project_for_task(&task).await.map_err(internal_error)?;
+    if !project.worktree_manager.is_git_repo() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "Attempts need a git repository"));
+    }
+
+    // A new round replaces attempts that were never picked
+    let previous = TaskAttempt::find_by_task(&state.db, id).await.map_err(internal_error)?;
+    discard_attempts(state, &project, &previous).await;
+
+    let branch_prefix = state.settings.get(&project.path).worktree.branch_prefix.clone();
+    let mut specs = Vec::new();
+    let mut attempts = Vec::new();
+    for (index, variant) in variants.into_iter().enumerate() {
+        let attempt = TaskAttempt::create(&state.db, CreateTaskAttempt {
+            task_id: id.to_string(),
+            // Numbers go on from earlier rounds
+            number: (previous.len() + index) as i64 + 1,
+            model: variant.model.map(|m| m.trim().to_string()),
+            prompt_template_id: variant.prompt_template_id,
+        })
+        .await
+        .map_err(internal_error)?;
+        attempts.push(attempt.clone());
+
+        let title = format!("{} attempt {}", task.title, attempt.number);
+        let (branch, wt_path) = match project.worktree_manager.create_worktree(&branch_prefix, &title, &attempt.id).await {
+            Ok(created) => created,
+            Err(e) => {
+                tracing::error!("Failed to create worktree for attempt {} of task {}: {}", attempt.number, id, e);
+                discard_attempts(state, &project, &attempts).await;
+                return Err(internal_error(format!("Failed to create worktree: {}", e)));
+            }
+        };
+        let wt_path_string = wt_path.to_string_lossy().to_string();
+        let attempt = TaskAttempt::set_worktree(&state.db, &attempt.id, &branch, &wt_path_string)
+            .await
+            .map_err(internal_error)?
+            .unwrap_or(attempt);
+        *attempts.last_mut().unwrap() = attempt.clone();
+
+        // Rendered as if it were the task, on the attempt's branch
+        let attempt_task = Task {
+            branch_name: Some(branch),
+            prompt_template_id: attempt.prompt_template_id.clone().or(task.prompt_template_id.clone()),
+            ..task.clone()
+        };
+        let attempt_prompt = prompt::build_task_prompt(state, &attempt_task, feedback)
+            .await
+            .map_err(internal_error)?;
+
+        specs.push(RunSpec {
+            task_id: id.to_string(),
+            attempt_id: Some(attempt.id.clone()),
+            working_dir: wt_path,
+            project_path: project.path.clone(),
+            prompt: attempt_prompt,
+            model: attempt.model.clone(),
+        });
+    }
+
+    // A new round's checks replace the last run's
+    if task.checks_status.is_some() {
+        let _ = Task::set_checks_status(&state.db, id, None).await;
+    }
+
+    let updated = Task::set_status(&state.db, id, TaskStatus::InProgress)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "Task not found"))?;
+    state.broadcast(WsMessage::TaskUpdated { task: updated }).await;
+    for attempt in &attempts {
+        state.broadcast(WsMessage::AttemptUpdated { attempt: attempt.clone() }).await;
+    }
+
+    Ok((attempts, specs))
+}
+
+/// Run the attempts side by side, passing a cancel of the task on to each
+/// of them, and update the task once all are done: review if any succeeded.
+async fn run_attempts(state: Arc<AppState>, task_id: String, specs: Vec<RunSpec>, mut cancel_rx: mpsc::Receiver<()>) {
+    let mut cancels = Vec::new();
+    let mut running = JoinSet::new();
+    for spec in specs {
+        let (attempt_cancel_tx, mut attempt_cancel_rx) = mpsc::channel::<()>(1);
+        cancels.push(attempt_cancel_tx);
+
+        let state = state.clone();
+        running.spawn(async move {
+            let end = tasks::run_to_completion(&state, &spec, &mut attempt_cancel_rx).await;
+            let attempt_id = spec.attempt_id.as_deref().unwrap_or_default();
+            finish_attempt(&state, attempt_id, end).await
+        });
+    }
+
+    let mut cancelled = false;
+    let mut succeeded = false;
+    let mut breach: Option<BudgetBreach> = None;
+    loop {
+        tokio::select! {
+            finished = running.join_next() => {
+                match finished {
+                    Some(Ok(RunEnd::Finished { success, .. })) => succeeded |= success,
+                    Some(Ok(RunEnd::OverBudget(b))) => breach = breach.or(Some(b)),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => tracing::error!("Attempt of task {} panicked: {}", task_id, e),
+                    None => break,
+                }
+            }
+            Some(()) = cancel_rx.recv(), if !cancelled => {
+                tracing::info!("Task {} cancelled, stopping its attempts", task_id);
+                cancelled = true;
+                for cancel in &cancels {
+                    let _ = cancel.try_send(());
+                }
+            }
+        }
+    }
+
+    if cancelled {
+        if let Ok(Some(task)) = Task::set_status(&state.db, &task_id, TaskStatus::Todo).await {
+            state.broadcast(WsMessage::TaskUpdated { task }).await;
+        }
+    } else if succeeded {
+        if let Ok(Some(task)) = Task::set_status(&state.db, &task_id, TaskStatus::Review).await {
+            state.broadcast(WsMessage::TaskUpdated { task }).await;
+        }
+
+        state.broadcast(WsMessage::ExecutionComplete {
+            task_id: task_id.clone(),
+            success: true,
+        }).await;
+    } else if let Some(breach) = breach {
+        tasks::set_budget_exceeded(&state, &task_id, breach).await;
+    } else {
+        if let Ok(Some(task)) = Task::set_error(&state.db, &task_id, "All attempts failed".to_string()).await {
+            state.broadcast(WsMessage::TaskUpdated { task }).await;
+        }
+
+        state.broadcast(WsMessage::ExecutionComplete {
+            task_id: task_id.clone(),
+            success: false,
+        }).await;
+    }
+
+    state.remove_running_task(&task_id).await;
+}
+
+/// Record how an attempt ended. Failing checks don't fail the attempt; they
+/// are shown when comparing it.
+async fn finish_attempt(state: &AppState, attempt_id: &str, end: RunEnd) -> RunEnd {
+    let (status, error) = match &end {
+        RunEnd::Finished { success: true, .. } => (AttemptStatus::Succeeded, None),
+        RunEnd::Finished { success: false, .. } => (
+            AttemptStatus::Failed,
+            Some("Executor completed with non-zero exit code".to_string()),
+        ),
+        RunEnd::SpawnFailed(error) => (AttemptStatus::Failed, Some(error.clone())),
+        RunEnd::Cancelled => (AttemptStatus::Cancelled, None),
+        RunEnd::OverBudget(breach) => (AttemptStatus::BudgetExceeded, Some(breach.message.clone())),
+    };
+
+    match TaskAttempt::finish(&state.db, attempt_id, status, error).await {
+        Ok(Some(attempt)) => state.broadcast(WsMessage::AttemptUpdated { attempt }).await,
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to record end of attempt {}: {}", attempt_id, e),
+    }
+    end
+}
+
+/// Remove the worktrees and branches of attempts that weren't picked, and
+/// mark them discarded.
+pub async fn discard_attempts(state: &AppState, project: &ProjectContext, attempts: &[TaskAttempt]) {
+    for attempt in attempts {
+        if matches!(attempt.status, AttemptStatus::Picked | AttemptStatus::Discarded) {
+            continue;
+        }
+
+        if let Some(wt_path) = &attempt.worktree_path {
+            if let Err(e) = project.worktree_manager.remove_worktree(&PathBuf::from(wt_path)).await {
+                tracing::warn!("Failed to remove worktree of attempt {}: {}", attempt.id, e);
+            }
+        }
+        if let Some(branch) = &attempt.branch_name {
+            if let Err(e) = project.worktree_manager.discard_branch(branch).await {
+                tracing::warn!("Failed to delete branch of attempt {}: {}", attempt.id, e);
+            }
+        }
+
+        if let Ok(Some(attempt)) = TaskAttempt::set_status(&state.db, &attempt.id, AttemptStatus::Discarded).await {
+            state.broadcast(WsMessage::AttemptUpdated { attempt }).await;
+        }
+    }
+}
+
+async fn list_attempts(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<AttemptsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    find_task(&state, &id).await?;
+    let attempts = TaskAttempt::find_by_task(&state.db, &id).await.map_err(internal_error)?;
+    let attempts = compare(&state, &id, attempts).await.map_err(internal_error)?;
+    Ok(Json(AttemptsResponse { attempts }))
+}
+
+/// Totals of each attempt's runs, its latest checks and the size of its diff.
+async fn compare(state: &AppState, task_id: &str, attempts: Vec<TaskAttempt>) -> Result<Vec<AttemptComparison>, sqlx::Error> {
+    let runs = TaskRun::find_by_task(&state.db, task_id).await?;
+
+    let mut comparisons = Vec::new();
+    for attempt in attempts {
+        let attempt_runs: Vec<&TaskRun> = runs.iter()
+            .filter(|run| run.attempt_id.as_deref() == Some(attempt.id.as_str()))
+            .collect();
+
+        let mut usage = RunUsage::default();
+        for run in &attempt_runs {
+            usage += run.usage;
+        }
+
+        let duration_secs = attempt.finished_at
+            .map(|finished| (finished - attempt.created_at).num_milliseconds() as f64 / 1000.0);
+        let checks = attempt_runs.last().map(|run| run.checks.clone()).unwrap_or_default();
+
+        let diff = match attempt.worktree_path.as_deref().map(PathBuf::from) {
+            Some(path) if attempt.status != AttemptStatus::Discarded && path.exists() => {
+                match tokio::task::spawn_blocking(move || get_worktree_diff(&path)).await {
+                    Ok(Ok(diff)) => Some(DiffStat {
+                        files_changed: diff.files.len(),
+                        additions: diff.total_additions,
+                        deletions: diff.total_deletions,
+                    }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        comparisons.push(AttemptComparison {
+            runs: attempt_runs.len(),
+            usage,
+            duration_secs,
+            checks,
+            diff,
+            attempt,
+        });
+    }
+    Ok(comparisons)
+}
+
+async fn get_attempt_diff(
+    State(state): State<Arc<AppState>>,
+    Path((id, attempt_id)): Path<(String, String)>,
+) -> Result<Json<DiffResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let attempt = find_attempt(&state, &id, &attempt_id).await?;
+
+    let worktree_path = attempt.worktree_path
+        .filter(|_| attempt.status != AttemptStatus::Discarded)
+        .map(PathBuf::from)
+        .ok_or_else(|| error_response(StatusCode::BAD_REQUEST, "Attempt has no worktree"))?;
+    if !worktree_path.exists() {
+        return Err(error_response(StatusCode::NOT_FOUND, "Worktree directory not found"));
+    }
+
+    let diff = tokio::task::spawn_blocking(move || get_worktree_diff(&worktree_path))
+        .await
+        .map_err(internal_error)?
+        .map_err(internal_error)?;
+
+    Ok(Json(diff))
+}
+
+/// Make a finished attempt's branch and worktree the task's, ready for
+/// review and merge, and discard the other attempts.
+async fn pick_attempt(
+    State(state): State<Arc<AppState>>,
+    Path((id, attempt_id)): Path<(String, String)>,
+) -> Result<Json<TaskResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let task = find_task(&state, &id).await?;
+    let attempt = find_attempt(&state, &id, &attempt_id).await?;
+
+    if state.is_task_running(&id).await {
+        return Err(error_response(StatusCode::CONFLICT, "Task is still running"));
+    }
+    if !attempt.status.is_finished() {
+        return Err(error_response(
+            StatusCode::CONFLICT,
+            format!("Attempt is {}", attempt.status.as_str()),
+        ));
+    }
+    let (Some(branch), Some(wt_path)) = (attempt.branch_name.clone(), attempt.worktree_path.clone()) else {
+        return Err(error_response(StatusCode::BAD_REQUEST, "Attempt has no worktree"));
+    };
+    if !PathBuf::from(&wt_path).exists() {
+        return Err(error_response(StatusCode::NOT_FOUND, "Worktree directory not found"));
+    }
+
+    let project = state.project_for_task(&task).await.map_err(internal_error)?;
+
+    Task::set_worktree(&state.db, &id, branch, wt_path).await.map_err(internal_error)?;
+    Task::set_checks_status(&state.db, &id, attempt.checks_status).await.map_err(internal_error)?;
+
+    let on_failure = state.settings.get(&project.path).verify.on_failure;
+    let status = if attempt.checks_status == Some(CheckStatus::Failed) && on_failure == OnCheckFailure::Block {
+        TaskStatus::ChecksFailed
+    } else {
+        TaskStatus::Review
+    };
+    let updated = Task::set_status(&state.db, &id, status)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "Task not found"))?;
+
+    if let Ok(Some(picked)) = TaskAttempt::set_status(&state.db, &attempt.id, AttemptStatus::Picked).await {
+        state.broadcast(WsMessage::AttemptUpdated { attempt: picked }).await;
+    }
+
+    let others: Vec<TaskAttempt> = TaskAttempt::find_by_task(&state.db, &id)
+        .await
+        .map_err(internal_error)?
+        .into_iter()
+        .filter(|other| other.id != attempt.id)
+        .collect();
+    discard_attempts(&state, &project, &others).await;
+
+    tracing::info!("Task {} picked attempt {}", id, attempt.number);
+    state.broadcast(WsMessage::TaskUpdated { task: updated.clone() }).await;
+
+    Ok(Json(TaskResponse { task: updated }))
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+
+use eval_kanban_db::{ProjectSettings, RunUsage, Task, TaskRun};
+
+use crate::budget::BudgetLimits;
+use crate::project::CurrentProject;
+use crate::state::AppState;
+use crate::routes::ws::WsMessage;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Serialize)]
+struct BudgetResponse {
+    limits: BudgetLimits,
+    spent: RunUsage,
+}
+
+/// New limits. Omitted or null fields remove the limit.
+#[derive(Deserialize)]
+struct SetBudgetRequest {
+    budget_usd: Option<f64>,
+    budget_tokens: Option<i64>,
+}
+
+pub fn budget_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/budget", get(get_project_budget).put(set_project_budget))
+        .route("/tasks/:id/budget", get(get_task_budget).put(set_task_budget))
+}
+
+fn validate(req: &SetBudgetRequest) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let negative_usd = req.budget_usd.is_some_and(|v| v < 0.0 || !v.is_finite());
+    let negative_tokens = req.budget_tokens.is_some_and(|v| v < 0);
+
+    if negative_usd || negative_tokens {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Budget limits must be non-negative".to_string(),
+            }),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn get_project_budget(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+) -> Result<Json<BudgetResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let project_path = project.path_string();
+    let map_err = |e: sqlx::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    };
+
+    let settings = ProjectSettings::get(&state.db, &project_path).await.map_err(map_err)?;
+    let spent = TaskRun::usage_for_project(&state.db, &project_path, None).await.map_err(map_err)?;
+
+    Ok(Json(BudgetResponse {
+        limits: BudgetLimits::for_project(&settings),
+        spent,
+    }))
+}
+
+async fn set_project_budget(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Json(req): Json<SetBudgetRequest>,
+) -> Result<Json<BudgetResponse>, (StatusCode, Json<ErrorResponse>)> {
+    validate(&req)?;
+
+    let project_path = project.path_string();
+    let map_err = |e: sqlx::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    };
+
+    let settings = ProjectSettings::set_budget(&state.db, &project_path, req.budget_usd, req.budget_tokens)
+        .await
+        .map_err(map_err)?;
+    let spent = TaskRun::usage_for_project(&state.db, &project_path, None).await.map_err(map_err)?;
+
+    tracing::info!(
+        "Project budget set: usd={:?}, tokens={:?}",
+        settings.budget_usd, settings.budget_tokens
+    );
+
+    Ok(Json(BudgetResponse {
+        limits: BudgetLimits::for_project(&settings),
+        spent,
+    }))
+}
+
+async fn get_task_budget(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<BudgetResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let map_err = |e: sqlx::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    };
+
+    let task = Task::find_by_id(&state.db, &id)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "Task not found".to_string(),
+                }),
+            )
+        })?;
+    let spent = TaskRun::usage_for_task(&state.db, &id, None).await.map_err(map_err)?;
+
+    Ok(Json(BudgetResponse {
+        limits: BudgetLimits::for_task(&task),
+        spent,
+    }))
+}
+
+async fn set_task_budget(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<SetBudgetRequest>,
+) -> Result<Json<BudgetResponse>, (StatusCode, Json<ErrorResponse>)> {
+    validate(&req)?;
+
+    let map_err = |e: sqlx::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    };
+
+    let task = Task::set_budget(&state.db, &id, req.budget_usd, req.budget_tokens)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "Task not found".to_string(),
+                }),
+            )
+        })?;
+    let spent = TaskRun::usage_for_task(&state.db, &id, None).await.map_err(map_err)?;
+
+    state.broadcast(WsMessage::TaskUpdated { task: task.clone() }).await;
+
+    Ok(Json(BudgetResponse {
+        limits: BudgetLimits::for_task(&task),
+        spent,
+    }))
+}
@@ -1,20 +1,28 @@
 use std::sync::Arc;
 use axum::{
     Json, Router,
-    extract::State,
+    extract::{Path, Query, State, rejection::JsonRejection},
     http::StatusCode,
     routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
-use std::process::Stdio;
+use tokio::sync::mpsc;
+use uuid::Uuid;
 
-use eval_kanban_db::{ChatMessage, CreateChatMessage};
+use eval_kanban_db::{Attachment, AttachmentOwner, ChatMessage, ChatThread, ChatToolCall, CreateChatMessage, CreateChatThread, CreateTask, Task};
 use eval_kanban_db::models::chat::ChatRole;
+use eval_kanban_db::models::chat_thread::DEFAULT_THREAD_TITLE;
+use eval_kanban_executor::{ClaudeExecutor, ExecutorEvent};
+use eval_kanban_executor::stream_json::{self, ContentBlock};
 
+use crate::attachments::{self, Upload};
+use crate::chat_context::{self, ProposedTask};
+use crate::project::{CurrentProject, ProjectContext};
 use crate::state::AppState;
-use crate::routes::ws::WsMessage;
+use crate::routes::{self, ws::WsMessage};
+
+/// Length of a thread title taken from its first message
+const AUTO_TITLE_CHARS: usize = 60;
 
 #[derive(Serialize)]
 struct ErrorResponse {
@@ -23,18 +31,61 @@ struct ErrorResponse {
 
 #[derive(Serialize)]
 struct ChatHistoryResponse {
+    thread_id: String,
     messages: Vec<ChatMessage>,
 }
 
+#[derive(Deserialize)]
+struct ThreadQuery {
+    /// Defaults to the most recently active thread
+    thread_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ThreadsResponse {
+    threads: Vec<ChatThread>,
+}
+
+#[derive(Deserialize)]
+struct CreateThreadRequest {
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RenameThreadRequest {
+    title: String,
+}
+
 #[derive(Deserialize)]
 struct SendMessageRequest {
+    /// Defaults to the most recently active thread
+    thread_id: Option<String>,
     content: String,
     image: Option<String>,
+    /// Files uploaded beforehand via `POST /api/chat/attachments`
+    #[serde(default)]
+    attachment_ids: Vec<String>,
 }
 
 #[derive(Serialize)]
 struct SendMessageResponse {
+    thread_id: String,
+    /// Id the reply is streamed and saved under
+    message_id: String,
     user_message: ChatMessage,
+    attachments: Vec<Attachment>,
+}
+
+#[derive(Deserialize, Default)]
+struct CancelRequest {
+    /// Defaults to the most recently active thread
+    thread_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CancelResponse {
+    thread_id: String,
+    message_id: String,
 }
 
 #[derive(Serialize)]
@@ -42,98 +93,425 @@ struct ClearHistoryResponse {
     deleted_count: u64,
 }
 
+#[derive(Serialize)]
+struct ProposedTasksResponse {
+    tasks: Vec<ProposedTask>,
+}
+
+/// Optional body for creating tasks from a message.
+#[derive(Deserialize, Default)]
+struct CreateTasksRequest {
+    /// Edited tasks to create instead of the ones read from the message
+    tasks: Option<Vec<ProposedTask>>,
+}
+
+#[derive(Serialize)]
+struct CreatedTasksResponse {
+    tasks: Vec<Task>,
+}
+
 pub fn chat_router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/history", get(get_history).delete(clear_history))
         .route("/message", post(send_message))
+        .route("/cancel", post(cancel_reply))
+        .route("/threads", get(list_threads).post(create_thread))
+        .route("/threads/:id", get(get_thread).patch(rename_thread).delete(delete_thread))
+        .route("/threads/:id/messages", get(get_thread_messages))
+        .route("/messages/:id/tasks", get(preview_message_tasks).post(create_message_tasks))
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse { error: e.to_string() }),
+    )
+}
+
+fn thread_not_found() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Chat thread not found".to_string() }),
+    )
+}
+
+/// The requested thread of the current project, or its most recent one.
+async fn resolve_thread(
+    state: &AppState,
+    project: &ProjectContext,
+    thread_id: Option<&str>,
+) -> Result<ChatThread, (StatusCode, Json<ErrorResponse>)> {
+    let project_path = project.path_string();
+
+    match thread_id {
+        Some(id) => ChatThread::find_by_id(&state.db, id)
+            .await
+            .map_err(internal_error)?
+            .filter(|thread| thread.project_path == project_path)
+            .ok_or_else(thread_not_found),
+        None => ChatThread::find_or_create_latest(&state.db, &project_path)
+            .await
+            .map_err(internal_error),
+    }
+}
+
+async fn list_threads(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+) -> Result<Json<ThreadsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let project_path = project.path_string();
+
+    let threads = ChatThread::find_all_by_project(&state.db, &project_path)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(ThreadsResponse { threads }))
+}
+
+async fn create_thread(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Json(req): Json<CreateThreadRequest>,
+) -> Result<Json<ChatThread>, (StatusCode, Json<ErrorResponse>)> {
+    let project_path = project.path_string();
+
+    let thread = ChatThread::create(&state.db, CreateChatThread { project_path, title: req.title })
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(thread))
+}
+
+async fn get_thread(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Path(id): Path<String>,
+) -> Result<Json<ChatThread>, (StatusCode, Json<ErrorResponse>)> {
+    Ok(Json(resolve_thread(&state, &project, Some(&id)).await?))
+}
+
+async fn rename_thread(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Path(id): Path<String>,
+    Json(req): Json<RenameThreadRequest>,
+) -> Result<Json<ChatThread>, (StatusCode, Json<ErrorResponse>)> {
+    let title = req.title.trim();
+    if title.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Title cannot be empty".to_string() }),
+        ));
+    }
+
+    resolve_thread(&state, &project, Some(&id)).await?;
+
+    let thread = ChatThread::rename(&state.db, &id, title)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(thread_not_found)?;
+
+    Ok(Json(thread))
+}
+
+async fn delete_thread(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    resolve_thread(&state, &project, Some(&id)).await?;
+
+    state.cancel_chat_generation(&id).await;
+    remove_thread_attachments(&state, &id).await;
+
+    ChatThread::delete(&state.db, &id)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_thread_messages(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Path(id): Path<String>,
+) -> Result<Json<ChatHistoryResponse>, (StatusCode, Json<ErrorResponse>)> {
+    get_history(State(state), project, Query(ThreadQuery { thread_id: Some(id) })).await
 }
 
 async fn get_history(
     State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Query(query): Query<ThreadQuery>,
 ) -> Result<Json<ChatHistoryResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let project_path = state.working_dir.to_string_lossy().to_string();
+    let thread = resolve_thread(&state, &project, query.thread_id.as_deref()).await?;
 
-    let messages = ChatMessage::find_by_project(&state.db, &project_path, Some(100))
+    let messages = ChatMessage::find_by_thread(&state.db, &thread.id, Some(100))
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: e.to_string() }),
-            )
-        })?;
+        .map_err(internal_error)?;
 
-    Ok(Json(ChatHistoryResponse { messages }))
+    Ok(Json(ChatHistoryResponse { thread_id: thread.id, messages }))
 }
 
+/// Clear one thread's messages, or the whole project's chat if no thread is given.
 async fn clear_history(
     State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Query(query): Query<ThreadQuery>,
 ) -> Result<Json<ClearHistoryResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let project_path = state.working_dir.to_string_lossy().to_string();
+    let project_path = project.path_string();
+
+    if let Some(thread_id) = query.thread_id {
+        resolve_thread(&state, &project, Some(&thread_id)).await?;
+        remove_thread_attachments(&state, &thread_id).await;
+
+        let deleted_count = ChatMessage::delete_by_thread(&state.db, &thread_id)
+            .await
+            .map_err(internal_error)?;
+
+        return Ok(Json(ClearHistoryResponse { deleted_count }));
+    }
+
+    if let Ok(chat_attachments) = Attachment::find_by_project(&state.db, &project_path, AttachmentOwner::ChatMessage).await {
+        attachments::remove_all(&state.db, &chat_attachments).await;
+    }
 
     let deleted_count = ChatMessage::delete_by_project(&state.db, &project_path)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: e.to_string() }),
-            )
-        })?;
+        .map_err(internal_error)?;
 
     Ok(Json(ClearHistoryResponse { deleted_count }))
 }
 
+async fn remove_thread_attachments(state: &AppState, thread_id: &str) {
+    let Ok(messages) = ChatMessage::find_by_thread(&state.db, thread_id, Some(i64::MAX)).await else {
+        return;
+    };
+
+    for message in messages {
+        if let Ok(message_attachments) = Attachment::find_by_owner(&state.db, AttachmentOwner::ChatMessage, &message.id).await {
+            attachments::remove_all(&state.db, &message_attachments).await;
+        }
+    }
+}
+
 async fn send_message(
     State(state): State<Arc<AppState>>,
+    project: CurrentProject,
     Json(req): Json<SendMessageRequest>,
 ) -> Result<Json<SendMessageResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let project_path = state.working_dir.to_string_lossy().to_string();
+    let project_path = project.path_string();
+    let thread = resolve_thread(&state, &project, req.thread_id.as_deref()).await?;
+
+    // One reply at a time per thread, so chunks of two answers never interleave
+    let message_id = Uuid::new_v4().to_string();
+    let (cancel_tx, cancel_rx) = mpsc::channel::<()>(1);
+    if let Err(in_progress) = state.start_chat_generation(&thread.id, message_id.clone(), cancel_tx).await {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: format!("A reply is still being generated in this thread ({}); cancel it or wait", in_progress),
+            }),
+        ));
+    }
 
     // Save user message
-    let user_message = ChatMessage::create(
+    let user_message = match ChatMessage::create(
         &state.db,
         CreateChatMessage {
             project_path: project_path.clone(),
+            thread_id: Some(thread.id.clone()),
             role: ChatRole::User,
             content: req.content.clone(),
             image_data: req.image.clone(),
+            tool_calls: Vec::new(),
         },
     )
     .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e.to_string() }),
-        )
-    })?;
+    {
+        Ok(message) => message,
+        Err(e) => {
+            state.finish_chat_generation(&thread.id, &message_id).await;
+            return Err(internal_error(e));
+        }
+    };
+
+    // Name untitled threads after their first message
+    if thread.title == DEFAULT_THREAD_TITLE && !req.content.trim().is_empty() {
+        let title: String = req.content.trim().chars().take(AUTO_TITLE_CHARS).collect();
+        if let Err(e) = ChatThread::rename(&state.db, &thread.id, &title).await {
+            tracing::warn!("[Chat] Failed to title thread {}: {}", thread.id, e);
+        }
+    }
+    if let Err(e) = ChatThread::touch(&state.db, &thread.id).await {
+        tracing::warn!("[Chat] Failed to update thread {}: {}", thread.id, e);
+    }
+
+    // Store the pasted image as a file so Claude can open it
+    if let Some(image) = req.image.clone() {
+        let upload = Upload {
+            filename: "pasted-image.png".to_string(),
+            mime_type: Some("image/png".to_string()),
+            data: image,
+        };
+        if let Err(e) = attachments::store(
+            &state.db,
+            &project_path,
+            AttachmentOwner::ChatMessage,
+            Some(user_message.id.clone()),
+            upload,
+        ).await {
+            tracing::warn!("[Chat] Failed to store pasted image: {}", e);
+        }
+    }
+
+    for attachment_id in &req.attachment_ids {
+        match Attachment::assign(&state.db, attachment_id, AttachmentOwner::ChatMessage, &user_message.id).await {
+            Ok(true) => {}
+            Ok(false) => tracing::warn!("[Chat] Attachment {} not found or already sent", attachment_id),
+            Err(e) => tracing::warn!("[Chat] Failed to attach {}: {}", attachment_id, e),
+        }
+    }
+
+    let message_attachments = Attachment::find_by_owner(&state.db, AttachmentOwner::ChatMessage, &user_message.id)
+        .await
+        .unwrap_or_default();
 
     // Spawn Claude in background and stream response
     let state_clone = state.clone();
+    let project_context = project.0.clone();
     let content = req.content.clone();
-    let image = req.image.clone();
-    let project_path_clone = project_path.clone();
+    let attachments_clone = message_attachments.clone();
+    let thread_id = thread.id.clone();
+    let reply_id = message_id.clone();
 
     tokio::spawn(async move {
-        spawn_claude_chat(state_clone, project_path_clone, content, image).await;
+        let reply = PendingReply {
+            thread_id: thread_id.clone(),
+            message_id: reply_id.clone(),
+            cancel_rx,
+        };
+        spawn_claude_chat(state_clone.clone(), project_context, reply, content, attachments_clone).await;
+        state_clone.finish_chat_generation(&thread_id, &reply_id).await;
     });
 
-    Ok(Json(SendMessageResponse { user_message }))
+    Ok(Json(SendMessageResponse {
+        thread_id: thread.id,
+        message_id,
+        user_message,
+        attachments: message_attachments,
+    }))
+}
+
+/// Stop the reply being generated in a thread. What was written so far is kept.
+async fn cancel_reply(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    body: Result<Json<CancelRequest>, JsonRejection>,
+) -> Result<Json<CancelResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let req: CancelRequest = routes::optional_body(body)
+        .map_err(|error| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })))?;
+    let thread = resolve_thread(&state, &project, req.thread_id.as_deref()).await?;
+
+    match state.cancel_chat_generation(&thread.id).await {
+        Some(message_id) => Ok(Json(CancelResponse { thread_id: thread.id, message_id })),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: "No reply is being generated in this thread".to_string() }),
+        )),
+    }
+}
+
+/// A message of the current project.
+async fn find_message(
+    state: &AppState,
+    project: &ProjectContext,
+    id: &str,
+) -> Result<ChatMessage, (StatusCode, Json<ErrorResponse>)> {
+    let project_path = project.path_string();
+
+    ChatMessage::find_by_id(&state.db, id)
+        .await
+        .map_err(internal_error)?
+        .filter(|message| message.project_path == project_path)
+        .ok_or_else(|| (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: "Chat message not found".to_string() }),
+        ))
+}
+
+/// The tasks a message would be turned into.
+async fn preview_message_tasks(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Path(id): Path<String>,
+) -> Result<Json<ProposedTasksResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let message = find_message(&state, &project, &id).await?;
+
+    Ok(Json(ProposedTasksResponse {
+        tasks: chat_context::proposed_tasks(&message.content),
+    }))
+}
+
+/// Turn a message into tasks: one per list item it contains, or the whole
+/// message as one task. The body can replace them with edited tasks.
+async fn create_message_tasks(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Path(id): Path<String>,
+    body: Result<Json<CreateTasksRequest>, JsonRejection>,
+) -> Result<Json<CreatedTasksResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let req: CreateTasksRequest = routes::optional_body(body)
+        .map_err(|error| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })))?;
+    let message = find_message(&state, &project, &id).await?;
+
+    let proposed = req.tasks.unwrap_or_else(|| chat_context::proposed_tasks(&message.content));
+    if proposed.is_empty() || proposed.iter().any(|t| t.title.trim().is_empty()) {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ErrorResponse { error: "Every task needs a title".to_string() }),
+        ));
+    }
+
+    let mut tasks = Vec::with_capacity(proposed.len());
+    for proposed_task in proposed {
+        let task = Task::create(
+            &state.db,
+            CreateTask {
+                title: proposed_task.title.trim().to_string(),
+                description: proposed_task.description.filter(|d| !d.trim().is_empty()),
+                project_path: message.project_path.clone(),
+            },
+        )
+        .await
+        .map_err(internal_error)?;
+
+        state.broadcast(WsMessage::TaskUpdated { task: task.clone() }).await;
+        tasks.push(task);
+    }
+
+    tracing::info!("[Chat] Created {} task(s) from message {}", tasks.len(), id);
+
+    Ok(Json(CreatedTasksResponse { tasks }))
 }
 
 /// Build prompt with conversation history
 async fn build_chat_prompt(
     state: &Arc<AppState>,
     project_path: &str,
+    thread_id: &str,
     new_content: &str,
-    new_image: Option<&str>,
+    new_attachments: &[Attachment],
 ) -> String {
     // Get recent history (last 20 messages for context)
-    let history = ChatMessage::find_by_project(&state.db, project_path, Some(20))
+    let history = ChatMessage::find_by_thread(&state.db, thread_id, Some(20))
         .await
         .unwrap_or_default();
 
     let mut prompt = String::new();
 
-    // System context
+    // System context. Read-only access is enforced through the allowed tools,
+    // this only explains it.
     prompt.push_str("You are a helpful assistant integrated into a Kanban task management app. ");
     prompt.push_str("You are running in READ-ONLY mode - you can read and analyze code, ");
     prompt.push_str("but you cannot modify files or execute commands that change the codebase. ");
@@ -155,159 +533,185 @@ async fn build_chat_prompt(
     prompt.push_str("## Current message\n\n");
     prompt.push_str(&format!("**User**: {}", new_content));
 
-    // Add image reference if present
-    if let Some(_image_data) = new_image {
-        prompt.push_str("\n\n[User has attached an image]");
+    // Point Claude at attached files (including pasted images)
+    if let Some(section) = attachments::prompt_section(new_attachments) {
+        prompt.push_str("\n\n");
+        prompt.push_str(&section);
+    }
+
+    // Pull in tasks referenced as #<id>
+    if let Some(section) = chat_context::referenced_tasks_section(state, project_path, new_content).await {
+        prompt.push_str("\n\n");
+        prompt.push_str(&section);
     }
 
     prompt
 }
 
-/// Spawn Claude and stream response via WebSocket
+/// A reply in progress, as handed to `spawn_claude_chat`.
+struct PendingReply {
+    thread_id: String,
+    message_id: String,
+    cancel_rx: mpsc::Receiver<()>,
+}
+
+/// Spawn Claude and stream its text and tool calls via WebSocket
 async fn spawn_claude_chat(
     state: Arc<AppState>,
-    project_path: String,
+    project: Arc<ProjectContext>,
+    reply: PendingReply,
     content: String,
-    image: Option<String>,
+    attachments: Vec<Attachment>,
 ) {
-    let prompt = build_chat_prompt(&state, &project_path, &content, image.as_deref()).await;
-
-    tracing::info!("[Chat] Spawning Claude for chat (prompt length: {})", prompt.len());
-
-    // Build command for read-only mode
-    let mut cmd = create_claude_command();
-
-    // Use --print with permission-mode for read-only
-    // Note: We use text output format for simpler parsing
-    cmd.args([
-        "--print",
-        "--permission-mode", "bypassPermissions", // Read-only mode
-    ])
-    .arg(&prompt)
-    .current_dir(&state.working_dir)
-    .stdin(Stdio::null())
-    .stdout(Stdio::piped())
-    .stderr(Stdio::piped())
-    .kill_on_drop(true);
-
-    let mut child = match cmd.spawn() {
-        Ok(child) => child,
+    let PendingReply { thread_id, message_id, mut cancel_rx } = reply;
+    let project_path = project.path_string();
+    let prompt = build_chat_prompt(&state, &project_path, &thread_id, &content, &attachments).await;
+
+    tracing::info!("[Chat {}] Spawning Claude for chat (prompt length: {})", thread_id, prompt.len());
+
+    let executor = ClaudeExecutor::new(project.path.clone());
+    let (mut rx, process) = match executor.spawn_chat(&prompt).await {
+        Ok(spawned) => spawned,
         Err(e) => {
-            tracing::error!("[Chat] Failed to spawn Claude: {}", e);
+            tracing::error!("[Chat {}] Failed to spawn Claude: {}", thread_id, e);
             state.broadcast(WsMessage::ChatError {
+                thread_id,
+                message_id,
                 error: format!("Failed to start Claude: {}", e),
             }).await;
             return;
         }
     };
 
-    let stdout = match child.stdout.take() {
-        Some(stdout) => stdout,
-        None => {
-            tracing::error!("[Chat] Failed to capture stdout");
-            state.broadcast(WsMessage::ChatError {
-                error: "Failed to capture Claude output".to_string(),
-            }).await;
-            return;
-        }
-    };
-
-    // Stream stdout line by line
-    let mut reader = BufReader::new(stdout).lines();
-    let mut full_response = String::new();
+    let (stop_tx, stop_rx) = mpsc::channel::<()>(1);
+    tokio::spawn(async move {
+        let _ = process.wait_or_stop(stop_rx).await;
+    });
 
-    while let Ok(Some(line)) = reader.next_line().await {
-        // Skip empty lines at the start
-        if full_response.is_empty() && line.trim().is_empty() {
-            continue;
+    let mut text_blocks: Vec<String> = Vec::new();
+    let mut tool_calls: Vec<ChatToolCall> = Vec::new();
+    let mut cancelled = false;
+
+    loop {
+        let event = tokio::select! {
+            event = rx.recv() => match event {
+                Some(event) => event,
+                None => break,
+            },
+            _ = cancel_rx.recv() => {
+                tracing::info!("[Chat {}] Reply cancelled", thread_id);
+                let _ = stop_tx.send(()).await;
+                cancelled = true;
+                break;
+            }
+        };
+
+        match event {
+            ExecutorEvent::Stdout(line) => {
+                for block in stream_json::content_blocks(&line) {
+                    match block {
+                        ContentBlock::Text(text) => {
+                            if text.trim().is_empty() {
+                                continue;
+                            }
+                            text_blocks.push(text.clone());
+                            state.broadcast(WsMessage::ChatChunk {
+                                thread_id: thread_id.clone(),
+                                message_id: message_id.clone(),
+                                content: text,
+                                is_complete: false,
+                            }).await;
+                        }
+                        ContentBlock::ToolUse { id, name, input } => {
+                            tracing::info!("[Chat {}] Tool call: {}", thread_id, name);
+                            tool_calls.push(ChatToolCall {
+                                id: id.clone(),
+                                name: name.clone(),
+                                input: input.clone(),
+                                is_error: false,
+                            });
+                            state.broadcast(WsMessage::ChatToolUse {
+                                thread_id: thread_id.clone(),
+                                message_id: message_id.clone(),
+                                tool_use_id: id,
+                                name,
+                                input,
+                            }).await;
+                        }
+                        ContentBlock::ToolResult { tool_use_id, is_error, .. } => {
+                            if let Some(call) = tool_calls.iter_mut().find(|c| c.id == tool_use_id) {
+                                call.is_error = is_error;
+                            }
+                            state.broadcast(WsMessage::ChatToolResult {
+                                thread_id: thread_id.clone(),
+                                message_id: message_id.clone(),
+                                tool_use_id,
+                                is_error,
+                            }).await;
+                        }
+                    }
+                }
+
+                // The result event is the last line of the answer
+                if stream_json::is_result(&line) {
+                    break;
+                }
+            }
+            ExecutorEvent::Stderr(line) => {
+                tracing::warn!("[Chat {}] Stderr: {}", thread_id, line);
+            }
+            // Output may still be buffered; the channel closes once it is read
+            ExecutorEvent::Completed { success: false } => {
+                tracing::warn!("[Chat {}] Claude process exited with non-success status", thread_id);
+            }
+            _ => {}
         }
-
-        full_response.push_str(&line);
-        full_response.push('\n');
-
-        // Stream chunk via WebSocket
-        state.broadcast(WsMessage::ChatChunk {
-            content: line,
-            is_complete: false,
-        }).await;
-    }
-
-    // Wait for process to complete
-    let status = child.wait().await;
-    let success = status.map(|s| s.success()).unwrap_or(false);
-
-    if !success {
-        tracing::warn!("[Chat] Claude process exited with non-success status");
     }
 
-    // Trim the response
-    let full_response = full_response.trim().to_string();
+    let full_response = text_blocks.join("\n\n").trim().to_string();
 
-    if full_response.is_empty() {
-        tracing::warn!("[Chat] Claude returned empty response");
+    if full_response.is_empty() && !cancelled {
+        tracing::warn!("[Chat {}] Claude returned empty response", thread_id);
         state.broadcast(WsMessage::ChatError {
+            thread_id,
+            message_id,
             error: "Claude returned an empty response".to_string(),
         }).await;
         return;
     }
 
-    // Save assistant response to database
-    if let Err(e) = ChatMessage::create(
-        &state.db,
-        CreateChatMessage {
-            project_path,
-            role: ChatRole::Assistant,
-            content: full_response.clone(),
-            image_data: None,
-        },
-    ).await {
-        tracing::error!("[Chat] Failed to save assistant message: {}", e);
+    // Save assistant response to database, including a partial one that was
+    // cancelled, unless the thread was deleted meanwhile
+    let thread_exists = matches!(ChatThread::find_by_id(&state.db, &thread_id).await, Ok(Some(_)));
+    if !full_response.is_empty() && thread_exists {
+        if let Err(e) = ChatMessage::create_with_id(
+            &state.db,
+            message_id.clone(),
+            CreateChatMessage {
+                project_path,
+                thread_id: Some(thread_id.clone()),
+                role: ChatRole::Assistant,
+                content: full_response.clone(),
+                image_data: None,
+                tool_calls,
+            },
+        ).await {
+            tracing::error!("[Chat {}] Failed to save assistant message: {}", thread_id, e);
+        }
+    }
+
+    if cancelled {
+        state.broadcast(WsMessage::ChatCancelled { thread_id, message_id }).await;
+        return;
     }
 
     // Send completion signal
     state.broadcast(WsMessage::ChatChunk {
+        thread_id: thread_id.clone(),
+        message_id,
         content: String::new(),
         is_complete: true,
     }).await;
 
-    tracing::info!("[Chat] Chat response completed ({} chars)", full_response.len());
-}
-
-#[cfg(windows)]
-fn get_node_path() -> String {
-    let common_paths = [
-        "C:\\Program Files\\nodejs\\node.exe",
-        "C:\\Program Files (x86)\\nodejs\\node.exe",
-    ];
-
-    for path in &common_paths {
-        if std::path::Path::new(path).exists() {
-            return path.to_string();
-        }
-    }
-
-    "node".to_string()
-}
-
-#[cfg(windows)]
-fn get_claude_cli_path() -> String {
-    if let Ok(appdata) = std::env::var("APPDATA") {
-        format!("{}\\npm\\node_modules\\@anthropic-ai\\claude-code\\cli.js", appdata)
-    } else {
-        "C:\\Users\\caio\\AppData\\Roaming\\npm\\node_modules\\@anthropic-ai\\claude-code\\cli.js".to_string()
-    }
-}
-
-#[cfg(windows)]
-fn create_claude_command() -> Command {
-    let node_path = get_node_path();
-    let cli_path = get_claude_cli_path();
-    let mut cmd = Command::new(&node_path);
-    cmd.arg(&cli_path);
-    cmd
-}
-
-#[cfg(not(windows))]
-fn create_claude_command() -> Command {
-    Command::new("claude")
+    tracing::info!("[Chat {}] Chat response completed ({} chars)", thread_id, full_response.len());
 }
@@ -0,0 +1,548 @@
+use std::collections::HashSet;
+use std::path::Path as FsPath;
+use std::sync::Arc;
+use std::time::Instant;
+use axum::{
+    Json, Router,
+    extract::{Path, State, rejection::JsonRejection},
+    http::StatusCode,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use eval_kanban_db::{
+    CheckStatus, CreateEvalRun, CreateEvalSuite, EvalCase, EvalResult, EvalResultStatus, EvalRun, EvalRunStatus,
+    EvalSuite, FinishEvalResult, ProjectSettings, PromptTemplate,
+};
+use eval_kanban_executor::ClaudeExecutor;
+use eval_kanban_worktree::WorktreeError;
+
+use crate::eval::{self, SuiteReport};
+use crate::project::{CurrentProject, ProjectContext};
+use crate::routes::{self, ws::WsMessage};
+use crate::run_events::{ClaudeRun, RunEvent};
+use crate::settings::VerifyCommand;
+use crate::state::AppState;
+use crate::verify;
+
+/// Revision a suite is pinned to when none is given
+const DEFAULT_BASE_COMMIT: &str = "HEAD";
+/// How long the score command may run, unless the suite says otherwise
+const DEFAULT_SCORE_TIMEOUT_SECS: u64 = 600;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Deserialize)]
+struct CreateSuiteRequest {
+    name: String,
+    /// Branch, tag or commit the cases start from, resolved to a commit now
+    base_commit: Option<String>,
+    score_command: String,
+    score_timeout_secs: Option<u64>,
+    cases: Vec<EvalCase>,
+}
+
+#[derive(Serialize)]
+struct SuitesResponse {
+    suites: Vec<EvalSuite>,
+}
+
+#[derive(Serialize)]
+struct SuiteResponse {
+    #[serde(flatten)]
+    suite: EvalSuite,
+    runs: Vec<EvalRun>,
+}
+
+/// Optional body for starting a run; omitted fields use the defaults.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct StartRunRequest {
+    label: Option<String>,
+    /// Template the case prompts are rendered with
+    prompt_template_id: Option<String>,
+    /// Model to run with, e.g. `sonnet` or `opus`
+    model: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RunResponse {
+    #[serde(flatten)]
+    run: EvalRun,
+    results: Vec<EvalResult>,
+}
+
+pub fn evals_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/evals", get(list_suites).post(create_suite))
+        .route("/evals/:id", get(get_suite).delete(delete_suite))
+        .route("/evals/:id/runs", post(start_run))
+        .route("/evals/:id/runs/:run_id", get(get_run))
+        .route("/evals/:id/runs/:run_id/cancel", post(cancel_run))
+        .route("/evals/:id/report", get(get_report))
+}
+
+fn error_response(status: StatusCode, error: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse { error: error.into() }))
+}
+
+fn internal_error(e: impl ToString) -> (StatusCode, Json<ErrorResponse>) {
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+async fn find_suite(state: &AppState, id: &str) -> Result<EvalSuite, (StatusCode, Json<ErrorResponse>)> {
+    EvalSuite::find_by_id(&state.db, id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "Eval suite not found"))
+}
+
+async fn find_run(state: &AppState, suite_id: &str, run_id: &str) -> Result<EvalRun, (StatusCode, Json<ErrorResponse>)> {
+    EvalRun::find_by_id(&state.db, run_id)
+        .await
+        .map_err(internal_error)?
+        .filter(|run| run.suite_id == suite_id)
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "Eval run not found"))
+}
+
+/// Every case needs a name and a prompt, and names must be unique since
+/// results are compared across runs by them.
+fn validate_cases(cases: &[EvalCase]) -> Result<(), String> {
+    if cases.is_empty() {
+        return Err("An eval suite needs at least one case".to_string());
+    }
+
+    let mut names = HashSet::new();
+    for case in cases {
+        if case.name.trim().is_empty() {
+            return Err("Every case needs a name".to_string());
+        }
+        if case.prompt.trim().is_empty() {
+            return Err(format!("Case {} has no prompt", case.name));
+        }
+        if !names.insert(case.name.as_str()) {
+            return Err(format!("Case {} is defined more than once", case.name));
+        }
+    }
+    Ok(())
+}
+
+async fn list_suites(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+) -> Result<Json<SuitesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let suites = EvalSuite::find_all_by_project(&state.db, &project.path_string())
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(SuitesResponse { suites }))
+}
+
+async fn create_suite(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Json(req): Json<CreateSuiteRequest>,
+) -> Result<(StatusCode, Json<EvalSuite>), (StatusCode, Json<ErrorResponse>)> {
+    if req.name.trim().is_empty() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "Eval suite name is required"));
+    }
+    if req.score_command.trim().is_empty() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "A score command is required"));
+    }
+    if req.score_timeout_secs == Some(0) {
+        return Err(error_response(StatusCode::BAD_REQUEST, "score_timeout_secs must be at least 1"));
+    }
+    let cases: Vec<EvalCase> = req.cases.into_iter()
+        .map(|case| EvalCase { name: case.name.trim().to_string(), prompt: case.prompt })
+        .collect();
+    validate_cases(&cases).map_err(|e| error_response(StatusCode::BAD_REQUEST, e))?;
+
+    if !project.worktree_manager.is_git_repo() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "Eval suites need the project to be a git repository"));
+    }
+    let rev = non_empty(req.base_commit).unwrap_or_else(|| DEFAULT_BASE_COMMIT.to_string());
+    let base_commit = project.worktree_manager.resolve_commit(&rev).await.map_err(|e| match e {
+        WorktreeError::CommitNotFound(_) => error_response(StatusCode::BAD_REQUEST, e.to_string()),
+        e => internal_error(e),
+    })?;
+
+    let suite = EvalSuite::create(
+        &state.db,
+        CreateEvalSuite {
+            project_path: project.path_string(),
+            name: req.name.trim().to_string(),
+            base_commit,
+            score_command: req.score_command,
+            score_timeout_secs: req.score_timeout_secs.unwrap_or(DEFAULT_SCORE_TIMEOUT_SECS) as i64,
+            cases,
+        },
+    )
+    .await
+    .map_err(internal_error)?;
+
+    Ok((StatusCode::CREATED, Json(suite)))
+}
+
+async fn get_suite(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<SuiteResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let suite = find_suite(&state, &id).await?;
+    let runs = EvalRun::find_by_suite(&state.db, &id).await.map_err(internal_error)?;
+
+    Ok(Json(SuiteResponse { suite, runs }))
+}
+
+async fn delete_suite(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    if state.is_eval_running(&id).await {
+        return Err(error_response(StatusCode::CONFLICT, "Cancel the suite's running eval first"));
+    }
+
+    if EvalSuite::delete(&state.db, &id).await.map_err(internal_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(error_response(StatusCode::NOT_FOUND, "Eval suite not found"))
+    }
+}
+
+async fn start_run(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    req: Result<Json<StartRunRequest>, JsonRejection>,
+) -> Result<(StatusCode, Json<RunResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let req: StartRunRequest = routes::optional_body(req)
+        .map_err(|error| error_response(StatusCode::BAD_REQUEST, error))?;
+    let suite = find_suite(&state, &id).await?;
+
+    // Reserved before anything is created and released if starting fails
+    let (cancel_tx, cancel_rx) = mpsc::channel::<()>(1);
+    if !state.try_add_running_eval(suite.id.clone(), cancel_tx).await {
+        return Err(error_response(StatusCode::CONFLICT, "The suite is already being run"));
+    }
+
+    match create_run(&state, suite.clone(), req, cancel_rx).await {
+        Ok(response) => Ok((StatusCode::ACCEPTED, Json(response))),
+        Err(e) => {
+            state.remove_running_eval(&suite.id).await;
+            Err(e)
+        }
+    }
+}
+
+/// Create a run of the suite with a pending result per case and start it.
+/// The caller has reserved the suite as running.
+async fn create_run(
+    state: &Arc<AppState>,
+    suite: EvalSuite,
+    req: StartRunRequest,
+    cancel_rx: mpsc::Receiver<()>,
+) -> Result<RunResponse, (StatusCode, Json<ErrorResponse>)> {
+    let prompt_template_id = non_empty(req.prompt_template_id);
+    let template = match prompt_template_id.as_deref() {
+        Some(template_id) => Some(
+            PromptTemplate::find_by_id(&state.db, template_id)
+                .await
+                .map_err(internal_error)?
+                .ok_or_else(|| error_response(StatusCode::BAD_REQUEST, "Template not found"))?,
+        ),
+        None => None,
+    };
+
+    let project = state.project_for_path(&suite.project_path).await.map_err(internal_error)?;
+    if !project.worktree_manager.is_git_repo() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "The suite's project is no longer a git repository"));
+    }
+    let settings = ProjectSettings::get(&state.db, &suite.project_path).await.map_err(internal_error)?;
+
+    let run = EvalRun::create(
+        &state.db,
+        CreateEvalRun {
+            suite_id: suite.id.clone(),
+            label: non_empty(req.label),
+            prompt_template_id,
+            model: non_empty(req.model),
+            cli_version: ClaudeExecutor::cli_version().await,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+    )
+    .await
+    .map_err(internal_error)?;
+
+    let mut results = Vec::with_capacity(suite.cases.len());
+    for (position, case) in suite.cases.iter().enumerate() {
+        let result = EvalResult::create(&state.db, &run.id, position as i64, &case.name)
+            .await
+            .map_err(internal_error)?;
+        results.push(result);
+    }
+
+    let prompts = suite.cases.iter()
+        .map(|case| eval::case_prompt(&suite, case, template.as_ref(), &settings))
+        .collect();
+
+    state.broadcast(WsMessage::EvalRunUpdated { run: run.clone() }).await;
+
+    let state_clone = state.clone();
+    let run_clone = run.clone();
+    let results_clone = results.clone();
+    tokio::spawn(async move {
+        run_suite(state_clone, project, suite, run_clone, results_clone, prompts, cancel_rx).await;
+    });
+
+    Ok(RunResponse { run, results })
+}
+
+async fn get_run(
+    State(state): State<Arc<AppState>>,
+    Path((id, run_id)): Path<(String, String)>,
+) -> Result<Json<RunResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let run = find_run(&state, &id, &run_id).await?;
+    let results = EvalResult::find_by_run(&state.db, &run_id).await.map_err(internal_error)?;
+
+    Ok(Json(RunResponse { run, results }))
+}
+
+async fn cancel_run(
+    State(state): State<Arc<AppState>>,
+    Path((id, run_id)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let run = find_run(&state, &id, &run_id).await?;
+
+    if run.status == EvalRunStatus::Running && state.cancel_eval(&id).await {
+        Ok(StatusCode::ACCEPTED)
+    } else {
+        Err(error_response(StatusCode::CONFLICT, "Eval run is not running"))
+    }
+}
+
+async fn get_report(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<SuiteReport>, (StatusCode, Json<ErrorResponse>)> {
+    let suite = find_suite(&state, &id).await?;
+
+    let mut runs = Vec::new();
+    for run in EvalRun::find_by_suite(&state.db, &id).await.map_err(internal_error)? {
+        let results = EvalResult::find_by_run(&state.db, &run.id).await.map_err(internal_error)?;
+        runs.push((run, results));
+    }
+
+    Ok(Json(SuiteReport::new(suite, runs)))
+}
+
+/// Run the cases one after another, each in a fresh worktree at the suite's
+/// commit, then record how the run ended.
+async fn run_suite(
+    state: Arc<AppState>,
+    project: Arc<ProjectContext>,
+    suite: EvalSuite,
+    run: EvalRun,
+    results: Vec<EvalResult>,
+    prompts: Vec<String>,
+    mut cancel_rx: mpsc::Receiver<()>,
+) {
+    tracing::info!("Eval run {} of suite {} started", run.id, suite.name);
+
+    let mut cancelled = false;
+    for (result, prompt) in results.iter().zip(&prompts) {
+        if !run_case(&state, &project, &suite, &run, result, prompt, &mut cancel_rx).await {
+            cancelled = true;
+            break;
+        }
+    }
+
+    let status = if cancelled {
+        if let Err(e) = EvalResult::skip_unfinished(&state.db, &run.id).await {
+            tracing::warn!("Failed to skip the remaining cases of eval run {}: {}", run.id, e);
+        }
+        EvalRunStatus::Cancelled
+    } else {
+        EvalRunStatus::Completed
+    };
+
+    match EvalRun::finish(&state.db, &run.id, status).await {
+        Ok(Some(run)) => state.broadcast(WsMessage::EvalRunUpdated { run }).await,
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to finish eval run {}: {}", run.id, e),
+    }
+    // Only once the run is finished, so the next one can't overlap it
+    state.remove_running_eval(&suite.id).await;
+    tracing::info!("Eval run {} of suite {} {}", run.id, suite.name, status.as_str());
+}
+
+/// How Claude's run of a case ended.
+enum ClaudeEnd {
+    Finished { success: bool },
+    SpawnFailed(String),
+    Cancelled,
+}
+
+/// Run Claude on a case and score the result. Returns false if the run was
+/// cancelled meanwhile.
+async fn run_case(
+    state: &AppState,
+    project: &ProjectContext,
+    suite: &EvalSuite,
+    run: &EvalRun,
+    result: &EvalResult,
+    prompt: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> bool {
+    if let Ok(Some(result)) = EvalResult::start(&state.db, &result.id).await {
+        state.broadcast(WsMessage::EvalResultUpdated { result }).await;
+    }
+    let started = Instant::now();
+
+    let worktree = match project.worktree_manager
+        .create_detached_worktree(&format!("eval {}", result.case_name), &result.id, &suite.base_commit)
+        .await
+    {
+        Ok(path) => path,
+        Err(e) => {
+            tracing::error!("Failed to create worktree for eval case {}: {}", result.case_name, e);
+            finish_case(state, &result.id, FinishEvalResult {
+                status: EvalResultStatus::Error,
+                score: None,
+                exit_code: None,
+                duration_ms: Some(started.elapsed().as_millis() as i64),
+                error: Some(format!("Failed to create worktree: {}", e)),
+                output: None,
+            }).await;
+            return true;
+        }
+    };
+
+    let outcome = match run_claude(state, &result.id, &worktree, prompt, run.model.clone(), cancel_rx).await {
+        ClaudeEnd::Cancelled => None,
+        ClaudeEnd::SpawnFailed(e) => Some(FinishEvalResult {
+            status: EvalResultStatus::Error,
+            score: None,
+            exit_code: None,
+            duration_ms: Some(started.elapsed().as_millis() as i64),
+            error: Some(format!("Failed to start Claude: {}", e)),
+            output: None,
+        }),
+        ClaudeEnd::Finished { success } => {
+            score_case(suite, &worktree, success, started, cancel_rx).await
+        }
+    };
+
+    if let Err(e) = project.worktree_manager.remove_worktree(&worktree).await {
+        tracing::warn!("Failed to remove eval worktree {}: {}", worktree.display(), e);
+    }
+
+    match outcome {
+        Some(outcome) => {
+            finish_case(state, &result.id, outcome).await;
+            true
+        }
+        None => false,
+    }
+}
+
+async fn finish_case(state: &AppState, result_id: &str, outcome: FinishEvalResult) {
+    match EvalResult::finish(&state.db, result_id, outcome).await {
+        Ok(Some(result)) => state.broadcast(WsMessage::EvalResultUpdated { result }).await,
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to record eval result {}: {}", result_id, e),
+    }
+}
+
+/// Run Claude in the case's worktree, recording its usage on the result.
+/// Eval spend is not counted against task or project budgets.
+async fn run_claude(
+    state: &AppState,
+    result_id: &str,
+    worktree: &FsPath,
+    prompt: &str,
+    model: Option<String>,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> ClaudeEnd {
+    let executor = ClaudeExecutor::new(worktree.to_path_buf()).with_model(model);
+    let mut claude = match ClaudeRun::spawn(&executor, prompt).await {
+        Ok(claude) => claude,
+        Err(e) => return ClaudeEnd::SpawnFailed(e.to_string()),
+    };
+
+    loop {
+        match claude.next(cancel_rx).await {
+            RunEvent::Output { .. } => {}
+            RunEvent::Usage(usage) => {
+                if let Err(e) = EvalResult::set_usage(&state.db, result_id, &usage).await {
+                    tracing::warn!("Failed to record usage for eval result {}: {}", result_id, e);
+                }
+            }
+            RunEvent::Finished { success } => {
+                // The session can be resumed with the CLI to see what Claude did
+                tracing::info!(
+                    "Eval result {} finished with success={}, session {}",
+                    result_id,
+                    success,
+                    claude.session_id().unwrap_or("unknown")
+                );
+                return ClaudeEnd::Finished { success };
+            }
+            RunEvent::Cancelled => return ClaudeEnd::Cancelled,
+        }
+    }
+}
+
+/// Run the suite's score command in the worktree. Returns None if the run
+/// was cancelled meanwhile.
+async fn score_case(
+    suite: &EvalSuite,
+    worktree: &FsPath,
+    claude_succeeded: bool,
+    started: Instant,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> Option<FinishEvalResult> {
+    let command = VerifyCommand {
+        name: "score".to_string(),
+        command: suite.score_command.clone(),
+        cwd: String::new(),
+        timeout_secs: suite.score_timeout_secs.max(1) as u64,
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let check = verify::run(&command, worktree, tx);
+    tokio::pin!(check);
+    let mut output = verify::OutputTail::default();
+    let check = loop {
+        tokio::select! {
+            check = &mut check => break check,
+            Some(line) = rx.recv() => output.push(&line),
+            _ = cancel_rx.recv() => return None,
+        }
+    };
+    while let Ok(line) = rx.try_recv() {
+        output.push(&line);
+    }
+
+    let output = output.to_string();
+    let passed = check.status == CheckStatus::Passed;
+    let error = match (check.error, claude_succeeded) {
+        (Some(error), _) => Some(format!("Score command {}", error)),
+        (None, false) => Some("Claude exited with an error".to_string()),
+        (None, true) => None,
+    };
+
+    Some(FinishEvalResult {
+        status: if passed { EvalResultStatus::Passed } else { EvalResultStatus::Failed },
+        score: Some(eval::parse_score(&output, passed)),
+        exit_code: check.exit_code.map(i64::from),
+        duration_ms: Some(started.elapsed().as_millis() as i64),
+        error,
+        output: (!output.is_empty()).then_some(output),
+    })
+}
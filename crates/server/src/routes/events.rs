@@ -0,0 +1,220 @@
+use std::collections::{HashSet, VecDeque};
+use std::convert::Infallible;
+use std::sync::Arc;
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::Stream;
+use serde::Deserialize;
+use tokio::sync::broadcast;
+
+use crate::state::AppState;
+use crate::project::CurrentProject;
+use crate::routes::ws::{WsEvent, WsMessage};
+
+/// Filters for `GET /api/events`. Without filters every event of the project
+/// is streamed, including the logs the WebSocket only sends to subscribers.
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    /// Only events about this task
+    task_id: Option<String>,
+    /// Comma-separated message types, e.g. `task_updated,execution_complete`
+    types: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct EventFilter {
+    /// Events about other projects are left out
+    project_path: Option<String>,
+    task_id: Option<String>,
+    types: Option<HashSet<String>>,
+}
+
+impl EventFilter {
+    fn new(query: EventsQuery, project_path: Option<String>) -> Self {
+        let types = query.types
+            .map(|types| {
+                types.split(',')
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect::<HashSet<_>>()
+            })
+            .filter(|types| !types.is_empty());
+
+        Self {
+            project_path,
+            task_id: query.task_id.filter(|id| !id.is_empty()),
+            types,
+        }
+    }
+
+    /// The event as JSON, if it passes the filters. Connection messages
+    /// always pass.
+    fn matches(&self, event: &WsEvent) -> Option<String> {
+        let json = serde_json::to_value(event).ok()?;
+        let control = matches!(event.message, WsMessage::Connected { .. } | WsMessage::Resync { .. });
+
+        if !control {
+            if let (Some(path), Some(project_path)) = (&event.project_path, &self.project_path) {
+                if path != project_path {
+                    return None;
+                }
+            }
+
+            if let Some(types) = &self.types {
+                let message_type = json.get("type").and_then(|t| t.as_str()).unwrap_or_default();
+                if !types.contains(message_type) {
+                    return None;
+                }
+            }
+
+            if let Some(task_id) = &self.task_id {
+                let event_task = json.get("task_id")
+                    .or_else(|| json.get("task").and_then(|t| t.get("id")))
+                    .and_then(|id| id.as_str());
+                if event_task != Some(task_id.as_str()) {
+                    return None;
+                }
+            }
+        }
+
+        // Same field order as on the WebSocket
+        serde_json::to_string(event).ok()
+    }
+}
+
+struct EventStream {
+    state: Arc<AppState>,
+    rx: broadcast::Receiver<WsEvent>,
+    filter: EventFilter,
+    /// Replayed events, sent before live ones
+    pending: VecDeque<WsEvent>,
+    /// Latest event handled, to skip duplicates and catch up after lagging
+    last_id: u64,
+}
+
+/// Server-Sent Events carrying the same payloads as the WebSocket. Event ids
+/// match WebSocket event ids; a `Last-Event-ID` header replays what was missed,
+/// or sends `resync` if it is no longer buffered.
+pub async fn events_handler(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Query(query): Query<EventsQuery>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (connected_at, rx) = state.subscribe_events();
+    let last_seen = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+
+    let mut pending = VecDeque::new();
+    match last_seen.map(|last_seen| state.events_since(last_seen)) {
+        Some(Some(missed)) => pending.extend(missed.into_iter().filter(|e| e.id <= connected_at)),
+        Some(None) => pending.push_back(WsEvent {
+            id: connected_at,
+            message: WsMessage::Resync { last_event_id: connected_at },
+            project_path: None,
+        }),
+        None => {}
+    }
+    // Last, so a client that drops during the replay resumes from the right id
+    pending.push_back(WsEvent {
+        id: connected_at,
+        message: WsMessage::Connected { last_event_id: connected_at },
+        project_path: None,
+    });
+
+    tracing::info!("[Events] New SSE client (resuming from {:?})", last_seen);
+
+    let stream = EventStream {
+        state,
+        rx,
+        filter: EventFilter::new(query, Some(project.path_string())),
+        pending,
+        last_id: connected_at,
+    };
+
+    let events = futures::stream::unfold(stream, |mut stream| async move {
+        loop {
+            if let Some(event) = stream.pending.pop_front() {
+                if let Some(data) = stream.filter.matches(&event) {
+                    let sse = Event::default().id(event.id.to_string()).data(data);
+                    return Some((Ok(sse), stream));
+                }
+                continue;
+            }
+
+            match stream.rx.recv().await {
+                Ok(event) => {
+                    if event.id > stream.last_id {
+                        stream.last_id = event.id;
+                        stream.pending.push_back(event);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("[Events] SSE client lagged behind by {} events, replaying", skipped);
+                    match stream.state.events_since(stream.last_id) {
+                        Some(missed) => {
+                            stream.last_id = missed.last().map_or(stream.last_id, |e| e.id);
+                            stream.pending.extend(missed);
+                        }
+                        None => {
+                            stream.last_id = stream.state.last_event_id();
+                            stream.pending.push_back(WsEvent {
+                                id: stream.last_id,
+                                message: WsMessage::Resync { last_event_id: stream.last_id },
+                                project_path: None,
+                            });
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: u64, message: WsMessage) -> WsEvent {
+        WsEvent { id, message, project_path: Some("/work/api".to_string()) }
+    }
+
+    fn log(task_id: &str) -> WsMessage {
+        WsMessage::Log {
+            task_id: task_id.to_string(),
+            content: "line".to_string(),
+            stream: "stdout".to_string(),
+            attempt_id: None,
+        }
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let filter = EventFilter::new(EventsQuery {
+            task_id: Some("t1".to_string()),
+            types: Some("log, execution_complete,".to_string()),
+        }, None);
+
+        assert!(filter.matches(&event(1, log("t1"))).is_some());
+        assert!(filter.matches(&event(2, log("t2"))).is_none());
+        assert!(filter.matches(&event(3, WsMessage::TaskDeleted { task_id: "t1".to_string() })).is_none());
+        assert!(filter.matches(&event(4, WsMessage::ExecutionComplete { task_id: "t1".to_string(), success: true })).is_some());
+        assert!(filter.matches(&event(5, WsMessage::Resync { last_event_id: 5 })).is_some());
+
+        let everything = EventFilter::new(EventsQuery::default(), Some("/work/api".to_string()));
+        let data = everything.matches(&event(6, log("t2"))).unwrap();
+        assert!(data.contains(r#""type":"log""#));
+
+        let other_project = EventFilter::new(EventsQuery::default(), Some("/work/web".to_string()));
+        assert!(other_project.matches(&event(7, log("t2"))).is_none());
+        assert!(other_project.matches(&event(8, WsMessage::Resync { last_event_id: 8 })).is_some());
+    }
+}
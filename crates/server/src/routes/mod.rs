@@ -1,15 +1,78 @@
+use axum::{Json, extract::rejection::JsonRejection};
+
+pub mod attachments;
+pub mod attempts;
+pub mod budget;
 pub mod chat;
+pub mod evals;
+pub mod events;
 pub mod tasks;
 pub mod ws;
 pub mod review;
 pub mod preview;
+pub mod preview_proxy;
 pub mod plan;
+pub mod projects;
+pub mod prompts;
 pub mod server;
+pub mod settings;
 
+pub use attachments::attachments_router;
+pub use attempts::attempts_router;
+pub use budget::budget_router;
 pub use chat::chat_router;
+pub use evals::evals_router;
+pub use events::events_handler;
 pub use tasks::tasks_router;
 pub use ws::ws_handler;
 pub use review::review_router;
 pub use preview::preview_router;
-pub use plan::plan_router;
+pub use preview_proxy::preview_proxy_router;
+pub use plan::{plan_router, task_plan_router};
+pub use projects::projects_router;
+pub use prompts::prompts_router;
 pub use server::server_router;
+pub use settings::settings_router;
+
+/// The body of a request that may be sent without one: the default when no
+/// JSON body was sent, an error when one was sent that doesn't parse as `T`.
+pub fn optional_body<T: Default>(body: Result<Json<T>, JsonRejection>) -> Result<T, String> {
+    match body {
+        Ok(Json(body)) => Ok(body),
+        Err(JsonRejection::MissingJsonContentType(_)) => Ok(T::default()),
+        Err(rejection) => Err(rejection.body_text()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::{FromRequest, Request};
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, PartialEq, Deserialize)]
+    struct Feedback {
+        feedback: Option<String>,
+    }
+
+    async fn parse(content_type: Option<&str>, body: &'static str) -> Result<Feedback, String> {
+        let mut req = Request::builder().method("POST").uri("/");
+        if let Some(content_type) = content_type {
+            req = req.header("content-type", content_type);
+        }
+        let req = req.body(Body::from(body)).unwrap();
+        optional_body(Json::<Feedback>::from_request(req, &()).await)
+    }
+
+    #[tokio::test]
+    async fn test_optional_body() {
+        assert_eq!(parse(None, "").await, Ok(Feedback::default()));
+        assert_eq!(
+            parse(Some("application/json"), r#"{"feedback":"again"}"#).await,
+            Ok(Feedback { feedback: Some("again".to_string()) })
+        );
+        assert!(parse(Some("application/json"), "{feedback").await.is_err());
+        assert!(parse(Some("application/json"), r#"{"feedback":1}"#).await.is_err());
+    }
+}
@@ -1,25 +1,25 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 use axum::{
     Json, Router,
     extract::{Path, State},
     http::StatusCode,
-    routing::{get, post},
+    routing::{get, post, put},
 };
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 use tokio::time::{timeout, Duration};
 use uuid::Uuid;
 
-/// Timeout for individual events (time between stdout lines)
-const EVENT_TIMEOUT_SECS: u64 = 120; // 2 minutes between events
-/// Maximum total time for a plan session
-const SESSION_TIMEOUT_SECS: u64 = 600; // 10 minutes total
-
-use eval_kanban_executor::{ClaudeExecutor, ExecutorEvent};
+use eval_kanban_db::{CreateTask, PlanSessionRecord, Task, TaskDependency, TaskDescriptionHistory, TaskStatus};
+use eval_kanban_executor::{ClaudeExecutor, ExecutorError, ExecutorEvent};
 use eval_kanban_executor::claude::ClaudeProcess;
+use eval_kanban_executor::stream_json;
 
-use crate::plan_session::{PlanSession, PlanSessionInfo, PlanQuestion, PlanAnswer, PlanStatus, QuestionOption, PLAN_MODE_SUFFIX};
+use crate::plan_session::{PlanSession, PlanSessionInfo, PlanQuestion, PlanAnswer, PlanStatus, PlanStep, QuestionOption, BREAKDOWN_SUFFIX, PLAN_MODE_SUFFIX};
+use crate::project::CurrentProject;
+use crate::settings::PlanSettings;
 use crate::state::AppState;
 use crate::routes::ws::WsMessage;
 
@@ -36,6 +36,9 @@ struct StartPlanRequest {
     /// Whether Claude should ask clarifying questions (uses PLAN_MODE_SUFFIX)
     #[serde(default)]
     ask_questions: bool,
+    /// Whether the plan is split into one task per step (uses BREAKDOWN_SUFFIX)
+    #[serde(default)]
+    breakdown: bool,
 }
 
 #[derive(Serialize)]
@@ -43,6 +46,11 @@ struct StartPlanResponse {
     session_id: String,
 }
 
+#[derive(Serialize)]
+struct PlansResponse {
+    sessions: Vec<PlanSessionInfo>,
+}
+
 #[derive(Deserialize)]
 struct SingleAnswerRequest {
     question_index: usize,
@@ -57,41 +65,186 @@ struct AnswerRequest {
 
 #[derive(Deserialize)]
 struct ExecuteRequest {
-    title: String,
+    /// Title of the new task. Ignored when the plan belongs to an existing task.
+    title: Option<String>,
     #[allow(dead_code)]
     description: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct UpdateStepsRequest {
+    steps: Vec<PlanStep>,
+}
+
+#[derive(Deserialize)]
+struct PlanTaskRequest {
+    /// Generated by frontend to avoid race condition
+    session_id: Option<String>,
+    #[serde(default = "default_ask_questions")]
+    ask_questions: bool,
+}
+
+fn default_ask_questions() -> bool {
+    true
+}
+
+#[derive(Serialize)]
+struct DescriptionHistoryResponse {
+    history: Vec<TaskDescriptionHistory>,
+}
+
 pub fn plan_router() -> Router<Arc<AppState>> {
     Router::new()
-        .route("/", post(start_plan))
+        .route("/", get(list_plans).post(start_plan))
         .route("/:session_id", get(get_plan).delete(cancel_plan))
         .route("/:session_id/answer", post(submit_answer))
+        .route("/:session_id/steps", put(update_steps))
         .route("/:session_id/execute", post(execute_plan))
         .route("/:session_id/redo", post(redo_plan))
         .route("/:session_id/resume", post(resume_plan))
 }
 
+/// Planning for existing tasks, merged at the API root.
+pub fn task_plan_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/tasks/:id/plan", post(plan_task))
+        .route("/tasks/:id/description-history", get(get_description_history))
+}
+
 async fn start_plan(
     State(state): State<Arc<AppState>>,
+    project: CurrentProject,
     Json(req): Json<StartPlanRequest>,
 ) -> Result<Json<StartPlanResponse>, (StatusCode, Json<ErrorResponse>)> {
     let session_id = req.session_id;
 
-    tracing::info!("[Plan {}] Starting plan session (ask_questions={})", session_id, req.ask_questions);
+    tracing::info!("[Plan {}] Starting plan session (ask_questions={}, breakdown={})", session_id, req.ask_questions, req.breakdown);
     tracing::info!("[Plan {}] Title: {}", session_id, req.title);
 
-    let session = PlanSession::new(session_id.clone(), req.title.clone(), req.prompt.clone(), req.ask_questions);
+    let project_path = project.path_string();
+    let mut session = PlanSession::new(session_id.clone(), project_path, req.title.clone(), req.prompt.clone(), req.ask_questions);
+    session.breakdown = req.breakdown;
+    begin_session(state, session).await;
+
+    Ok(Json(StartPlanResponse { session_id }))
+}
+
+/// Store a new session and spawn Claude with its initial prompt
+async fn begin_session(state: Arc<AppState>, session: PlanSession) {
+    let session_id = session.id.clone();
     state.add_plan_session(session).await;
+    spawn_claude_for_session(state, session_id, SpawnPrompt::Initial).await;
+}
 
-    // Spawn Claude with the initial prompt
-    spawn_claude_for_session(state, session_id.clone(), false).await;
+/// Start a plan session seeded from an existing Todo task. Executing the plan
+/// writes it back into the task instead of creating a new one.
+async fn plan_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+    Json(req): Json<PlanTaskRequest>,
+) -> Result<Json<StartPlanResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let task = Task::find_by_id(&state.db, &task_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "Task not found".to_string(),
+                }),
+            )
+        })?;
+
+    if task.status != TaskStatus::Todo {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: "Only tasks in Todo can be planned".to_string(),
+            }),
+        ));
+    }
+
+    let session_id = req.session_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let prompt = task.description.clone()
+        .filter(|d| !d.trim().is_empty())
+        .unwrap_or_else(|| task.title.clone());
+
+    tracing::info!("[Plan {}] Starting plan session for task {}", session_id, task_id);
+
+    let project_path = task.project_path.clone()
+        .unwrap_or_else(|| state.default_project.path_string());
+    let mut session = PlanSession::new(session_id.clone(), project_path, task.title.clone(), prompt, req.ask_questions);
+    session.task_id = Some(task_id);
+    begin_session(state, session).await;
 
     Ok(Json(StartPlanResponse { session_id }))
 }
 
-/// Spawn Claude for a planning session (used for initial spawn and re-spawn after answer)
-async fn spawn_claude_for_session(state: Arc<AppState>, session_id: String, is_respawn: bool) {
+async fn get_description_history(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+) -> Result<Json<DescriptionHistoryResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let history = TaskDescriptionHistory::find_by_task(&state.db, &task_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?;
+
+    Ok(Json(DescriptionHistoryResponse { history }))
+}
+
+/// What a spawned planning process is asked
+enum SpawnPrompt {
+    /// The session's prompt, starting a new conversation
+    Initial,
+    /// The next user turn of an earlier conversation (answers, or a nudge to
+    /// carry on after an error)
+    Continue(String),
+}
+
+/// Spawn Claude for a planning session (used for initial spawn and re-spawn after answer).
+///
+/// New sessions get a live stream-json conversation where the platform allows
+/// it. Continuing resumes Claude's own conversation when its id is known, and
+/// only falls back to replaying the Q&A transcript in a fresh prompt otherwise.
+async fn spawn_claude_for_session(state: Arc<AppState>, session_id: String, next: SpawnPrompt) {
+    let (claude_session_id, is_respawn, project_path) = {
+        let sessions = state.plan_sessions.read().await;
+        match sessions.get(&session_id) {
+            Some(session) => (
+                session.claude_session_id.clone(),
+                matches!(next, SpawnPrompt::Continue(_)),
+                session.project_path.clone(),
+            ),
+            None => {
+                tracing::error!("[Plan {}] Session not found for spawn", session_id);
+                return;
+            }
+        }
+    };
+
+    // Plan in the session's project
+    let executor = ClaudeExecutor::new(PathBuf::from(project_path));
+
+    if let (SpawnPrompt::Continue(message), Some(claude_session_id)) = (&next, &claude_session_id) {
+        tracing::info!("[Plan {}] Resuming Claude conversation {}", session_id, claude_session_id);
+        let spawned = executor.spawn_resume(claude_session_id, message).await;
+        handle_spawn_result(state, session_id, spawned, false).await;
+        return;
+    }
+
     // Build prompt based on whether this is initial or re-spawn
     let prompt = {
         let sessions = state.plan_sessions.read().await;
@@ -106,11 +259,15 @@ async fn spawn_claude_for_session(state: Arc<AppState>, session_id: String, is_r
                 };
 
                 // Only add PLAN_MODE_SUFFIX if ask_questions is enabled
-                if session.ask_questions {
+                let mut prompt = if session.ask_questions {
                     format!("{}{}", base_prompt, PLAN_MODE_SUFFIX)
                 } else {
                     base_prompt
+                };
+                if session.breakdown {
+                    prompt.push_str(BREAKDOWN_SUFFIX);
                 }
+                prompt
             }
             None => {
                 tracing::error!("[Plan {}] Session not found for spawn", session_id);
@@ -125,23 +282,42 @@ async fn spawn_claude_for_session(state: Arc<AppState>, session_id: String, is_r
         prompt.len()
     );
 
-    let working_dir = state.working_dir.clone();
-    let executor = ClaudeExecutor::new(working_dir);
+    if !is_respawn && ClaudeExecutor::supports_conversation() {
+        let spawned = executor.spawn_conversation(&prompt).await;
+        handle_spawn_result(state, session_id, spawned, true).await;
+    } else {
+        let spawned = executor.spawn_interactive(&prompt).await;
+        handle_spawn_result(state, session_id, spawned, false).await;
+    }
+}
+
+/// Start processing the events of a newly spawned planning process. For live
+/// conversations the stdin sender is kept on the session so answers can be
+/// delivered to the same process.
+async fn handle_spawn_result(
+    state: Arc<AppState>,
+    session_id: String,
+    spawned: Result<(mpsc::Receiver<ExecutorEvent>, ClaudeProcess, mpsc::Sender<String>), ExecutorError>,
+    live: bool,
+) {
+    match spawned {
+        Ok((rx, process, stdin_tx)) => {
+            tracing::info!("[Plan {}] Claude spawned successfully (live conversation: {})", session_id, live);
 
-    match executor.spawn_interactive(&prompt).await {
-        Ok((rx, process, _stdin_tx)) => {
-            tracing::info!("[Plan {}] Claude spawned successfully", session_id);
+            let input = live.then_some(stdin_tx);
 
             // Update session status
             state.update_plan_session(&session_id, |s| {
                 s.status = PlanStatus::Processing;
+                s.input = input.clone();
+                s.turn_ended = false;
             }).await;
 
             // Process events in background
             let state_clone = state.clone();
             let session_id_clone = session_id.clone();
             tokio::spawn(async move {
-                handle_plan_executor_events(state_clone, session_id_clone, rx, process).await;
+                handle_plan_executor_events(state_clone, session_id_clone, rx, process, input).await;
             });
         }
         Err(e) => {
@@ -162,32 +338,44 @@ async fn handle_plan_executor_events(
     session_id: String,
     mut rx: mpsc::Receiver<ExecutorEvent>,
     mut process: ClaudeProcess,
+    input: Option<mpsc::Sender<String>>,
 ) {
     let mut stdout_line_count = 0;
     let mut stderr_line_count = 0;
-    let session_start = Instant::now();
+    let mut session_start = Instant::now();
+    // Set while a live conversation waits for the user's answers
+    let mut waiting_since: Option<Instant> = None;
+
+    let project_path = {
+        let sessions = state.plan_sessions.read().await;
+        sessions.get(&session_id).map(|s| PathBuf::from(&s.project_path))
+    };
+    let timeouts = match project_path {
+        Some(path) => state.settings.get(&path).plan.clone(),
+        None => PlanSettings::default(),
+    };
 
     tracing::info!("[Plan {}] Starting event handler (timeout: {}s per event, {}s total)",
-        session_id, EVENT_TIMEOUT_SECS, SESSION_TIMEOUT_SECS);
+        session_id, timeouts.event_timeout_secs, timeouts.session_timeout_secs);
 
     loop {
         // Check total session timeout
-        if session_start.elapsed().as_secs() > SESSION_TIMEOUT_SECS {
-            tracing::error!("[Plan {}] Session timeout after {}s", session_id, SESSION_TIMEOUT_SECS);
+        if waiting_since.is_none() && session_start.elapsed().as_secs() > timeouts.session_timeout_secs {
+            tracing::error!("[Plan {}] Session timeout after {}s", session_id, timeouts.session_timeout_secs);
             let _ = process.try_kill();
             state.update_plan_session(&session_id, |s| {
                 s.status = PlanStatus::Error;
             }).await;
             state.broadcast(WsMessage::PlanError {
                 session_id: session_id.clone(),
-                error: format!("Planning session timed out after {} minutes", SESSION_TIMEOUT_SECS / 60),
+                error: format!("Planning session timed out after {} minutes", timeouts.session_timeout_secs / 60),
             }).await;
             break;
         }
 
         // Wait for next event with timeout
         let event_result = timeout(
-            Duration::from_secs(EVENT_TIMEOUT_SECS),
+            Duration::from_secs(timeouts.event_timeout_secs),
             rx.recv()
         ).await;
 
@@ -206,24 +394,51 @@ async fn handle_plan_executor_events(
                 };
 
                 if status == Some(PlanStatus::WaitingForAnswer) {
+                    // A live conversation keeps waiting for the user, up to a limit
+                    if let Some(since) = waiting_since {
+                        if since.elapsed().as_secs() < timeouts.answer_timeout_secs {
+                            continue;
+                        }
+                        tracing::info!("[Plan {}] No answer after {}s, stopping live conversation", session_id, timeouts.answer_timeout_secs);
+                        break;
+                    }
+
                     // This is expected - we killed the process after finding a question
                     tracing::info!("[Plan {}] Timeout while waiting for answer (expected)", session_id);
                     break;
                 }
 
-                tracing::error!("[Plan {}] Timeout waiting for event ({}s)", session_id, EVENT_TIMEOUT_SECS);
+                if status.is_none() {
+                    tracing::info!("[Plan {}] Session was removed, stopping", session_id);
+                    break;
+                }
+
+                tracing::error!("[Plan {}] Timeout waiting for event ({}s)", session_id, timeouts.event_timeout_secs);
                 let _ = process.try_kill();
                 state.update_plan_session(&session_id, |s| {
                     s.status = PlanStatus::Error;
                 }).await;
                 state.broadcast(WsMessage::PlanError {
                     session_id: session_id.clone(),
-                    error: format!("Claude stopped responding (no output for {} minutes)", EVENT_TIMEOUT_SECS / 60),
+                    error: format!("Claude stopped responding (no output for {} minutes)", timeouts.event_timeout_secs / 60),
                 }).await;
                 break;
             }
         };
 
+        // Once answered, the conversation is processing again. Time spent
+        // waiting for answers does not count towards the session timeout
+        if let Some(since) = waiting_since {
+            let status = {
+                let sessions = state.plan_sessions.read().await;
+                sessions.get(&session_id).map(|s| s.status.clone())
+            };
+            if status != Some(PlanStatus::WaitingForAnswer) {
+                session_start += since.elapsed();
+                waiting_since = None;
+            }
+        }
+
         match &event {
             ExecutorEvent::Stdout(line) => {
                 stdout_line_count += 1;
@@ -239,11 +454,26 @@ async fn handle_plan_executor_events(
                 };
                 tracing::info!("[Plan {}] Stdout #{}: {}", session_id, stdout_line_count, truncated);
 
-                // Accumulate output in session
+                // Accumulate output in session, and remember Claude's
+                // conversation id so it can be resumed
+                let claude_session_id = stream_json::session_id(line);
                 state.update_plan_session(&session_id, |s| {
                     s.append_output(line);
+                    if claude_session_id.is_some() {
+                        s.claude_session_id = claude_session_id.clone();
+                    }
                 }).await;
 
+                // A live conversation that ends its turn while questions are
+                // pending keeps waiting; the answers start the next turn
+                if waiting_since.is_some() && stream_json::is_result(line) {
+                    tracing::info!("[Plan {}] Turn ended with questions pending", session_id);
+                    state.update_plan_session(&session_id, |s| {
+                        s.turn_ended = true;
+                    }).await;
+                    continue;
+                }
+
                 // Try to parse AskUserQuestion tool calls from the output
                 let questions = parse_ask_user_questions(line);
                 if !questions.is_empty() {
@@ -278,6 +508,13 @@ async fn handle_plan_executor_events(
                         questions: indexed_questions,
                     }).await;
 
+                    // A live conversation gets the answers over stdin
+                    if input.is_some() {
+                        tracing::info!("[Plan {}] Waiting for user answers in live conversation", session_id);
+                        waiting_since = Some(Instant::now());
+                        continue;
+                    }
+
                     // Kill the process - we'll re-spawn after user answers
                     tracing::info!("[Plan {}] Killing process to wait for user answers", session_id);
                     let _ = process.try_kill();
@@ -351,9 +588,14 @@ async fn handle_plan_executor_events(
                             };
                             tracing::info!("[Plan {}] Using summary from {} ({} chars)", session_id, source, summary.len());
 
-                            // Update session with summary
+                            // Update session with summary. The breakdown may be in
+                            // the final answer rather than the plan file.
+                            let result = json.get("result").and_then(|r| r.as_str()).unwrap_or("");
                             state.update_plan_session(&session_id, |s| {
                                 s.set_summary(summary.clone());
+                                if s.breakdown && s.steps.is_empty() && !result.is_empty() {
+                                    s.apply_breakdown(result);
+                                }
                             }).await;
 
                             // Broadcast summary via WebSocket
@@ -434,9 +676,20 @@ async fn handle_plan_executor_events(
             ExecutorEvent::Error(msg) => {
                 tracing::error!("[Plan {}] Error event: {}", session_id, msg);
             }
+            ExecutorEvent::Usage(_) => {}
         }
     }
 
+    // The process is stopped when it is dropped, so its stdin is no longer
+    // usable; answers will resume the conversation instead
+    if let Some(input) = &input {
+        state.update_plan_session(&session_id, |s| {
+            if s.input.as_ref().is_some_and(|current| current.same_channel(input)) {
+                s.input = None;
+            }
+        }).await;
+    }
+
     tracing::info!("[Plan {}] Event handler finished (total duration: {}s)",
         session_id, session_start.elapsed().as_secs());
 }
@@ -645,6 +898,28 @@ fn extract_summary(output: &str) -> String {
     String::new()
 }
 
+/// List the project's plan sessions, including finished and cancelled ones.
+async fn list_plans(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+) -> Result<Json<PlansResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let project_path = project.path_string();
+    let records = PlanSessionRecord::find_all_by_project(&state.db, &project_path)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?;
+
+    let sessions = records.into_iter().map(PlanSessionInfo::from_record).collect();
+
+    Ok(Json(PlansResponse { sessions }))
+}
+
 async fn get_plan(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
@@ -682,7 +957,14 @@ async fn submit_answer(
         .collect();
 
     // Add all answers to the session
+    state.load_plan_session(&session_id).await;
+    let mut live = None;
+    let mut answer_text = String::new();
     let updated = state.update_plan_session(&session_id, |s| {
+        if let Some(input) = &s.input {
+            live = Some((input.clone(), s.answer_message(&answers)));
+        }
+        answer_text = s.format_answers(&answers);
         s.add_answers(answers);
     }).await;
 
@@ -695,58 +977,186 @@ async fn submit_answer(
         ));
     }
 
+    // Deliver the answers to the running conversation
+    if let Some((input, message)) = live {
+        if input.send(message).await.is_ok() {
+            tracing::info!("[Plan {}] All answers sent to the live conversation", session_id);
+            state.update_plan_session(&session_id, |s| {
+                s.turn_ended = false;
+            }).await;
+            return Ok(StatusCode::ACCEPTED);
+        }
+
+        tracing::warn!("[Plan {}] Live conversation ended, falling back to re-spawn", session_id);
+        state.update_plan_session(&session_id, |s| {
+            s.input = None;
+        }).await;
+    }
+
     tracing::info!("[Plan {}] All answers added, re-spawning Claude with context", session_id);
 
-    // Re-spawn Claude with the updated prompt including the answers
-    spawn_claude_for_session(state, session_id, true).await;
+    // Re-spawn Claude, resuming its conversation with the answers if possible
+    let message = format!("Here are my answers:\n\n{}\n\nContinue planning. Ask more questions if needed or provide the final implementation plan.", answer_text);
+    spawn_claude_for_session(state, session_id, SpawnPrompt::Continue(message)).await;
 
     Ok(StatusCode::ACCEPTED)
 }
 
+/// Replace the steps of a breakdown, e.g. after the user edited the preview.
+async fn update_steps(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(req): Json<UpdateStepsRequest>,
+) -> Result<Json<PlanSessionInfo>, (StatusCode, Json<ErrorResponse>)> {
+    let info = state.get_plan_session_info(&session_id).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Plan session not found".to_string(),
+            }),
+        )
+    })?;
+
+    if info.task_id.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: "A plan for an existing task cannot be split into steps".to_string(),
+            }),
+        ));
+    }
+
+    let mut result = Ok(());
+    state.update_plan_session(&session_id, |s| {
+        result = s.set_steps(req.steps);
+    }).await;
+
+    if let Err(error) = result {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(ErrorResponse { error })));
+    }
+
+    let info = state.get_plan_session_info(&session_id).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Plan session not found".to_string(),
+            }),
+        )
+    })?;
+
+    Ok(Json(info))
+}
+
 async fn execute_plan(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
     Json(req): Json<ExecuteRequest>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
-    // Get and remove the session
-    let session = state.remove_plan_session(&session_id).await;
-
-    let session = match session {
-        Some(s) => s,
-        None => {
+    // A breakdown that failed to parse has to be fixed before executing
+    if let Some(info) = state.get_plan_session_info(&session_id).await {
+        if info.breakdown && info.task_id.is_none() && info.steps.is_empty() {
             return Err((
-                StatusCode::NOT_FOUND,
+                StatusCode::UNPROCESSABLE_ENTITY,
                 Json(ErrorResponse {
-                    error: "Plan session not found".to_string(),
+                    error: info.steps_error.unwrap_or_else(|| "The breakdown has no steps".to_string()),
                 }),
             ));
         }
-    };
+    }
 
-    // Build the full description with Q&A context
-    let mut full_description = session.prompt.clone();
+    // Mark the session completed (any stored session can be turned into a
+    // task) and drop it from memory, before creating anything so a repeated
+    // request can't create the tasks twice
+    let (mut session, previous) = state.take_plan_session(&session_id).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Plan session not found".to_string(),
+            }),
+        )
+    })?;
+    if previous == PlanStatus::Completed {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: "Plan was already executed".to_string(),
+            }),
+        ));
+    }
 
-    if !session.questions.is_empty() {
-        full_description.push_str("\n\n## Interview Answers\n\n");
-        for (q, a) in session.questions.iter().zip(session.answers.iter()) {
-            full_description.push_str(&format!("**{}**: {}\n", q.header, q.question));
-            full_description.push_str(&format!("Answer: {}\n\n", a.answers.join(", ")));
-        }
+    // Tasks go to the project the plan was made in
+    let result = create_plan_tasks(&state, &session, req.title, session.project_path.clone()).await;
+    if result.is_err() {
+        // Let the session be executed again
+        session.status = previous;
+        state.add_plan_session(session).await;
     }
+    result
+}
 
-    if let Some(summary) = &session.summary {
-        full_description.push_str("\n\n## Implementation Plan\n\n");
-        full_description.push_str(summary);
+async fn create_plan_tasks(
+    state: &AppState,
+    session: &PlanSession,
+    title: Option<String>,
+    project_path: String,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    // Build the full description with Q&A context
+    let full_description = session.build_task_description();
+
+    // Attach the plan to the task it was started from
+    if let Some(task_id) = &session.task_id {
+        let task = Task::replace_description(&state.db, task_id, &full_description, "plan", Some(&session.id))
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: e.to_string(),
+                    }),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(ErrorResponse {
+                        error: "Planned task no longer exists".to_string(),
+                    }),
+                )
+            })?;
+
+        state.broadcast(WsMessage::TaskUpdated { task: task.clone() }).await;
+
+        return Ok(Json(serde_json::json!({
+            "task_id": task.id,
+            "message": "Plan attached to task"
+        })));
     }
 
-    // Create the task
-    use eval_kanban_db::{CreateTask, Task};
+    // One task per step, linked to the tasks of the steps it depends on
+    if session.breakdown && !session.steps.is_empty() {
+        let task_ids = create_step_tasks(state, session, &project_path)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: e.to_string(),
+                    }),
+                )
+            })?;
+
+        return Ok(Json(serde_json::json!({
+            "task_id": task_ids.first(),
+            "task_ids": task_ids,
+            "message": format!("Created {} tasks from plan", task_ids.len())
+        })));
+    }
 
-    let project_path = state.working_dir.to_string_lossy().to_string();
+    // Create the task
     let task = Task::create(
         &state.db,
         CreateTask {
-            title: req.title,
+            title: title.unwrap_or_else(|| session.title.clone()),
             description: Some(full_description),
             project_path,
         },
@@ -770,10 +1180,45 @@ async fn execute_plan(
     })))
 }
 
+async fn create_step_tasks(
+    state: &AppState,
+    session: &PlanSession,
+    project_path: &str,
+) -> Result<Vec<String>, sqlx::Error> {
+    let mut task_ids: Vec<String> = Vec::with_capacity(session.steps.len());
+
+    for (index, step) in session.steps.iter().enumerate() {
+        let task = Task::create(
+            &state.db,
+            CreateTask {
+                title: step.title.clone(),
+                description: Some(session.build_step_description(index)),
+                project_path: project_path.to_string(),
+            },
+        )
+        .await?;
+
+        // Steps only depend on earlier steps, so their tasks already exist
+        for &dep in &step.depends_on {
+            TaskDependency::create(&state.db, &task.id, &task_ids[dep]).await?;
+        }
+
+        state.broadcast(WsMessage::TaskUpdated { task: task.clone() }).await;
+        task_ids.push(task.id);
+    }
+
+    Ok(task_ids)
+}
+
 async fn cancel_plan(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    // The session stays in the database so it can be resumed later
+    state.load_plan_session(&session_id).await;
+    state.update_plan_session(&session_id, |s| {
+        s.status = PlanStatus::Cancelled;
+    }).await;
     let session = state.remove_plan_session(&session_id).await;
 
     if session.is_some() {
@@ -793,11 +1238,15 @@ async fn redo_plan(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
 ) -> Result<Json<StartPlanResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Get the current session info
-    let info = state.get_plan_session_info(&session_id).await;
+    // Cancel the current session
+    state.load_plan_session(&session_id).await;
+    state.update_plan_session(&session_id, |s| {
+        s.status = PlanStatus::Cancelled;
+    }).await;
+    let old = state.remove_plan_session(&session_id).await;
 
-    let info = match info {
-        Some(i) => i,
+    let old = match old {
+        Some(s) => s,
         None => {
             return Err((
                 StatusCode::NOT_FOUND,
@@ -808,19 +1257,15 @@ async fn redo_plan(
         }
     };
 
-    // Cancel the current session
-    state.remove_plan_session(&session_id).await;
+    // Start a new one with the same prompt in the same project (generate new
+    // session_id for redo)
+    let new_session_id = Uuid::new_v4().to_string();
+    let mut session = PlanSession::new(new_session_id.clone(), old.project_path, old.title, old.prompt, old.ask_questions);
+    session.task_id = old.task_id;
+    session.breakdown = old.breakdown;
+    begin_session(state, session).await;
 
-    // Start a new one with the same prompt (generate new session_id for redo)
-    start_plan(
-        State(state),
-        Json(StartPlanRequest {
-            title: info.title,
-            prompt: info.prompt,
-            session_id: Uuid::new_v4().to_string(),
-            ask_questions: info.ask_questions,
-        }),
-    ).await
+    Ok(Json(StartPlanResponse { session_id: new_session_id }))
 }
 
 /// Resume a plan session after error (preserves Q&A history)
@@ -846,8 +1291,10 @@ async fn resume_plan(
         s.status = PlanStatus::Processing;
     }).await;
 
-    // Re-spawn Claude with history (is_respawn=true uses build_respawn_prompt)
-    spawn_claude_for_session(state, session_id.clone(), true).await;
+    // Re-spawn Claude with history (resumes the conversation, or replays it
+    // with build_respawn_prompt)
+    let message = "Continue planning from where you left off.".to_string();
+    spawn_claude_for_session(state, session_id.clone(), SpawnPrompt::Continue(message)).await;
 
     Ok(Json(StartPlanResponse { session_id }))
 }
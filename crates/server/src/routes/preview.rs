@@ -1,18 +1,25 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::path::PathBuf;
-use std::net::TcpListener;
+use std::time::Duration;
 use axum::{
     Json, Router,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    routing::post,
+    routing::{get, post},
 };
-use serde::Serialize;
-use tokio::process::Command;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Child;
+use tokio::sync::oneshot;
+use uuid::Uuid;
 
 use eval_kanban_db::Task;
 
-use crate::state::{AppState, PreviewProcess, PreviewInfo, PreviewStatus};
+use crate::preview::{self, LogBuffer, PreviewLogLine};
+use crate::routes::ws::WsMessage;
+use crate::settings::{PreviewService, ReadyCheck, Settings};
+use crate::state::{AppState, PreviewActivity, PreviewProcess, PreviewInfo, PreviewStatus, ServiceProcess};
 
 #[derive(Serialize)]
 struct ErrorResponse {
@@ -22,216 +29,398 @@ struct ErrorResponse {
 pub fn preview_router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/tasks/:id/preview", post(start_preview).delete(stop_preview).get(get_preview_status))
-        .route("/tasks/:id/preview/restart/:server", post(restart_server))
+        .route("/tasks/:id/preview/logs", get(get_preview_logs))
+        .route("/tasks/:id/preview/restart/:service", post(restart_service))
 }
 
-/// Find an available port in the given range
-fn find_available_port(start: u16, range: u16) -> Option<u16> {
-    for port in start..(start + range) {
-        if TcpListener::bind(("127.0.0.1", port)).is_ok() {
-            return Some(port);
-        }
-    }
-    None
+fn error_response(status: StatusCode, error: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse { error: error.into() }))
 }
 
-async fn start_preview(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> Result<Json<PreviewInfo>, (StatusCode, Json<ErrorResponse>)> {
-    // Find the task
-    let task = Task::find_by_id(&state.db, &id).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: e.to_string(),
-            }),
-        )
-    })?;
-
-    let task = match task {
-        Some(t) => t,
-        None => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "Task not found".to_string(),
-                }),
-            ))
-        }
-    };
+/// The task and its worktree, which must exist.
+async fn find_task_worktree(state: &AppState, id: &str) -> Result<(Task, PathBuf), (StatusCode, Json<ErrorResponse>)> {
+    let task = Task::find_by_id(&state.db, id)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "Task not found"))?;
 
-    // Check if task has a worktree path
-    let worktree_path = match task.worktree_path {
-        Some(path) => PathBuf::from(path),
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Task has no worktree".to_string(),
-                }),
-            ))
-        }
-    };
+    let worktree_path = task
+        .worktree_path
+        .as_deref()
+        .map(PathBuf::from)
+        .ok_or_else(|| error_response(StatusCode::BAD_REQUEST, "Task has no worktree"))?;
 
-    // Check if worktree exists
     if !worktree_path.exists() {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Worktree directory not found".to_string(),
-            }),
-        ));
+        return Err(error_response(StatusCode::NOT_FOUND, "Worktree directory not found"));
     }
 
-    // Check if preview already running
-    if state.is_preview_running(&id).await {
-        return Err((
-            StatusCode::CONFLICT,
-            Json(ErrorResponse {
-                error: "Preview already running for this task".to_string(),
-            }),
+    Ok((task, worktree_path))
+}
+
+/// Settings of the task's project, which must define preview services.
+async fn preview_settings(state: &AppState, task: &Task) -> Result<Arc<Settings>, (StatusCode, Json<ErrorResponse>)> {
+    let project = state
+        .project_for_task(task)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let settings = state.settings.get(&project.path);
+
+    if settings.preview.services.is_empty() {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "No preview services configured (add preview.services to .eval-kanban.json)",
         ));
     }
+    Ok(settings)
+}
 
-    // Allocate ports
-    let backend_port = find_available_port(9900, 100).ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "No available ports for backend".to_string(),
-            }),
-        )
-    })?;
-
-    let frontend_port = find_available_port(5200, 100).ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "No available ports for frontend".to_string(),
-            }),
-        )
-    })?;
-
-    // Start backend process
-    // Get cargo path from CARGO_HOME or default locations (using PathBuf for cross-platform)
-    let cargo_path = std::env::var("CARGO_HOME")
-        .map(|home| PathBuf::from(home).join("bin").join("cargo"))
-        .unwrap_or_else(|_| {
-            if cfg!(windows) {
-                std::env::var("USERPROFILE")
-                    .map(|home| PathBuf::from(home).join(".cargo").join("bin").join("cargo.exe"))
-                    .unwrap_or_else(|_| PathBuf::from("cargo"))
-            } else {
-                std::env::var("HOME")
-                    .map(|home| PathBuf::from(home).join(".cargo").join("bin").join("cargo"))
-                    .unwrap_or_else(|_| PathBuf::from("cargo"))
+/// How often previews are checked for idleness
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Stop previews that nobody has used for their idle timeout.
+pub async fn reap_idle_previews(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let idle: Vec<(String, Duration)> = state
+            .preview_processes
+            .read()
+            .await
+            .values()
+            .filter_map(|p| {
+                let timeout = p.idle_timeout?;
+                (p.activity.idle_for() >= timeout).then(|| (p.task_id.clone(), timeout))
+            })
+            .collect();
+
+        for (task_id, timeout) in idle {
+            if state.stop_preview(&task_id).await {
+                tracing::info!("Stopped preview for task {} after {} minutes idle", task_id, timeout.as_secs() / 60);
             }
-        });
+        }
+    }
+}
 
-    let backend_process = if cfg!(windows) {
-        Command::new(&cargo_path)
-            .args(["run", "--release", "-p", "eval-kanban-server"])
-            .env("PORT", backend_port.to_string())
-            .current_dir(&worktree_path)
-            .kill_on_drop(true)
-            .spawn()
-    } else {
-        Command::new(&cargo_path)
-            .args(["run", "--release", "-p", "eval-kanban-server"])
-            .env("PORT", backend_port.to_string())
-            .current_dir(&worktree_path)
-            .kill_on_drop(true)
-            .spawn()
-    }.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("Failed to start backend: {}", e),
-            }),
-        )
-    })?;
-
-    // Start frontend dev server
-    let frontend_dir = worktree_path.join("frontend");
-    let frontend_process = if frontend_dir.exists() {
-        // Check if node_modules exists, if not run npm install first (in blocking task)
-        let node_modules = frontend_dir.join("node_modules");
-        if !node_modules.exists() {
-            tracing::info!("Installing frontend dependencies in worktree (this may take a minute)...");
-            let frontend_dir_clone = frontend_dir.clone();
-            let install_result = tokio::task::spawn_blocking(move || {
-                if cfg!(windows) {
-                    std::process::Command::new("cmd")
-                        .args(["/C", "npm", "install"])
-                        .current_dir(&frontend_dir_clone)
-                        .output()
-                } else {
-                    std::process::Command::new("npm")
-                        .args(["install"])
-                        .current_dir(&frontend_dir_clone)
-                        .output()
-                }
-            }).await;
-
-            match install_result {
-                Ok(Ok(output)) => {
-                    if !output.status.success() {
-                        tracing::warn!("npm install failed: {}", String::from_utf8_lossy(&output.stderr));
-                    } else {
-                        tracing::info!("Frontend dependencies installed successfully");
+/// Where a service's output goes: the preview's log buffer, and clients
+/// subscribed to the task.
+#[derive(Clone)]
+struct LogSink {
+    state: Arc<AppState>,
+    task_id: String,
+    service: String,
+    logs: Arc<Mutex<LogBuffer>>,
+}
+
+impl LogSink {
+    async fn write(&self, stream: &str, content: String) {
+        let line = self.logs.lock().unwrap_or_else(PoisonError::into_inner).push(&self.service, stream, content);
+        self.state.broadcast(WsMessage::PreviewLog { task_id: self.task_id.clone(), line }).await;
+    }
+
+    /// Forward the process's output until it closes it.
+    fn capture(&self, process: &mut Child) {
+        if let Some(stdout) = process.stdout.take() {
+            tokio::spawn(forward_lines(stdout, "stdout", self.clone()));
+        }
+        if let Some(stderr) = process.stderr.take() {
+            tokio::spawn(forward_lines(stderr, "stderr", self.clone()));
+        }
+    }
+}
+
+async fn forward_lines(reader: impl AsyncRead + Unpin, stream: &'static str, sink: LogSink) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => sink.write(stream, String::from_utf8_lossy(&line).trim_end().to_string()).await,
+        }
+    }
+}
+
+/// Whether the preview started as `run_id` is still the task's preview.
+async fn is_current(state: &AppState, task_id: &str, run_id: &str) -> bool {
+    state.preview_processes.read().await.get(task_id).is_some_and(|p| p.run_id == run_id)
+}
+
+/// Wait until the service's dependencies are ready. Fails if one of them
+/// fails or the preview is stopped.
+async fn wait_for_dependencies(state: &AppState, task_id: &str, service: &PreviewService) -> Result<(), String> {
+    loop {
+        {
+            let previews = state.preview_processes.read().await;
+            let preview = previews.get(task_id).ok_or("preview stopped")?;
+
+            let mut waiting = false;
+            for dependency in &service.depends_on {
+                // Not started if its directory doesn't exist in this worktree
+                let Some(process) = preview.services.iter().find(|s| &s.name == dependency) else {
+                    continue;
+                };
+                match process.status {
+                    PreviewStatus::Ready => {}
+                    PreviewStatus::Starting => waiting = true,
+                    PreviewStatus::Failed | PreviewStatus::Stopped => {
+                        return Err(format!("dependency {} failed", dependency));
                     }
                 }
-                Ok(Err(e)) => {
-                    tracing::warn!("Failed to run npm install: {}", e);
-                }
-                Err(e) => {
-                    tracing::warn!("npm install task panicked: {}", e);
-                }
+            }
+            if !waiting {
+                return Ok(());
             }
         }
+        tokio::time::sleep(preview::READY_POLL_INTERVAL).await;
+    }
+}
 
-        let process = if cfg!(windows) {
-            Command::new("cmd")
-                .args(["/C", "npm", "run", "dev", "--", "--port", &frontend_port.to_string(), "--host"])
-                .current_dir(&frontend_dir)
-                .kill_on_drop(true)
-                .spawn()
-        } else {
-            Command::new("npm")
-                .args(["run", "dev", "--", "--port", &frontend_port.to_string(), "--host"])
-                .current_dir(&frontend_dir)
-                .kill_on_drop(true)
-                .spawn()
+/// Start the services in order, each once its dependencies are ready.
+async fn run_preview(
+    state: Arc<AppState>,
+    task_id: String,
+    preview_run_id: String,
+    settings: Arc<Settings>,
+    worktree: PathBuf,
+    ports: HashMap<String, u16>,
+    launches: Vec<(usize, String)>,
+) {
+    for (index, run_id) in launches {
+        if !is_current(&state, &task_id, &preview_run_id).await {
+            return;
+        }
+
+        let service = &settings.preview.services[index];
+        let result = match wait_for_dependencies(&state, &task_id, service).await {
+            Ok(()) => launch_service(&state, &task_id, &run_id, service, &worktree, &ports).await,
+            Err(e) => Err(e),
         };
+        if let Err(e) = result {
+            tracing::warn!("[Preview {}] Not started for task {}: {}", service.name, task_id, e);
+            state
+                .update_preview_service(&task_id, &run_id, |s| {
+                    s.status = PreviewStatus::Failed;
+                    s.error = Some(e);
+                })
+                .await;
+        }
+    }
+}
 
-        match process {
-            Ok(p) => Some(p),
-            Err(e) => {
-                tracing::warn!("Failed to start frontend dev server: {}", e);
-                None
+/// Run the service's setup, then start it on its port and hand it to a
+/// supervisor.
+async fn launch_service(
+    state: &Arc<AppState>,
+    task_id: &str,
+    run_id: &str,
+    service: &PreviewService,
+    worktree: &std::path::Path,
+    ports: &HashMap<String, u16>,
+) -> Result<(), String> {
+    let port = ports[&service.name];
+    let logs = state
+        .preview_processes
+        .read()
+        .await
+        .get(task_id)
+        .map(|p| p.logs.clone())
+        .ok_or("preview stopped")?;
+    let sink = LogSink {
+        state: state.clone(),
+        task_id: task_id.to_string(),
+        service: service.name.clone(),
+        logs,
+    };
+
+    // Setup failures are logged; the service may still start
+    let base = preview::proxy_base(task_id, &service.name);
+    let dir = preview::service_dir(service, worktree, port, ports);
+    match preview::spawn_setup(service, &dir, port, ports, &base) {
+        Some(Ok(mut setup)) => {
+            sink.capture(&mut setup);
+            match setup.wait().await {
+                Ok(status) if status.success() => tracing::info!("[Preview {}] Setup finished", service.name),
+                Ok(status) => tracing::warn!("[Preview {}] Setup failed ({})", service.name, status),
+                Err(e) => tracing::warn!("[Preview {}] Setup failed: {}", service.name, e),
             }
         }
-    } else {
-        None
-    };
+        Some(Err(e)) => tracing::warn!("[Preview {}] Failed to run setup: {}", service.name, e),
+        None => {}
+    }
+
+    let mut process =
+        preview::spawn_service(service, worktree, port, ports, &base).map_err(|e| format!("failed to start: {}", e))?;
+    sink.capture(&mut process);
+
+    let pid = process.id();
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let check = service.ready.clone().unwrap_or_default();
+    tokio::spawn(supervise(
+        state.clone(),
+        task_id.to_string(),
+        run_id.to_string(),
+        service.name.clone(),
+        check,
+        port,
+        process,
+        stop_rx,
+    ));
+    // If the service was stopped or restarted meanwhile, stop_tx is dropped
+    // here, which kills the process
+    state
+        .update_preview_service(task_id, run_id, |s| {
+            s.stop_tx = Some(stop_tx);
+            s.pid = pid;
+        })
+        .await;
+
+    tracing::info!("[Preview {}] Started for task {} on port {}", service.name, task_id, port);
+    Ok(())
+}
+
+/// Owns a service's process: reports it ready once it passes its readiness
+/// check, or failed if that times out or the process exits. Kills it once
+/// the sender of `stop_rx` is dropped.
+#[allow(clippy::too_many_arguments)]
+async fn supervise(
+    state: Arc<AppState>,
+    task_id: String,
+    run_id: String,
+    name: String,
+    check: ReadyCheck,
+    port: u16,
+    mut process: Child,
+    mut stop_rx: oneshot::Receiver<()>,
+) {
+    let ready = preview::wait_ready(&check, port);
+    tokio::pin!(ready);
+    let mut checking = true;
+
+    loop {
+        tokio::select! {
+            result = &mut ready, if checking => {
+                checking = false;
+                let (status, error) = match result {
+                    Ok(()) => {
+                        tracing::info!("[Preview {}] Ready on port {}", name, port);
+                        (PreviewStatus::Ready, None)
+                    }
+                    Err(e) => {
+                        tracing::warn!("[Preview {}] {}", name, e);
+                        (PreviewStatus::Failed, Some(e))
+                    }
+                };
+                state
+                    .update_preview_service(&task_id, &run_id, |s| {
+                        s.status = status;
+                        s.error = error;
+                    })
+                    .await;
+            }
+            exit = process.wait() => {
+                let error = match exit {
+                    Ok(status) => format!("exited ({})", status),
+                    Err(e) => format!("lost track of the process: {}", e),
+                };
+                tracing::warn!("[Preview {}] {}", name, error);
+                state
+                    .update_preview_service(&task_id, &run_id, |s| {
+                        s.status = PreviewStatus::Failed;
+                        s.error = Some(error);
+                    })
+                    .await;
+                return;
+            }
+            _ = &mut stop_rx => {
+                preview::kill(&mut process).await;
+                return;
+            }
+        }
+    }
+}
+
+/// Start the preview's services in the background. They are reported as
+/// they become ready, over `preview_updated` messages.
+async fn start_preview(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<PreviewInfo>, (StatusCode, Json<ErrorResponse>)> {
+    let (task, worktree_path) = find_task_worktree(&state, &id).await?;
+
+    // Check if preview already running
+    if state.is_preview_running(&id).await {
+        return Err(error_response(StatusCode::CONFLICT, "Preview already running for this task"));
+    }
 
+    let settings = preview_settings(&state, &task).await?;
+    let services = &settings.preview.services;
+
+    let order = preview::start_order(services).map_err(|e| error_response(StatusCode::BAD_REQUEST, e))?;
+
+    // Reserved until the preview is removed
+    let run_id = Uuid::new_v4().to_string();
+    let ports = state
+        .preview_ports
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .reserve(&run_id, services.len(), settings.preview.port_start, settings.preview.port_range)
+        .ok_or_else(|| error_response(StatusCode::SERVICE_UNAVAILABLE, "No available ports for preview services"))?;
+    let ports: HashMap<String, u16> = services.iter().map(|s| s.name.clone()).zip(ports).collect();
+
+    let mut processes = Vec::new();
+    let mut launches = Vec::new();
+    for index in order {
+        let service = &services[index];
+        let port = ports[&service.name];
+
+        let dir = preview::service_dir(service, &worktree_path, port, &ports);
+        if !dir.is_dir() {
+            tracing::warn!("[Preview {}] Skipped: {} doesn't exist in the worktree", service.name, dir.display());
+            continue;
+        }
+
+        let service_run_id = Uuid::new_v4().to_string();
+        processes.push(ServiceProcess {
+            name: service.name.clone(),
+            port,
+            keep_base: service.keep_base,
+            run_id: service_run_id.clone(),
+            status: PreviewStatus::Starting,
+            error: None,
+            stop_tx: None,
+            pid: None,
+        });
+        launches.push((index, service_run_id));
+    }
+    if processes.is_empty() {
+        state.preview_ports.lock().unwrap_or_else(PoisonError::into_inner).release(&run_id);
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "None of the preview services' directories exist in this worktree",
+        ));
+    }
+
+    let idle_timeout_mins = settings.preview.idle_timeout_mins;
     let preview = PreviewProcess {
         task_id: id.clone(),
-        backend_port,
-        frontend_port,
-        backend_process,
-        frontend_process,
+        run_id: run_id.clone(),
+        open: settings.preview.open.clone(),
+        proxy_port: state.preview_proxy_port.get().copied().unwrap_or_default(),
+        services: processes,
+        logs: Arc::new(Mutex::new(LogBuffer::new(preview::LOG_BUFFER_SIZE))),
+        idle_timeout: (idle_timeout_mins > 0).then(|| Duration::from_secs(idle_timeout_mins * 60)),
+        activity: Arc::new(PreviewActivity::new()),
     };
 
-    let info = preview.to_info(PreviewStatus::Starting);
-    state.add_preview(id, preview).await;
+    let info = preview.to_info();
+    if !state.add_preview(id.clone(), preview).await {
+        state.preview_ports.lock().unwrap_or_else(PoisonError::into_inner).release(&run_id);
+        return Err(error_response(StatusCode::CONFLICT, "Preview already running for this task"));
+    }
+    state.broadcast(WsMessage::PreviewUpdated { preview: info.clone() }).await;
 
-    tracing::info!(
-        "Started preview for task - backend: {}, frontend: {}",
-        info.backend_url,
-        info.frontend_url
-    );
+    tokio::spawn(run_preview(state.clone(), id, run_id, settings.clone(), worktree_path, ports, launches));
 
     Ok(Json(info))
 }
@@ -240,18 +429,11 @@ async fn stop_preview(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    let removed = state.remove_preview(&id).await;
-
-    if removed.is_some() {
+    if state.stop_preview(&id).await {
         tracing::info!("Stopped preview for task {}", id);
         Ok(StatusCode::NO_CONTENT)
     } else {
-        Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "No preview running for this task".to_string(),
-            }),
-        ))
+        Err(error_response(StatusCode::NOT_FOUND, "No preview running for this task"))
     }
 }
 
@@ -259,211 +441,137 @@ async fn get_preview_status(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<Json<PreviewInfo>, (StatusCode, Json<ErrorResponse>)> {
+    state.touch_preview(&id).await;
     let info = state.get_preview_info(&id).await;
 
     match info {
         Some(info) => Ok(Json(info)),
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "No preview running for this task".to_string(),
-            }),
-        )),
+        None => Err(error_response(StatusCode::NOT_FOUND, "No preview running for this task")),
     }
 }
 
-#[derive(serde::Deserialize)]
+#[derive(Deserialize)]
+struct LogsQuery {
+    /// Only this service's output
+    service: Option<String>,
+    /// Only lines after this id
+    after: Option<u64>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct LogsResponse {
+    logs: Vec<PreviewLogLine>,
+}
+
+/// Buffered output of the preview's services and their setup, oldest first.
+async fn get_preview_logs(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<LogsQuery>,
+) -> Result<Json<LogsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    state.touch_preview(&id).await;
+    let logs = state
+        .preview_processes
+        .read()
+        .await
+        .get(&id)
+        .map(|p| p.logs.clone())
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "No preview running for this task"))?;
+
+    let limit = query.limit.unwrap_or(preview::LOG_BUFFER_SIZE).max(1);
+    let logs = logs
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .since(query.after.unwrap_or(0), query.service.as_deref(), limit);
+
+    Ok(Json(LogsResponse { logs }))
+}
+
+#[derive(Deserialize)]
 struct RestartParams {
     id: String,
-    server: String, // "backend" or "frontend"
+    service: String,
 }
 
-async fn restart_server(
+/// Restart one service of a running preview, on the same port if it's free.
+async fn restart_service(
     State(state): State<Arc<AppState>>,
     Path(params): Path<RestartParams>,
 ) -> Result<Json<PreviewInfo>, (StatusCode, Json<ErrorResponse>)> {
-    let RestartParams { id, server } = params;
-
-    // Validate server type
-    if server != "backend" && server != "frontend" {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Server must be 'backend' or 'frontend'".to_string(),
-            }),
-        ));
-    }
-
-    // Get current preview info
-    let current_preview = state.remove_preview(&id).await;
-    let preview = match current_preview {
-        Some(p) => p,
-        None => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "No preview running for this task".to_string(),
-                }),
-            ));
-        }
-    };
-
-    // Get task for worktree path
-    let task = Task::find_by_id(&state.db, &id).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: e.to_string(),
-            }),
-        )
-    })?;
-
-    let task = match task {
-        Some(t) => t,
-        None => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "Task not found".to_string(),
-                }),
-            ))
-        }
-    };
+    let RestartParams { id, service: name } = params;
 
-    let worktree_path = match task.worktree_path {
-        Some(path) => PathBuf::from(path),
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Task has no worktree".to_string(),
-                }),
-            ))
-        }
+    let (task, worktree_path) = find_task_worktree(&state, &id).await?;
+    let settings = preview_settings(&state, &task).await?;
+    let Some(index) = settings.preview.services.iter().position(|s| s.name == name) else {
+        return Err(error_response(StatusCode::BAD_REQUEST, format!("No preview service named '{}'", name)));
     };
 
-    // Drop old processes (they will be killed due to kill_on_drop)
-    let PreviewProcess {
-        backend_port: old_backend_port,
-        frontend_port: old_frontend_port,
-        backend_process: old_backend,
-        frontend_process: old_frontend,
-        ..
-    } = preview;
-
-    let (backend_port, backend_process, frontend_port, frontend_process) = if server == "backend" {
-        // Restart only backend - find new port if old one is occupied
-        drop(old_backend); // Kill old backend
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-
-        let new_backend_port = find_available_port(9900, 100).ok_or_else(|| {
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(ErrorResponse {
-                    error: "No available ports for backend".to_string(),
-                }),
-            )
-        })?;
-
-        let cargo_path = get_cargo_path();
-        let new_backend = Command::new(&cargo_path)
-            .args(["run", "--release", "-p", "eval-kanban-server"])
-            .env("PORT", new_backend_port.to_string())
-            .current_dir(&worktree_path)
-            .kill_on_drop(true)
-            .spawn()
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: format!("Failed to restart backend: {}", e),
-                    }),
-                )
-            })?;
-
-        (new_backend_port, new_backend, old_frontend_port, old_frontend)
-    } else {
-        // Restart only frontend - find new port if old one is occupied
-        drop(old_frontend); // Kill old frontend
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-
-        let new_frontend_port = find_available_port(5200, 100).ok_or_else(|| {
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(ErrorResponse {
-                    error: "No available ports for frontend".to_string(),
-                }),
-            )
-        })?;
-
-        let frontend_dir = worktree_path.join("frontend");
-        let new_frontend = if frontend_dir.exists() {
-            let process = if cfg!(windows) {
-                Command::new("cmd")
-                    .args(["/C", "npm", "run", "dev", "--", "--port", &new_frontend_port.to_string(), "--host"])
-                    .current_dir(&frontend_dir)
-                    .kill_on_drop(true)
-                    .spawn()
-            } else {
-                Command::new("npm")
-                    .args(["run", "dev", "--", "--port", &new_frontend_port.to_string(), "--host"])
-                    .current_dir(&frontend_dir)
-                    .kill_on_drop(true)
-                    .spawn()
-            };
-
-            match process {
-                Ok(p) => Some(p),
-                Err(e) => {
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(ErrorResponse {
-                            error: format!("Failed to restart frontend: {}", e),
-                        }),
-                    ));
-                }
-            }
-        } else {
-            None
+    // Updates from the old launch are ignored from here on
+    let run_id = Uuid::new_v4().to_string();
+    let (preview_run_id, old_port, mut ports) = {
+        let mut previews = state.preview_processes.write().await;
+        let preview = previews
+            .get_mut(&id)
+            .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "No preview running for this task"))?;
+        let Some(process) = preview.services.iter_mut().find(|s| s.name == name) else {
+            return Err(error_response(StatusCode::BAD_REQUEST, format!("Service '{}' isn't running in this preview", name)));
         };
 
-        (old_backend_port, old_backend, new_frontend_port, new_frontend)
-    };
+        // Kills the old process
+        process.stop_tx = None;
+        process.pid = None;
+        process.run_id = run_id.clone();
+        process.status = PreviewStatus::Starting;
+        process.error = None;
 
-    let new_preview = PreviewProcess {
-        task_id: id.clone(),
-        backend_port,
-        frontend_port,
-        backend_process,
-        frontend_process,
+        let old_port = process.port;
+
+        let ports: HashMap<String, u16> = preview.services.iter().map(|s| (s.name.clone(), s.port)).collect();
+        (preview.run_id.clone(), old_port, ports)
     };
 
-    let info = new_preview.to_info(PreviewStatus::Starting);
-    state.add_preview(id.clone(), new_preview).await;
+    // Give the old process a moment to release its port
+    tokio::time::sleep(Duration::from_millis(500)).await;
 
-    tracing::info!(
-        "Restarted {} server for task {} - backend: {}, frontend: {}",
-        server,
-        id,
-        info.backend_url,
-        info.frontend_url
+    let port = state.preview_ports.lock().unwrap_or_else(PoisonError::into_inner).renew(
+        &preview_run_id,
+        old_port,
+        settings.preview.port_start,
+        settings.preview.port_range,
     );
+    let Some(port) = port else {
+        let error = format!("No available port for {}", name);
+        state
+            .update_preview_service(&id, &run_id, |s| {
+                s.status = PreviewStatus::Failed;
+                s.error = Some(error.clone());
+            })
+            .await;
+        return Err(error_response(StatusCode::SERVICE_UNAVAILABLE, error));
+    };
+    ports.insert(name.clone(), port);
+    state.update_preview_service(&id, &run_id, |s| s.port = port).await;
+
+    let info = state
+        .get_preview_info(&id)
+        .await
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "No preview running for this task"))?;
+
+    tokio::spawn(async move {
+        let service = &settings.preview.services[index];
+        if let Err(e) = launch_service(&state, &id, &run_id, service, &worktree_path, &ports).await {
+            tracing::warn!("[Preview {}] Failed to restart for task {}: {}", name, id, e);
+            state
+                .update_preview_service(&id, &run_id, |s| {
+                    s.status = PreviewStatus::Failed;
+                    s.error = Some(e);
+                })
+                .await;
+        } else {
+            tracing::info!("[Preview {}] Restarted for task {} on port {}", name, id, port);
+        }
+    });
 
     Ok(Json(info))
 }
-
-fn get_cargo_path() -> PathBuf {
-    std::env::var("CARGO_HOME")
-        .map(|home| PathBuf::from(home).join("bin").join("cargo"))
-        .unwrap_or_else(|_| {
-            if cfg!(windows) {
-                std::env::var("USERPROFILE")
-                    .map(|home| PathBuf::from(home).join(".cargo").join("bin").join("cargo.exe"))
-                    .unwrap_or_else(|_| PathBuf::from("cargo"))
-            } else {
-                std::env::var("HOME")
-                    .map(|home| PathBuf::from(home).join(".cargo").join("bin").join("cargo"))
-                    .unwrap_or_else(|_| PathBuf::from("cargo"))
-            }
-        })
-}
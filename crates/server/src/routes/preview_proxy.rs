@@ -0,0 +1,265 @@
+//! Reverse proxy to running preview services, so any task's preview can be
+//! opened through one port of the server (e.g. over an SSH tunnel). It is
+//! served on a port apart from the board's, so preview pages are another origin.
+
+use std::sync::Arc;
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Path, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    routing::any,
+};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+
+use crate::auth::TOKEN_COOKIE;
+use crate::preview;
+use crate::state::AppState;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+fn error_response(status: StatusCode, error: impl Into<String>) -> Response {
+    (status, Json(ErrorResponse { error: error.into() })).into_response()
+}
+
+/// Headers that apply to one connection and aren't forwarded
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+pub fn preview_proxy_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/preview/:task_id/:service", any(redirect_to_base))
+        .route("/preview/:task_id/:service/", any(proxy))
+        .route("/preview/:task_id/:service/*path", any(proxy))
+}
+
+#[derive(Deserialize)]
+struct ProxyParams {
+    task_id: String,
+    service: String,
+}
+
+/// Relative URLs in the service's pages only resolve under the trailing slash.
+async fn redirect_to_base(Path(params): Path<ProxyParams>, uri: Uri) -> Redirect {
+    let query = uri.query().map(|q| format!("?{}", q)).unwrap_or_default();
+    Redirect::temporary(&format!("{}{}", preview::proxy_base(&params.task_id, &params.service), query))
+}
+
+/// Forward a request, or a WebSocket (or other) upgrade, to the service.
+async fn proxy(State(state): State<Arc<AppState>>, Path(params): Path<ProxyParams>, mut req: Request) -> Response {
+    let target = state.preview_processes.read().await.get(&params.task_id).and_then(|p| {
+        p.services
+            .iter()
+            .find(|s| s.name == params.service)
+            .map(|s| (s.port, s.keep_base, p.activity.clone()))
+    });
+    let Some((port, keep_base, activity)) = target else {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            format!("No preview service '{}' running for this task", params.service),
+        );
+    };
+    activity.touch();
+    let base = preview::proxy_base(&params.task_id, &params.service);
+
+    let Ok(uri) = forward_uri(req.uri(), keep_base).parse::<Uri>() else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid preview path");
+    };
+    *req.uri_mut() = uri;
+
+    let upgrade = is_upgrade(req.headers());
+    let host = req.headers().get(header::HOST).cloned();
+    prepare_headers(req.headers_mut(), upgrade, port);
+    if let Some(host) = host {
+        req.headers_mut().insert("x-forwarded-host", host);
+    }
+    if !keep_base {
+        if let Ok(prefix) = HeaderValue::from_str(base.trim_end_matches('/')) {
+            req.headers_mut().insert("x-forwarded-prefix", prefix);
+        }
+    }
+
+    let stream = match TcpStream::connect(("127.0.0.1", port)).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            return error_response(
+                StatusCode::BAD_GATEWAY,
+                format!("Preview service '{}' isn't accepting connections on port {}: {}", params.service, port, e),
+            );
+        }
+    };
+    let (mut sender, connection) = match hyper::client::conn::http1::handshake(TokioIo::new(stream)).await {
+        Ok(handshake) => handshake,
+        Err(e) => return error_response(StatusCode::BAD_GATEWAY, format!("Preview connection failed: {}", e)),
+    };
+    tokio::spawn(async move {
+        if let Err(e) = connection.with_upgrades().await {
+            tracing::debug!("[Preview proxy] Connection to port {} closed: {}", port, e);
+        }
+    });
+
+    let client_upgrade = upgrade.then(|| hyper::upgrade::on(&mut req));
+    let mut response = match sender.send_request(req).await {
+        Ok(response) => response,
+        Err(e) => return error_response(StatusCode::BAD_GATEWAY, format!("Preview request failed: {}", e)),
+    };
+
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+        if let Some(client_upgrade) = client_upgrade {
+            let service_upgrade = hyper::upgrade::on(&mut response);
+            let connection = activity.connection();
+            tokio::spawn(async move {
+                let _connection = connection;
+                let (Ok(client), Ok(service)) = tokio::join!(client_upgrade, service_upgrade) else {
+                    return;
+                };
+                let _ = tokio::io::copy_bidirectional(&mut TokioIo::new(client), &mut TokioIo::new(service)).await;
+            });
+        }
+    }
+
+    // Cookies are shared by every port of the host, the board's included
+    response.headers_mut().remove(header::SET_COOKIE);
+
+    let location = response.headers().get(header::LOCATION).and_then(|l| l.to_str().ok());
+    if let Some(location) = location.and_then(|l| rewrite_location(l, port, &base, keep_base)) {
+        if let Ok(location) = HeaderValue::from_str(&location) {
+            response.headers_mut().insert(header::LOCATION, location);
+        }
+    }
+
+    response.map(Body::new)
+}
+
+fn is_upgrade(headers: &HeaderMap) -> bool {
+    headers.contains_key(header::UPGRADE)
+        && headers
+            .get_all(header::CONNECTION)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim().eq_ignore_ascii_case("upgrade"))
+}
+
+/// Drop connection-specific headers (keeping an upgrade) and our token
+/// cookie, and address the service as localhost.
+fn prepare_headers(headers: &mut HeaderMap, upgrade: bool, port: u16) {
+    let protocol = headers.get(header::UPGRADE).cloned();
+    for name in HOP_BY_HOP_HEADERS {
+        headers.remove(name);
+    }
+    if let Some(protocol) = protocol.filter(|_| upgrade) {
+        headers.insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+        headers.insert(header::UPGRADE, protocol);
+    }
+
+    let cookies: Vec<String> = headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .map(str::trim)
+        .filter(|c| !c.is_empty() && c.split('=').next() != Some(TOKEN_COOKIE))
+        .map(str::to_string)
+        .collect();
+    headers.remove(header::COOKIE);
+    if let Ok(cookies) = HeaderValue::from_str(&cookies.join("; ")) {
+        if !cookies.is_empty() {
+            headers.insert(header::COOKIE, cookies);
+        }
+    }
+
+    if let Ok(host) = HeaderValue::from_str(&format!("localhost:{}", port)) {
+        headers.insert(header::HOST, host);
+    }
+    headers.insert("x-forwarded-proto", HeaderValue::from_static("http"));
+}
+
+/// Path and query to request from the service: without the
+/// `/preview/<task>/<service>` prefix unless it is kept, and without our
+/// `token` parameter.
+fn forward_uri(uri: &Uri, keep_base: bool) -> String {
+    let path = if keep_base {
+        uri.path().to_string()
+    } else {
+        // "", "preview", task, service, rest
+        format!("/{}", uri.path().splitn(5, '/').nth(4).unwrap_or(""))
+    };
+
+    let query: Vec<&str> = uri
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .filter(|pair| !pair.is_empty() && pair.split('=').next() != Some("token"))
+        .collect();
+
+    if query.is_empty() {
+        path
+    } else {
+        format!("{}?{}", path, query.join("&"))
+    }
+}
+
+/// Redirects to the service's own localhost URL, or to a path of a service
+/// that doesn't know its prefix, are sent back under the proxy.
+fn rewrite_location(location: &str, port: u16, base: &str, keep_base: bool) -> Option<String> {
+    let origins = [format!("http://localhost:{}", port), format!("http://127.0.0.1:{}", port)];
+    let local = origins.iter().find_map(|origin| location.strip_prefix(origin.as_str()));
+
+    let path = match local {
+        Some("") => "/",
+        Some(path) => path,
+        None if location.starts_with('/') && !location.starts_with("//") => location,
+        None => return None,
+    };
+
+    if keep_base {
+        local.map(|_| path.to_string())
+    } else {
+        Some(format!("{}{}", base, path.trim_start_matches('/')))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forward_uri() {
+        let uri: Uri = "/preview/t1/web/assets/app.js?v=2&token=secret".parse().unwrap();
+        assert_eq!(forward_uri(&uri, false), "/assets/app.js?v=2");
+        assert_eq!(forward_uri(&uri, true), "/preview/t1/web/assets/app.js?v=2");
+
+        let root: Uri = "/preview/t1/web/?token=secret".parse().unwrap();
+        assert_eq!(forward_uri(&root, false), "/");
+    }
+
+    #[test]
+    fn test_rewrite_location() {
+        let base = "/preview/t1/web/";
+        assert_eq!(rewrite_location("/login", 5200, base, false).as_deref(), Some("/preview/t1/web/login"));
+        assert_eq!(rewrite_location("http://localhost:5200", 5200, base, false).as_deref(), Some("/preview/t1/web/"));
+        assert_eq!(rewrite_location("next", 5200, base, false), None);
+        assert_eq!(rewrite_location("https://example.com/", 5200, base, false), None);
+
+        assert_eq!(rewrite_location("/preview/t1/web/login", 5200, base, true), None);
+        assert_eq!(
+            rewrite_location("http://127.0.0.1:5200/preview/t1/web/login", 5200, base, true).as_deref(),
+            Some("/preview/t1/web/login")
+        );
+    }
+}
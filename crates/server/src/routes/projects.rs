@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+
+use eval_kanban_db::Project;
+
+use crate::config_setup;
+use crate::state::AppState;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Serialize)]
+struct ProjectResponse {
+    #[serde(flatten)]
+    project: Project,
+    /// The project the server was started in, served by the unscoped routes
+    is_default: bool,
+    /// False if the directory was moved or deleted
+    exists: bool,
+    is_git_repo: bool,
+}
+
+#[derive(Serialize)]
+struct ProjectsResponse {
+    projects: Vec<ProjectResponse>,
+}
+
+#[derive(Deserialize)]
+struct OpenProjectRequest {
+    path: String,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UpdateProjectRequest {
+    name: String,
+}
+
+pub fn projects_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_projects).post(open_project))
+        .route("/:id", get(get_project).patch(update_project).delete(delete_project))
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse { error: e.to_string() }),
+    )
+}
+
+fn not_found() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Project not found".to_string() }),
+    )
+}
+
+async fn to_response(state: &AppState, project: Project) -> ProjectResponse {
+    let is_default = project.id == state.default_project.project.id;
+    let is_git_repo = match state.project(&project.id).await {
+        Ok(Some(context)) => context.worktree_manager.is_git_repo(),
+        _ => false,
+    };
+
+    ProjectResponse {
+        is_default,
+        exists: std::path::Path::new(&project.path).is_dir(),
+        is_git_repo,
+        project,
+    }
+}
+
+/// Known projects, most recently opened first.
+async fn list_projects(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ProjectsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let recent = Project::find_recent(&state.db).await.map_err(internal_error)?;
+
+    let mut projects = Vec::with_capacity(recent.len());
+    for project in recent {
+        projects.push(to_response(&state, project).await);
+    }
+
+    Ok(Json(ProjectsResponse { projects }))
+}
+
+/// Register a directory as a project, or reopen it if it already is one.
+async fn open_project(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<OpenProjectRequest>,
+) -> Result<(StatusCode, Json<ProjectResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let path = PathBuf::from(req.path.trim());
+    if !path.is_absolute() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Project path must be absolute".to_string() }),
+        ));
+    }
+
+    // Same spelling as the working directory of a server started there
+    let path = path.canonicalize().ok().filter(|p| p.is_dir()).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: format!("{} is not a directory", path.display()) }),
+        )
+    })?;
+    let path_string = path.to_string_lossy().to_string();
+
+    let existed = Project::find_by_path(&state.db, &path_string)
+        .await
+        .map_err(internal_error)?
+        .is_some();
+    let project = Project::open(&state.db, &path_string, req.name.as_deref())
+        .await
+        .map_err(internal_error)?;
+
+    if !existed {
+        tracing::info!("Registered project {} at {}", project.name, path_string);
+        if let Err(e) = config_setup::ensure_config(&path) {
+            tracing::warn!("Failed to setup Claude config for {}: {}", path_string, e);
+        }
+    }
+
+    let status = if existed { StatusCode::OK } else { StatusCode::CREATED };
+    Ok((status, Json(to_response(&state, project).await)))
+}
+
+async fn get_project(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<ProjectResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let project = Project::find_by_id(&state.db, &id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(to_response(&state, project).await))
+}
+
+async fn update_project(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateProjectRequest>,
+) -> Result<Json<ProjectResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Name is required".to_string() }),
+        ));
+    }
+
+    let project = Project::rename(&state.db, &id, name)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(not_found)?;
+    // Cached contexts hold a copy of the project
+    state.forget_project(&id).await;
+
+    Ok(Json(to_response(&state, project).await))
+}
+
+/// Remove a project from the list. Its tasks and worktrees are kept.
+async fn delete_project(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    if id == state.default_project.project.id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "The project the server was started in can't be removed".to_string() }),
+        ));
+    }
+
+    if !Project::delete(&state.db, &id).await.map_err(internal_error)? {
+        return Err(not_found());
+    }
+    state.forget_project(&id).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
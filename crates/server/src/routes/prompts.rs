@@ -0,0 +1,276 @@
+use std::sync::Arc;
+use axum::{
+    Json, Router,
+    extract::{Path, State, rejection::JsonRejection},
+    http::StatusCode,
+    routing::{get, post, put},
+};
+use serde::{Deserialize, Serialize};
+
+use eval_kanban_db::{CreatePromptTemplate, ProjectSettings, PromptTemplate, Task, UpdatePromptTemplate};
+
+use crate::prompt::{self, TEMPLATE_VARIABLES};
+use crate::project::CurrentProject;
+use crate::state::AppState;
+use crate::routes::{self, ws::WsMessage};
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Serialize)]
+struct TemplatesResponse {
+    templates: Vec<PromptTemplate>,
+    variables: &'static [&'static str],
+}
+
+#[derive(Deserialize)]
+struct CreateTemplateRequest {
+    name: String,
+    body: String,
+}
+
+#[derive(Deserialize)]
+struct UpdateTemplateRequest {
+    name: Option<String>,
+    body: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ProjectPromptResponse {
+    prompt_prefix: Option<String>,
+    prompt_suffix: Option<String>,
+}
+
+/// New prefix/suffix. Omitted, null or empty fields remove the text.
+#[derive(Deserialize)]
+struct SetProjectPromptRequest {
+    prompt_prefix: Option<String>,
+    prompt_suffix: Option<String>,
+}
+
+/// Template to use for the task. Null selects the default prompt.
+#[derive(Deserialize)]
+struct SetTaskTemplateRequest {
+    template_id: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct PromptPreviewRequest {
+    feedback: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PromptPreviewResponse {
+    prompt: String,
+}
+
+pub fn prompts_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/prompts", get(list_templates).post(create_template))
+        .route("/prompts/project", get(get_project_prompt).put(set_project_prompt))
+        .route("/prompts/:id", get(get_template).patch(update_template).delete(delete_template))
+        .route("/tasks/:id/prompt-template", put(set_task_template))
+        .route("/tasks/:id/prompt-preview", post(preview_prompt))
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: e.to_string(),
+        }),
+    )
+}
+
+fn not_found(what: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: format!("{} not found", what),
+        }),
+    )
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+async fn list_templates(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+) -> Result<Json<TemplatesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let project_path = project.path_string();
+    let templates = PromptTemplate::find_all_by_project(&state.db, &project_path)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(TemplatesResponse {
+        templates,
+        variables: TEMPLATE_VARIABLES,
+    }))
+}
+
+async fn create_template(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Json(req): Json<CreateTemplateRequest>,
+) -> Result<(StatusCode, Json<PromptTemplate>), (StatusCode, Json<ErrorResponse>)> {
+    if req.name.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Template name is required".to_string(),
+            }),
+        ));
+    }
+
+    let template = PromptTemplate::create(
+        &state.db,
+        CreatePromptTemplate {
+            project_path: project.path_string(),
+            name: req.name.trim().to_string(),
+            body: req.body,
+        },
+    )
+    .await
+    .map_err(internal_error)?;
+
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+async fn get_template(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<PromptTemplate>, (StatusCode, Json<ErrorResponse>)> {
+    let template = PromptTemplate::find_by_id(&state.db, &id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| not_found("Template"))?;
+
+    Ok(Json(template))
+}
+
+async fn update_template(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateTemplateRequest>,
+) -> Result<Json<PromptTemplate>, (StatusCode, Json<ErrorResponse>)> {
+    if req.name.as_ref().is_some_and(|n| n.trim().is_empty()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Template name cannot be empty".to_string(),
+            }),
+        ));
+    }
+
+    let template = PromptTemplate::update(
+        &state.db,
+        &id,
+        UpdatePromptTemplate {
+            name: req.name.map(|n| n.trim().to_string()),
+            body: req.body,
+        },
+    )
+    .await
+    .map_err(internal_error)?
+    .ok_or_else(|| not_found("Template"))?;
+
+    Ok(Json(template))
+}
+
+async fn delete_template(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let deleted = PromptTemplate::delete(&state.db, &id).await.map_err(internal_error)?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found("Template"))
+    }
+}
+
+async fn get_project_prompt(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+) -> Result<Json<ProjectPromptResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let project_path = project.path_string();
+    let settings = ProjectSettings::get(&state.db, &project_path).await.map_err(internal_error)?;
+
+    Ok(Json(ProjectPromptResponse {
+        prompt_prefix: settings.prompt_prefix,
+        prompt_suffix: settings.prompt_suffix,
+    }))
+}
+
+async fn set_project_prompt(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Json(req): Json<SetProjectPromptRequest>,
+) -> Result<Json<ProjectPromptResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let project_path = project.path_string();
+    let prefix = non_empty(req.prompt_prefix);
+    let suffix = non_empty(req.prompt_suffix);
+
+    let settings = ProjectSettings::set_prompt(&state.db, &project_path, prefix.as_deref(), suffix.as_deref())
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(ProjectPromptResponse {
+        prompt_prefix: settings.prompt_prefix,
+        prompt_suffix: settings.prompt_suffix,
+    }))
+}
+
+async fn set_task_template(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<SetTaskTemplateRequest>,
+) -> Result<Json<Task>, (StatusCode, Json<ErrorResponse>)> {
+    if let Some(template_id) = req.template_id.as_deref() {
+        PromptTemplate::find_by_id(&state.db, template_id)
+            .await
+            .map_err(internal_error)?
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse {
+                        error: "Template not found".to_string(),
+                    }),
+                )
+            })?;
+    }
+
+    let task = Task::set_prompt_template(&state.db, &id, req.template_id.as_deref())
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| not_found("Task"))?;
+
+    state.broadcast(WsMessage::TaskUpdated { task: task.clone() }).await;
+
+    Ok(Json(task))
+}
+
+async fn preview_prompt(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    req: Result<Json<PromptPreviewRequest>, JsonRejection>,
+) -> Result<Json<PromptPreviewResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let req: PromptPreviewRequest = routes::optional_body(req)
+        .map_err(|error| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })))?;
+
+    let task = Task::find_by_id(&state.db, &id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| not_found("Task"))?;
+
+    let prompt = prompt::build_task_prompt(&state, &task, req.feedback.as_deref())
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(PromptPreviewResponse { prompt }))
+}
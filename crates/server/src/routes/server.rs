@@ -8,12 +8,16 @@ use axum::{
 };
 use serde::Serialize;
 
+use eval_kanban_db::Project;
+
+use crate::project::CurrentProject;
 use crate::state::AppState;
 
 #[derive(Serialize)]
 pub struct ServerInfo {
     name: String,
     path: String,
+    project_id: String,
 }
 
 pub fn server_router() -> Router<Arc<AppState>> {
@@ -22,20 +26,28 @@ pub fn server_router() -> Router<Arc<AppState>> {
         .route("/restart", post(restart_server))
 }
 
+/// The current project: the one the server was started in, or the one in
+/// the URL.
 async fn get_info(
     State(state): State<Arc<AppState>>,
+    project: CurrentProject,
 ) -> Json<ServerInfo> {
-    let path = state.working_dir.to_string_lossy().to_string();
-    let name = state.working_dir
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| "root".to_string());
+    // Read again, the project may have been renamed since it was loaded
+    let name = Project::find_by_id(&state.db, &project.project.id)
+        .await
+        .ok()
+        .flatten()
+        .map_or_else(|| project.project.name.clone(), |p| p.name);
 
-    Json(ServerInfo { name, path })
+    Json(ServerInfo {
+        name,
+        path: project.path_string(),
+        project_id: project.project.id.clone(),
+    })
 }
 
 async fn restart_server(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
 ) -> StatusCode {
     tracing::info!("Server restart requested");
 
@@ -59,8 +71,9 @@ async fn restart_server(
 
     tracing::info!("Spawning new server instance: {:?}", exe);
 
-    // Spawn a new server process
+    // Spawn a new server process, with the same options (port, --no-open)
     match std::process::Command::new(&exe)
+        .args(std::env::args().skip(1))
         .current_dir(&cwd)
         .spawn()
     {
@@ -68,10 +81,11 @@ async fn restart_server(
             tracing::info!("New server instance spawned, scheduling shutdown");
 
             // Schedule graceful shutdown after a delay
-            tokio::spawn(async {
+            tokio::spawn(async move {
                 // Give the new process time to start and bind to a different port
                 tokio::time::sleep(Duration::from_secs(2)).await;
                 tracing::info!("Shutting down old server instance");
+                state.cleanup_all_previews().await;
                 std::process::exit(0);
             });
 
@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::project::CurrentProject;
+use crate::settings::{self, ResolvedSettings, Scope, Settings, SettingsError};
+use crate::state::AppState;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Serialize)]
+struct SettingsResponse {
+    /// What the project runs with
+    settings: Arc<Settings>,
+    global_file: PathBuf,
+    project_file: PathBuf,
+    /// Contents of each file; null if they can't be read
+    global: Option<Value>,
+    project: Option<Value>,
+    env_overrides: Vec<String>,
+    /// Why the files are being ignored, if they are
+    error: Option<String>,
+}
+
+impl From<ResolvedSettings> for SettingsResponse {
+    fn from(resolved: ResolvedSettings) -> Self {
+        Self {
+            settings: Arc::new(resolved.settings),
+            global_file: resolved.global_file,
+            project_file: resolved.project_file,
+            global: Some(resolved.global),
+            project: Some(resolved.project),
+            env_overrides: resolved.env_overrides,
+            error: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UpdateQuery {
+    /// File the change is written to (default: global)
+    scope: Option<Scope>,
+}
+
+pub fn settings_router() -> Router<Arc<AppState>> {
+    Router::new().route("/settings", get(get_settings).patch(update_settings))
+}
+
+async fn get_settings(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+) -> Json<SettingsResponse> {
+    match state.settings.resolve(&project.path) {
+        Ok(resolved) => Json(resolved.into()),
+        // Show what is in use instead, and why
+        Err(e) => {
+            let (settings, _) = state.settings.cached(&project.path);
+            Json(SettingsResponse {
+                settings,
+                global_file: settings::global_config_path(),
+                project_file: settings::project_config_path(&project.path),
+                global: None,
+                project: None,
+                env_overrides: Vec::new(),
+                error: Some(e.to_string()),
+            })
+        }
+    }
+}
+
+/// Change settings with a JSON merge patch; `null` removes a key from the
+/// file. Takes effect right away, except `server.port`.
+async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    project: CurrentProject,
+    Query(query): Query<UpdateQuery>,
+    Json(patch): Json<Value>,
+) -> Result<Json<SettingsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let scope = query.scope.unwrap_or(Scope::Global);
+
+    let resolved = state.settings.update(&project.path, scope, &patch).map_err(|e| {
+        let status = match e {
+            SettingsError::Read { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        (status, Json(ErrorResponse { error: e.to_string() }))
+    })?;
+
+    tracing::info!("Updated {:?} settings for {}", scope, project.project.name);
+    Ok(Json(resolved.into()))
+}
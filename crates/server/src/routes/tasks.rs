@@ -1,18 +1,27 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use axum::{
     Json, Router,
-    extract::{Path, State},
+    extract::{Path, Query, State, rejection::JsonRejection},
     http::StatusCode,
     routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 
-use eval_kanban_db::{CreateTask, Task, TaskStatus, UpdateTask};
-use eval_kanban_executor::{ClaudeExecutor, ExecutorEvent};
+use eval_kanban_db::{Attachment, AttachmentOwner, CheckStatus, CreateTask, ExecutionLog, RunCheck, RunStatus, Task, TaskAttempt, TaskDependency, TaskRun, TaskStatus, UpdateTask};
+use eval_kanban_executor::ClaudeExecutor;
 
+use crate::attachments;
+use crate::routes::{self, attempts};
+use crate::budget::{self, BudgetBreach};
+use crate::project::{CurrentProject, ProjectContext};
+use crate::prompt;
+use crate::settings::{OnCheckFailure, VerifyCommand};
 use crate::state::AppState;
 use crate::routes::ws::WsMessage;
+use crate::run_events::{ClaudeRun, RunEvent};
+use crate::verify;
 
 #[derive(Serialize)]
 struct TaskResponse {
@@ -36,6 +45,13 @@ struct CreateTaskRequest {
     description: Option<String>,
 }
 
+/// Optional body for starting a task.
+#[derive(Deserialize, Default)]
+struct StartTaskRequest {
+    /// Feedback on a previous attempt, available to templates as `{{feedback}}`
+    feedback: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct UpdateTaskRequest {
     title: Option<String>,
@@ -51,12 +67,16 @@ pub fn tasks_router() -> Router<Arc<AppState>> {
         .route("/:id/cancel", post(cancel_task))
         .route("/:id/complete", post(complete_task))
         .route("/:id/merge", post(merge_task))
+        .route("/:id/runs", get(list_runs))
+        .route("/:id/dependencies", get(list_dependencies))
+        .route("/:id/logs", get(list_logs))
 }
 
 async fn list_tasks(
     State(state): State<Arc<AppState>>,
+    project: CurrentProject,
 ) -> Result<Json<TasksResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let project_path = state.working_dir.to_string_lossy().to_string();
+    let project_path = project.path_string();
     let tasks = Task::find_all_by_project(&state.db, &project_path).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -93,8 +113,109 @@ async fn get_task(
     }
 }
 
+#[derive(Serialize)]
+struct RunsResponse {
+    runs: Vec<TaskRun>,
+}
+
+async fn list_runs(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<RunsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let runs = TaskRun::find_by_task(&state.db, &id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    Ok(Json(RunsResponse { runs }))
+}
+
+/// Lines returned by `GET /:id/logs` when no limit is given
+const DEFAULT_LOG_LIMIT: i64 = 1000;
+
+#[derive(Deserialize)]
+struct LogsQuery {
+    limit: Option<i64>,
+    /// Only lines after this log id, oldest first
+    after: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct LogsResponse {
+    logs: Vec<ExecutionLog>,
+}
+
+/// The most recent stored output of a task, across runs, oldest first. With
+/// `after`, the lines following that one instead.
+async fn list_logs(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<LogsQuery>,
+) -> Result<Json<LogsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let limit = query.limit.unwrap_or(DEFAULT_LOG_LIMIT).max(1);
+    let logs = match query.after {
+        Some(after) => ExecutionLog::find_after(&state.db, &id, after, limit).await,
+        None => ExecutionLog::find_latest_by_task(&state.db, &id, limit).await,
+    };
+    let logs = logs.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    Ok(Json(LogsResponse { logs }))
+}
+
+#[derive(Serialize)]
+struct DependenciesResponse {
+    /// Tasks that must be done before this one can start
+    depends_on: Vec<TaskDependency>,
+    /// Tasks waiting for this one
+    dependents: Vec<TaskDependency>,
+}
+
+async fn list_dependencies(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<DependenciesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let map_err = |e: sqlx::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    };
+
+    let depends_on = TaskDependency::find_by_task(&state.db, &id).await.map_err(map_err)?;
+    let dependents = TaskDependency::find_dependents(&state.db, &id).await.map_err(map_err)?;
+
+    Ok(Json(DependenciesResponse { depends_on, dependents }))
+}
+
+/// Dependencies of a task that are not done yet.
+pub async fn unfinished_dependencies(state: &AppState, task_id: &str) -> Result<Vec<Task>, sqlx::Error> {
+    let mut unfinished = Vec::new();
+    for dep in TaskDependency::find_by_task(&state.db, task_id).await? {
+        if let Some(task) = Task::find_by_id(&state.db, &dep.depends_on_task_id).await? {
+            if task.status != TaskStatus::Done {
+                unfinished.push(task);
+            }
+        }
+    }
+    Ok(unfinished)
+}
+
 async fn create_task(
     State(state): State<Arc<AppState>>,
+    project: CurrentProject,
     Json(req): Json<CreateTaskRequest>,
 ) -> Result<(StatusCode, Json<TaskResponse>), (StatusCode, Json<ErrorResponse>)> {
     if req.title.trim().is_empty() {
@@ -106,7 +227,7 @@ async fn create_task(
         ));
     }
 
-    let project_path = state.working_dir.to_string_lossy().to_string();
+    let project_path = project.path_string();
     let task = Task::create(
         &state.db,
         CreateTask {
@@ -185,6 +306,23 @@ async fn delete_task(
     })?;
 
     if deleted {
+        if let Err(e) = TaskDependency::delete_for_task(&state.db, &id).await {
+            tracing::warn!("Failed to remove dependencies of task {}: {}", id, e);
+        }
+
+        if let Err(e) = ExecutionLog::delete_by_task(&state.db, &id).await {
+            tracing::warn!("Failed to remove logs of task {}: {}", id, e);
+        }
+
+        if let Ok(task_attachments) = Attachment::find_by_owner(&state.db, AttachmentOwner::Task, &id).await {
+            attachments::remove_all(&state.db, &task_attachments).await;
+        }
+
+        // Their worktrees are removed with the other orphans on the next start
+        if let Err(e) = TaskAttempt::delete_by_task(&state.db, &id).await {
+            tracing::warn!("Failed to remove attempts of task {}: {}", id, e);
+        }
+
         state.broadcast(WsMessage::TaskDeleted { task_id: id }).await;
         Ok(StatusCode::NO_CONTENT)
     } else {
@@ -197,10 +335,364 @@ async fn delete_task(
     }
 }
 
+/// Store a line of executor output and send it to clients.
+async fn record_output(state: &AppState, spec: &RunSpec, run_id: Option<&str>, stream: &str, line: String) {
+    if let Err(e) = ExecutionLog::append(&state.db, &spec.task_id, run_id, stream, &line).await {
+        tracing::warn!("Failed to store log line for task {}: {}", spec.task_id, e);
+    }
+
+    state.broadcast(WsMessage::Log {
+        task_id: spec.task_id.clone(),
+        content: line,
+        stream: stream.to_string(),
+        attempt_id: spec.attempt_id.clone(),
+    }).await;
+}
+
+/// What Claude works on: the task itself, or one of its parallel attempts.
+pub struct RunSpec {
+    pub task_id: String,
+    /// Set when running one of the task's attempts, which get their own
+    /// worktree and checks status
+    pub attempt_id: Option<String>,
+    pub working_dir: PathBuf,
+    pub project_path: PathBuf,
+    pub prompt: String,
+    /// Model to run with instead of the CLI's default
+    pub model: Option<String>,
+}
+
+/// How running Claude for a task or attempt ended.
+pub enum RunEnd {
+    /// Claude exited, with the checks of its last run if it succeeded
+    Finished { success: bool, checks: Vec<RunCheck> },
+    /// Claude couldn't be started
+    SpawnFailed(String),
+    Cancelled,
+    /// Stopped because the task or project budget was spent; the worktree is
+    /// left in place so partial work is preserved
+    OverBudget(BudgetBreach),
+}
+
+/// Run Claude once, recording its output and usage on a new run. Returns how
+/// it ended, without checks, and the run.
+async fn execute_run(
+    state: &AppState,
+    spec: &RunSpec,
+    prompt: &str,
+    cancel_rx: &mut mpsc::Receiver<()>,
+) -> (RunEnd, Option<String>) {
+    let task_id = spec.task_id.as_str();
+    let executor = ClaudeExecutor::new(spec.working_dir.clone()).with_model(spec.model.clone());
+    let mut claude = match ClaudeRun::spawn(&executor, prompt).await {
+        Ok(claude) => claude,
+        Err(e) => {
+            tracing::error!("Failed to spawn executor: {}", e);
+            return (RunEnd::SpawnFailed(e.to_string()), None);
+        }
+    };
+
+    let run_id = match TaskRun::create(&state.db, task_id, spec.attempt_id.as_deref()).await {
+        Ok(run) => Some(run.id),
+        Err(e) => {
+            tracing::error!("Failed to record run for task {}: {}", task_id, e);
+            None
+        }
+    };
+
+    loop {
+        match claude.next(cancel_rx).await {
+            RunEvent::Output { stream, line } => {
+                record_output(state, spec, run_id.as_deref(), stream, line).await;
+            }
+            RunEvent::Usage(run_usage) => {
+                let Some(run_id) = run_id.as_deref() else {
+                    continue;
+                };
+
+                if let Err(e) = TaskRun::set_usage(&state.db, run_id, &run_usage).await {
+                    tracing::warn!("Failed to record usage for task {}: {}", task_id, e);
+                    continue;
+                }
+
+                state.broadcast(WsMessage::UsageUpdated {
+                    task_id: task_id.to_string(),
+                    run_id: run_id.to_string(),
+                    usage: run_usage,
+                }).await;
+
+                let breach = match Task::find_by_id(&state.db, task_id).await {
+                    Ok(Some(task)) => budget::check_task_spend(&state.db, &task).await.ok().flatten(),
+                    _ => None,
+                };
+
+                if let Some(breach) = breach {
+                    tracing::warn!("Task {} stopped: {}", task_id, breach.message);
+                    claude.stop().await;
+                    let _ = TaskRun::finish(&state.db, run_id, RunStatus::BudgetExceeded).await;
+                    return (RunEnd::OverBudget(breach), Some(run_id.to_string()));
+                }
+            }
+            RunEvent::Finished { success } => {
+                tracing::info!("Task {} executor completed with success={}", task_id, success);
+
+                if let Some(run_id) = run_id.as_deref() {
+                    let status = if success { RunStatus::Succeeded } else { RunStatus::Failed };
+                    let _ = TaskRun::finish(&state.db, run_id, status).await;
+                }
+
+                return (RunEnd::Finished { success, checks: Vec::new() }, run_id);
+            }
+            RunEvent::Cancelled => {
+                tracing::info!("Task {} cancelled", task_id);
+
+                if let Some(run_id) = run_id.as_deref() {
+                    let _ = TaskRun::finish(&state.db, run_id, RunStatus::Cancelled).await;
+                }
+
+                return (RunEnd::Cancelled, run_id);
+            }
+        }
+    }
+}
+
+/// Run Claude until it is done with the task: once, then the verification
+/// checks if it succeeded, and again with the failing output for as many
+/// times as `verify.auto_fix_attempts` allows. Leaves the task's (or
+/// attempt's) status to the caller.
+pub async fn run_to_completion(state: &AppState, spec: &RunSpec, cancel_rx: &mut mpsc::Receiver<()>) -> RunEnd {
+    let task_id = spec.task_id.as_str();
+    // With auto-fix, failing checks start another run with their output
+    let mut run_prompt = spec.prompt.clone();
+    let mut fix_attempts = 0;
+
+    loop {
+        let (end, run_id) = execute_run(state, spec, &run_prompt, cancel_rx).await;
+        let RunEnd::Finished { success, .. } = end else {
+            return end;
+        };
+
+        // The task stays in progress while its checks run
+        let verify = state.settings.get(&spec.project_path).verify.clone();
+        let mut checks = Vec::new();
+        let mut outputs = Vec::new();
+        if success && !verify.commands.is_empty() {
+            let cancelled = tokio::select! {
+                _ = run_checks(state, spec, run_id.as_deref(), &verify.commands, &mut checks, &mut outputs) => false,
+                _ = cancel_rx.recv() => true,
+            };
+
+            if cancelled {
+                tracing::info!("Task {} cancelled during checks", task_id);
+                for check in checks.iter_mut().filter(|c| c.status != CheckStatus::Passed) {
+                    check.status = CheckStatus::Failed;
+                    check.error = Some("cancelled".to_string());
+                }
+                store_checks(state, task_id, run_id.as_deref(), &checks).await;
+                set_checks_status(state, spec, None).await;
+                return RunEnd::Cancelled;
+            }
+
+            set_checks_status(state, spec, Some(CheckStatus::overall(&checks))).await;
+        }
+        let checks_failed = CheckStatus::overall(&checks) == CheckStatus::Failed;
+
+        if checks_failed && fix_attempts < verify.auto_fix_attempts {
+            let breach = match Task::find_by_id(&state.db, task_id).await {
+                Ok(Some(task)) => budget::check_task_spend(&state.db, &task).await.ok().flatten(),
+                _ => None,
+            };
+
+            match breach {
+                Some(breach) => {
+                    record_output(state, spec, run_id.as_deref(), "check", format!("[auto-fix] Not running Claude again: {}", breach.message)).await;
+                }
+                None => {
+                    fix_attempts += 1;
+                    tracing::info!("Task {} auto-fix attempt {} of {}", task_id, fix_attempts, verify.auto_fix_attempts);
+                    record_output(
+                        state,
+                        spec,
+                        run_id.as_deref(),
+                        "check",
+                        format!("[auto-fix] Running Claude again with the failing output ({} of {})", fix_attempts, verify.auto_fix_attempts),
+                    ).await;
+
+                    run_prompt = format!("{}\n\n{}", spec.prompt, prompt::check_failures_section(&checks, &outputs));
+                    continue;
+                }
+            }
+        }
+
+        return RunEnd::Finished { success, checks };
+    }
+}
+
+/// Run the verification checks in the worktree one after another, storing
+/// them on the run as they finish and streaming their output. `checks` ends
+/// up with one result per command, and `outputs` with the end of each one's
+/// output.
+async fn run_checks(
+    state: &AppState,
+    spec: &RunSpec,
+    run_id: Option<&str>,
+    commands: &[VerifyCommand],
+    checks: &mut Vec<RunCheck>,
+    outputs: &mut Vec<String>,
+) {
+    let task_id = spec.task_id.as_str();
+    *checks = commands.iter().map(verify::pending).collect();
+    outputs.clear();
+    set_checks_status(state, spec, Some(CheckStatus::Running)).await;
+
+    for (index, command) in commands.iter().enumerate() {
+        checks[index].status = CheckStatus::Running;
+        store_checks(state, task_id, run_id, checks).await;
+        record_output(state, spec, run_id, "check", format!("[{}] $ {}", command.name, command.command)).await;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let check = verify::run(command, &spec.working_dir, tx);
+        tokio::pin!(check);
+        let mut output = verify::OutputTail::default();
+        let result = loop {
+            tokio::select! {
+                result = &mut check => break result,
+                Some(line) = rx.recv() => {
+                    output.push(&line);
+                    record_output(state, spec, run_id, "check", format!("[{}] {}", command.name, line)).await;
+                }
+            }
+        };
+        while let Ok(line) = rx.try_recv() {
+            output.push(&line);
+            record_output(state, spec, run_id, "check", format!("[{}] {}", command.name, line)).await;
+        }
+        outputs.push(output.to_string());
+
+        tracing::info!("Task {} check {} {}", task_id, command.name, verify::summary(&result));
+        record_output(state, spec, run_id, "check", format!("[{}] {}", command.name, verify::summary(&result))).await;
+        checks[index] = result;
+    }
+    store_checks(state, task_id, run_id, checks).await;
+}
+
+async fn store_checks(state: &AppState, task_id: &str, run_id: Option<&str>, checks: &[RunCheck]) {
+    let Some(run_id) = run_id else {
+        return;
+    };
+    if let Err(e) = TaskRun::set_checks(&state.db, run_id, checks).await {
+        tracing::warn!("Failed to record checks for task {}: {}", task_id, e);
+    }
+
+    state.broadcast(WsMessage::ChecksUpdated {
+        task_id: task_id.to_string(),
+        run_id: run_id.to_string(),
+        checks: checks.to_vec(),
+    }).await;
+}
+
+/// Record the state of the latest checks on the attempt, or the task.
+async fn set_checks_status(state: &AppState, spec: &RunSpec, checks_status: Option<CheckStatus>) {
+    match spec.attempt_id.as_deref() {
+        Some(attempt_id) => {
+            if let Ok(Some(attempt)) = TaskAttempt::set_checks_status(&state.db, attempt_id, checks_status).await {
+                state.broadcast(WsMessage::AttemptUpdated { attempt }).await;
+            }
+        }
+        None => {
+            if let Ok(Some(task)) = Task::set_checks_status(&state.db, &spec.task_id, checks_status).await {
+                state.broadcast(WsMessage::TaskUpdated { task }).await;
+            }
+        }
+    }
+}
+
+/// Update the task once Claude is done with it, and let clients know.
+async fn finish_task(state: &AppState, task_id: &str, end: RunEnd, on_failure: OnCheckFailure) {
+    match end {
+        RunEnd::Finished { success, checks } => {
+            let checks_failed = CheckStatus::overall(&checks) == CheckStatus::Failed;
+            let task_result = if checks_failed && on_failure == OnCheckFailure::Block {
+                Task::set_status(&state.db, task_id, TaskStatus::ChecksFailed).await
+            } else if success {
+                Task::set_status(&state.db, task_id, TaskStatus::Review).await
+            } else {
+                Task::set_error(
+                    &state.db,
+                    task_id,
+                    "Executor completed with non-zero exit code".to_string(),
+                ).await
+            };
+
+            if let Ok(Some(task)) = task_result {
+                state.broadcast(WsMessage::TaskUpdated { task }).await;
+            }
+
+            state.broadcast(WsMessage::ExecutionComplete {
+                task_id: task_id.to_string(),
+                success,
+            }).await;
+        }
+        RunEnd::SpawnFailed(error) => {
+            if let Ok(Some(task)) = Task::set_error(&state.db, task_id, error).await {
+                state.broadcast(WsMessage::TaskUpdated { task }).await;
+            }
+
+            state.broadcast(WsMessage::ExecutionComplete {
+                task_id: task_id.to_string(),
+                success: false,
+            }).await;
+        }
+        RunEnd::Cancelled => {
+            if let Ok(Some(task)) = Task::set_status(&state.db, task_id, TaskStatus::Todo).await {
+                state.broadcast(WsMessage::TaskUpdated { task }).await;
+            }
+        }
+        RunEnd::OverBudget(breach) => {
+            set_budget_exceeded(state, task_id, breach).await;
+        }
+    }
+
+    state.remove_running_task(task_id).await;
+}
+
+/// Stop the task at its budget, keeping its worktree.
+pub async fn set_budget_exceeded(state: &AppState, task_id: &str, breach: BudgetBreach) {
+    if let Ok(Some(task)) = Task::update(
+        &state.db,
+        task_id,
+        UpdateTask {
+            title: None,
+            description: None,
+            status: Some(TaskStatus::BudgetExceeded),
+            error_message: Some(breach.message.clone()),
+            branch_name: None,
+            worktree_path: None,
+        },
+    ).await {
+        state.broadcast(WsMessage::TaskUpdated { task }).await;
+    }
+
+    state.broadcast(WsMessage::BudgetExceeded {
+        task_id: task_id.to_string(),
+        scope: breach.scope.as_str().to_string(),
+        message: breach.message,
+    }).await;
+
+    state.broadcast(WsMessage::ExecutionComplete {
+        task_id: task_id.to_string(),
+        success: false,
+    }).await;
+}
+
 async fn start_task(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
+    req: Result<Json<StartTaskRequest>, JsonRejection>,
 ) -> Result<Json<TaskResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let req: StartTaskRequest = routes::optional_body(req)
+        .map_err(|error| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })))?;
+
     let task = Task::find_by_id(&state.db, &id).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -222,7 +714,10 @@ async fn start_task(
         }
     };
 
-    if state.is_task_running(&id).await {
+    // Reserve the task before anything is set up for it, so a second start
+    // can't create another worktree or run alongside this one
+    let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+    if !state.try_add_running_task(id.clone(), cancel_tx).await {
         return Err((
             StatusCode::CONFLICT,
             Json(ErrorResponse {
@@ -231,9 +726,102 @@ async fn start_task(
         ));
     }
 
-    // Create worktree if in a git repo
-    let (working_dir, branch_name, worktree_path) = if state.worktree_manager.is_git_repo() {
-        match state.worktree_manager.create_worktree(&task.title, &task.id).await {
+    let (updated, spec) = match prepare_run(&state, task, req.feedback.as_deref()).await {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            state.remove_running_task(&id).await;
+            return Err(e);
+        }
+    };
+
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let end = run_to_completion(&state_clone, &spec, &mut cancel_rx).await;
+        let on_failure = state_clone.settings.get(&spec.project_path).verify.on_failure;
+        finish_task(&state_clone, &spec.task_id, end, on_failure).await;
+    });
+
+    Ok(Json(TaskResponse { task: updated }))
+}
+
+/// Check a reserved task can run, then set up its worktree and mark it in
+/// progress. Returns the updated task and what to run.
+async fn prepare_run(
+    state: &Arc<AppState>,
+    task: Task,
+    feedback: Option<&str>,
+) -> Result<(Task, RunSpec), (StatusCode, Json<ErrorResponse>)> {
+    let id = task.id.clone();
+
+    let blocking = unfinished_dependencies(state, &id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    if !blocking.is_empty() {
+        let titles: Vec<&str> = blocking.iter().map(|t| t.title.as_str()).collect();
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: format!("Task is waiting for: {}", titles.join(", ")),
+            }),
+        ));
+    }
+
+    let breach = budget::check_task_spend(&state.db, &task).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    if let Some(breach) = breach {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: breach.message,
+            }),
+        ));
+    }
+
+    let prompt = prompt::build_task_prompt(state, &task, feedback)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?;
+
+    let project = state.project_for_task(&task).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    // Reuse the task's worktree if it still exists (e.g. partial work from a
+    // run stopped by its budget), otherwise create one if in a git repo
+    let existing_worktree = task.worktree_path.as_ref()
+        .map(std::path::PathBuf::from)
+        .filter(|p| p.exists());
+
+    let (working_dir, branch_name, worktree_path) = if let Some(wt_path) = existing_worktree {
+        tracing::info!("Reusing worktree for task {} at {}", id, wt_path.display());
+        (wt_path, None, None)
+    } else if project.worktree_manager.is_git_repo() {
+        let branch_prefix = &state.settings.get(&project.path).worktree.branch_prefix;
+        match project.worktree_manager.create_worktree(branch_prefix, &task.title, &task.id).await {
             Ok((branch, wt_path)) => {
                 tracing::info!("Created worktree for task {}: {} at {}", id, branch, wt_path.display());
 
@@ -251,14 +839,24 @@ async fn start_task(
             }
             Err(e) => {
                 tracing::warn!("Failed to create worktree, using main directory: {}", e);
-                (state.working_dir.clone(), None, None)
+                (project.path.clone(), None, None)
             }
         }
     } else {
         tracing::info!("Not a git repo, running in main directory");
-        (state.working_dir.clone(), None, None)
+        (project.path.clone(), None, None)
     };
 
+    // Running the task itself gives up on attempts that were never picked
+    if let Ok(attempts) = TaskAttempt::find_by_task(&state.db, &id).await {
+        attempts::discard_attempts(state, &project, &attempts).await;
+    }
+
+    // A new run's checks replace the last run's
+    if task.checks_status.is_some() {
+        let _ = Task::set_checks_status(&state.db, &id, None).await;
+    }
+
     let updated = Task::set_status(&state.db, &id, TaskStatus::InProgress)
         .await
         .map_err(|e| {
@@ -284,117 +882,16 @@ async fn start_task(
 
     state.broadcast(WsMessage::TaskUpdated { task: updated.clone() }).await;
 
-    let prompt = task.description.clone().unwrap_or_else(|| task.title.clone());
-    let executor = ClaudeExecutor::new(working_dir);
-
-    let state_clone = state.clone();
-    let task_id = id.clone();
-
-    let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
-    state.add_running_task(id.clone(), cancel_tx).await;
-
-    tokio::spawn(async move {
-        match executor.spawn(&prompt).await {
-            Ok((mut rx, process)) => {
-                let state_for_wait = state_clone.clone();
-                let task_id_for_wait = task_id.clone();
-
-                let wait_handle = tokio::spawn(async move {
-                    process.wait().await
-                });
-
-                loop {
-                    tokio::select! {
-                        Some(event) = rx.recv() => {
-                            match event {
-                                ExecutorEvent::Stdout(line) => {
-                                    state_clone.broadcast(WsMessage::Log {
-                                        task_id: task_id.clone(),
-                                        content: line,
-                                        stream: "stdout".to_string(),
-                                    }).await;
-                                }
-                                ExecutorEvent::Stderr(line) => {
-                                    state_clone.broadcast(WsMessage::Log {
-                                        task_id: task_id.clone(),
-                                        content: line,
-                                        stream: "stderr".to_string(),
-                                    }).await;
-                                }
-                                ExecutorEvent::Completed { success } => {
-                                    tracing::info!("Task {} executor completed with success={}", task_id, success);
-
-                                    let task_result = if success {
-                                        Task::set_status(
-                                            &state_clone.db,
-                                            &task_id,
-                                            TaskStatus::Review,
-                                        ).await
-                                    } else {
-                                        Task::set_error(
-                                            &state_clone.db,
-                                            &task_id,
-                                            "Executor completed with non-zero exit code".to_string(),
-                                        ).await
-                                    };
-
-                                    if let Ok(Some(task)) = task_result {
-                                        state_clone.broadcast(WsMessage::TaskUpdated { task }).await;
-                                    }
-
-                                    state_clone.broadcast(WsMessage::ExecutionComplete {
-                                        task_id: task_id.clone(),
-                                        success,
-                                    }).await;
-
-                                    state_clone.remove_running_task(&task_id).await;
-                                    break;
-                                }
-                                _ => {}
-                            }
-                        }
-                        _ = cancel_rx.recv() => {
-                            tracing::info!("Task {} cancelled", task_id);
-                            drop(wait_handle);
-
-                            if let Ok(Some(task)) = Task::set_status(
-                                &state_clone.db,
-                                &task_id,
-                                TaskStatus::Todo,
-                            ).await {
-                                state_clone.broadcast(WsMessage::TaskUpdated { task }).await;
-                            }
-
-                            state_clone.remove_running_task(&task_id).await;
-                            break;
-                        }
-                    }
-                }
-
-                let _ = state_for_wait.remove_running_task(&task_id_for_wait).await;
-            }
-            Err(e) => {
-                tracing::error!("Failed to spawn executor: {}", e);
-
-                if let Ok(Some(task)) = Task::set_error(
-                    &state_clone.db,
-                    &task_id,
-                    e.to_string(),
-                ).await {
-                    state_clone.broadcast(WsMessage::TaskUpdated { task }).await;
-                }
-
-                state_clone.broadcast(WsMessage::ExecutionComplete {
-                    task_id: task_id.clone(),
-                    success: false,
-                }).await;
-
-                state_clone.remove_running_task(&task_id).await;
-            }
-        }
-    });
+    let spec = RunSpec {
+        task_id: id.clone(),
+        attempt_id: None,
+        working_dir,
+        project_path: project.path.clone(),
+        prompt,
+        model: None,
+    };
 
-    Ok(Json(TaskResponse { task: updated }))
+    Ok((updated, spec))
 }
 
 async fn cancel_task(
@@ -475,6 +972,15 @@ async fn complete_task(
         ));
     }
 
+    let project = state.project_for_task(&task).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
     // Get branch name
     let branch_name = match &task.branch_name {
         Some(name) => name.clone(),
@@ -482,20 +988,20 @@ async fn complete_task(
             return Err((
                 StatusCode::BAD_REQUEST,
                 Json(ErrorResponse {
-                    error: "Task has no branch to merge".to_string(),
+                    error: no_branch_error(&state, &id).await,
                 }),
             ))
         }
     };
 
     // Stop any running preview for this task
-    let _ = state.remove_preview(&id).await;
+    state.stop_preview(&id).await;
 
     // Get worktree path for cleanup
     let worktree_path = task.worktree_path.clone();
 
     // Merge branch to main
-    if let Err(e) = state.worktree_manager.merge_branch(&branch_name).await {
+    if let Err(e) = project.worktree_manager.merge_branch(&branch_name).await {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
@@ -507,13 +1013,13 @@ async fn complete_task(
     // Remove worktree if it exists
     if let Some(ref wt_path) = worktree_path {
         let path = std::path::PathBuf::from(wt_path);
-        if let Err(e) = state.worktree_manager.remove_worktree(&path).await {
+        if let Err(e) = project.worktree_manager.remove_worktree(&path).await {
             tracing::warn!("Failed to remove worktree: {}", e);
         }
     }
 
     // Delete the branch
-    if let Err(e) = state.worktree_manager.delete_branch(&branch_name).await {
+    if let Err(e) = project.worktree_manager.delete_branch(&branch_name).await {
         tracing::warn!("Failed to delete branch: {}", e);
     }
 
@@ -555,6 +1061,16 @@ async fn complete_task(
     Ok(Json(TaskResponse { task: updated }))
 }
 
+/// Why a task in review can't be merged without a branch.
+async fn no_branch_error(state: &AppState, task_id: &str) -> String {
+    let attempts = TaskAttempt::find_by_task(&state.db, task_id).await.unwrap_or_default();
+    if attempts.iter().any(|attempt| attempt.status.is_finished()) {
+        "Pick one of the task's attempts to merge first".to_string()
+    } else {
+        "Task has no branch to merge".to_string()
+    }
+}
+
 #[derive(Serialize)]
 struct MergeResponse {
     success: bool,
@@ -600,6 +1116,15 @@ async fn merge_task(
         ));
     }
 
+    let project = state.project_for_task(&task).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
     // Get branch name
     let branch_name = match &task.branch_name {
         Some(name) => name.clone(),
@@ -607,7 +1132,7 @@ async fn merge_task(
             return Err((
                 StatusCode::BAD_REQUEST,
                 Json(ErrorResponse {
-                    error: "Task has no branch to merge".to_string(),
+                    error: no_branch_error(&state, &id).await,
                 }),
             ))
         }
@@ -619,7 +1144,7 @@ async fn merge_task(
     }).await;
 
     // Stop any running preview for this task
-    let _ = state.remove_preview(&id).await;
+    state.stop_preview(&id).await;
 
     // Get worktree path for cleanup
     let worktree_path = task.worktree_path.clone();
@@ -631,7 +1156,7 @@ async fn merge_task(
     }).await;
 
     // Merge branch to main
-    if let Err(e) = state.worktree_manager.merge_branch(&branch_name).await {
+    if let Err(e) = project.worktree_manager.merge_branch(&branch_name).await {
         state.broadcast(WsMessage::MergeFailed {
             task_id: id.clone(),
             error: format!("Failed to merge branch: {}", e),
@@ -646,7 +1171,7 @@ async fn merge_task(
     }
 
     // Get the merge commit hash
-    let merge_commit = state.worktree_manager
+    let merge_commit = project.worktree_manager
         .get_head_commit()
         .await
         .ok()
@@ -661,13 +1186,13 @@ async fn merge_task(
     // Remove worktree if it exists
     if let Some(ref wt_path) = worktree_path {
         let path = std::path::PathBuf::from(wt_path);
-        if let Err(e) = state.worktree_manager.remove_worktree(&path).await {
+        if let Err(e) = project.worktree_manager.remove_worktree(&path).await {
             tracing::warn!("Failed to remove worktree: {}", e);
         }
     }
 
     // Delete the branch
-    if let Err(e) = state.worktree_manager.delete_branch(&branch_name).await {
+    if let Err(e) = project.worktree_manager.delete_branch(&branch_name).await {
         tracing::warn!("Failed to delete branch: {}", e);
     }
 
@@ -722,7 +1247,7 @@ async fn merge_task(
     // Trigger rebuild in background
     let state_for_rebuild = state.clone();
     tokio::spawn(async move {
-        trigger_rebuild(state_for_rebuild).await;
+        trigger_rebuild(state_for_rebuild, project).await;
     });
 
     Ok(Json(MergeResponse {
@@ -733,16 +1258,16 @@ async fn merge_task(
     }))
 }
 
-async fn trigger_rebuild(state: Arc<AppState>) {
+async fn trigger_rebuild(state: Arc<AppState>, project: Arc<ProjectContext>) {
     // Only rebuild if this is the eval-kanban project itself
-    let cargo_toml = state.working_dir.join("Cargo.toml");
+    let cargo_toml = project.path.join("Cargo.toml");
     if !cargo_toml.exists() {
         tracing::debug!("Skipping rebuild: not a Cargo project");
         return;
     }
 
     // Check if this is specifically eval-kanban (has the server crate)
-    let server_crate = state.working_dir.join("crates/server/Cargo.toml");
+    let server_crate = project.path.join("crates/server/Cargo.toml");
     if !server_crate.exists() {
         tracing::debug!("Skipping rebuild: not the eval-kanban project");
         return;
@@ -760,7 +1285,7 @@ async fn trigger_rebuild(state: Arc<AppState>) {
     // Run cargo build
     let output = tokio::process::Command::new("cargo")
         .args(["build", "-p", "eval-kanban-server"])
-        .current_dir(&state.working_dir)
+        .current_dir(&project.path)
         .output()
         .await;
 
@@ -1,3 +1,4 @@
+use std::collections::{HashSet, VecDeque};
 use std::sync::Arc;
 use axum::{
     extract::{
@@ -6,12 +7,15 @@ use axum::{
     },
     response::IntoResponse,
 };
-use futures::{SinkExt, StreamExt};
+use futures::{SinkExt, StreamExt, stream::SplitSink};
 use serde::{Deserialize, Serialize};
-use eval_kanban_db::Task;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use eval_kanban_db::{EvalResult, EvalRun, RunCheck, RunUsage, Task, TaskAttempt};
 
-use crate::state::AppState;
+use crate::project::CurrentProject;
+use crate::state::{AppState, PreviewInfo};
 use crate::plan_session::PlanQuestion;
+use crate::preview::PreviewLogLine;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -22,14 +26,54 @@ pub enum WsMessage {
         task_id: String,
         content: String,
         stream: String,
+        /// Set for output of one of the task's parallel attempts
+        #[serde(skip_serializing_if = "Option::is_none")]
+        attempt_id: Option<String>,
     },
     ExecutionComplete {
         task_id: String,
         success: bool,
     },
+    UsageUpdated {
+        task_id: String,
+        run_id: String,
+        usage: RunUsage,
+    },
+    BudgetExceeded {
+        task_id: String,
+        scope: String,
+        message: String,
+    },
+    /// A run's verification checks started, or one of them finished
+    ChecksUpdated {
+        task_id: String,
+        run_id: String,
+        checks: Vec<RunCheck>,
+    },
+    /// A parallel attempt of a task was created, finished, picked or discarded
+    AttemptUpdated {
+        attempt: TaskAttempt,
+    },
+    /// An eval run started or finished
+    EvalRunUpdated {
+        run: EvalRun,
+    },
+    /// A case of an eval run started or was scored
+    EvalResultUpdated {
+        result: EvalResult,
+    },
     MergeStarted {
         task_id: String,
     },
+    /// A preview or one of its services changed status
+    PreviewUpdated {
+        preview: PreviewInfo,
+    },
+    /// A line of output from a preview service
+    PreviewLog {
+        task_id: String,
+        line: PreviewLogLine,
+    },
     MergeProgress {
         task_id: String,
         status: String,
@@ -68,65 +112,324 @@ pub enum WsMessage {
         error: String,
     },
     ChatChunk {
+        thread_id: String,
+        /// Id the reply is saved under once complete
+        message_id: String,
         content: String,
         is_complete: bool,
     },
     ChatError {
+        thread_id: String,
+        message_id: String,
         error: String,
     },
+    /// A reply was stopped via `POST /api/chat/cancel`
+    ChatCancelled {
+        thread_id: String,
+        message_id: String,
+    },
+    /// A tool Claude called while answering in a chat thread
+    ChatToolUse {
+        thread_id: String,
+        message_id: String,
+        tool_use_id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    ChatToolResult {
+        thread_id: String,
+        message_id: String,
+        tool_use_id: String,
+        is_error: bool,
+    },
+    /// First message on every connection
+    Connected {
+        last_event_id: u64,
+    },
+    /// Events the client missed are no longer buffered; it should reload
+    /// its state over HTTP
+    Resync {
+        last_event_id: u64,
+    },
     Ping,
     Pong,
 }
 
+/// A message as sent to clients, with an id that increases by one per
+/// broadcast. Clients send the last id they saw in `resume` after reconnecting.
+#[derive(Debug, Clone, Serialize)]
+pub struct WsEvent {
+    pub id: u64,
+    #[serde(flatten)]
+    pub message: WsMessage,
+    /// Project the message is about, if any; clients of other projects
+    /// don't get it
+    #[serde(skip)]
+    pub project_path: Option<String>,
+}
+
+/// Events kept for clients that reconnect or fall behind
+pub const EVENT_BUFFER_SIZE: usize = 1000;
+
+/// The most recent broadcast events, oldest first.
+pub struct EventLog {
+    next_id: u64,
+    events: VecDeque<WsEvent>,
+    capacity: usize,
+}
+
+impl EventLog {
+    /// Ids continue from `first_id`. Starting from the startup time keeps ids
+    /// seen before a restart older than anything buffered after it.
+    pub fn new(first_id: u64, capacity: usize) -> Self {
+        Self {
+            next_id: first_id.max(1),
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn record(&mut self, message: WsMessage, project_path: Option<String>) -> WsEvent {
+        let event = WsEvent { id: self.next_id, message, project_path };
+        self.next_id += 1;
+
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event.clone());
+
+        event
+    }
+
+    /// Id of the latest event, or one below the first id if there is none yet.
+    pub fn last_id(&self) -> u64 {
+        self.next_id - 1
+    }
+
+    /// Events after `last_seen`, or None if some of them are no longer
+    /// buffered (or `last_seen` comes from before a restart).
+    pub fn since(&self, last_seen: u64) -> Option<Vec<WsEvent>> {
+        if last_seen > self.last_id() {
+            return None;
+        }
+
+        let oldest = self.events.front().map_or(self.next_id, |e| e.id);
+        if last_seen + 1 < oldest {
+            return None;
+        }
+
+        Some(self.events.iter().filter(|e| e.id > last_seen).cloned().collect())
+    }
+}
+
+/// Who a message is delivered to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageScope<'a> {
+    /// Board-level events every client receives
+    All,
+    /// Only clients subscribed to the task
+    Task(&'a str),
+    /// Only clients subscribed to the plan session
+    PlanSession(&'a str),
+}
+
+/// What a message is about, which decides the project it belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageSubject<'a> {
+    /// The project at this path
+    Project(&'a str),
+    Task(&'a str),
+    PlanSession(&'a str),
+}
+
+impl WsMessage {
+    /// High-volume output is only sent to clients watching it; everything
+    /// else keeps the board in sync and goes to all clients (of the project
+    /// the message is about).
+    pub fn scope(&self) -> MessageScope<'_> {
+        match self {
+            WsMessage::Log { task_id, .. }
+            | WsMessage::MergeProgress { task_id, .. }
+            | WsMessage::ChecksUpdated { task_id, .. }
+            | WsMessage::PreviewLog { task_id, .. } => MessageScope::Task(task_id),
+            WsMessage::PreviewUpdated { preview } => MessageScope::Task(&preview.task_id),
+            WsMessage::AttemptUpdated { attempt } => MessageScope::Task(&attempt.task_id),
+            WsMessage::PlanOutput { session_id, .. } => MessageScope::PlanSession(session_id),
+            _ => MessageScope::All,
+        }
+    }
+
+    /// The task or plan session the message is about, or the project for a
+    /// task that carries its own. None for board-wide messages.
+    pub fn subject(&self) -> Option<MessageSubject<'_>> {
+        match self {
+            WsMessage::TaskUpdated { task } => task.project_path.as_deref().map(MessageSubject::Project),
+            WsMessage::TaskDeleted { task_id }
+            | WsMessage::Log { task_id, .. }
+            | WsMessage::ExecutionComplete { task_id, .. }
+            | WsMessage::UsageUpdated { task_id, .. }
+            | WsMessage::BudgetExceeded { task_id, .. }
+            | WsMessage::ChecksUpdated { task_id, .. }
+            | WsMessage::MergeStarted { task_id }
+            | WsMessage::PreviewLog { task_id, .. }
+            | WsMessage::MergeProgress { task_id, .. }
+            | WsMessage::MergeComplete { task_id, .. }
+            | WsMessage::MergeFailed { task_id, .. } => Some(MessageSubject::Task(task_id)),
+            WsMessage::AttemptUpdated { attempt } => Some(MessageSubject::Task(&attempt.task_id)),
+            WsMessage::PreviewUpdated { preview } => Some(MessageSubject::Task(&preview.task_id)),
+            WsMessage::PlanQuestions { session_id, .. }
+            | WsMessage::PlanSummary { session_id, .. }
+            | WsMessage::PlanError { session_id, .. }
+            | WsMessage::PlanOutput { session_id, .. } => Some(MessageSubject::PlanSession(session_id)),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ClientMessage {
     Subscribe { task_id: String },
     Unsubscribe { task_id: String },
+    SubscribePlan { session_id: String },
+    UnsubscribePlan { session_id: String },
+    /// Replay events after `last_seen`, sent after re-subscribing on reconnect
+    Resume { last_seen: u64 },
     Ping,
 }
 
+/// Project, tasks and plan sessions a connection is watching.
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    /// Path of the connection's project; None receives every project's events
+    project_path: Option<String>,
+    tasks: HashSet<String>,
+    plan_sessions: HashSet<String>,
+}
+
+impl Subscriptions {
+    pub fn for_project(project_path: String) -> Self {
+        Self {
+            project_path: Some(project_path),
+            ..Self::default()
+        }
+    }
+
+    pub fn wants(&self, event: &WsEvent) -> bool {
+        if let (Some(own), Some(path)) = (&self.project_path, &event.project_path) {
+            if own != path {
+                return false;
+            }
+        }
+
+        match event.message.scope() {
+            MessageScope::All => true,
+            MessageScope::Task(task_id) => self.tasks.contains(task_id),
+            MessageScope::PlanSession(session_id) => self.plan_sessions.contains(session_id),
+        }
+    }
+
+    /// Update the subscriptions from a client message. Returns false for
+    /// messages that are not about subscriptions.
+    pub fn apply(&mut self, msg: &ClientMessage) -> bool {
+        match msg {
+            ClientMessage::Subscribe { task_id } => self.tasks.insert(task_id.clone()),
+            ClientMessage::Unsubscribe { task_id } => self.tasks.remove(task_id),
+            ClientMessage::SubscribePlan { session_id } => self.plan_sessions.insert(session_id.clone()),
+            ClientMessage::UnsubscribePlan { session_id } => self.plan_sessions.remove(session_id),
+            ClientMessage::Resume { .. } | ClientMessage::Ping => return false,
+        };
+        true
+    }
+}
+
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
+    project: CurrentProject,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    let project_path = project.path_string();
+    ws.on_upgrade(|socket| handle_socket(socket, state, project_path))
 }
 
-async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
+async fn handle_socket(socket: WebSocket, state: Arc<AppState>, project_path: String) {
     tracing::info!("[WebSocket] New client connected");
     let (mut sender, mut receiver) = socket.split();
-    let mut broadcast_rx = state.ws_broadcast.subscribe();
+    let (connected_at, mut broadcast_rx) = state.subscribe_events();
+    let subscriptions = Arc::new(RwLock::new(Subscriptions::for_project(project_path)));
+    let send_subscriptions = subscriptions.clone();
+    let (resume_tx, mut resume_rx) = mpsc::channel::<u64>(4);
 
     let send_task = tokio::spawn(async move {
-        while let Ok(msg) = broadcast_rx.recv().await {
-            // Log plan-related messages for debugging
-            match &msg {
-                WsMessage::PlanQuestions { session_id, questions } => {
-                    tracing::info!("[WebSocket] Sending PlanQuestions ({} questions) for session {}", questions.len(), &session_id[..8.min(session_id.len())]);
-                }
-                WsMessage::PlanSummary { session_id, .. } => {
-                    tracing::info!("[WebSocket] Sending PlanSummary for session {}", &session_id[..8.min(session_id.len())]);
-                }
-                WsMessage::PlanError { session_id, error } => {
-                    tracing::warn!("[WebSocket] Sending PlanError for session {}: {}", &session_id[..8.min(session_id.len())], error);
-                }
-                WsMessage::PlanOutput { session_id, .. } => {
-                    tracing::debug!("[WebSocket] Sending PlanOutput for session {}", &session_id[..8.min(session_id.len())]);
+        let connected = WsEvent {
+            id: connected_at,
+            message: WsMessage::Connected { last_event_id: connected_at },
+            project_path: None,
+        };
+        if !send_event(&mut sender, &connected).await {
+            return;
+        }
+
+        // Live events all come after `connected_at`; `last_id` is the latest
+        // one handled, used to catch up after lagging behind the channel
+        let mut last_id = connected_at;
+
+        loop {
+            let missed = tokio::select! {
+                received = broadcast_rx.recv() => match received {
+                    Ok(event) => {
+                        if event.id <= last_id {
+                            continue;
+                        }
+                        last_id = event.id;
+
+                        if send_subscriptions.read().await.wants(&event) {
+                            log_plan_message(&event.message);
+                            if !send_event(&mut sender, &event).await {
+                                break;
+                            }
+                        }
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("[WebSocket] Client lagged behind by {} events, replaying", skipped);
+                        state.events_since(last_id).inspect(|events| {
+                            last_id = events.last().map_or(last_id, |e| e.id);
+                        })
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                Some(last_seen) = resume_rx.recv() => {
+                    // Only what happened before this connection; the rest arrives live
+                    state.events_since(last_seen)
+                        .map(|events| events.into_iter().filter(|e| e.id <= connected_at).collect())
                 }
-                _ => {}
-            }
+            };
 
-            let json = match serde_json::to_string(&msg) {
-                Ok(j) => j,
-                Err(e) => {
-                    tracing::error!("[WebSocket] Failed to serialize message: {}", e);
-                    continue;
+            let delivered = match missed {
+                Some(events) => {
+                    let subscriptions = send_subscriptions.read().await;
+                    let mut delivered = true;
+                    for event in events.iter().filter(|e| subscriptions.wants(e)) {
+                        if !send_event(&mut sender, event).await {
+                            delivered = false;
+                            break;
+                        }
+                    }
+                    delivered
+                }
+                None => {
+                    last_id = last_id.max(state.last_event_id());
+                    let resync = WsEvent {
+                        id: last_id,
+                        message: WsMessage::Resync { last_event_id: last_id },
+                        project_path: None,
+                    };
+                    send_event(&mut sender, &resync).await
                 }
             };
 
-            if sender.send(Message::Text(json.into())).await.is_err() {
-                tracing::warn!("[WebSocket] Failed to send message, client disconnected");
+            if !delivered {
                 break;
             }
         }
@@ -137,16 +440,13 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
             match msg {
                 Message::Text(text) => {
                     if let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
-                        match client_msg {
-                            ClientMessage::Subscribe { task_id } => {
-                                tracing::debug!("Client subscribed to task: {}", task_id);
-                            }
-                            ClientMessage::Unsubscribe { task_id } => {
-                                tracing::debug!("Client unsubscribed from task: {}", task_id);
-                            }
-                            ClientMessage::Ping => {
-                                tracing::debug!("Received ping");
-                            }
+                        if let ClientMessage::Resume { last_seen } = client_msg {
+                            tracing::debug!("[WebSocket] Client resuming after event {}", last_seen);
+                            let _ = resume_tx.send(last_seen).await;
+                        } else if subscriptions.write().await.apply(&client_msg) {
+                            tracing::debug!("[WebSocket] Subscriptions updated: {:?}", client_msg);
+                        } else {
+                            tracing::debug!("Received ping");
                         }
                     }
                 }
@@ -163,3 +463,165 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
 
     tracing::info!("[WebSocket] Client disconnected");
 }
+
+/// Returns false once the client is gone.
+async fn send_event(sender: &mut SplitSink<WebSocket, Message>, event: &WsEvent) -> bool {
+    let json = match serde_json::to_string(event) {
+        Ok(j) => j,
+        Err(e) => {
+            tracing::error!("[WebSocket] Failed to serialize message: {}", e);
+            return true;
+        }
+    };
+
+    if sender.send(Message::Text(json)).await.is_err() {
+        tracing::warn!("[WebSocket] Failed to send message, client disconnected");
+        return false;
+    }
+
+    true
+}
+
+// Log plan-related messages for debugging
+fn log_plan_message(msg: &WsMessage) {
+    match msg {
+        WsMessage::PlanQuestions { session_id, questions } => {
+            tracing::info!("[WebSocket] Sending PlanQuestions ({} questions) for session {}", questions.len(), &session_id[..8.min(session_id.len())]);
+        }
+        WsMessage::PlanSummary { session_id, .. } => {
+            tracing::info!("[WebSocket] Sending PlanSummary for session {}", &session_id[..8.min(session_id.len())]);
+        }
+        WsMessage::PlanError { session_id, error } => {
+            tracing::warn!("[WebSocket] Sending PlanError for session {}: {}", &session_id[..8.min(session_id.len())], error);
+        }
+        WsMessage::PlanOutput { session_id, .. } => {
+            tracing::debug!("[WebSocket] Sending PlanOutput for session {}", &session_id[..8.min(session_id.len())]);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(task_id: &str) -> WsMessage {
+        WsMessage::Log {
+            task_id: task_id.to_string(),
+            content: "line".to_string(),
+            stream: "stdout".to_string(),
+            attempt_id: None,
+        }
+    }
+
+    fn event(message: WsMessage) -> WsEvent {
+        WsEvent { id: 1, message, project_path: None }
+    }
+
+    #[test]
+    fn test_subscriptions_filter_scoped_messages() {
+        let mut subscriptions = Subscriptions::default();
+        let output = WsMessage::PlanOutput {
+            session_id: "s1".to_string(),
+            content: "thinking".to_string(),
+        };
+
+        assert!(subscriptions.wants(&event(WsMessage::TaskDeleted { task_id: "t1".to_string() })));
+        assert!(!subscriptions.wants(&event(log("t1"))));
+        assert!(!subscriptions.wants(&event(output.clone())));
+
+        assert!(subscriptions.apply(&ClientMessage::Subscribe { task_id: "t1".to_string() }));
+        assert!(subscriptions.apply(&ClientMessage::SubscribePlan { session_id: "s1".to_string() }));
+        assert!(subscriptions.wants(&event(log("t1"))));
+        assert!(!subscriptions.wants(&event(log("t2"))));
+        assert!(subscriptions.wants(&event(output.clone())));
+
+        let preview_log = |task_id: &str| WsMessage::PreviewLog {
+            task_id: task_id.to_string(),
+            line: PreviewLogLine {
+                id: 1,
+                service: "web".to_string(),
+                stream: "stderr".to_string(),
+                content: "listening".to_string(),
+            },
+        };
+        assert!(subscriptions.wants(&event(preview_log("t1"))));
+        assert!(!subscriptions.wants(&event(preview_log("t2"))));
+        assert!(!subscriptions.wants(&event(WsMessage::ChecksUpdated {
+            task_id: "t2".to_string(),
+            run_id: "r1".to_string(),
+            checks: Vec::new(),
+        })));
+
+        subscriptions.apply(&ClientMessage::Unsubscribe { task_id: "t1".to_string() });
+        assert!(!subscriptions.wants(&event(log("t1"))));
+        assert!(!subscriptions.apply(&ClientMessage::Ping));
+    }
+
+    #[test]
+    fn test_subscriptions_filter_other_projects() {
+        let task_in = |project_path: Option<&str>| WsMessage::TaskUpdated {
+            task: serde_json::from_value(serde_json::json!({
+                "id": "t1",
+                "title": "Task",
+                "status": "todo",
+                "project_path": project_path,
+                "created_at": "2026-01-01T00:00:00Z",
+                "updated_at": "2026-01-01T00:00:00Z",
+            }))
+            .unwrap(),
+        };
+
+        assert_eq!(task_in(Some("/work/api")).subject(), Some(MessageSubject::Project("/work/api")));
+        assert_eq!(task_in(None).subject(), None);
+        assert_eq!(log("t1").subject(), Some(MessageSubject::Task("t1")));
+        assert_eq!(WsMessage::RebuildStarted.subject(), None);
+
+        let in_project = |message: WsMessage, project_path: Option<&str>| WsEvent {
+            id: 1,
+            message,
+            project_path: project_path.map(str::to_string),
+        };
+
+        let mut subscriptions = Subscriptions::for_project("/work/api".to_string());
+        subscriptions.apply(&ClientMessage::Subscribe { task_id: "t1".to_string() });
+        assert!(subscriptions.wants(&in_project(task_in(Some("/work/api")), Some("/work/api"))));
+        assert!(!subscriptions.wants(&in_project(task_in(Some("/work/web")), Some("/work/web"))));
+        assert!(subscriptions.wants(&in_project(task_in(None), None)));
+        assert!(subscriptions.wants(&in_project(log("t1"), Some("/work/api"))));
+        // Subscribing doesn't let a task's output through to other projects
+        assert!(!subscriptions.wants(&in_project(log("t1"), Some("/work/web"))));
+        assert!(Subscriptions::default().wants(&in_project(task_in(Some("/work/web")), Some("/work/web"))));
+    }
+
+    #[test]
+    fn test_event_log_replay() {
+        let mut events = EventLog::new(100, 3);
+        assert_eq!(events.last_id(), 99);
+        assert_eq!(events.since(99).unwrap().len(), 0);
+
+        for task in ["t1", "t2", "t3", "t4"] {
+            events.record(log(task), None);
+        }
+        assert_eq!(events.last_id(), 103);
+
+        // 100 was dropped from the buffer, so resuming from 99 needs a resync
+        assert!(events.since(99).is_none());
+        let ids: Vec<u64> = events.since(100).unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![101, 102, 103]);
+        assert!(events.since(103).unwrap().is_empty());
+
+        // An id from before a restart
+        assert!(events.since(5000).is_none());
+    }
+
+    #[test]
+    fn test_event_serializes_flat() {
+        let event = WsEvent { id: 7, message: log("t1"), project_path: Some("/work/api".to_string()) };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["type"], "log");
+        assert_eq!(json["task_id"], "t1");
+        assert!(json.get("project_path").is_none());
+    }
+}
@@ -0,0 +1,142 @@
+//! Following a Claude run: its output and usage as they arrive, until it
+//! exits or is cancelled. Task runs and eval cases both go through this.
+
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use eval_kanban_db::RunUsage;
+use eval_kanban_executor::stream_json;
+use eval_kanban_executor::{ClaudeExecutor, ExecutorError, ExecutorEvent};
+
+use crate::budget;
+
+/// How long to wait for remaining output once a process has exited
+pub const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What happened next in a run.
+#[derive(Debug, PartialEq)]
+pub enum RunEvent {
+    /// A line Claude wrote, on `stdout` or `stderr`
+    Output { stream: &'static str, line: String },
+    /// Usage of the run so far
+    Usage(RunUsage),
+    /// Claude exited; its remaining output came before this
+    Finished { success: bool },
+    /// The run was cancelled and Claude told to stop
+    Cancelled,
+}
+
+/// A spawned Claude process whose events are read with `next`.
+pub struct ClaudeRun {
+    rx: mpsc::Receiver<ExecutorEvent>,
+    stop_tx: mpsc::Sender<()>,
+    /// Set once Claude has exited, while output still queued is read
+    exited: Option<bool>,
+    session_id: Option<String>,
+}
+
+impl ClaudeRun {
+    pub async fn spawn(executor: &ClaudeExecutor, prompt: &str) -> Result<Self, ExecutorError> {
+        let (rx, process) = executor.spawn(prompt).await?;
+
+        let (stop_tx, stop_rx) = mpsc::channel::<()>(1);
+        tokio::spawn(async move {
+            process.wait_or_stop(stop_rx).await
+        });
+
+        Ok(Self::new(rx, stop_tx))
+    }
+
+    fn new(rx: mpsc::Receiver<ExecutorEvent>, stop_tx: mpsc::Sender<()>) -> Self {
+        Self { rx, stop_tx, exited: None, session_id: None }
+    }
+
+    /// The conversation id Claude reported, once it has.
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    /// The next event, stopping Claude if `cancel_rx` fires first. Returns
+    /// `Finished` or `Cancelled` last.
+    pub async fn next(&mut self, cancel_rx: &mut mpsc::Receiver<()>) -> RunEvent {
+        loop {
+            let event = match self.exited {
+                // Output read just before exit can still be queued behind Completed
+                Some(success) => match tokio::time::timeout(OUTPUT_DRAIN_TIMEOUT, self.rx.recv()).await {
+                    Ok(Some(event)) => event,
+                    _ => return RunEvent::Finished { success },
+                },
+                None => tokio::select! {
+                    event = self.rx.recv() => match event {
+                        Some(event) => event,
+                        None => return RunEvent::Finished { success: false },
+                    },
+                    _ = cancel_rx.recv() => {
+                        self.stop().await;
+                        return RunEvent::Cancelled;
+                    }
+                },
+            };
+
+            match event {
+                ExecutorEvent::Stdout(line) => {
+                    if self.session_id.is_none() {
+                        self.session_id = stream_json::session_id(&line);
+                    }
+                    return RunEvent::Output { stream: "stdout", line };
+                }
+                ExecutorEvent::Stderr(line) => return RunEvent::Output { stream: "stderr", line },
+                ExecutorEvent::Usage(usage) => return RunEvent::Usage(budget::to_run_usage(&usage)),
+                ExecutorEvent::Completed { success } => self.exited = Some(success),
+                _ => {}
+            }
+        }
+    }
+
+    /// Tell Claude to stop, e.g. once a budget is spent.
+    pub async fn stop(&self) {
+        let _ = self.stop_tx.send(()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run() -> (ClaudeRun, mpsc::Sender<ExecutorEvent>, mpsc::Receiver<()>) {
+        let (tx, rx) = mpsc::channel(16);
+        let (stop_tx, stop_rx) = mpsc::channel(1);
+        (ClaudeRun::new(rx, stop_tx), tx, stop_rx)
+    }
+
+    #[tokio::test]
+    async fn test_output_queued_behind_exit() {
+        let (mut run, tx, _stop_rx) = run();
+        let (_cancel_tx, mut cancel_rx) = mpsc::channel(1);
+
+        let init = r#"{"type":"system","subtype":"init","session_id":"abc"}"#.to_string();
+        tx.send(ExecutorEvent::Stdout(init.clone())).await.unwrap();
+        tx.send(ExecutorEvent::Completed { success: true }).await.unwrap();
+        tx.send(ExecutorEvent::Stderr("last words".to_string())).await.unwrap();
+        drop(tx);
+
+        assert_eq!(run.next(&mut cancel_rx).await, RunEvent::Output { stream: "stdout", line: init });
+        assert_eq!(run.session_id(), Some("abc"));
+        assert_eq!(
+            run.next(&mut cancel_rx).await,
+            RunEvent::Output { stream: "stderr", line: "last words".to_string() }
+        );
+        assert_eq!(run.next(&mut cancel_rx).await, RunEvent::Finished { success: true });
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_claude() {
+        let (mut run, _tx, mut stop_rx) = run();
+        let (cancel_tx, mut cancel_rx) = mpsc::channel(1);
+
+        cancel_tx.send(()).await.unwrap();
+        assert_eq!(run.next(&mut cancel_rx).await, RunEvent::Cancelled);
+        assert_eq!(stop_rx.try_recv(), Ok(()));
+    }
+}
@@ -0,0 +1,855 @@
+//! Layered configuration.
+//!
+//! Settings are read from, in increasing order of precedence:
+//! built-in defaults, the global `~/.eval-kanban/config.json`, the
+//! repository's `.eval-kanban.json` and `EVAL_KANBAN_<SECTION>_<KEY>`
+//! environment variables. Files only need the keys they change, and are
+//! upgraded from older `config_version`s when read. Files are re-read when
+//! they change on disk, so edits apply without a restart (except
+//! `server.port`).
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub const CONFIG_VERSION: &str = "v3";
+/// Per-repository settings, next to the code they apply to
+pub const PROJECT_CONFIG_FILE: &str = ".eval-kanban.json";
+const ENV_PREFIX: &str = "EVAL_KANBAN";
+/// Each attempt is a full run, so this bounds what a task can spend on its own
+const MAX_AUTO_FIX_ATTEMPTS: u32 = 10;
+
+/// Upgrades a file from one version to the next
+type Migration = fn(&mut Map<String, Value>);
+
+/// Each older version and its upgrade, in order.
+const MIGRATIONS: &[(&str, Migration)] = &[("v1", migrate_v1), ("v2", migrate_v2)];
+
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsError {
+    #[error("{}: {source}", path.display())]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("{}: invalid JSON: {source}", path.display())]
+    Parse { path: PathBuf, source: serde_json::Error },
+    #[error("{}: {message}", path.display())]
+    Version { path: PathBuf, message: String },
+    #[error("{0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub config_version: String,
+    pub server: ServerSettings,
+    pub worktree: WorktreeSettings,
+    pub plan: PlanSettings,
+    pub preview: PreviewSettings,
+    pub verify: VerifySettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    /// Port tried first when `--port` isn't given. Read at startup.
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorktreeSettings {
+    /// Task branches are named `<prefix>/<title>-<id>`
+    pub branch_prefix: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlanSettings {
+    /// Longest silence from Claude before a planning session fails
+    pub event_timeout_secs: u64,
+    /// Longest a planning session may run, not counting time waiting for answers
+    pub session_timeout_secs: u64,
+    /// How long a live conversation waits for the user's answers
+    pub answer_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreviewSettings {
+    /// Each service gets the first free port from here
+    pub port_start: u16,
+    /// How many ports after `port_start` are tried
+    pub port_range: u16,
+    /// Stop a preview after this many minutes without proxied requests or a
+    /// client watching it; 0 never does
+    pub idle_timeout_mins: u64,
+    /// Service shown in the preview tab (default: the first one)
+    pub open: Option<String>,
+    /// Started in dependency order when a task is previewed
+    pub services: Vec<PreviewService>,
+}
+
+/// A process started in the worktree of a previewed task. `{port}` in the
+/// command, cwd and env is replaced by the service's port, `{port:<name>}` by
+/// another service's. `PORT` is also set. `{base}` in the command and env is
+/// the path the server proxies the service under.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PreviewService {
+    pub name: String,
+    /// Run through the shell
+    pub command: String,
+    /// Relative to the worktree
+    #[serde(default)]
+    pub cwd: String,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Run before the command, e.g. to install dependencies
+    #[serde(default)]
+    pub setup: Option<String>,
+    /// Skip `setup` if this path (relative to `cwd`) exists
+    #[serde(default)]
+    pub creates: Option<String>,
+    /// How to tell the service is up; by default, its port accepting
+    /// connections within 60s. Dependents wait for it.
+    #[serde(default)]
+    pub ready: Option<ReadyCheck>,
+    /// Services that must be ready before this one starts
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Forward proxied requests with their `{base}` prefix, for services
+    /// that serve under it; otherwise the prefix is removed
+    #[serde(default)]
+    pub keep_base: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReadyCheck {
+    /// Path that must answer without a server error. Without it, accepting
+    /// a TCP connection is enough.
+    pub http: Option<String>,
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerifySettings {
+    /// Run in order in the task's worktree each time Claude finishes a task
+    pub commands: Vec<VerifyCommand>,
+    /// What failing checks do: `block` keeps the task out of review, `badge`
+    /// moves it there marked as failing
+    pub on_failure: OnCheckFailure,
+    /// Times Claude is run again with the failing checks' output before
+    /// `on_failure` applies; 0 never does
+    pub auto_fix_attempts: u32,
+}
+
+/// A check, e.g. a build, tests or a linter. Passes if it exits with 0.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerifyCommand {
+    pub name: String,
+    /// Run through the shell
+    pub command: String,
+    /// Relative to the worktree
+    #[serde(default)]
+    pub cwd: String,
+    /// Fails if it runs longer
+    #[serde(default = "default_check_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_check_timeout_secs() -> u64 {
+    600
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnCheckFailure {
+    Block,
+    Badge,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            config_version: CONFIG_VERSION.to_string(),
+            server: ServerSettings::default(),
+            worktree: WorktreeSettings::default(),
+            plan: PlanSettings::default(),
+            preview: PreviewSettings::default(),
+            verify: VerifySettings::default(),
+        }
+    }
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self { port: 9847 }
+    }
+}
+
+impl Default for WorktreeSettings {
+    fn default() -> Self {
+        Self { branch_prefix: eval_kanban_worktree::DEFAULT_BRANCH_PREFIX.to_string() }
+    }
+}
+
+impl Default for PlanSettings {
+    fn default() -> Self {
+        Self {
+            event_timeout_secs: 120,
+            session_timeout_secs: 600,
+            answer_timeout_secs: 1800,
+        }
+    }
+}
+
+impl Default for PreviewSettings {
+    fn default() -> Self {
+        Self {
+            port_start: 5200,
+            port_range: 100,
+            idle_timeout_mins: 30,
+            open: None,
+            services: Vec::new(),
+        }
+    }
+}
+
+impl Default for VerifySettings {
+    fn default() -> Self {
+        Self { commands: Vec::new(), on_failure: OnCheckFailure::Badge, auto_fix_attempts: 0 }
+    }
+}
+
+impl Default for ReadyCheck {
+    fn default() -> Self {
+        Self { http: None, timeout_secs: 60 }
+    }
+}
+
+impl Settings {
+    /// Checks what types alone can't.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let mut errors = Vec::new();
+
+        if self.server.port == 0 {
+            errors.push("server.port must not be 0".to_string());
+        }
+        if let Err(e) = validate_branch_prefix(&self.worktree.branch_prefix) {
+            errors.push(format!("worktree.branch_prefix {}", e));
+        }
+
+        let timeouts = [
+            ("plan.event_timeout_secs", self.plan.event_timeout_secs),
+            ("plan.session_timeout_secs", self.plan.session_timeout_secs),
+            ("plan.answer_timeout_secs", self.plan.answer_timeout_secs),
+        ];
+        for (key, secs) in timeouts {
+            if secs == 0 {
+                errors.push(format!("{} must be greater than 0", key));
+            }
+        }
+
+        self.validate_preview(&mut errors);
+        self.validate_verify(&mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SettingsError::Invalid(errors.join("; ")))
+        }
+    }
+
+    fn validate_preview(&self, errors: &mut Vec<String>) {
+        let preview = &self.preview;
+        if preview.port_range == 0 {
+            errors.push("preview.port_range must be greater than 0".to_string());
+        }
+        if preview.port_start == 0 || u32::from(preview.port_start) + u32::from(preview.port_range) > u32::from(u16::MAX) + 1 {
+            errors.push("preview.port_start leaves no room for preview.port_range ports".to_string());
+        }
+
+        let names: Vec<&str> = preview.services.iter().map(|s| s.name.as_str()).collect();
+        if let Some(open) = &preview.open {
+            if !names.contains(&open.as_str()) {
+                errors.push(format!("preview.open: no service named {:?}", open));
+            }
+        }
+
+        for (index, service) in preview.services.iter().enumerate() {
+            let key = format!("preview.services[{}]", index);
+            if service.name.is_empty() || !service.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                errors.push(format!("{}.name may only contain letters, digits, '-' and '_'", key));
+            } else if names[..index].contains(&service.name.as_str()) {
+                errors.push(format!("{}.name: {:?} is used twice", key, service.name));
+            }
+            if service.command.trim().is_empty() {
+                errors.push(format!("{}.command must not be empty", key));
+            }
+            if !is_relative_inside(&service.cwd) {
+                errors.push(format!("{}.cwd must be inside the worktree", key));
+            }
+            if let Some(ready) = &service.ready {
+                if ready.timeout_secs == 0 {
+                    errors.push(format!("{}.ready.timeout_secs must be greater than 0", key));
+                }
+                if ready.http.as_ref().is_some_and(|path| !path.starts_with('/')) {
+                    errors.push(format!("{}.ready.http must be a path starting with /", key));
+                }
+            }
+
+            let templates = std::iter::once(&service.command)
+                .chain(std::iter::once(&service.cwd))
+                .chain(service.setup.iter())
+                .chain(service.env.values());
+            for name in templates.flat_map(|t| crate::preview::port_references(t)) {
+                if !names.contains(&name) {
+                    errors.push(format!("{}: {{port:{}}} refers to an unknown service", key, name));
+                }
+            }
+            for name in &service.depends_on {
+                if !names.contains(&name.as_str()) {
+                    errors.push(format!("{}.depends_on: no service named {:?}", key, name));
+                }
+            }
+        }
+
+        if let Err(e) = crate::preview::start_order(&preview.services) {
+            errors.push(format!("preview.services: {}", e));
+        }
+    }
+
+    fn validate_verify(&self, errors: &mut Vec<String>) {
+        if self.verify.auto_fix_attempts > MAX_AUTO_FIX_ATTEMPTS {
+            errors.push(format!("verify.auto_fix_attempts must be at most {}", MAX_AUTO_FIX_ATTEMPTS));
+        }
+        for (index, check) in self.verify.commands.iter().enumerate() {
+            let key = format!("verify.commands[{}]", index);
+            if check.name.trim().is_empty() {
+                errors.push(format!("{}.name must not be empty", key));
+            } else if self.verify.commands[..index].iter().any(|c| c.name == check.name) {
+                errors.push(format!("{}.name: {:?} is used twice", key, check.name));
+            }
+            if check.command.trim().is_empty() {
+                errors.push(format!("{}.command must not be empty", key));
+            }
+            if !is_relative_inside(&check.cwd) {
+                errors.push(format!("{}.cwd must be inside the worktree", key));
+            }
+            if check.timeout_secs == 0 {
+                errors.push(format!("{}.timeout_secs must be greater than 0", key));
+            }
+        }
+    }
+}
+
+/// A relative path that doesn't leave the directory it's relative to.
+fn is_relative_inside(path: &str) -> bool {
+    !Path::new(path).is_absolute() && !path.split(['/', '\\']).any(|c| c == "..")
+}
+
+/// Something git accepts as the first components of a branch name.
+fn validate_branch_prefix(prefix: &str) -> Result<(), &'static str> {
+    if prefix.is_empty() {
+        return Err("must not be empty");
+    }
+    if !prefix.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/')) {
+        return Err("may only contain letters, digits, '-', '_', '.' and '/'");
+    }
+    if prefix.split('/').any(|c| c.is_empty() || c.starts_with('.') || c.ends_with(".lock")) || prefix.contains("..") {
+        return Err("is not a valid branch name");
+    }
+    Ok(())
+}
+
+pub fn global_config_path() -> PathBuf {
+    eval_kanban_db::get_data_dir().join("config.json")
+}
+
+pub fn project_config_path(project_path: &Path) -> PathBuf {
+    project_path.join(PROJECT_CONFIG_FILE)
+}
+
+/// Which file a change is written to.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Global,
+    Project,
+}
+
+/// The settings of a project and where they come from.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedSettings {
+    pub settings: Settings,
+    pub global_file: PathBuf,
+    pub project_file: PathBuf,
+    /// Contents of each file, upgraded to the current version
+    pub global: Value,
+    pub project: Value,
+    /// Environment variables that override a file or default
+    pub env_overrides: Vec<String>,
+}
+
+/// Modification times of the files the settings were read from
+type Stamps = (Option<SystemTime>, Option<SystemTime>);
+
+struct CachedSettings {
+    stamps: Stamps,
+    settings: Arc<Settings>,
+    /// Why the files were ignored, if they were
+    error: Option<String>,
+}
+
+/// Effective settings per project, re-read when a file changes.
+pub struct SettingsStore {
+    global_file: PathBuf,
+    cache: Mutex<HashMap<PathBuf, CachedSettings>>,
+}
+
+impl SettingsStore {
+    pub fn new(global_file: PathBuf) -> Self {
+        Self { global_file, cache: Mutex::new(HashMap::new()) }
+    }
+
+    /// The project's settings. If a file is broken, the last good settings
+    /// (or the defaults) are used and the problem logged.
+    pub fn get(&self, project_path: &Path) -> Arc<Settings> {
+        self.cached(project_path).0
+    }
+
+    /// The project's settings and the error that made the files be ignored.
+    pub fn cached(&self, project_path: &Path) -> (Arc<Settings>, Option<String>) {
+        let project_file = project_config_path(project_path);
+        let stamps = (modified(&self.global_file), modified(&project_file));
+
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(cached) = cache.get(project_path).filter(|c| c.stamps == stamps) {
+            return (cached.settings.clone(), cached.error.clone());
+        }
+
+        let previous = cache.get(project_path).map(|c| c.settings.clone());
+        let (settings, error) = match self.resolve(project_path) {
+            Ok(resolved) => (Arc::new(resolved.settings), None),
+            Err(e) => {
+                tracing::warn!("Ignoring settings for {}: {}", project_path.display(), e);
+                (previous.unwrap_or_default(), Some(e.to_string()))
+            }
+        };
+
+        cache.insert(
+            project_path.to_path_buf(),
+            CachedSettings { stamps, settings: settings.clone(), error: error.clone() },
+        );
+        (settings, error)
+    }
+
+    /// Read every layer of the project's settings.
+    pub fn resolve(&self, project_path: &Path) -> Result<ResolvedSettings, SettingsError> {
+        let project_file = project_config_path(project_path);
+        let global = read_layer(&self.global_file)?;
+        let project = read_layer(&project_file)?;
+        let (settings, env_overrides) = combine(&global, &project, |name| std::env::var(name).ok())?;
+
+        Ok(ResolvedSettings {
+            settings,
+            global_file: self.global_file.clone(),
+            project_file,
+            global,
+            project,
+            env_overrides,
+        })
+    }
+
+    /// Apply a JSON merge patch (`null` removes a key) to one of the
+    /// project's files. Nothing is written unless the result is valid.
+    pub fn update(&self, project_path: &Path, scope: Scope, patch: &Value) -> Result<ResolvedSettings, SettingsError> {
+        let Value::Object(patch) = patch else {
+            return Err(SettingsError::Invalid("settings must be a JSON object".to_string()));
+        };
+        let mut patch = patch.clone();
+        patch.remove("config_version");
+
+        let defaults = default_value();
+        let mut unknown = Vec::new();
+        unknown_keys(&patch, defaults.as_object().unwrap_or(&Map::new()), "", &mut unknown);
+        if !unknown.is_empty() {
+            return Err(SettingsError::Invalid(format!("unknown settings: {}", unknown.join(", "))));
+        }
+
+        let project_file = project_config_path(project_path);
+        let path = match scope {
+            Scope::Global => &self.global_file,
+            Scope::Project => &project_file,
+        };
+
+        let mut layer = read_layer(path)?;
+        merge_patch(&mut layer, &Value::Object(patch));
+        if let Value::Object(map) = &mut layer {
+            map.insert("config_version".to_string(), Value::String(CONFIG_VERSION.to_string()));
+        }
+
+        let (global, project) = match scope {
+            Scope::Global => (layer.clone(), read_layer(&project_file)?),
+            Scope::Project => (read_layer(&self.global_file)?, layer.clone()),
+        };
+        combine(&global, &project, |name| std::env::var(name).ok())?;
+
+        write_layer(path, &layer)?;
+
+        // Cached right away, as a write within the same mtime tick would
+        // otherwise go unnoticed
+        let resolved = self.resolve(project_path)?;
+        let stamps = (modified(&self.global_file), modified(&project_file));
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner).insert(
+            project_path.to_path_buf(),
+            CachedSettings { stamps, settings: Arc::new(resolved.settings.clone()), error: None },
+        );
+        Ok(resolved)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn default_value() -> Value {
+    serde_json::to_value(Settings::default()).unwrap_or(Value::Null)
+}
+
+/// Defaults, then each file, then the environment. Returns the settings and
+/// the environment variables that were used.
+fn combine(
+    global: &Value,
+    project: &Value,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<(Settings, Vec<String>), SettingsError> {
+    let defaults = default_value();
+    let mut value = defaults.clone();
+    merge_patch(&mut value, global);
+    merge_patch(&mut value, project);
+
+    let mut env_overrides = Vec::new();
+    apply_env(&mut value, &defaults, ENV_PREFIX, &env, &mut env_overrides)?;
+    if let Value::Object(map) = &mut value {
+        map.insert("config_version".to_string(), Value::String(CONFIG_VERSION.to_string()));
+    }
+
+    let settings: Settings = serde_path_to_error::deserialize(value)
+        .map_err(|e| SettingsError::Invalid(format!("{}: {}", e.path(), e.inner())))?;
+    settings.validate()?;
+    Ok((settings, env_overrides))
+}
+
+/// A settings file, upgraded to the current version. Missing files are empty.
+fn read_layer(path: &Path) -> Result<Value, SettingsError> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Value::Object(Map::new())),
+        Err(source) => return Err(SettingsError::Read { path: path.to_path_buf(), source }),
+    };
+
+    let value: Value = serde_json::from_str(&contents)
+        .map_err(|source| SettingsError::Parse { path: path.to_path_buf(), source })?;
+    let Value::Object(mut map) = value else {
+        return Err(SettingsError::Version {
+            path: path.to_path_buf(),
+            message: "expected a JSON object".to_string(),
+        });
+    };
+
+    migrate(&mut map).map_err(|message| SettingsError::Version { path: path.to_path_buf(), message })?;
+    Ok(Value::Object(map))
+}
+
+fn write_layer(path: &Path, layer: &Value) -> Result<(), SettingsError> {
+    let write = || -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut contents = serde_json::to_string_pretty(layer).map_err(std::io::Error::other)?;
+        contents.push('\n');
+        std::fs::write(path, contents)
+    };
+    write().map_err(|source| SettingsError::Read { path: path.to_path_buf(), source })
+}
+
+/// Bring a file to the current version. Files without a version are taken
+/// to be current, so hand-written files only need the keys they change.
+fn migrate(map: &mut Map<String, Value>) -> Result<(), String> {
+    let Some(version) = map.get("config_version") else {
+        return Ok(());
+    };
+    let Some(mut version) = version.as_str().map(str::to_string) else {
+        return Err("config_version must be a string".to_string());
+    };
+
+    for (index, (from, upgrade)) in MIGRATIONS.iter().enumerate() {
+        if version == *from {
+            upgrade(map);
+            version = MIGRATIONS.get(index + 1).map_or(CONFIG_VERSION, |(next, _)| next).to_string();
+        }
+    }
+
+    if version != CONFIG_VERSION {
+        return Err(format!("unsupported config_version {:?} (this version reads up to {})", version, CONFIG_VERSION));
+    }
+    map.insert("config_version".to_string(), Value::String(version));
+    Ok(())
+}
+
+/// v1 was flat; its branch prefix moves into the `worktree` section. The
+/// interface preferences it also held are left alone.
+fn migrate_v1(map: &mut Map<String, Value>) {
+    if let Some(prefix) = map.remove("branch_prefix") {
+        let worktree = map
+            .entry("worktree")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(worktree) = worktree {
+            worktree.entry("branch_prefix").or_insert(prefix);
+        }
+    }
+}
+
+/// v2 had a fixed backend and frontend; they become the first services.
+fn migrate_v2(map: &mut Map<String, Value>) {
+    const OLD_KEYS: [&str; 5] = ["backend_command", "backend_port_start", "frontend_command", "frontend_dir", "frontend_port_start"];
+    let Some(Value::Object(preview)) = map.get_mut("preview") else {
+        return;
+    };
+    if !OLD_KEYS.iter().any(|key| preview.contains_key(*key)) {
+        return;
+    }
+
+    let mut take = |key: &str, default: &str| -> Value {
+        preview.remove(key).unwrap_or_else(|| Value::String(default.to_string()))
+    };
+    let backend_command = take("backend_command", "cargo run --release -p eval-kanban-server");
+    let frontend_command = take("frontend_command", "npm run dev -- --port {port} --host");
+    let frontend_dir = take("frontend_dir", "frontend");
+    let port_start = take("frontend_port_start", "");
+    preview.remove("backend_port_start");
+    if port_start.is_number() {
+        preview.entry("port_start").or_insert(port_start);
+    }
+
+    let services = serde_json::json!([
+        { "name": "backend", "command": backend_command },
+        {
+            "name": "frontend",
+            "command": frontend_command,
+            "cwd": frontend_dir,
+            "setup": "npm install",
+            "creates": "node_modules"
+        }
+    ]);
+    preview.entry("services").or_insert(services);
+}
+
+/// RFC 7396 merge patch: objects merge, `null` removes, anything else replaces.
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        return;
+    };
+
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+fn unknown_keys(patch: &Map<String, Value>, schema: &Map<String, Value>, prefix: &str, unknown: &mut Vec<String>) {
+    for (key, value) in patch {
+        let path = format!("{}{}", prefix, key);
+        match (schema.get(key), value) {
+            (None, _) => unknown.push(path),
+            (Some(Value::Object(schema)), Value::Object(value)) => {
+                unknown_keys(value, schema, &format!("{}.", path), unknown)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Override each setting from `<PREFIX>_<SECTION>_<KEY>`, parsed as the
+/// type of its default.
+fn apply_env(
+    value: &mut Value,
+    defaults: &Value,
+    name: &str,
+    env: &impl Fn(&str) -> Option<String>,
+    applied: &mut Vec<String>,
+) -> Result<(), SettingsError> {
+    if let Value::Object(defaults) = defaults {
+        for (key, default) in defaults {
+            if key == "config_version" {
+                continue;
+            }
+            let name = format!("{}_{}", name, key.to_uppercase());
+            let Value::Object(map) = value else {
+                return Ok(());
+            };
+            let entry = map.entry(key.clone()).or_insert_with(|| default.clone());
+            apply_env(entry, default, &name, env, applied)?;
+        }
+        return Ok(());
+    }
+
+    // Lists can only be set in files
+    if defaults.is_array() {
+        return Ok(());
+    }
+    let Some(raw) = env(name) else {
+        return Ok(());
+    };
+    let raw = raw.trim();
+    let invalid = || SettingsError::Invalid(format!("{}={:?} is not a valid {}", name, raw, type_name(defaults)));
+
+    *value = match defaults {
+        Value::Number(_) => Value::Number(raw.parse::<u64>().map_err(|_| invalid())?.into()),
+        Value::Bool(_) => Value::Bool(raw.parse().map_err(|_| invalid())?),
+        _ => Value::String(raw.to_string()),
+    };
+    applied.push(name.to_string());
+    Ok(())
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        _ => "string",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_layers_and_env() {
+        let global = json!({ "plan": { "event_timeout_secs": 60 }, "worktree": { "branch_prefix": "task" } });
+        let project = json!({ "worktree": { "branch_prefix": "agent" } });
+        let env = |name: &str| (name == "EVAL_KANBAN_PREVIEW_PORT_RANGE").then(|| "20".to_string());
+
+        let (settings, applied) = combine(&global, &project, env).unwrap();
+        assert_eq!(settings.plan.event_timeout_secs, 60);
+        assert_eq!(settings.plan.session_timeout_secs, 600);
+        assert_eq!(settings.worktree.branch_prefix, "agent");
+        assert_eq!(settings.preview.port_range, 20);
+        assert_eq!(applied, vec!["EVAL_KANBAN_PREVIEW_PORT_RANGE"]);
+
+        let bad_env = |name: &str| (name == "EVAL_KANBAN_SERVER_PORT").then(|| "http".to_string());
+        assert!(combine(&global, &project, bad_env).is_err());
+        assert!(combine(&json!({ "server": { "port": "9000" } }), &json!({}), |_| None).is_err());
+        assert!(combine(&json!({ "worktree": { "branch_prefix": "a..b" } }), &json!({}), |_| None).is_err());
+    }
+
+    #[test]
+    fn test_migrate_v1() {
+        let mut map = json!({ "config_version": "v1", "theme": "dark", "branch_prefix": "kb" })
+            .as_object()
+            .unwrap()
+            .clone();
+        migrate(&mut map).unwrap();
+        assert_eq!(Value::Object(map), json!({ "config_version": "v3", "theme": "dark", "worktree": { "branch_prefix": "kb" } }));
+
+        let mut map = json!({ "config_version": "v2", "preview": { "frontend_dir": "web", "port_range": 10 } })
+            .as_object()
+            .unwrap()
+            .clone();
+        migrate(&mut map).unwrap();
+        let settings: Settings = serde_json::from_value(Value::Object(map)).unwrap();
+        assert_eq!(settings.preview.port_range, 10);
+        assert_eq!(settings.preview.services.len(), 2);
+        assert_eq!(settings.preview.services[1].cwd, "web");
+        assert!(settings.validate().is_ok());
+
+        let mut newer = json!({ "config_version": "v9" }).as_object().unwrap().clone();
+        assert!(migrate(&mut newer).is_err());
+    }
+
+    #[test]
+    fn test_repo_preview_services() {
+        let project: Value = serde_json::from_str(include_str!("../../../.eval-kanban.json")).unwrap();
+        let (settings, _) = combine(&json!({}), &project, |_| None).unwrap();
+        assert!(settings.validate().is_ok());
+
+        let services = &settings.preview.services;
+        let order: Vec<&str> = crate::preview::start_order(services)
+            .unwrap()
+            .into_iter()
+            .map(|i| services[i].name.as_str())
+            .collect();
+        assert_eq!(order, vec!["backend", "frontend"]);
+
+        let ports = HashMap::from([("backend".to_string(), 7000), ("frontend".to_string(), 7001)]);
+        let frontend = &services[1];
+        assert_eq!(
+            crate::preview::expand(&frontend.env["VITE_API_TARGET"], 7001, &ports),
+            "http://localhost:7000"
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let dir = std::env::temp_dir().join(format!("eval-kanban-settings-{}", uuid::Uuid::new_v4()));
+        let project = dir.join("repo");
+        std::fs::create_dir_all(&project).unwrap();
+        let store = SettingsStore::new(dir.join("config.json"));
+
+        let resolved = store
+            .update(&project, Scope::Project, &json!({ "preview": { "port_start": 7000 } }))
+            .unwrap();
+        assert_eq!(resolved.settings.preview.port_start, 7000);
+        assert_eq!(store.get(&project).preview.port_start, 7000);
+
+        assert!(store.update(&project, Scope::Global, &json!({ "preview": { "colour": "red" } })).is_err());
+        assert!(store.update(&project, Scope::Global, &json!({ "plan": { "event_timeout_secs": 0 } })).is_err());
+        assert!(!store.global_file.exists());
+
+        let resolved = store
+            .update(&project, Scope::Project, &json!({ "preview": { "port_start": null } }))
+            .unwrap();
+        assert_eq!(resolved.settings.preview.port_start, 5200);
+        assert_eq!(resolved.project, json!({ "config_version": "v3", "preview": {} }));
+
+        let web = json!({ "name": "web", "command": "npm start", "depends_on": ["api"] });
+        assert!(store.update(&project, Scope::Project, &json!({ "preview": { "services": [web] } })).is_err());
+        let api = json!({ "name": "api", "command": "./serve --port {port}" });
+        let resolved = store
+            .update(&project, Scope::Project, &json!({ "preview": { "services": [web, api], "open": "web" } }))
+            .unwrap();
+        assert_eq!(resolved.settings.preview.services[0].depends_on, vec!["api"]);
+
+        let test = json!({ "name": "test", "command": "cargo test" });
+        let resolved = store
+            .update(&project, Scope::Project, &json!({ "verify": { "commands": [test], "on_failure": "block" } }))
+            .unwrap();
+        assert_eq!(resolved.settings.verify.commands[0].timeout_secs, 600);
+        assert_eq!(resolved.settings.verify.on_failure, OnCheckFailure::Block);
+        assert!(store.update(&project, Scope::Project, &json!({ "verify": { "on_failure": "ignore" } })).is_err());
+        assert!(store.update(&project, Scope::Project, &json!({ "verify": { "auto_fix_attempts": 50 } })).is_err());
+        assert!(store.update(&project, Scope::Project, &json!({ "verify": { "commands": [test, test] } })).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
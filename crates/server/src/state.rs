@@ -1,14 +1,17 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use sqlx::{Pool, Sqlite};
-use tokio::sync::{broadcast, RwLock, mpsc};
-use tokio::process::Child;
-use serde::Serialize;
+use tokio::sync::{broadcast, oneshot, RwLock, mpsc};
+use serde::{Deserialize, Serialize};
 
-use eval_kanban_worktree::WorktreeManager;
-use crate::routes::ws::WsMessage;
-use crate::plan_session::{PlanSession, PlanSessionInfo};
+use eval_kanban_db::{PlanSessionRecord, Project, Task};
+use crate::routes::ws::{EventLog, MessageSubject, WsEvent, WsMessage, EVENT_BUFFER_SIZE};
+use crate::plan_session::{PlanSession, PlanSessionInfo, PlanStatus};
+use crate::preview::{self, LogBuffer, PortRegistry};
+use crate::project::ProjectContext;
+use crate::settings::{self, SettingsStore};
 
 pub struct RunningTask {
     #[allow(dead_code)]
@@ -16,78 +19,211 @@ pub struct RunningTask {
     pub cancel_tx: mpsc::Sender<()>,
 }
 
+/// A chat reply being generated. At most one runs per thread.
+pub struct ChatGeneration {
+    /// Id the reply is streamed and saved under
+    pub message_id: String,
+    pub cancel_tx: mpsc::Sender<()>,
+}
+
 // Preview types defined here to avoid circular dependency
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PreviewInfo {
     pub task_id: String,
-    pub backend_url: String,
-    pub frontend_url: String,
-    pub backend_port: u16,
-    pub frontend_port: u16,
+    /// Failed if any service failed, ready once all of them are
+    pub status: PreviewStatus,
+    /// Service shown in the preview tab
+    pub open: Option<String>,
+    /// Port the services' `proxy_url`s are served on, apart from the board
+    pub proxy_port: u16,
+    pub services: Vec<PreviewServiceInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewServiceInfo {
+    pub name: String,
+    pub port: u16,
+    pub url: String,
+    /// Same service through the server's preview proxy, a path on `proxy_port`
+    pub proxy_url: String,
     pub status: PreviewStatus,
+    /// Why the service failed
+    pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
-#[allow(dead_code)]
 pub enum PreviewStatus {
     Starting,
-    Running,
+    Ready,
+    Failed,
+    /// Sent once when the preview is stopped
     Stopped,
-    Error,
 }
 
 pub struct PreviewProcess {
     pub task_id: String,
-    pub backend_port: u16,
-    pub frontend_port: u16,
-    pub backend_process: Child,
-    pub frontend_process: Option<Child>,
+    /// Identifies this start of the preview, and holds its ports
+    pub run_id: String,
+    pub open: Option<String>,
+    /// Port the server proxies the services on
+    pub proxy_port: u16,
+    /// In start order
+    pub services: Vec<ServiceProcess>,
+    pub logs: Arc<Mutex<LogBuffer>>,
+    /// Stopped once idle this long
+    pub idle_timeout: Option<Duration>,
+    pub activity: Arc<PreviewActivity>,
+}
+
+/// When a preview was last used: proxied to, or polled by the web UI.
+pub struct PreviewActivity {
+    last_seen: Mutex<Instant>,
+    /// Proxied connections still open, e.g. a dev server's WebSocket
+    connections: AtomicUsize,
+}
+
+impl PreviewActivity {
+    pub fn new() -> Self {
+        Self {
+            last_seen: Mutex::new(Instant::now()),
+            connections: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn touch(&self) {
+        *self.last_seen.lock().unwrap_or_else(PoisonError::into_inner) = Instant::now();
+    }
+
+    /// Counts as activity until the returned guard is dropped.
+    pub fn connection(self: &Arc<Self>) -> PreviewConnection {
+        self.connections.fetch_add(1, Ordering::SeqCst);
+        PreviewConnection(self.clone())
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        if self.connections.load(Ordering::SeqCst) > 0 {
+            return Duration::ZERO;
+        }
+        self.last_seen.lock().unwrap_or_else(PoisonError::into_inner).elapsed()
+    }
+}
+
+pub struct PreviewConnection(Arc<PreviewActivity>);
+
+impl Drop for PreviewConnection {
+    fn drop(&mut self) {
+        self.0.touch();
+        self.0.connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub struct ServiceProcess {
+    pub name: String,
+    pub port: u16,
+    /// Proxied with the `/preview/<task>/<service>` prefix
+    pub keep_base: bool,
+    /// Identifies this launch of the service; updates from earlier launches
+    /// are ignored
+    pub run_id: String,
+    pub status: PreviewStatus,
+    pub error: Option<String>,
+    /// Dropping it kills the process, once started
+    pub stop_tx: Option<oneshot::Sender<()>>,
+    pub pid: Option<u32>,
 }
 
 impl PreviewProcess {
-    pub fn to_info(&self, status: PreviewStatus) -> PreviewInfo {
+    pub fn to_info(&self) -> PreviewInfo {
+        let status = if self.services.iter().any(|s| s.status == PreviewStatus::Failed) {
+            PreviewStatus::Failed
+        } else if self.services.iter().all(|s| s.status == PreviewStatus::Ready) {
+            PreviewStatus::Ready
+        } else {
+            PreviewStatus::Starting
+        };
+
         PreviewInfo {
             task_id: self.task_id.clone(),
-            backend_url: format!("http://localhost:{}", self.backend_port),
-            frontend_url: format!("http://localhost:{}", self.frontend_port),
-            backend_port: self.backend_port,
-            frontend_port: self.frontend_port,
             status,
+            open: self.open.clone().or_else(|| self.services.first().map(|s| s.name.clone())),
+            proxy_port: self.proxy_port,
+            services: self
+                .services
+                .iter()
+                .map(|s| PreviewServiceInfo {
+                    name: s.name.clone(),
+                    port: s.port,
+                    url: format!("http://localhost:{}", s.port),
+                    proxy_url: preview::proxy_base(&self.task_id, &s.name),
+                    status: s.status,
+                    error: s.error.clone(),
+                })
+                .collect(),
         }
     }
 }
 
 pub struct AppState {
     pub db: Pool<Sqlite>,
-    pub working_dir: PathBuf,
-    pub worktree_manager: WorktreeManager,
-    pub ws_broadcast: broadcast::Sender<WsMessage>,
+    /// The project the server was started in, used by unscoped routes
+    pub default_project: Arc<ProjectContext>,
+    /// Other projects used since startup, by id
+    pub projects: RwLock<HashMap<String, Arc<ProjectContext>>>,
+    /// Layered configuration, per project
+    pub settings: SettingsStore,
+    pub ws_broadcast: broadcast::Sender<WsEvent>,
+    /// Recent broadcasts, replayed to clients that missed them
+    pub event_log: Mutex<EventLog>,
     pub running_tasks: RwLock<HashMap<String, RunningTask>>,
     pub preview_processes: RwLock<HashMap<String, PreviewProcess>>,
+    pub preview_ports: Mutex<PortRegistry>,
+    /// Port previews are proxied on, set once it is bound
+    pub preview_proxy_port: OnceLock<u16>,
     pub plan_sessions: RwLock<HashMap<String, PlanSession>>,
+    /// Project path of each task, for filtering its events by project
+    pub task_projects: RwLock<HashMap<String, String>>,
+    /// Chat replies in progress, by thread id
+    pub chat_generations: RwLock<HashMap<String, ChatGeneration>>,
+    /// Cancel channels of eval runs in progress, by suite id; a suite runs
+    /// one at a time
+    pub running_evals: RwLock<HashMap<String, mpsc::Sender<()>>>,
 }
 
 impl AppState {
-    pub fn new(db: Pool<Sqlite>, working_dir: PathBuf) -> Arc<Self> {
+    pub fn new(db: Pool<Sqlite>, default_project: Project) -> Arc<Self> {
         let (ws_broadcast, _) = broadcast::channel(100);
-
-        let worktrees_base_dir = eval_kanban_db::get_worktrees_dir();
-        let worktree_manager = WorktreeManager::new(working_dir.clone(), worktrees_base_dir);
+        let first_event_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or(1);
 
         Arc::new(Self {
             db,
-            working_dir,
-            worktree_manager,
+            default_project: Arc::new(ProjectContext::new(default_project)),
+            projects: RwLock::new(HashMap::new()),
+            settings: SettingsStore::new(settings::global_config_path()),
             ws_broadcast,
+            event_log: Mutex::new(EventLog::new(first_event_id, EVENT_BUFFER_SIZE)),
             running_tasks: RwLock::new(HashMap::new()),
             preview_processes: RwLock::new(HashMap::new()),
+            preview_ports: Mutex::new(PortRegistry::default()),
+            preview_proxy_port: OnceLock::new(),
             plan_sessions: RwLock::new(HashMap::new()),
+            task_projects: RwLock::new(HashMap::new()),
+            chat_generations: RwLock::new(HashMap::new()),
+            running_evals: RwLock::new(HashMap::new()),
         })
     }
 
     pub async fn broadcast(&self, msg: WsMessage) {
-        match self.ws_broadcast.send(msg) {
+        let project_path = self.message_project(&msg).await;
+
+        // Sent while holding the log so events reach the channel in id order
+        let mut event_log = self.event_log.lock().unwrap_or_else(PoisonError::into_inner);
+        let event = event_log.record(msg, project_path);
+
+        match self.ws_broadcast.send(event) {
             Ok(receivers) => {
                 tracing::debug!("[Broadcast] Message sent to {} receivers", receivers);
             }
@@ -98,9 +234,117 @@ impl AppState {
         }
     }
 
-    pub async fn add_running_task(&self, task_id: String, cancel_tx: mpsc::Sender<()>) {
+    /// Path of the project a message is about, via its task or plan session.
+    async fn message_project(&self, msg: &WsMessage) -> Option<String> {
+        match msg.subject()? {
+            MessageSubject::Project(path) => Some(path.to_string()),
+            MessageSubject::Task(task_id) => {
+                let path = self.task_project(task_id).await;
+                if matches!(msg, WsMessage::TaskDeleted { .. }) {
+                    self.task_projects.write().await.remove(task_id);
+                }
+                path
+            }
+            MessageSubject::PlanSession(session_id) => {
+                if let Some(session) = self.plan_sessions.read().await.get(session_id) {
+                    return Some(session.project_path.clone());
+                }
+                PlanSessionRecord::find_by_id(&self.db, session_id)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|record| record.project_path)
+            }
+        }
+    }
+
+    /// Path of a task's project, cached since a task never changes project.
+    async fn task_project(&self, task_id: &str) -> Option<String> {
+        if let Some(path) = self.task_projects.read().await.get(task_id) {
+            return Some(path.clone());
+        }
+
+        let path = Task::find_by_id(&self.db, task_id).await.ok().flatten()?.project_path?;
+        self.task_projects.write().await.insert(task_id.to_string(), path.clone());
+        Some(path)
+    }
+
+    /// The latest event id and a receiver for everything after it.
+    pub fn subscribe_events(&self) -> (u64, broadcast::Receiver<WsEvent>) {
+        let event_log = self.event_log.lock().unwrap_or_else(PoisonError::into_inner);
+        (event_log.last_id(), self.ws_broadcast.subscribe())
+    }
+
+    pub fn events_since(&self, last_seen: u64) -> Option<Vec<WsEvent>> {
+        self.event_log.lock().unwrap_or_else(PoisonError::into_inner).since(last_seen)
+    }
+
+    pub fn last_event_id(&self) -> u64 {
+        self.event_log.lock().unwrap_or_else(PoisonError::into_inner).last_id()
+    }
+
+    // Projects
+
+    /// A registered project, by id.
+    pub async fn project(&self, id: &str) -> Result<Option<Arc<ProjectContext>>, sqlx::Error> {
+        if self.default_project.project.id == id {
+            return Ok(Some(self.default_project.clone()));
+        }
+        if let Some(project) = self.projects.read().await.get(id) {
+            return Ok(Some(project.clone()));
+        }
+
+        let Some(project) = Project::find_by_id(&self.db, id).await? else {
+            return Ok(None);
+        };
+        let mut projects = self.projects.write().await;
+        let context = projects
+            .entry(project.id.clone())
+            .or_insert_with(|| Arc::new(ProjectContext::new(project)));
+        Ok(Some(context.clone()))
+    }
+
+    /// The project a task belongs to, registered if needed. Tasks without a
+    /// project belong to the default one.
+    pub async fn project_for_task(&self, task: &Task) -> Result<Arc<ProjectContext>, sqlx::Error> {
+        match task.project_path.as_deref() {
+            Some(path) => self.project_for_path(path).await,
+            None => Ok(self.default_project.clone()),
+        }
+    }
+
+    /// The project at a path as stored in `project_path` columns, registered
+    /// if needed.
+    pub async fn project_for_path(&self, path: &str) -> Result<Arc<ProjectContext>, sqlx::Error> {
+        if path == self.default_project.project.path {
+            return Ok(self.default_project.clone());
+        }
+
+        if let Some(project) = self.projects.read().await.values().find(|p| p.project.path == path) {
+            return Ok(project.clone());
+        }
+
+        let project = match Project::find_by_path(&self.db, path).await? {
+            Some(project) => project,
+            None => Project::open(&self.db, path, None).await?,
+        };
+        Ok(self.project(&project.id).await?.unwrap_or_else(|| self.default_project.clone()))
+    }
+
+    /// Forget a project's cached state after it was removed.
+    pub async fn forget_project(&self, id: &str) {
+        self.projects.write().await.remove(id);
+    }
+
+    /// Mark a task as running unless it already is, in one step, so two
+    /// requests can't both start it. Returns false if it was running.
+    pub async fn try_add_running_task(&self, task_id: String, cancel_tx: mpsc::Sender<()>) -> bool {
         let mut tasks = self.running_tasks.write().await;
+        if tasks.contains_key(&task_id) {
+            return false;
+        }
         tasks.insert(task_id.clone(), RunningTask { task_id, cancel_tx });
+        true
     }
 
     pub async fn remove_running_task(&self, task_id: &str) -> Option<RunningTask> {
@@ -113,15 +357,113 @@ impl AppState {
         tasks.contains_key(task_id)
     }
 
+    // Eval run management
+
+    /// Mark a suite as being run unless it already is, in one step, so two
+    /// requests can't both start a run. Returns false if one is running.
+    pub async fn try_add_running_eval(&self, suite_id: String, cancel_tx: mpsc::Sender<()>) -> bool {
+        let mut evals = self.running_evals.write().await;
+        if evals.contains_key(&suite_id) {
+            return false;
+        }
+        evals.insert(suite_id, cancel_tx);
+        true
+    }
+
+    pub async fn remove_running_eval(&self, suite_id: &str) {
+        self.running_evals.write().await.remove(suite_id);
+    }
+
+    pub async fn is_eval_running(&self, suite_id: &str) -> bool {
+        self.running_evals.read().await.contains_key(suite_id)
+    }
+
+    /// Ask the run of a suite in progress to stop. Returns false if none is.
+    pub async fn cancel_eval(&self, suite_id: &str) -> bool {
+        let evals = self.running_evals.read().await;
+        let Some(cancel_tx) = evals.get(suite_id) else {
+            return false;
+        };
+        let _ = cancel_tx.try_send(());
+        true
+    }
+
+    // Chat generation management
+
+    /// Register a reply for the thread. Fails with the id of the reply in
+    /// progress if the thread already has one.
+    pub async fn start_chat_generation(
+        &self,
+        thread_id: &str,
+        message_id: String,
+        cancel_tx: mpsc::Sender<()>,
+    ) -> Result<(), String> {
+        let mut generations = self.chat_generations.write().await;
+        if let Some(existing) = generations.get(thread_id) {
+            return Err(existing.message_id.clone());
+        }
+        generations.insert(thread_id.to_string(), ChatGeneration { message_id, cancel_tx });
+        Ok(())
+    }
+
+    /// Remove the thread's generation, if it is still the one for `message_id`.
+    pub async fn finish_chat_generation(&self, thread_id: &str, message_id: &str) {
+        let mut generations = self.chat_generations.write().await;
+        if generations.get(thread_id).is_some_and(|g| g.message_id == message_id) {
+            generations.remove(thread_id);
+        }
+    }
+
+    /// Ask the thread's reply in progress to stop. Returns the reply's id.
+    pub async fn cancel_chat_generation(&self, thread_id: &str) -> Option<String> {
+        let generations = self.chat_generations.read().await;
+        let generation = generations.get(thread_id)?;
+        let _ = generation.cancel_tx.try_send(());
+        Some(generation.message_id.clone())
+    }
+
     // Preview process management
-    pub async fn add_preview(&self, task_id: String, process: PreviewProcess) {
+    /// Returns false if the task already has a preview.
+    pub async fn add_preview(&self, task_id: String, process: PreviewProcess) -> bool {
         let mut previews = self.preview_processes.write().await;
+        if previews.contains_key(&task_id) {
+            return false;
+        }
         previews.insert(task_id, process);
+        true
     }
 
+    /// Remove the preview and release its ports. Its processes are killed
+    /// once it is dropped.
     pub async fn remove_preview(&self, task_id: &str) -> Option<PreviewProcess> {
         let mut previews = self.preview_processes.write().await;
-        previews.remove(task_id)
+        let preview = previews.remove(task_id)?;
+        self.preview_ports.lock().unwrap_or_else(PoisonError::into_inner).release(&preview.run_id);
+        Some(preview)
+    }
+
+    /// Stop the task's preview and tell clients. Returns false if it had none.
+    pub async fn stop_preview(&self, task_id: &str) -> bool {
+        let Some(preview) = self.remove_preview(task_id).await else {
+            return false;
+        };
+
+        let mut info = preview.to_info();
+        info.status = PreviewStatus::Stopped;
+        for service in &mut info.services {
+            service.status = PreviewStatus::Stopped;
+        }
+        drop(preview);
+
+        self.broadcast(WsMessage::PreviewUpdated { preview: info }).await;
+        true
+    }
+
+    /// Note that the task's preview is in use.
+    pub async fn touch_preview(&self, task_id: &str) {
+        if let Some(preview) = self.preview_processes.read().await.get(task_id) {
+            preview.activity.touch();
+        }
     }
 
     pub async fn is_preview_running(&self, task_id: &str) -> bool {
@@ -131,25 +473,87 @@ impl AppState {
 
     pub async fn get_preview_info(&self, task_id: &str) -> Option<PreviewInfo> {
         let previews = self.preview_processes.read().await;
-        previews.get(task_id).map(|p| p.to_info(PreviewStatus::Running))
+        previews.get(task_id).map(|p| p.to_info())
     }
 
-    #[allow(dead_code)]
+    /// Change the service launched as `run_id` and broadcast the preview.
+    /// Returns false, without calling `update`, if the preview was stopped
+    /// or the service restarted since.
+    pub async fn update_preview_service(
+        &self,
+        task_id: &str,
+        run_id: &str,
+        update: impl FnOnce(&mut ServiceProcess),
+    ) -> bool {
+        let info = {
+            let mut previews = self.preview_processes.write().await;
+            let Some(preview) = previews.get_mut(task_id) else {
+                return false;
+            };
+            let Some(service) = preview.services.iter_mut().find(|s| s.run_id == run_id) else {
+                return false;
+            };
+            update(service);
+            preview.to_info()
+        };
+
+        self.broadcast(WsMessage::PreviewUpdated { preview: info }).await;
+        true
+    }
+
+    /// Kill every preview's processes, before the server exits.
     pub async fn cleanup_all_previews(&self) {
         let mut previews = self.preview_processes.write().await;
-        for (task_id, _process) in previews.drain() {
+        for (task_id, preview) in previews.drain() {
             tracing::info!("Cleaning up preview for task {}", task_id);
-            // Processes will be killed on drop due to kill_on_drop(true)
+            for pid in preview.services.iter().filter_map(|s| s.pid) {
+                preview::kill_group(pid);
+            }
         }
     }
 
     // Plan session management
+    // Sessions are kept in memory while active and written through to the
+    // database whenever their status, questions, answers or summary change.
     pub async fn add_plan_session(&self, session: PlanSession) {
+        self.persist_plan_session(&session).await;
         let mut sessions = self.plan_sessions.write().await;
         sessions.insert(session.id.clone(), session);
     }
 
+    async fn persist_plan_session(&self, session: &PlanSession) {
+        if let Err(e) = PlanSessionRecord::save(&self.db, &session.to_record()).await {
+            tracing::warn!("[Plan {}] Failed to persist session: {}", session.id, e);
+        }
+    }
+
+    /// Make sure a session is in memory, restoring it from the database if needed.
+    /// Returns false if the session does not exist.
+    pub async fn load_plan_session(&self, session_id: &str) -> bool {
+        if self.plan_sessions.read().await.contains_key(session_id) {
+            return true;
+        }
+
+        let record = match PlanSessionRecord::find_by_id(&self.db, session_id).await {
+            Ok(Some(record)) => record,
+            Ok(None) => return false,
+            Err(e) => {
+                tracing::warn!("[Plan {}] Failed to load session: {}", session_id, e);
+                return false;
+            }
+        };
+
+        let session = PlanSession::from_record(record);
+        tracing::info!("[Plan {}] Restored session from database (status={:?})", session_id, session.status);
+        let mut sessions = self.plan_sessions.write().await;
+        sessions.entry(session_id.to_string()).or_insert(session);
+        true
+    }
+
     pub async fn get_plan_session_info(&self, session_id: &str) -> Option<PlanSessionInfo> {
+        if !self.load_plan_session(session_id).await {
+            return None;
+        }
         let sessions = self.plan_sessions.read().await;
         sessions.get(session_id).map(|s| s.to_info())
     }
@@ -159,17 +563,48 @@ impl AppState {
         sessions.remove(session_id)
     }
 
+    /// Mark a session completed and drop it from memory, so it can be turned
+    /// into tasks once. Returns the session with the status it had before,
+    /// or None if it does not exist.
+    pub async fn take_plan_session(&self, session_id: &str) -> Option<(PlanSession, PlanStatus)> {
+        // Loaded and saved under the lock so a second request sees it completed
+        let mut sessions = self.plan_sessions.write().await;
+        let mut session = match sessions.remove(session_id) {
+            Some(session) => session,
+            None => match PlanSessionRecord::find_by_id(&self.db, session_id).await {
+                Ok(Some(record)) => PlanSession::from_record(record),
+                Ok(None) => return None,
+                Err(e) => {
+                    tracing::warn!("[Plan {}] Failed to load session: {}", session_id, e);
+                    return None;
+                }
+            },
+        };
+
+        let previous = std::mem::replace(&mut session.status, PlanStatus::Completed);
+        if previous != PlanStatus::Completed {
+            self.persist_plan_session(&session).await;
+        }
+        Some((session, previous))
+    }
+
     pub async fn update_plan_session<F>(&self, session_id: &str, f: F) -> bool
     where
         F: FnOnce(&mut PlanSession),
     {
+        // Saved under the lock so concurrent updates reach the database in order
         let mut sessions = self.plan_sessions.write().await;
-        if let Some(session) = sessions.get_mut(session_id) {
-            f(session);
-            true
-        } else {
-            false
+        let Some(session) = sessions.get_mut(session_id) else {
+            return false;
+        };
+
+        let before = session.checkpoint();
+        f(session);
+        if session.checkpoint() != before {
+            self.persist_plan_session(session).await;
         }
+
+        true
     }
 
     #[allow(dead_code)]
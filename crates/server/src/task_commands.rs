@@ -0,0 +1,413 @@
+//! The `task` subcommands.
+//!
+//! Commands go through the HTTP API of the server running for the current
+//! directory. Without one, reading and adding tasks works on the database
+//! directly; starting and merging need the server, which runs Claude and the
+//! merge.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use sqlx::{Pool, Sqlite};
+
+use eval_kanban_db::{CreateTask, ExecutionLog, Task, TaskStatus};
+use eval_kanban_executor::stream_json::{self, ContentBlock};
+use eval_kanban_worktree::{DiffResponse, get_worktree_diff};
+
+use crate::api_client::{ApiClient, ApiError};
+use crate::auth;
+use crate::cli::TaskCommand;
+use crate::settings::{self, SettingsStore};
+
+/// Shortest task id prefix accepted
+const MIN_ID_PREFIX: usize = 4;
+/// How often `logs --follow` checks for new output
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+/// Lines fetched per poll while following
+const FOLLOW_BATCH: i64 = 1000;
+
+/// Where the board is read from.
+enum Board {
+    Server {
+        client: ApiClient,
+        /// `/api/tasks`, or the project-scoped path when the directory isn't
+        /// the project the server was started in
+        tasks_path: String,
+    },
+    Database {
+        db: Pool<Sqlite>,
+        project_path: String,
+    },
+}
+
+pub async fn run(server: Option<String>, command: TaskCommand) -> Result<(), String> {
+    let board = connect(server).await?;
+
+    match command {
+        TaskCommand::Add { title, description } => {
+            let task = board.create_task(title, description).await?;
+            println!("Created {}  {}", short_id(&task.id), task.title);
+        }
+        TaskCommand::List => {
+            let tasks = board.list_tasks().await?;
+            if tasks.is_empty() {
+                println!("No tasks");
+            }
+            for task in tasks {
+                println!("{}  {:<15}  {}", short_id(&task.id), task.status.as_str(), task.title);
+            }
+        }
+        TaskCommand::Start { id, feedback } => {
+            let client = board.require_server("start")?;
+            let task = board.resolve(&id).await?;
+            client
+                .post(&format!("/api/tasks/{}/start", task.id), Some(&json!({ "feedback": feedback })))
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Started {}  {}", short_id(&task.id), task.title);
+            println!("Follow its output with: eval-kanban task logs {} --follow", short_id(&task.id));
+        }
+        TaskCommand::Logs { id, follow, raw, lines } => {
+            let task = board.resolve(&id).await?;
+            let logs = board.latest_logs(&task.id, lines).await?;
+            let mut last_id = logs.last().map_or(0, |l| l.id);
+            print_logs(&logs, raw);
+
+            if !follow {
+                return Ok(());
+            }
+            loop {
+                tokio::time::sleep(FOLLOW_INTERVAL).await;
+
+                let logs = board.logs_after(&task.id, last_id).await?;
+                print_logs(&logs, raw);
+                last_id = logs.last().map_or(last_id, |l| l.id);
+
+                // Stop once the run is over and everything it wrote is shown
+                if logs.is_empty() && board.get_task(&task.id).await?.status != TaskStatus::InProgress {
+                    break;
+                }
+            }
+        }
+        TaskCommand::Diff { id, stat } => {
+            let task = board.resolve(&id).await?;
+            let diff = board.diff(&task).await?;
+            print_diff(&diff, stat);
+        }
+        TaskCommand::Merge { id } => {
+            let client = board.require_server("merge")?;
+            let task = board.resolve(&id).await?;
+            println!("Merging {}  {}...", short_id(&task.id), task.title);
+            let response = client
+                .post(&format!("/api/tasks/{}/merge", task.id), None)
+                .await
+                .map_err(|e| e.to_string())?;
+            let message = response.get("message").and_then(|m| m.as_str()).unwrap_or("Merged");
+            println!("{}", message);
+        }
+    }
+
+    Ok(())
+}
+
+/// Use the given server, or the default one if it serves the current
+/// directory, or else the database.
+async fn connect(server: Option<String>) -> Result<Board, String> {
+    let project_path = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?
+        .to_string_lossy()
+        .to_string();
+
+    if let Some(url) = server {
+        let client = ApiClient::new(&url).map_err(|e| e.to_string())?.with_token(auth::client_token());
+        // Elsewhere than in one of its projects, use its default project
+        let tasks_path = tasks_path(&client, &project_path)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(|| "/api/tasks".to_string());
+        return Ok(Board::Server { client, tasks_path });
+    }
+
+    // The port a server started here would use
+    let settings = SettingsStore::new(settings::global_config_path()).get(Path::new(&project_path));
+    let client = ApiClient::new(&format!("http://127.0.0.1:{}", settings.server.port))
+        .map_err(|e| e.to_string())?
+        .with_token(auth::client_token());
+    match tasks_path(&client, &project_path).await {
+        Ok(Some(tasks_path)) => return Ok(Board::Server { client, tasks_path }),
+        Ok(None) => {}
+        // A server is running but won't talk to us; don't quietly bypass it
+        Err(e @ ApiError::Status { status: 401, .. }) => return Err(e.to_string()),
+        Err(_) => {}
+    }
+
+    let db = eval_kanban_db::init_db()
+        .await
+        .map_err(|e| format!("Failed to open the database: {}", e))?;
+    Ok(Board::Database { db, project_path })
+}
+
+/// Where the server lists the tasks of the project at `project_path`, if it
+/// manages it.
+async fn tasks_path(client: &ApiClient, project_path: &str) -> Result<Option<String>, ApiError> {
+    let info = client.get("/api/server/info").await?;
+    if info.get("path").and_then(|p| p.as_str()) == Some(project_path) {
+        return Ok(Some("/api/tasks".to_string()));
+    }
+
+    let projects = client.get("/api/projects").await?;
+    let project_id = projects.get("projects")
+        .and_then(|p| p.as_array())
+        .and_then(|projects| {
+            projects.iter().find(|p| p.get("path").and_then(|p| p.as_str()) == Some(project_path))
+        })
+        .and_then(|p| p.get("id"))
+        .and_then(|id| id.as_str());
+
+    Ok(project_id.map(|id| format!("/api/projects/{}/tasks", id)))
+}
+
+impl Board {
+    fn require_server(&self, action: &str) -> Result<&ApiClient, String> {
+        match self {
+            Board::Server { client, .. } => Ok(client),
+            Board::Database { .. } => Err(format!(
+                "No server is running for this directory; `{}` needs one (start it with `eval-kanban serve --no-open`)",
+                action
+            )),
+        }
+    }
+
+    async fn create_task(&self, title: String, description: Option<String>) -> Result<Task, String> {
+        match self {
+            Board::Server { client, tasks_path } => {
+                let body = json!({ "title": title, "description": description });
+                let response = client.post(tasks_path, Some(&body)).await.map_err(|e| e.to_string())?;
+                parse(response)
+            }
+            Board::Database { db, project_path } => {
+                if title.trim().is_empty() {
+                    return Err("Title is required".to_string());
+                }
+                Task::create(db, CreateTask { title, description, project_path: project_path.clone() })
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    async fn list_tasks(&self) -> Result<Vec<Task>, String> {
+        match self {
+            Board::Server { client, tasks_path } => {
+                let response = client.get(tasks_path).await.map_err(|e| e.to_string())?;
+                parse(response.get("tasks").cloned().unwrap_or_default())
+            }
+            Board::Database { db, project_path } => {
+                Task::find_all_by_project(db, project_path).await.map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    async fn get_task(&self, id: &str) -> Result<Task, String> {
+        match self {
+            Board::Server { client, .. } => {
+                let response = client.get(&format!("/api/tasks/{}", id)).await.map_err(|e| e.to_string())?;
+                parse(response)
+            }
+            Board::Database { db, .. } => Task::find_by_id(db, id)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Task {} not found", id)),
+        }
+    }
+
+    /// The task with this id or unique id prefix.
+    async fn resolve(&self, id: &str) -> Result<Task, String> {
+        if id.len() < MIN_ID_PREFIX {
+            return Err(format!("Task id '{}' is too short, use at least {} characters", id, MIN_ID_PREFIX));
+        }
+
+        let mut matches: Vec<Task> = self
+            .list_tasks()
+            .await?
+            .into_iter()
+            .filter(|t| t.id.starts_with(id))
+            .collect();
+
+        match matches.len() {
+            1 => Ok(matches.remove(0)),
+            0 => Err(format!("No task matches '{}'", id)),
+            n => Err(format!("'{}' matches {} tasks, use a longer id", id, n)),
+        }
+    }
+
+    async fn latest_logs(&self, task_id: &str, limit: i64) -> Result<Vec<ExecutionLog>, String> {
+        match self {
+            Board::Server { client, .. } => {
+                let response = client
+                    .get(&format!("/api/tasks/{}/logs?limit={}", task_id, limit))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                parse(response.get("logs").cloned().unwrap_or_default())
+            }
+            Board::Database { db, .. } => ExecutionLog::find_latest_by_task(db, task_id, limit)
+                .await
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    async fn logs_after(&self, task_id: &str, after_id: i64) -> Result<Vec<ExecutionLog>, String> {
+        match self {
+            Board::Server { client, .. } => {
+                let response = client
+                    .get(&format!("/api/tasks/{}/logs?after={}&limit={}", task_id, after_id, FOLLOW_BATCH))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                parse(response.get("logs").cloned().unwrap_or_default())
+            }
+            Board::Database { db, .. } => ExecutionLog::find_after(db, task_id, after_id, FOLLOW_BATCH)
+                .await
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    async fn diff(&self, task: &Task) -> Result<DiffResponse, String> {
+        match self {
+            Board::Server { client, .. } => {
+                let response = client.get(&format!("/api/tasks/{}/diff", task.id)).await.map_err(|e| match e {
+                    ApiError::Status { message, .. } => message,
+                    e => e.to_string(),
+                })?;
+                parse(response)
+            }
+            Board::Database { .. } => {
+                let worktree_path = task.worktree_path
+                    .as_ref()
+                    .map(PathBuf::from)
+                    .filter(|p| p.exists())
+                    .ok_or_else(|| "Task has no worktree".to_string())?;
+
+                tokio::task::spawn_blocking(move || get_worktree_diff(&worktree_path))
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(value: Value) -> Result<T, String> {
+    serde_json::from_value(value).map_err(|e| format!("Unexpected response from the server: {}", e))
+}
+
+fn short_id(id: &str) -> &str {
+    &id[..8.min(id.len())]
+}
+
+fn print_logs(logs: &[ExecutionLog], raw: bool) {
+    for log in logs {
+        if raw {
+            println!("{}", log.content);
+            continue;
+        }
+        for line in readable_log_lines(log) {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Claude's text, tool calls and final result from a stream-json line;
+/// other lines as they are.
+fn readable_log_lines(log: &ExecutionLog) -> Vec<String> {
+    if log.stream == "stderr" {
+        return vec![format!("[stderr] {}", log.content)];
+    }
+
+    let Ok(json) = serde_json::from_str::<Value>(&log.content) else {
+        return vec![log.content.clone()];
+    };
+
+    if stream_json::is_result(&log.content) {
+        return json.get("result")
+            .and_then(|r| r.as_str())
+            .map(|r| vec![format!("[result] {}", r)])
+            .unwrap_or_default();
+    }
+
+    stream_json::content_blocks(&log.content)
+        .into_iter()
+        .filter_map(|block| match block {
+            ContentBlock::Text(text) => Some(text),
+            ContentBlock::ToolUse { name, input, .. } => {
+                // The first string argument is usually the most telling one
+                let detail = input.as_object()
+                    .and_then(|args| args.values().find_map(|v| v.as_str()))
+                    .map(|v| format!(" {}", v.lines().next().unwrap_or_default()))
+                    .unwrap_or_default();
+                Some(format!("> {}{}", name, detail))
+            }
+            ContentBlock::ToolResult { content, is_error: true, .. } => {
+                Some(format!("! {}", content.lines().next().unwrap_or_default()))
+            }
+            ContentBlock::ToolResult { .. } => None,
+        })
+        .collect()
+}
+
+fn print_diff(diff: &DiffResponse, stat: bool) {
+    if diff.files.is_empty() {
+        println!("No changes");
+        return;
+    }
+
+    if stat {
+        for file in &diff.files {
+            println!(" {}  +{} -{}", file.path, file.additions, file.deletions);
+        }
+        println!(
+            " {} files changed, {} insertions(+), {} deletions(-)",
+            diff.files.len(),
+            diff.total_additions,
+            diff.total_deletions
+        );
+        return;
+    }
+
+    for file in &diff.files {
+        println!("{}", file.content.trim_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn log(stream: &str, content: &str) -> ExecutionLog {
+        ExecutionLog {
+            id: 1,
+            task_id: "t1".to_string(),
+            run_id: None,
+            stream: stream.to_string(),
+            content: content.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_readable_log_lines() {
+        let assistant = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Reading it"},{"type":"tool_use","id":"t","name":"Read","input":{"file_path":"src/main.rs"}}]}}"#;
+        assert_eq!(readable_log_lines(&log("stdout", assistant)), vec!["Reading it", "> Read src/main.rs"]);
+
+        let failed = r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t","content":"No such file","is_error":true}]}}"#;
+        assert_eq!(readable_log_lines(&log("stdout", failed)), vec!["! No such file"]);
+
+        let result = r#"{"type":"result","subtype":"success","result":"Done"}"#;
+        assert_eq!(readable_log_lines(&log("stdout", result)), vec!["[result] Done"]);
+
+        assert!(readable_log_lines(&log("stdout", r#"{"type":"system","subtype":"init"}"#)).is_empty());
+        assert_eq!(readable_log_lines(&log("stderr", "warn")), vec!["[stderr] warn"]);
+        assert_eq!(readable_log_lines(&log("stdout", "plain")), vec!["plain"]);
+    }
+}
@@ -0,0 +1,204 @@
+//! Verification checks: the commands in `verify.commands`, run in a task's
+//! worktree after Claude finishes and before the task goes to review.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc;
+
+use eval_kanban_db::{CheckStatus, RunCheck};
+
+use crate::preview;
+use crate::run_events::OUTPUT_DRAIN_TIMEOUT;
+use crate::settings::VerifyCommand;
+
+/// Lines of a failing check's output shown to Claude when auto-fixing
+const OUTPUT_TAIL_LINES: usize = 200;
+
+/// The last lines of a check's output.
+#[derive(Default)]
+pub struct OutputTail(VecDeque<String>);
+
+impl OutputTail {
+    pub fn push(&mut self, line: &str) {
+        if self.0.len() == OUTPUT_TAIL_LINES {
+            self.0.pop_front();
+        }
+        self.0.push_back(line.to_string());
+    }
+}
+
+impl fmt::Display for OutputTail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, line) in self.0.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// A check that hasn't run yet.
+pub fn pending(check: &VerifyCommand) -> RunCheck {
+    RunCheck {
+        name: check.name.clone(),
+        command: check.command.clone(),
+        status: CheckStatus::Pending,
+        exit_code: None,
+        error: None,
+        duration_ms: None,
+    }
+}
+
+/// Run a check in the worktree, sending each line it writes to `output`.
+/// Returns the finished check.
+pub async fn run(check: &VerifyCommand, worktree: &Path, output: mpsc::UnboundedSender<String>) -> RunCheck {
+    let mut result = pending(check);
+    let started = Instant::now();
+
+    let spawned = preview::shell_command(&check.command, &worktree.join(&check.cwd))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn();
+    let mut process = match spawned {
+        Ok(process) => process,
+        Err(e) => {
+            result.status = CheckStatus::Failed;
+            result.error = Some(format!("failed to start: {}", e));
+            return result;
+        }
+    };
+    // Stops what the check started, when it is cancelled or leaves
+    // processes running
+    let group = ProcessGroup(process.id());
+
+    let mut readers = Vec::new();
+    if let Some(stdout) = process.stdout.take() {
+        readers.push(tokio::spawn(forward_lines(stdout, output.clone())));
+    }
+    if let Some(stderr) = process.stderr.take() {
+        readers.push(tokio::spawn(forward_lines(stderr, output)));
+    }
+
+    match tokio::time::timeout(Duration::from_secs(check.timeout_secs), process.wait()).await {
+        Ok(Ok(status)) => {
+            result.exit_code = status.code();
+            if status.success() {
+                result.status = CheckStatus::Passed;
+            } else {
+                result.status = CheckStatus::Failed;
+                if status.code().is_none() {
+                    result.error = Some(format!("exited ({})", status));
+                }
+            }
+        }
+        Ok(Err(e)) => {
+            result.status = CheckStatus::Failed;
+            result.error = Some(e.to_string());
+        }
+        Err(_) => {
+            preview::kill(&mut process).await;
+            result.status = CheckStatus::Failed;
+            result.error = Some(format!("timed out after {}s", check.timeout_secs));
+        }
+    }
+    result.duration_ms = Some(started.elapsed().as_millis() as u64);
+    drop(group);
+
+    // Output is still read for a while, e.g. from processes the check left running
+    for reader in readers {
+        let _ = tokio::time::timeout(OUTPUT_DRAIN_TIMEOUT, reader).await;
+    }
+    result
+}
+
+/// One line describing how a finished check went.
+pub fn summary(check: &RunCheck) -> String {
+    let took = check.duration_ms.map(|ms| format!(" in {:.1}s", ms as f64 / 1000.0)).unwrap_or_default();
+    match (check.status, &check.error, check.exit_code) {
+        (CheckStatus::Passed, _, _) => format!("passed{}", took),
+        (_, Some(error), _) => format!("failed{}: {}", took, error),
+        (_, None, Some(code)) => format!("failed{} with exit code {}", took, code),
+        _ => format!("failed{}", took),
+    }
+}
+
+/// Kills the process group of a check when dropped.
+struct ProcessGroup(Option<u32>);
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if let Some(pid) = self.0 {
+            preview::kill_group(pid);
+        }
+    }
+}
+
+async fn forward_lines(reader: impl AsyncRead + Unpin, output: mpsc::UnboundedSender<String>) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let _ = output.send(String::from_utf8_lossy(&line).trim_end().to_string());
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn check(command: &str, timeout_secs: u64) -> VerifyCommand {
+        VerifyCommand {
+            name: "test".to_string(),
+            command: command.to_string(),
+            cwd: String::new(),
+            timeout_secs,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_check() {
+        let dir = std::env::temp_dir();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let passed = run(&check("echo one; echo two >&2", 10), &dir, tx.clone()).await;
+        assert_eq!(passed.status, CheckStatus::Passed);
+        assert_eq!(passed.exit_code, Some(0));
+        let mut lines = vec![rx.recv().await.unwrap(), rx.recv().await.unwrap()];
+        lines.sort();
+        assert_eq!(lines, vec!["one", "two"]);
+
+        let failed = run(&check("echo failing; exit 3", 10), &dir, tx.clone()).await;
+        assert_eq!(failed.status, CheckStatus::Failed);
+        assert_eq!(failed.exit_code, Some(3));
+        assert!(summary(&failed).ends_with("with exit code 3"));
+
+        let timed_out = run(&check("sleep 5", 1), &dir, tx).await;
+        assert_eq!(timed_out.status, CheckStatus::Failed);
+        assert_eq!(timed_out.error.as_deref(), Some("timed out after 1s"));
+    }
+
+    #[test]
+    fn test_output_tail() {
+        let mut tail = OutputTail::default();
+        for line in 0..OUTPUT_TAIL_LINES + 2 {
+            tail.push(&line.to_string());
+        }
+        let text = tail.to_string();
+        assert!(text.starts_with("2\n3\n"));
+        assert!(text.ends_with(&format!("\n{}", OUTPUT_TAIL_LINES + 1)));
+    }
+}
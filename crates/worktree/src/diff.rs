@@ -1,10 +1,10 @@
 use std::path::Path;
 use std::process::Command;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::WorktreeError;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiffFile {
     pub path: String,
     pub change_type: DiffChangeType,
@@ -13,7 +13,7 @@ pub struct DiffFile {
     pub content: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DiffChangeType {
     Added,
@@ -22,7 +22,7 @@ pub enum DiffChangeType {
     Renamed,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiffResponse {
     pub files: Vec<DiffFile>,
     pub total_additions: usize,
@@ -19,10 +19,18 @@ pub enum WorktreeError {
     WorktreeExists(String),
     #[error("Branch not found: {0}")]
     BranchNotFound(String),
+    #[error("Commit not found: {0}")]
+    CommitNotFound(String),
     #[error("Merge conflict: {0}")]
     MergeConflict(String),
 }
 
+/// Branch that task branches are created from and merged back into
+pub const BASE_BRANCH: &str = "main";
+
+/// Prefix of task branch names, unless configured otherwise
+pub const DEFAULT_BRANCH_PREFIX: &str = "ek";
+
 /// Create a slug from a title for branch naming
 pub fn slugify(title: &str) -> String {
     title
@@ -49,9 +57,9 @@ pub fn slugify(title: &str) -> String {
 }
 
 /// Generate a unique branch name from task title and task ID
-pub fn generate_branch_name(title: &str, task_id: &str) -> String {
+pub fn generate_branch_name(prefix: &str, title: &str, task_id: &str) -> String {
     let short_id = &task_id[..8.min(task_id.len())];
-    format!("ek/{}-{}", slugify(title), short_id)
+    format!("{}/{}-{}", prefix, slugify(title), short_id)
 }
 
 /// Manager for git worktrees
@@ -93,10 +101,11 @@ impl WorktreeManager {
     /// Create a worktree for a task
     pub async fn create_worktree(
         &self,
+        branch_prefix: &str,
         task_title: &str,
         task_id: &str,
     ) -> Result<(String, PathBuf), WorktreeError> {
-        let branch_name = generate_branch_name(task_title, task_id);
+        let branch_name = generate_branch_name(branch_prefix, task_title, task_id);
         let short_id = &task_id[..8.min(task_id.len())];
         let slug = format!("{}-{}", slugify(task_title), short_id);
         let worktree_path = self.get_worktree_path(&slug);
@@ -109,14 +118,35 @@ impl WorktreeManager {
             create_worktree_sync(&repo_path, &branch_name_clone, &worktree_path_clone)
         })
         .await
-        .map_err(|e| WorktreeError::Io(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            e.to_string(),
-        )))??;
+        .map_err(|e| WorktreeError::Io(std::io::Error::other(e.to_string())))??;
 
         Ok((branch_name, worktree_path))
     }
 
+    /// Create a worktree checked out at a commit, without a branch, e.g. to
+    /// replay an eval case
+    pub async fn create_detached_worktree(
+        &self,
+        name: &str,
+        id: &str,
+        commit: &str,
+    ) -> Result<PathBuf, WorktreeError> {
+        let short_id = &id[..8.min(id.len())];
+        let worktree_path = self.get_worktree_path(&format!("{}-{}", slugify(name), short_id));
+
+        let repo_path = self.repo_path.clone();
+        let commit = commit.to_string();
+        let worktree_path_clone = worktree_path.clone();
+
+        tokio::task::spawn_blocking(move || {
+            create_detached_worktree_sync(&repo_path, &commit, &worktree_path_clone)
+        })
+        .await
+        .map_err(|e| WorktreeError::Io(std::io::Error::other(e.to_string())))??;
+
+        Ok(worktree_path)
+    }
+
     /// Remove a worktree
     pub async fn remove_worktree(&self, worktree_path: &Path) -> Result<(), WorktreeError> {
         let repo_path = self.repo_path.clone();
@@ -126,10 +156,7 @@ impl WorktreeManager {
             remove_worktree_sync(&repo_path, &worktree_path)
         })
         .await
-        .map_err(|e| WorktreeError::Io(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            e.to_string(),
-        )))?
+        .map_err(|e| WorktreeError::Io(std::io::Error::other(e.to_string())))?
     }
 
     /// List all worktrees for this repo
@@ -179,10 +206,7 @@ impl WorktreeManager {
             merge_branch_sync(&repo_path, &branch_name)
         })
         .await
-        .map_err(|e| WorktreeError::Io(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            e.to_string(),
-        )))?
+        .map_err(|e| WorktreeError::Io(std::io::Error::other(e.to_string())))?
     }
 
     /// Delete a branch after merge
@@ -194,10 +218,42 @@ impl WorktreeManager {
             delete_branch_sync(&repo_path, &branch_name)
         })
         .await
-        .map_err(|e| WorktreeError::Io(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            e.to_string(),
-        )))?
+        .map_err(|e| WorktreeError::Io(std::io::Error::other(e.to_string())))?
+    }
+
+    /// Delete a branch that was never merged, e.g. of a discarded attempt
+    pub async fn discard_branch(&self, branch_name: &str) -> Result<(), WorktreeError> {
+        let repo_path = self.repo_path.clone();
+        let branch_name = branch_name.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let repo = Repository::open(&repo_path)?;
+            let mut branch = repo
+                .find_branch(&branch_name, BranchType::Local)
+                .map_err(|_| WorktreeError::BranchNotFound(branch_name.clone()))?;
+            branch.delete()?;
+            tracing::info!("Deleted branch {}", branch_name);
+            Ok(())
+        })
+        .await
+        .map_err(|e| WorktreeError::Io(std::io::Error::other(e.to_string())))?
+    }
+
+    /// Full hash of the commit a revision (branch, tag or hash) points to
+    pub async fn resolve_commit(&self, rev: &str) -> Result<String, WorktreeError> {
+        let repo_path = self.repo_path.clone();
+        let rev = rev.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let repo = Repository::open(&repo_path)?;
+            let commit = repo
+                .revparse_single(&rev)
+                .and_then(|object| object.peel_to_commit())
+                .map_err(|_| WorktreeError::CommitNotFound(rev.clone()))?;
+            Ok(commit.id().to_string())
+        })
+        .await
+        .map_err(|e| WorktreeError::Io(std::io::Error::other(e.to_string())))?
     }
 
     /// Get the HEAD commit hash of the main repo
@@ -211,10 +267,7 @@ impl WorktreeManager {
             Ok(commit.id().to_string())
         })
         .await
-        .map_err(|e| WorktreeError::Io(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            e.to_string(),
-        )))?
+        .map_err(|e| WorktreeError::Io(std::io::Error::other(e.to_string())))?
     }
 }
 
@@ -274,7 +327,58 @@ fn create_worktree_sync(
         branch_name
     );
 
-    // Copy .claude/ directory from main repo to worktree
+    copy_claude_config(repo_path, worktree_path);
+
+    Ok(())
+}
+
+fn create_detached_worktree_sync(
+    repo_path: &Path,
+    commit: &str,
+    worktree_path: &Path,
+) -> Result<(), WorktreeError> {
+    if let Some(parent) = worktree_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if worktree_path.exists() {
+        return Err(WorktreeError::WorktreeExists(
+            worktree_path.to_string_lossy().to_string(),
+        ));
+    }
+
+    let output = std::process::Command::new("git")
+        .args([
+            "worktree",
+            "add",
+            "--detach",
+            worktree_path.to_str().unwrap(),
+            commit,
+        ])
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(WorktreeError::Git(git2::Error::from_str(&format!(
+            "Failed to create worktree: {}",
+            stderr
+        ))));
+    }
+
+    tracing::info!(
+        "Created worktree at {} at commit {}",
+        worktree_path.display(),
+        commit
+    );
+
+    copy_claude_config(repo_path, worktree_path);
+
+    Ok(())
+}
+
+/// Copy the .claude/ directory from the main repo to a new worktree.
+fn copy_claude_config(repo_path: &Path, worktree_path: &Path) {
     let source_claude = repo_path.join(".claude");
     let dest_claude = worktree_path.join(".claude");
 
@@ -288,8 +392,6 @@ fn create_worktree_sync(
             }
         }
     }
-
-    Ok(())
 }
 
 /// Recursively copy a directory and its contents.
@@ -345,7 +447,7 @@ fn remove_worktree_sync(repo_path: &Path, worktree_path: &Path) -> Result<(), Wo
 fn merge_branch_sync(repo_path: &Path, branch_name: &str) -> Result<(), WorktreeError> {
     // First, checkout main
     let output = std::process::Command::new("git")
-        .args(["checkout", "main"])
+        .args(["checkout", BASE_BRANCH])
         .current_dir(repo_path)
         .output()?;
 
@@ -420,8 +522,9 @@ mod tests {
 
     #[test]
     fn test_generate_branch_name() {
-        assert_eq!(generate_branch_name("Add login"), "ek/add-login");
-        assert_eq!(generate_branch_name("Fix bug #123"), "ek/fix-bug-123");
+        assert_eq!(generate_branch_name(DEFAULT_BRANCH_PREFIX, "Add login", "1234abcd-5678"), "ek/add-login-1234abcd");
+        assert_eq!(generate_branch_name(DEFAULT_BRANCH_PREFIX, "Fix bug #123", "deadbeef"), "ek/fix-bug-123-deadbeef");
+        assert_eq!(generate_branch_name("agents/kb", "Add login", "1234abcd"), "agents/kb/add-login-1234abcd");
     }
 
     #[test]
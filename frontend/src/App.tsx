@@ -55,11 +55,27 @@ function App() {
     // Task completion is reflected via WebSocket task updates
   }, []);
 
-  const { isConnected } = useWebSocket({
+  const { isConnected, subscribe, unsubscribe } = useWebSocket({
     onLog: handleLog,
     onExecutionComplete: handleExecutionComplete,
   });
 
+  // Logs and merge progress are only sent for subscribed tasks: follow running
+  // tasks and tasks in review (which can be merged)
+  const watchedTaskIds = useRef<Set<string>>(new Set());
+  useEffect(() => {
+    const watched = new Set(
+      tasks.filter((t) => t.status === 'in_progress' || t.status === 'review').map((t) => t.id)
+    );
+    watched.forEach((id) => {
+      if (!watchedTaskIds.current.has(id)) subscribe(id);
+    });
+    watchedTaskIds.current.forEach((id) => {
+      if (!watched.has(id)) unsubscribe(id);
+    });
+    watchedTaskIds.current = watched;
+  }, [tasks, subscribe, unsubscribe]);
+
   // Fetch project info on mount
   useEffect(() => {
     api.server.getInfo()
@@ -0,0 +1,121 @@
+import { useState } from 'react';
+import clsx from 'clsx';
+import { AttemptComparison } from '../types/review';
+import { useAttemptDiff, usePickAttempt } from '../hooks/useAttempts';
+import { DiffViewer } from './DiffViewer';
+
+interface AttemptsComparisonProps {
+  taskId: string;
+  attempts: AttemptComparison[];
+}
+
+function formatDuration(secs: number | null) {
+  if (secs === null) return '—';
+  if (secs < 60) return `${secs.toFixed(0)}s`;
+  return `${Math.floor(secs / 60)}m ${Math.round(secs % 60)}s`;
+}
+
+export function AttemptsComparison({ taskId, attempts }: AttemptsComparisonProps) {
+  const [selectedId, setSelectedId] = useState<string | null>(null);
+  const [pickError, setPickError] = useState<string | null>(null);
+  const pickAttempt = usePickAttempt();
+  const { data: diff, isLoading: diffLoading } = useAttemptDiff(taskId, selectedId);
+  const hasPick = attempts.some((a) => a.status === 'picked');
+
+  const handlePick = async (attemptId: string) => {
+    setPickError(null);
+    try {
+      await pickAttempt.mutateAsync({ taskId, attemptId });
+    } catch (error) {
+      setPickError(error instanceof Error ? error.message : 'Failed to pick attempt');
+    }
+  };
+
+  return (
+    <div className="h-full flex flex-col">
+      <table className="w-full text-sm text-left text-gray-300" data-testid="attempts-table">
+        <thead className="text-xs text-gray-400 border-b border-gray-700">
+          <tr>
+            <th className="px-4 py-2">Attempt</th>
+            <th className="px-4 py-2">Model</th>
+            <th className="px-4 py-2">Status</th>
+            <th className="px-4 py-2">Checks</th>
+            <th className="px-4 py-2">Cost</th>
+            <th className="px-4 py-2">Duration</th>
+            <th className="px-4 py-2">Changes</th>
+            <th className="px-4 py-2" />
+          </tr>
+        </thead>
+        <tbody>
+          {attempts.map((attempt) => (
+            <tr
+              key={attempt.id}
+              onClick={() => attempt.diff && setSelectedId(attempt.id)}
+              className={clsx(
+                'border-b border-gray-800',
+                attempt.diff && 'cursor-pointer hover:bg-gray-800',
+                selectedId === attempt.id && 'bg-gray-800',
+                attempt.status === 'discarded' && 'opacity-50'
+              )}
+            >
+              <td className="px-4 py-2">#{attempt.number}</td>
+              <td className="px-4 py-2 font-mono">{attempt.model || 'default'}</td>
+              <td className="px-4 py-2" title={attempt.error_message || undefined}>
+                {attempt.status.replace('_', ' ')}
+              </td>
+              <td
+                className={clsx(
+                  'px-4 py-2',
+                  attempt.checks_status === 'passed' && 'text-green-400',
+                  attempt.checks_status === 'failed' && 'text-red-400'
+                )}
+                title={attempt.checks.map((c) => `${c.name}: ${c.status}`).join('\n') || undefined}
+              >
+                {attempt.checks_status || '—'}
+              </td>
+              <td className="px-4 py-2">${attempt.usage.cost_usd.toFixed(2)}</td>
+              <td className="px-4 py-2">{formatDuration(attempt.duration_secs)}</td>
+              <td className="px-4 py-2">
+                {attempt.diff ? (
+                  <>
+                    {attempt.diff.files_changed} files{' '}
+                    <span className="text-green-400">+{attempt.diff.additions}</span>{' '}
+                    <span className="text-red-400">-{attempt.diff.deletions}</span>
+                  </>
+                ) : (
+                  '—'
+                )}
+              </td>
+              <td className="px-4 py-2 text-right">
+                {!hasPick && attempt.diff && attempt.status !== 'running' && (
+                  <button
+                    onClick={(e) => {
+                      e.stopPropagation();
+                      handlePick(attempt.id);
+                    }}
+                    disabled={pickAttempt.isPending}
+                    className="px-2 py-1 text-xs bg-green-600 hover:bg-green-700 disabled:bg-gray-600 rounded text-white"
+                  >
+                    Pick
+                  </button>
+                )}
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+
+      {pickError && (
+        <div className="px-4 py-2 bg-red-900/50 text-red-300 text-sm">{pickError}</div>
+      )}
+
+      <div className="flex-1 overflow-hidden">
+        {selectedId ? (
+          <DiffViewer diff={diff} isLoading={diffLoading} />
+        ) : (
+          <p className="p-4 text-sm text-gray-400">Select an attempt to see its changes.</p>
+        )}
+      </div>
+    </div>
+  );
+}
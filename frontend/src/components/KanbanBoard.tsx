@@ -1,5 +1,5 @@
 import { DragDropContext, DropResult } from '@hello-pangea/dnd';
-import { Task, TaskStatus, COLUMN_ORDER, canDropInColumn } from '../types/task';
+import { Task, TaskStatus, COLUMN_ORDER, canDropInColumn, columnFor } from '../types/task';
 import { KanbanColumn } from './KanbanColumn';
 
 interface KanbanBoardProps {
@@ -29,7 +29,7 @@ export function KanbanBoard({
 }: KanbanBoardProps) {
   const tasksByStatus = COLUMN_ORDER.reduce(
     (acc, status) => {
-      acc[status] = tasks.filter((t) => t.status === status);
+      acc[status] = tasks.filter((t) => columnFor(t.status) === status);
       return acc;
     },
     {} as Record<TaskStatus, Task[]>
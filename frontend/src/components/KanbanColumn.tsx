@@ -35,6 +35,8 @@ export function KanbanColumn({
     in_progress: 'border-blue-500',
     review: 'border-yellow-500',
     done: 'border-green-500',
+    budget_exceeded: 'border-red-500',
+    checks_failed: 'border-red-500',
   }[status];
 
   return (
@@ -2,6 +2,7 @@ import { useEffect, useRef } from 'react';
 import clsx from 'clsx';
 import { Task } from '../types/task';
 import { LogEntry } from '../hooks/useWebSocket';
+import { ClaudeOutputLine } from './ClaudeOutputLine';
 
 interface LogPanelProps {
   task: Task | null;
@@ -61,13 +62,19 @@ export function LogPanel({ task, logs, onClear, onClose }: LogPanelProps) {
               key={i}
               className={clsx(
                 'whitespace-pre-wrap break-all',
-                entry.stream === 'stderr' ? 'text-red-400' : 'text-gray-300'
+                entry.stream === 'stderr' && 'text-red-400',
+                entry.stream === 'check' && 'text-cyan-300',
+                entry.stream === 'stdout' && 'text-gray-300'
               )}
             >
               <span className="text-gray-500 select-none">
                 {new Date(entry.timestamp).toLocaleTimeString()} &gt;{' '}
               </span>
-              {entry.content}
+              {entry.stream === 'stdout' ? (
+                <ClaudeOutputLine line={entry.content} />
+              ) : (
+                entry.content
+              )}
             </div>
           ))
         )}
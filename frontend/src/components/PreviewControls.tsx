@@ -1,40 +1,37 @@
 import { Task } from '../types/task';
-import { PreviewInfo } from '../types/review';
+import { PreviewInfo, PreviewLogLine, proxyUrl } from '../types/review';
 import { Spinner, EmptyState } from './ui';
-import { useServerHealth } from '../hooks/useServerHealth';
 import { ServerStatusBadge } from './ServerStatusBadge';
 
 interface PreviewControlsProps {
   task: Task;
   status: PreviewInfo | null;
   error: string | null;
+  /** Output of the preview's services */
+  logs: PreviewLogLine[];
   isStarting: boolean;
   isStopping: boolean;
-  isRestartingBackend: boolean;
-  isRestartingFrontend: boolean;
+  /** Service being restarted, if any */
+  restartingService: string | null;
   onStart: () => void;
   onStop: () => void;
-  onRestartBackend: () => void;
-  onRestartFrontend: () => void;
+  onRestart: (service: string) => void;
 }
 
 export function PreviewControls({
   task,
   status,
   error,
+  logs,
   isStarting,
   isStopping,
-  isRestartingBackend,
-  isRestartingFrontend,
+  restartingService,
   onStart,
   onStop,
-  onRestartBackend,
-  onRestartFrontend,
+  onRestart,
 }: PreviewControlsProps) {
-  const health = useServerHealth(
-    status?.frontend_url ?? null,
-    status?.backend_url ?? null
-  );
+  const services = status?.services ?? [];
+  const shown = services.find((s) => s.name === status?.open) ?? services[0];
 
   const WarningIcon = (
     <svg className="w-full h-full" fill="none" stroke="currentColor" viewBox="0 0 24 24">
@@ -101,22 +98,18 @@ export function PreviewControls({
             </button>
 
             <div className="flex items-center gap-3 ml-auto">
-              <ServerStatusBadge
-                status={health.frontend}
-                label="Frontend"
-                url={status.frontend_url}
-                port={status.frontend_port}
-                onRestart={onRestartFrontend}
-                isRestarting={isRestartingFrontend}
-              />
-              <ServerStatusBadge
-                status={health.backend}
-                label="Backend"
-                url={status.backend_url}
-                port={status.backend_port}
-                onRestart={onRestartBackend}
-                isRestarting={isRestartingBackend}
-              />
+              {services.map((service) => (
+                <ServerStatusBadge
+                  key={service.name}
+                  status={service.status}
+                  error={service.error}
+                  label={service.name}
+                  url={proxyUrl(status, service)}
+                  port={service.port}
+                  onRestart={() => onRestart(service.name)}
+                  isRestarting={restartingService === service.name}
+                />
+              ))}
             </div>
           </>
         )}
@@ -131,9 +124,20 @@ export function PreviewControls({
 
       {/* Preview iframe or placeholder */}
       <div className="flex-1 p-4">
-        {status ? (
+        {shown && shown.status !== 'ready' ? (
+          <div className="flex items-center justify-center h-full border border-gray-700 border-dashed rounded-lg text-gray-500">
+            {shown.status === 'starting' ? (
+              <div className="flex items-center gap-3">
+                <Spinner size="sm" />
+                <span>Waiting for {shown.name} to be ready...</span>
+              </div>
+            ) : (
+              <p className="text-red-400">{shown.name} {shown.error ?? 'is not running'}</p>
+            )}
+          </div>
+        ) : shown ? (
           <iframe
-            src={status.frontend_url}
+            src={proxyUrl(status!, shown)}
             className="w-full h-full border border-gray-700 rounded-lg bg-white"
             title="Preview"
           />
@@ -144,11 +148,27 @@ export function PreviewControls({
                 <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={1} d="M9.75 17L9 20l-1 1h8l-1-1-.75-3M3 13h18M5 17h14a2 2 0 002-2V5a2 2 0 00-2-2H5a2 2 0 00-2 2v10a2 2 0 002 2z" />
               </svg>
               <p>Start preview to see your changes</p>
-              <p className="text-sm mt-1">This will run the backend and frontend servers</p>
+              <p className="text-sm mt-1">This will run the preview services configured for the project</p>
             </div>
           </div>
         )}
       </div>
+
+      {/* Service output */}
+      {logs.length > 0 && (
+        <details className="border-t border-gray-700" open={status?.status === 'failed'}>
+          <summary className="px-4 py-2 text-sm text-gray-400 cursor-pointer select-none">
+            Output ({logs.length} lines)
+          </summary>
+          <pre className="max-h-64 overflow-auto px-4 pb-4 text-xs font-mono text-gray-300 whitespace-pre-wrap">
+            {logs.map((line) => (
+              <div key={line.id} className={line.stream === 'stderr' ? 'text-red-300' : undefined}>
+                <span className="text-gray-500">[{line.service}]</span> {line.content}
+              </div>
+            ))}
+          </pre>
+        </details>
+      )}
     </div>
   );
 }
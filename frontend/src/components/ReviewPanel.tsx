@@ -4,6 +4,8 @@ import { Task } from '../types/task';
 import { useDiff } from '../hooks/useDiff';
 import { usePreview } from '../hooks/usePreview';
 import { useMergeTask } from '../hooks/useTasks';
+import { useAttempts } from '../hooks/useAttempts';
+import { AttemptsComparison } from './AttemptsComparison';
 import { DiffViewer } from './DiffViewer';
 import { PreviewControls } from './PreviewControls';
 import { EmptyState } from './ui';
@@ -14,15 +16,18 @@ interface ReviewPanelProps {
   onMerge?: (id: string) => void;
 }
 
-type TabType = 'diff' | 'preview';
+type TabType = 'diff' | 'preview' | 'attempts';
 
 export function ReviewPanel({ task, onClose, onMerge }: ReviewPanelProps) {
-  const [activeTab, setActiveTab] = useState<TabType>('diff');
+  // Until an attempt is picked the task has no branch of its own to review
+  const [activeTab, setActiveTab] = useState<TabType>(task.branch_name ? 'diff' : 'attempts');
   const [mergeError, setMergeError] = useState<string | null>(null);
   const [showMergeConfirm, setShowMergeConfirm] = useState(false);
   const { data: diff, isLoading: diffLoading, error: diffError } = useDiff(task.id);
   const preview = usePreview(task.id);
   const mergeTask = useMergeTask();
+  const { data: attempts } = useAttempts(task.id);
+  const canMerge = !!task.branch_name;
 
   const handleApproveAndMerge = async () => {
     setMergeError(null);
@@ -90,16 +95,33 @@ export function ReviewPanel({ task, onClose, onMerge }: ReviewPanelProps) {
                   <span className="ml-2 w-2 h-2 inline-block bg-green-400 rounded-full animate-pulse" />
                 )}
               </button>
+              {attempts && attempts.length > 0 && (
+                <button
+                  onClick={() => setActiveTab('attempts')}
+                  className={clsx(
+                    'px-4 py-1.5 rounded-md text-sm font-medium transition-colors',
+                    activeTab === 'attempts'
+                      ? 'bg-blue-600 text-white'
+                      : 'text-gray-400 hover:text-white'
+                  )}
+                >
+                  Attempts
+                  <span className="ml-2 text-xs bg-gray-700 px-1.5 py-0.5 rounded">
+                    {attempts.length}
+                  </span>
+                </button>
+              )}
             </div>
 
             {/* Approve & Merge button */}
             <button
               onClick={() => setShowMergeConfirm(true)}
-              disabled={mergeTask.isPending}
+              disabled={mergeTask.isPending || !canMerge}
+              title={canMerge ? undefined : 'Pick an attempt first'}
               data-testid="approve-merge-button"
               className={clsx(
                 'px-4 py-2 rounded-lg text-sm font-medium transition-colors',
-                mergeTask.isPending
+                mergeTask.isPending || !canMerge
                   ? 'bg-gray-600 text-gray-400 cursor-not-allowed'
                   : 'bg-green-600 hover:bg-green-700 text-white'
               )}
@@ -192,19 +214,22 @@ export function ReviewPanel({ task, onClose, onMerge }: ReviewPanelProps) {
             </>
           )}
 
+          {activeTab === 'attempts' && attempts && (
+            <AttemptsComparison taskId={task.id} attempts={attempts} />
+          )}
+
           {activeTab === 'preview' && (
             <PreviewControls
               task={task}
               status={preview.status}
               error={preview.error}
+              logs={preview.logs}
               isStarting={preview.isStarting}
               isStopping={preview.isStopping}
-              isRestartingBackend={preview.isRestartingBackend}
-              isRestartingFrontend={preview.isRestartingFrontend}
+              restartingService={preview.restartingService}
               onStart={preview.start}
               onStop={preview.stop}
-              onRestartBackend={preview.restartBackend}
-              onRestartFrontend={preview.restartFrontend}
+              onRestart={preview.restart}
             />
           )}
         </main>
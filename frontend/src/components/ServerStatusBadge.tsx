@@ -1,7 +1,9 @@
-import { ServerStatus } from '../hooks/useServerHealth';
+import { PreviewStatus } from '../types/review';
 
 interface ServerStatusBadgeProps {
-  status: ServerStatus;
+  status: PreviewStatus;
+  /** Why the service failed, shown on hover */
+  error?: string | null;
   label: string;
   url: string;
   port: number;
@@ -9,8 +11,8 @@ interface ServerStatusBadgeProps {
   isRestarting?: boolean;
 }
 
-const statusConfig: Record<ServerStatus, { bg: string; dot: string; animate: boolean }> = {
-  checking: {
+const statusConfig: Record<PreviewStatus, { bg: string; dot: string; animate: boolean }> = {
+  starting: {
     bg: 'bg-yellow-900/30',
     dot: 'bg-yellow-400',
     animate: true,
@@ -20,21 +22,28 @@ const statusConfig: Record<ServerStatus, { bg: string; dot: string; animate: boo
     dot: 'bg-green-400',
     animate: false,
   },
-  error: {
+  failed: {
     bg: 'bg-red-900/30',
     dot: 'bg-red-400',
     animate: false,
   },
+  stopped: {
+    bg: 'bg-gray-800',
+    dot: 'bg-gray-500',
+    animate: false,
+  },
 };
 
-const statusText: Record<ServerStatus, string> = {
-  checking: 'text-yellow-400',
+const statusText: Record<PreviewStatus, string> = {
+  starting: 'text-yellow-400',
   ready: 'text-green-400',
-  error: 'text-red-400',
+  failed: 'text-red-400',
+  stopped: 'text-gray-400',
 };
 
 export function ServerStatusBadge({
   status,
+  error,
   label,
   url,
   port,
@@ -53,7 +62,10 @@ export function ServerStatusBadge({
   };
 
   return (
-    <div className={`inline-flex items-center gap-2 px-3 py-1.5 rounded-md ${config.bg}`}>
+    <div
+      className={`inline-flex items-center gap-2 px-3 py-1.5 rounded-md ${config.bg}`}
+      title={error ? `${label}: ${error}` : `${label}: ${status}`}
+    >
       <span className={`w-2 h-2 rounded-full ${config.dot} ${config.animate || isRestarting ? 'animate-pulse' : ''}`} />
       <a
         href={url}
@@ -69,6 +69,21 @@ export function TaskCard({
             </p>
           )}
 
+          {task.checks_status && task.checks_status !== 'pending' && (
+            <span
+              className={clsx(
+                'inline-block mt-2 px-1.5 py-0.5 text-xs rounded',
+                task.checks_status === 'running' && 'bg-blue-600/20 text-blue-300',
+                task.checks_status === 'passed' && 'bg-green-600/20 text-green-400',
+                task.checks_status === 'failed' && 'bg-red-600/20 text-red-400'
+              )}
+            >
+              {task.checks_status === 'running' && 'Checks running'}
+              {task.checks_status === 'passed' && 'Checks passed'}
+              {task.checks_status === 'failed' && 'Checks failing'}
+            </span>
+          )}
+
           {hasError && (
             <p className="text-xs text-red-400 mt-1 line-clamp-1">
               {task.error_message}
@@ -85,7 +100,7 @@ export function TaskCard({
           )}
 
           <div className="flex items-center justify-end gap-1 mt-2">
-            {task.status === 'todo' && (
+            {(task.status === 'todo' || task.status === 'budget_exceeded' || task.status === 'checks_failed') && (
               <button
                 onClick={(e) => {
                   e.stopPropagation();
@@ -1,8 +1,12 @@
 import { useState, useEffect } from 'react';
 import clsx from 'clsx';
 import { Task } from '../types/task';
+import { useStartAttempts } from '../hooks/useAttempts';
 import { Spinner } from './ui';
 
+/** Attempts started by "Best of N" */
+const DEFAULT_ATTEMPTS = 3;
+
 interface TaskDetailsModalProps {
   task: Task;
   onClose: () => void;
@@ -72,6 +76,17 @@ export function TaskDetailsModal({
     onClose();
   };
 
+  const startAttempts = useStartAttempts();
+  const handleStartAttempts = async () => {
+    setError(null);
+    try {
+      await startAttempts.mutateAsync({ taskId: task.id, count: DEFAULT_ATTEMPTS });
+      onClose();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to start attempts');
+    }
+  };
+
   const isRunning = task.status === 'in_progress';
   const canEdit = task.status === 'todo';
   const canStart = task.status === 'todo';
@@ -158,7 +173,8 @@ export function TaskDetailsModal({
                 task.status === 'todo' && 'bg-gray-600 text-gray-200',
                 task.status === 'in_progress' && 'bg-blue-600 text-white',
                 task.status === 'review' && 'bg-yellow-600 text-white',
-                task.status === 'done' && 'bg-green-600 text-white'
+                task.status === 'done' && 'bg-green-600 text-white',
+                (task.status === 'budget_exceeded' || task.status === 'checks_failed') && 'bg-red-600 text-white'
               )}
             >
               {task.status.replace('_', ' ').toUpperCase()}
@@ -310,6 +326,16 @@ export function TaskDetailsModal({
                     Edit
                   </button>
                 )}
+                {canStart && !task.worktree_path && (
+                  <button
+                    onClick={handleStartAttempts}
+                    disabled={startAttempts.isPending}
+                    title={`Run ${DEFAULT_ATTEMPTS} attempts side by side and pick the best`}
+                    className="px-4 py-2 text-sm bg-gray-700 hover:bg-gray-600 rounded-lg text-gray-300 hover:text-white transition-colors"
+                  >
+                    Best of {DEFAULT_ATTEMPTS}
+                  </button>
+                )}
                 {canStart && (
                   <button
                     onClick={handleStart}
@@ -0,0 +1,52 @@
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query';
+import { api } from '../lib/api';
+import { AttemptComparison, AttemptVariant, DiffResponse } from '../types/review';
+import { Task } from '../types/task';
+
+const DIFF_STALE_TIME_MS = 30_000;
+
+export function useAttempts(taskId: string | null) {
+  return useQuery<AttemptComparison[]>({
+    queryKey: ['attempts', taskId],
+    queryFn: () => api.attempts.list(taskId!),
+    enabled: !!taskId,
+  });
+}
+
+export function useAttemptDiff(taskId: string, attemptId: string | null) {
+  return useQuery<DiffResponse>({
+    queryKey: ['attempts', taskId, attemptId, 'diff'],
+    queryFn: () => api.attempts.diff(taskId, attemptId!),
+    enabled: !!attemptId,
+    staleTime: DIFF_STALE_TIME_MS,
+  });
+}
+
+export function useStartAttempts() {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationFn: ({ taskId, count, variants }: { taskId: string; count?: number; variants?: AttemptVariant[] }) =>
+      api.attempts.start(taskId, { count, variants }),
+    onSuccess: (attempts, { taskId }) => {
+      queryClient.setQueryData(['attempts', taskId], attempts);
+    },
+  });
+}
+
+export function usePickAttempt() {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationFn: ({ taskId, attemptId }: { taskId: string; attemptId: string }) =>
+      api.attempts.pick(taskId, attemptId),
+    onSuccess: (task) => {
+      queryClient.setQueryData<Task[]>(['tasks'], (old) => {
+        return old?.map((t) => (t.id === task.id ? task : t));
+      });
+      queryClient.setQueryData(['tasks', task.id], task);
+      queryClient.invalidateQueries({ queryKey: ['attempts', task.id] });
+      queryClient.invalidateQueries({ queryKey: ['diff', task.id] });
+    },
+  });
+}
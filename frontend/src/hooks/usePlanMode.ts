@@ -1,7 +1,7 @@
 import { useState, useCallback, useEffect, useRef } from 'react';
 import { api } from '../lib/api';
 import { PlanModeState } from '../types/plan';
-import { setPlanEventHandlers, clearPlanEventHandlers, PlanQuestion } from './useWebSocket';
+import { setPlanEventHandlers, clearPlanEventHandlers, subscribeToPlan, unsubscribeFromPlan, PlanQuestion } from './useWebSocket';
 
 interface UsePlanModeOptions {
   onTaskCreated?: (taskId: string) => void;
@@ -42,7 +42,11 @@ export function usePlanMode(options: UsePlanModeOptions = {}) {
 
   // Set up event handlers when session starts
   const setupEventHandlers = useCallback((sessionId: string, logFn: (msg: string) => void) => {
+    if (sessionIdRef.current && sessionIdRef.current !== sessionId) {
+      unsubscribeFromPlan(sessionIdRef.current);
+    }
     sessionIdRef.current = sessionId;
+    subscribeToPlan(sessionId);
     logFn(`Event handlers registered for session ${sessionId}`);
 
     setPlanEventHandlers({
@@ -239,6 +243,7 @@ export function usePlanMode(options: UsePlanModeOptions = {}) {
 
       // Clear event handlers on success
       clearPlanEventHandlers();
+      if (sessionIdRef.current) unsubscribeFromPlan(sessionIdRef.current);
       sessionIdRef.current = null;
 
       setState({
@@ -355,6 +360,7 @@ export function usePlanMode(options: UsePlanModeOptions = {}) {
 
     // Clear event handlers
     clearPlanEventHandlers();
+    if (sessionIdRef.current) unsubscribeFromPlan(sessionIdRef.current);
     sessionIdRef.current = null;
 
     setState({
@@ -376,6 +382,7 @@ export function usePlanMode(options: UsePlanModeOptions = {}) {
   useEffect(() => {
     return () => {
       clearPlanEventHandlers();
+      if (sessionIdRef.current) unsubscribeFromPlan(sessionIdRef.current);
       sessionIdRef.current = null;
     };
   }, []);
@@ -12,6 +12,7 @@ vi.mock('../lib/api', () => ({
       stop: vi.fn(),
       status: vi.fn(),
       restart: vi.fn(),
+      logs: vi.fn(),
     },
   },
 }));
@@ -20,6 +21,20 @@ const mockPreviewStart = api.preview.start as Mock;
 const mockPreviewStop = api.preview.stop as Mock;
 const mockPreviewStatus = api.preview.status as Mock;
 const mockPreviewRestart = api.preview.restart as Mock;
+const mockPreviewLogs = api.preview.logs as Mock;
+
+function previewInfo(apiPort = 5200, webPort = 5201) {
+  return {
+    task_id: '550e8400-e29b-41d4-a716-446655440000',
+    status: 'ready' as const,
+    open: 'web',
+    proxy_port: 9848,
+    services: [
+      { name: 'api', port: apiPort, url: `http://localhost:${apiPort}`, proxy_url: '/preview/550e8400-e29b-41d4-a716-446655440000/api/', status: 'ready' as const, error: null },
+      { name: 'web', port: webPort, url: `http://localhost:${webPort}`, proxy_url: '/preview/550e8400-e29b-41d4-a716-446655440000/web/', status: 'ready' as const, error: null },
+    ],
+  };
+}
 
 function createWrapper() {
   const queryClient = new QueryClient({
@@ -42,6 +57,7 @@ describe('usePreview', () => {
   beforeEach(() => {
     vi.clearAllMocks();
     vi.useFakeTimers();
+    mockPreviewLogs.mockResolvedValue([]);
   });
 
   afterEach(() => {
@@ -55,20 +71,13 @@ describe('usePreview', () => {
     expect(result.current.error).toBeNull();
     expect(result.current.isStarting).toBe(false);
     expect(result.current.isStopping).toBe(false);
-    expect(result.current.isRestartingBackend).toBe(false);
-    expect(result.current.isRestartingFrontend).toBe(false);
+    expect(result.current.restartingService).toBeNull();
+    expect(result.current.logs).toEqual([]);
   });
 
   it('should check initial status on mount', async () => {
     vi.useRealTimers();
-    const mockInfo = {
-      task_id: '550e8400-e29b-41d4-a716-446655440000',
-      backend_url: 'http://localhost:9900',
-      frontend_url: 'http://localhost:5200',
-      backend_port: 9900,
-      frontend_port: 5200,
-      status: 'running' as const,
-    };
+    const mockInfo = previewInfo();
 
     mockPreviewStatus.mockResolvedValueOnce(mockInfo);
 
@@ -95,14 +104,7 @@ describe('usePreview', () => {
 
   it('should start preview', async () => {
     vi.useRealTimers();
-    const mockInfo = {
-      task_id: '550e8400-e29b-41d4-a716-446655440000',
-      backend_url: 'http://localhost:9900',
-      frontend_url: 'http://localhost:5200',
-      backend_port: 9900,
-      frontend_port: 5200,
-      status: 'running' as const,
-    };
+    const mockInfo = previewInfo();
 
     mockPreviewStatus.mockRejectedValueOnce(new Error('Not found'));
     mockPreviewStart.mockResolvedValueOnce(mockInfo);
@@ -159,16 +161,24 @@ describe('usePreview', () => {
     expect(mockPreviewStop).not.toHaveBeenCalled();
   });
 
-  it('should restart backend server', async () => {
+  it('should load the output of a running preview', async () => {
+    vi.useRealTimers();
+    const line = { id: 1, service: 'api', stream: 'stdout', content: 'listening' };
+    mockPreviewStatus.mockResolvedValueOnce(previewInfo());
+    mockPreviewLogs.mockResolvedValueOnce([line]);
+
+    const { result } = renderHook(
+      () => usePreview('550e8400-e29b-41d4-a716-446655440000'),
+      { wrapper: createWrapper() }
+    );
+
+    await waitFor(() => expect(result.current.logs).toEqual([line]));
+    expect(mockPreviewLogs).toHaveBeenCalledWith('550e8400-e29b-41d4-a716-446655440000', 0);
+  });
+
+  it('should restart the api service', async () => {
     vi.useRealTimers();
-    const mockInfo = {
-      task_id: '550e8400-e29b-41d4-a716-446655440000',
-      backend_url: 'http://localhost:9901',
-      frontend_url: 'http://localhost:5200',
-      backend_port: 9901,
-      frontend_port: 5200,
-      status: 'running' as const,
-    };
+    const mockInfo = previewInfo(5202, 5201);
 
     mockPreviewStatus.mockRejectedValueOnce(new Error('Not found'));
     mockPreviewRestart.mockResolvedValueOnce(mockInfo);
@@ -179,23 +189,16 @@ describe('usePreview', () => {
     );
 
     await act(async () => {
-      result.current.restartBackend();
+      result.current.restart('api');
     });
 
     await waitFor(() => expect(result.current.status).toEqual(mockInfo));
-    expect(mockPreviewRestart).toHaveBeenCalledWith('550e8400-e29b-41d4-a716-446655440000', 'backend');
+    expect(mockPreviewRestart).toHaveBeenCalledWith('550e8400-e29b-41d4-a716-446655440000', 'api');
   });
 
-  it('should restart frontend server', async () => {
+  it('should restart the web service', async () => {
     vi.useRealTimers();
-    const mockInfo = {
-      task_id: '550e8400-e29b-41d4-a716-446655440000',
-      backend_url: 'http://localhost:9900',
-      frontend_url: 'http://localhost:5201',
-      backend_port: 9900,
-      frontend_port: 5201,
-      status: 'running' as const,
-    };
+    const mockInfo = previewInfo(5200, 5202);
 
     mockPreviewStatus.mockRejectedValueOnce(new Error('Not found'));
     mockPreviewRestart.mockResolvedValueOnce(mockInfo);
@@ -206,11 +209,11 @@ describe('usePreview', () => {
     );
 
     await act(async () => {
-      result.current.restartFrontend();
+      result.current.restart('web');
     });
 
     await waitFor(() => expect(result.current.status).toEqual(mockInfo));
-    expect(mockPreviewRestart).toHaveBeenCalledWith('550e8400-e29b-41d4-a716-446655440000', 'frontend');
+    expect(mockPreviewRestart).toHaveBeenCalledWith('550e8400-e29b-41d4-a716-446655440000', 'web');
   });
 
   it('should not restart when taskId is null', async () => {
@@ -218,8 +221,7 @@ describe('usePreview', () => {
     const { result } = renderHook(() => usePreview(null), { wrapper: createWrapper() });
 
     await act(async () => {
-      result.current.restartBackend();
-      result.current.restartFrontend();
+      result.current.restart('api');
     });
 
     expect(mockPreviewRestart).not.toHaveBeenCalled();
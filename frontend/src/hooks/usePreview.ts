@@ -1,17 +1,41 @@
-import { useState, useEffect, useCallback } from 'react';
+import { useState, useEffect, useCallback, useRef } from 'react';
 import { useMutation } from '@tanstack/react-query';
 import { api } from '../lib/api';
-import { PreviewInfo } from '../types/review';
+import { PreviewInfo, PreviewLogLine } from '../types/review';
 
 const PREVIEW_POLL_INTERVAL_MS = 5_000;
+/** Faster while services are coming up */
+const PREVIEW_STARTING_POLL_INTERVAL_MS = 1_000;
+/** Same as the server keeps */
+const MAX_PREVIEW_LOG_LINES = 2_000;
 
 export function usePreview(taskId: string | null) {
   const [status, setStatus] = useState<PreviewInfo | null>(null);
   const [error, setError] = useState<string | null>(null);
+  const [logs, setLogs] = useState<PreviewLogLine[]>([]);
+  const lastLogIdRef = useRef(0);
+
+  const resetLogs = useCallback(() => {
+    lastLogIdRef.current = 0;
+    setLogs([]);
+  }, []);
+
+  const fetchLogs = useCallback(async () => {
+    if (!taskId) return;
+    try {
+      const lines = await api.preview.logs(taskId, lastLogIdRef.current);
+      if (lines.length === 0) return;
+      lastLogIdRef.current = lines[lines.length - 1].id;
+      setLogs((prev) => [...prev, ...lines].slice(-MAX_PREVIEW_LOG_LINES));
+    } catch {
+      // Preview might have stopped
+    }
+  }, [taskId]);
 
   const startMutation = useMutation({
     mutationFn: () => api.preview.start(taskId!),
     onSuccess: (data) => {
+      resetLogs();
       setStatus(data);
       setError(null);
     },
@@ -31,19 +55,8 @@ export function usePreview(taskId: string | null) {
     },
   });
 
-  const restartBackendMutation = useMutation({
-    mutationFn: () => api.preview.restart(taskId!, 'backend'),
-    onSuccess: (data) => {
-      setStatus(data);
-      setError(null);
-    },
-    onError: (err: Error) => {
-      setError(err.message);
-    },
-  });
-
-  const restartFrontendMutation = useMutation({
-    mutationFn: () => api.preview.restart(taskId!, 'frontend'),
+  const restartMutation = useMutation({
+    mutationFn: (service: string) => api.preview.restart(taskId!, service),
     onSuccess: (data) => {
       setStatus(data);
       setError(null);
@@ -53,7 +66,8 @@ export function usePreview(taskId: string | null) {
     },
   });
 
-  // Poll status while running
+  // Poll status and output while running
+  const isStarting = status?.status === 'starting';
   useEffect(() => {
     if (!taskId || !status) return;
 
@@ -61,23 +75,28 @@ export function usePreview(taskId: string | null) {
       try {
         const newStatus = await api.preview.status(taskId);
         setStatus(newStatus);
+        await fetchLogs();
       } catch {
         // Preview might have stopped
         setStatus(null);
       }
-    }, PREVIEW_POLL_INTERVAL_MS);
+    }, isStarting ? PREVIEW_STARTING_POLL_INTERVAL_MS : PREVIEW_POLL_INTERVAL_MS);
 
     return () => clearInterval(interval);
-  }, [taskId, status]);
+  }, [taskId, status, isStarting, fetchLogs]);
 
   // Check if preview is already running on mount
   useEffect(() => {
+    resetLogs();
     if (!taskId) return;
 
     api.preview.status(taskId)
-      .then(setStatus)
+      .then((info) => {
+        setStatus(info);
+        fetchLogs();
+      })
       .catch(() => setStatus(null));
-  }, [taskId]);
+  }, [taskId, resetLogs, fetchLogs]);
 
   const start = useCallback(() => {
     if (taskId) {
@@ -91,28 +110,21 @@ export function usePreview(taskId: string | null) {
     }
   }, [taskId, stopMutation]);
 
-  const restartBackend = useCallback(() => {
-    if (taskId) {
-      restartBackendMutation.mutate();
-    }
-  }, [taskId, restartBackendMutation]);
-
-  const restartFrontend = useCallback(() => {
+  const restart = useCallback((service: string) => {
     if (taskId) {
-      restartFrontendMutation.mutate();
+      restartMutation.mutate(service);
     }
-  }, [taskId, restartFrontendMutation]);
+  }, [taskId, restartMutation]);
 
   return {
     status,
     error,
+    logs,
     start,
     stop,
-    restartBackend,
-    restartFrontend,
+    restart,
     isStarting: startMutation.isPending,
     isStopping: stopMutation.isPending,
-    isRestartingBackend: restartBackendMutation.isPending,
-    isRestartingFrontend: restartFrontendMutation.isPending,
+    restartingService: restartMutation.isPending ? restartMutation.variables ?? null : null,
   };
 }
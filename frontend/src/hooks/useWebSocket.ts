@@ -1,6 +1,7 @@
 import { useEffect, useRef, useCallback, useState } from 'react';
 import { useQueryClient } from '@tanstack/react-query';
-import { Task, TaskSchema } from '../types/task';
+import { RunCheckSchema, Task, TaskSchema } from '../types/task';
+import { PreviewInfoSchema, PreviewLogLineSchema } from '../types/review';
 import { z } from 'zod';
 
 const PlanQuestionOptionSchema = z.object({
@@ -33,16 +34,62 @@ const WsMessageSchema = z.discriminatedUnion('type', [
     task_id: z.string(),
     content: z.string(),
     stream: z.string(),
+    attempt_id: z.string().optional(),
   }),
   z.object({
     type: z.literal('execution_complete'),
     task_id: z.string(),
     success: z.boolean(),
   }),
+  z.object({
+    type: z.literal('usage_updated'),
+    task_id: z.string(),
+    run_id: z.string(),
+    usage: z.object({
+      input_tokens: z.number(),
+      output_tokens: z.number(),
+      cache_creation_tokens: z.number(),
+      cache_read_tokens: z.number(),
+      cost_usd: z.number(),
+    }),
+  }),
+  z.object({
+    type: z.literal('budget_exceeded'),
+    task_id: z.string(),
+    scope: z.string(),
+    message: z.string(),
+  }),
+  z.object({
+    type: z.literal('checks_updated'),
+    task_id: z.string(),
+    run_id: z.string(),
+    checks: z.array(RunCheckSchema),
+  }),
+  z.object({
+    type: z.literal('attempt_updated'),
+    attempt: z.object({ id: z.string(), task_id: z.string() }).passthrough(),
+  }),
+  z.object({
+    type: z.literal('eval_run_updated'),
+    run: z.object({ id: z.string(), suite_id: z.string() }).passthrough(),
+  }),
+  z.object({
+    type: z.literal('eval_result_updated'),
+    result: z.object({ id: z.string(), eval_run_id: z.string() }).passthrough(),
+  }),
   z.object({
     type: z.literal('merge_started'),
     task_id: z.string(),
   }),
+  z.object({
+    type: z.literal('preview_updated'),
+    preview: PreviewInfoSchema,
+  }),
+  z.object({
+    type: z.literal('preview_log'),
+    task_id: z.string(),
+    line: PreviewLogLineSchema,
+  }),
   z.object({
     type: z.literal('merge_progress'),
     task_id: z.string(),
@@ -95,13 +142,45 @@ const WsMessageSchema = z.discriminatedUnion('type', [
   }),
   z.object({
     type: z.literal('chat_chunk'),
+    thread_id: z.string().optional(),
+    message_id: z.string().optional(),
     content: z.string(),
     is_complete: z.boolean(),
   }),
   z.object({
     type: z.literal('chat_error'),
+    thread_id: z.string().optional(),
+    message_id: z.string().optional(),
     error: z.string(),
   }),
+  z.object({
+    type: z.literal('chat_cancelled'),
+    thread_id: z.string(),
+    message_id: z.string(),
+  }),
+  z.object({
+    type: z.literal('chat_tool_use'),
+    thread_id: z.string(),
+    message_id: z.string().optional(),
+    tool_use_id: z.string(),
+    name: z.string(),
+    input: z.unknown(),
+  }),
+  z.object({
+    type: z.literal('chat_tool_result'),
+    thread_id: z.string(),
+    message_id: z.string().optional(),
+    tool_use_id: z.string(),
+    is_error: z.boolean(),
+  }),
+  z.object({
+    type: z.literal('connected'),
+    last_event_id: z.number(),
+  }),
+  z.object({
+    type: z.literal('resync'),
+    last_event_id: z.number(),
+  }),
   z.object({
     type: z.literal('ping'),
   }),
@@ -114,7 +193,7 @@ type WsMessage = z.infer<typeof WsMessageSchema>;
 
 export interface LogEntry {
   content: string;
-  stream: 'stdout' | 'stderr';
+  stream: 'stdout' | 'stderr' | 'check';
   timestamp: number;
 }
 
@@ -142,6 +221,8 @@ export interface RebuildEventHandlers {
 export interface ChatEventHandlers {
   onChatChunk?: (content: string, isComplete: boolean) => void;
   onChatError?: (error: string) => void;
+  onChatToolUse?: (toolUseId: string, name: string, input: unknown) => void;
+  onChatToolResult?: (toolUseId: string, isError: boolean) => void;
 }
 
 interface UseWebSocketOptions {
@@ -149,6 +230,46 @@ interface UseWebSocketOptions {
   onExecutionComplete?: (taskId: string, success: boolean) => void;
 }
 
+// Logs, merge progress and plan output are only sent for subscribed tasks and
+// plan sessions. Subscriptions are remembered and re-sent after reconnecting.
+let activeSocket: WebSocket | null = null;
+// Id of the last event received, sent as `resume` after reconnecting so the
+// server replays what was missed
+let lastEventId: number | null = null;
+const taskSubscriptions = new Set<string>();
+const planSubscriptions = new Set<string>();
+
+function sendClientMessage(message: Record<string, string>) {
+  if (activeSocket?.readyState === WebSocket.OPEN) {
+    activeSocket.send(JSON.stringify(message));
+  }
+}
+
+function resendSubscriptions() {
+  taskSubscriptions.forEach((taskId) => sendClientMessage({ type: 'subscribe', task_id: taskId }));
+  planSubscriptions.forEach((sessionId) => sendClientMessage({ type: 'subscribe_plan', session_id: sessionId }));
+}
+
+export function subscribeToTask(taskId: string) {
+  taskSubscriptions.add(taskId);
+  sendClientMessage({ type: 'subscribe', task_id: taskId });
+}
+
+export function unsubscribeFromTask(taskId: string) {
+  taskSubscriptions.delete(taskId);
+  sendClientMessage({ type: 'unsubscribe', task_id: taskId });
+}
+
+export function subscribeToPlan(sessionId: string) {
+  planSubscriptions.add(sessionId);
+  sendClientMessage({ type: 'subscribe_plan', session_id: sessionId });
+}
+
+export function unsubscribeFromPlan(sessionId: string) {
+  planSubscriptions.delete(sessionId);
+  sendClientMessage({ type: 'unsubscribe_plan', session_id: sessionId });
+}
+
 // Global plan event listeners (can be set by usePlanMode)
 let planEventHandlers: PlanEventHandlers = {};
 
@@ -211,6 +332,11 @@ export function useWebSocket(options: UseWebSocketOptions = {}) {
     const ws = new WebSocket(wsUrl);
 
     ws.onopen = () => {
+      activeSocket = ws;
+      resendSubscriptions();
+      if (lastEventId !== null) {
+        ws.send(JSON.stringify({ type: 'resume', last_seen: lastEventId }));
+      }
       setIsConnected(true);
       console.log('WebSocket connected');
     };
@@ -236,6 +362,10 @@ export function useWebSocket(options: UseWebSocketOptions = {}) {
 
         const message = WsMessageSchema.parse(data);
 
+        if (typeof data.id === 'number' && (message.type !== 'connected' || lastEventId === null)) {
+          lastEventId = Math.max(lastEventId ?? 0, data.id);
+        }
+
         handleMessage(message);
       } catch (error) {
         console.error('Failed to parse WebSocket message:', error, event.data);
@@ -271,11 +401,20 @@ export function useWebSocket(options: UseWebSocketOptions = {}) {
         case 'log':
           options.onLog?.(message.task_id, {
             content: message.content,
-            stream: message.stream as 'stdout' | 'stderr',
+            stream: message.stream as LogEntry['stream'],
             timestamp: Date.now(),
           });
           break;
 
+        case 'attempt_updated':
+          queryClient.invalidateQueries({ queryKey: ['attempts', message.attempt.task_id] });
+          break;
+
+        case 'eval_run_updated':
+        case 'eval_result_updated':
+          queryClient.invalidateQueries({ queryKey: ['evals'] });
+          break;
+
         case 'execution_complete':
           options.onExecutionComplete?.(message.task_id, message.success);
           break;
@@ -336,6 +475,27 @@ export function useWebSocket(options: UseWebSocketOptions = {}) {
           chatEventHandlers.onChatError?.(message.error);
           break;
 
+        case 'chat_cancelled':
+          // The partial reply is kept, so finish it like a completed one
+          chatEventHandlers.onChatChunk?.('', true);
+          break;
+
+        case 'chat_tool_use':
+          chatEventHandlers.onChatToolUse?.(message.tool_use_id, message.name, message.input);
+          break;
+
+        case 'chat_tool_result':
+          chatEventHandlers.onChatToolResult?.(message.tool_use_id, message.is_error);
+          break;
+
+        case 'connected':
+          break;
+
+        case 'resync':
+          // Missed events are gone; reload everything from the API
+          queryClient.invalidateQueries();
+          break;
+
         case 'ping':
           wsRef.current?.send(JSON.stringify({ type: 'pong' }));
           break;
@@ -359,11 +519,11 @@ export function useWebSocket(options: UseWebSocketOptions = {}) {
   }, [connect]);
 
   const subscribe = useCallback((taskId: string) => {
-    wsRef.current?.send(JSON.stringify({ type: 'subscribe', task_id: taskId }));
+    subscribeToTask(taskId);
   }, []);
 
   const unsubscribe = useCallback((taskId: string) => {
-    wsRef.current?.send(JSON.stringify({ type: 'unsubscribe', task_id: taskId }));
+    unsubscribeFromTask(taskId);
   }, []);
 
   return {
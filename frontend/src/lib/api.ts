@@ -6,10 +6,15 @@ import {
   TaskSchema,
 } from '../types/task';
 import {
+  AttemptComparison,
+  AttemptsResponseSchema,
+  AttemptVariant,
   DiffResponse,
   DiffResponseSchema,
   PreviewInfo,
   PreviewInfoSchema,
+  PreviewLogLine,
+  PreviewLogsResponseSchema,
 } from '../types/review';
 import {
   StartPlanRequest,
@@ -53,13 +58,32 @@ const ExecutePlanResponseSchema = z.object({
 const ServerInfoSchema = z.object({
   name: z.string(),
   path: z.string(),
+  project_id: z.string(),
 });
 
 export interface ServerInfo {
   name: string;
   path: string;
+  project_id: string;
 }
 
+const ProjectSchema = z.object({
+  id: z.string(),
+  name: z.string(),
+  path: z.string(),
+  created_at: z.string(),
+  last_opened_at: z.string(),
+  is_default: z.boolean(),
+  exists: z.boolean(),
+  is_git_repo: z.boolean(),
+});
+
+export type Project = z.infer<typeof ProjectSchema>;
+
+const ProjectsResponseSchema = z.object({
+  projects: z.array(ProjectSchema),
+});
+
 const API_BASE = '/api';
 
 class ApiError extends Error {
@@ -159,6 +183,38 @@ export const api = {
     },
   },
 
+  attempts: {
+    list: async (taskId: string): Promise<AttemptComparison[]> => {
+      const response = await fetch(`${API_BASE}/tasks/${taskId}/attempts`);
+      const data = await handleResponse(response, AttemptsResponseSchema);
+      return data.attempts;
+    },
+
+    /** Start `count` attempts, or one per variant */
+    start: async (taskId: string, request: { count?: number; variants?: AttemptVariant[] }): Promise<AttemptComparison[]> => {
+      const response = await fetch(`${API_BASE}/tasks/${taskId}/attempts`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(request),
+      });
+      const data = await handleResponse(response, AttemptsResponseSchema);
+      return data.attempts;
+    },
+
+    diff: async (taskId: string, attemptId: string): Promise<DiffResponse> => {
+      const response = await fetch(`${API_BASE}/tasks/${taskId}/attempts/${attemptId}/diff`);
+      return handleResponse(response, DiffResponseSchema);
+    },
+
+    /** Make the attempt the task's result and discard the others */
+    pick: async (taskId: string, attemptId: string): Promise<Task> => {
+      const response = await fetch(`${API_BASE}/tasks/${taskId}/attempts/${attemptId}/pick`, {
+        method: 'POST',
+      });
+      return handleResponse(response, TaskSchema);
+    },
+  },
+
   preview: {
     start: async (taskId: string): Promise<PreviewInfo> => {
       const response = await fetch(`${API_BASE}/tasks/${taskId}/preview`, {
@@ -179,12 +235,20 @@ export const api = {
       return handleResponse(response, PreviewInfoSchema);
     },
 
-    restart: async (taskId: string, server: 'backend' | 'frontend'): Promise<PreviewInfo> => {
-      const response = await fetch(`${API_BASE}/tasks/${taskId}/preview/restart/${server}`, {
+    restart: async (taskId: string, service: string): Promise<PreviewInfo> => {
+      const response = await fetch(`${API_BASE}/tasks/${taskId}/preview/restart/${encodeURIComponent(service)}`, {
         method: 'POST',
       });
       return handleResponse(response, PreviewInfoSchema);
     },
+
+    /** Output of the preview's services, after the line with id `after` */
+    logs: async (taskId: string, after?: number): Promise<PreviewLogLine[]> => {
+      const query = after ? `?after=${after}` : '';
+      const response = await fetch(`${API_BASE}/tasks/${taskId}/preview/logs${query}`);
+      const data = await handleResponse(response, PreviewLogsResponseSchema);
+      return data.logs;
+    },
   },
 
   plan: {
@@ -256,6 +320,30 @@ export const api = {
     },
   },
 
+  projects: {
+    list: async (): Promise<Project[]> => {
+      const response = await fetch(`${API_BASE}/projects`);
+      const data = await handleResponse(response, ProjectsResponseSchema);
+      return data.projects;
+    },
+
+    open: async (path: string, name?: string): Promise<Project> => {
+      const response = await fetch(`${API_BASE}/projects`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ path, name }),
+      });
+      return handleResponse(response, ProjectSchema);
+    },
+
+    remove: async (id: string): Promise<void> => {
+      const response = await fetch(`${API_BASE}/projects/${id}`, {
+        method: 'DELETE',
+      });
+      await handleVoidResponse(response);
+    },
+  },
+
   chat: {
     getHistory: async (): Promise<ChatMessage[]> => {
       const response = await fetch(`${API_BASE}/chat/history`);
@@ -273,6 +361,15 @@ export const api = {
       return data.user_message;
     },
 
+    cancelReply: async (threadId?: string): Promise<void> => {
+      const response = await fetch(`${API_BASE}/chat/cancel`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ thread_id: threadId }),
+      });
+      await handleVoidResponse(response);
+    },
+
     clearHistory: async (): Promise<number> => {
       const response = await fetch(`${API_BASE}/chat/history`, {
         method: 'DELETE',
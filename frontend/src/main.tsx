@@ -4,6 +4,13 @@ import { QueryClient, QueryClientProvider } from '@tanstack/react-query'
 import App from './App'
 import './index.css'
 
+// The server turned ?token= into a cookie; keep it out of history and bookmarks
+const url = new URL(window.location.href)
+if (url.searchParams.has('token')) {
+  url.searchParams.delete('token')
+  window.history.replaceState(null, '', url.toString())
+}
+
 const queryClient = new QueryClient({
   defaultOptions: {
     queries: {
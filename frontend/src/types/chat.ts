@@ -3,18 +3,40 @@ import { z } from 'zod';
 export const ChatRoleSchema = z.enum(['user', 'assistant']);
 export type ChatRole = z.infer<typeof ChatRoleSchema>;
 
+export const ChatToolCallSchema = z.object({
+  id: z.string(),
+  name: z.string(),
+  input: z.unknown(),
+  is_error: z.boolean(),
+});
+
+export type ChatToolCall = z.infer<typeof ChatToolCallSchema>;
+
 export const ChatMessageSchema = z.object({
   id: z.string(),
   project_path: z.string(),
+  thread_id: z.string().nullable().optional(),
   role: ChatRoleSchema,
   content: z.string(),
   image_data: z.string().nullable().optional(),
+  tool_calls: z.array(ChatToolCallSchema).optional(),
   created_at: z.string(),
 });
 
 export type ChatMessage = z.infer<typeof ChatMessageSchema>;
 
+export const ChatThreadSchema = z.object({
+  id: z.string(),
+  project_path: z.string(),
+  title: z.string(),
+  created_at: z.string(),
+  updated_at: z.string(),
+});
+
+export type ChatThread = z.infer<typeof ChatThreadSchema>;
+
 export const ChatHistoryResponseSchema = z.object({
+  thread_id: z.string().optional(),
   messages: z.array(ChatMessageSchema),
 });
 
@@ -28,6 +50,8 @@ export const SendMessageRequestSchema = z.object({
 export type SendMessageRequest = z.infer<typeof SendMessageRequestSchema>;
 
 export const SendMessageResponseSchema = z.object({
+  thread_id: z.string().optional(),
+  message_id: z.string().optional(),
   user_message: ChatMessageSchema,
 });
 
@@ -69,9 +69,9 @@ describe('Review Types', () => {
   describe('PreviewStatus', () => {
     it('should accept valid statuses', () => {
       expect(PreviewStatus.parse('starting')).toBe('starting');
-      expect(PreviewStatus.parse('running')).toBe('running');
+      expect(PreviewStatus.parse('ready')).toBe('ready');
+      expect(PreviewStatus.parse('failed')).toBe('failed');
       expect(PreviewStatus.parse('stopped')).toBe('stopped');
-      expect(PreviewStatus.parse('error')).toBe('error');
     });
 
     it('should reject invalid statuses', () => {
@@ -83,11 +83,13 @@ describe('Review Types', () => {
     it('should validate a valid preview info', () => {
       const validInfo = {
         task_id: '550e8400-e29b-41d4-a716-446655440000',
-        backend_url: 'http://localhost:9900',
-        frontend_url: 'http://localhost:5200',
-        backend_port: 9900,
-        frontend_port: 5200,
-        status: 'running',
+        status: 'failed',
+        open: 'frontend',
+        proxy_port: 9848,
+        services: [
+          { name: 'backend', port: 5200, url: 'http://localhost:5200', proxy_url: '/preview/550e8400-e29b-41d4-a716-446655440000/backend/', status: 'ready', error: null },
+          { name: 'frontend', port: 5201, url: 'http://localhost:5201', proxy_url: '/preview/550e8400-e29b-41d4-a716-446655440000/frontend/', status: 'failed', error: 'exited (exit status: 1)' },
+        ],
       };
       expect(() => PreviewInfoSchema.parse(validInfo)).not.toThrow();
     });
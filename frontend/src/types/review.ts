@@ -1,4 +1,5 @@
 import { z } from 'zod';
+import { CheckStatus, RunCheckSchema } from './task';
 
 export const DiffChangeType = z.enum(['added', 'modified', 'deleted', 'renamed']);
 export type DiffChangeType = z.infer<typeof DiffChangeType>;
@@ -21,16 +22,99 @@ export const DiffResponseSchema = z.object({
 
 export type DiffResponse = z.infer<typeof DiffResponseSchema>;
 
-export const PreviewStatus = z.enum(['starting', 'running', 'stopped', 'error']);
+export const PreviewStatus = z.enum(['starting', 'ready', 'failed', 'stopped']);
 export type PreviewStatus = z.infer<typeof PreviewStatus>;
 
+export const PreviewServiceSchema = z.object({
+  name: z.string(),
+  port: z.number(),
+  url: z.string(),
+  /** Same service through the server's preview proxy, a path on `proxy_port` */
+  proxy_url: z.string(),
+  status: PreviewStatus,
+  error: z.string().nullable(),
+});
+
+export type PreviewService = z.infer<typeof PreviewServiceSchema>;
+
 export const PreviewInfoSchema = z.object({
   task_id: z.string(),
-  backend_url: z.string(),
-  frontend_url: z.string(),
-  backend_port: z.number(),
-  frontend_port: z.number(),
   status: PreviewStatus,
+  open: z.string().nullable(),
+  /** Port previews are served on, apart from the board's */
+  proxy_port: z.number(),
+  services: z.array(PreviewServiceSchema),
 });
 
 export type PreviewInfo = z.infer<typeof PreviewInfoSchema>;
+
+/** A service's proxied URL, on the preview port of the host the board was opened on */
+export function proxyUrl(preview: PreviewInfo, service: PreviewService): string {
+  return `${window.location.protocol}//${window.location.hostname}:${preview.proxy_port}${service.proxy_url}`;
+}
+
+export const PreviewLogLineSchema = z.object({
+  id: z.number(),
+  service: z.string(),
+  stream: z.string(),
+  content: z.string(),
+});
+
+export type PreviewLogLine = z.infer<typeof PreviewLogLineSchema>;
+
+export const PreviewLogsResponseSchema = z.object({
+  logs: z.array(PreviewLogLineSchema),
+});
+
+export const AttemptStatus = z.enum([
+  'running',
+  'succeeded',
+  'failed',
+  'cancelled',
+  'budget_exceeded',
+  'picked',
+  'discarded',
+]);
+export type AttemptStatus = z.infer<typeof AttemptStatus>;
+
+/** One of a task's parallel attempts, with what is needed to compare it */
+export const AttemptComparisonSchema = z.object({
+  id: z.string(),
+  task_id: z.string(),
+  number: z.number(),
+  model: z.string().nullable(),
+  prompt_template_id: z.string().nullable(),
+  branch_name: z.string().nullable(),
+  worktree_path: z.string().nullable(),
+  status: AttemptStatus,
+  error_message: z.string().nullable(),
+  checks_status: CheckStatus.nullable(),
+  created_at: z.string(),
+  finished_at: z.string().nullable(),
+  runs: z.number(),
+  usage: z.object({
+    input_tokens: z.number(),
+    output_tokens: z.number(),
+    cache_creation_tokens: z.number(),
+    cache_read_tokens: z.number(),
+    cost_usd: z.number(),
+  }),
+  duration_secs: z.number().nullable(),
+  checks: z.array(RunCheckSchema),
+  diff: z.object({
+    files_changed: z.number(),
+    additions: z.number(),
+    deletions: z.number(),
+  }).nullable(),
+});
+
+export type AttemptComparison = z.infer<typeof AttemptComparisonSchema>;
+
+export const AttemptsResponseSchema = z.object({
+  attempts: z.array(AttemptComparisonSchema),
+});
+
+export interface AttemptVariant {
+  model?: string;
+  prompt_template_id?: string;
+}
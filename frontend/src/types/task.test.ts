@@ -4,6 +4,7 @@ import {
   TaskStatus,
   CreateTaskSchema,
   canDropInColumn,
+  columnFor,
   COLUMN_ORDER,
   COLUMN_TITLES,
 } from './task';
@@ -67,6 +68,8 @@ describe('TaskStatus', () => {
     expect(TaskStatus.safeParse('in_progress').success).toBe(true);
     expect(TaskStatus.safeParse('review').success).toBe(true);
     expect(TaskStatus.safeParse('done').success).toBe(true);
+    expect(TaskStatus.safeParse('budget_exceeded').success).toBe(true);
+    expect(TaskStatus.safeParse('checks_failed').success).toBe(true);
   });
 
   it('should reject invalid status values', () => {
@@ -132,3 +135,19 @@ describe('COLUMN_TITLES', () => {
     expect(COLUMN_TITLES.done).toBe('Done');
   });
 });
+
+describe('columnFor', () => {
+  it('should show budget exceeded tasks in the review column', () => {
+    expect(columnFor('budget_exceeded')).toBe('review');
+  });
+
+  it('should keep tasks with failing checks out of the review column', () => {
+    expect(columnFor('checks_failed')).toBe('in_progress');
+  });
+
+  it('should keep board statuses in their own column', () => {
+    for (const status of COLUMN_ORDER) {
+      expect(columnFor(status)).toBe(status);
+    }
+  });
+});
@@ -1,8 +1,22 @@
 import { z } from 'zod';
 
-export const TaskStatus = z.enum(['todo', 'in_progress', 'review', 'done']);
+export const TaskStatus = z.enum(['todo', 'in_progress', 'review', 'done', 'budget_exceeded', 'checks_failed']);
 export type TaskStatus = z.infer<typeof TaskStatus>;
 
+export const CheckStatus = z.enum(['pending', 'running', 'passed', 'failed']);
+export type CheckStatus = z.infer<typeof CheckStatus>;
+
+export const RunCheckSchema = z.object({
+  name: z.string(),
+  command: z.string(),
+  status: CheckStatus,
+  exit_code: z.number().nullable(),
+  error: z.string().nullable(),
+  duration_ms: z.number().nullable(),
+});
+
+export type RunCheck = z.infer<typeof RunCheckSchema>;
+
 export const TaskSchema = z.object({
   id: z.string().uuid(),
   title: z.string().min(1),
@@ -11,6 +25,10 @@ export const TaskSchema = z.object({
   error_message: z.string().nullable(),
   branch_name: z.string().nullable(),
   worktree_path: z.string().nullable(),
+  budget_usd: z.number().nullable().optional(),
+  budget_tokens: z.number().nullable().optional(),
+  prompt_template_id: z.string().nullable().optional(),
+  checks_status: CheckStatus.nullable().optional(),
   created_at: z.string().datetime(),
   updated_at: z.string().datetime(),
 });
@@ -49,8 +67,18 @@ export const COLUMN_TITLES: Record<TaskStatus, string> = {
   in_progress: 'In Progress',
   review: 'Review',
   done: 'Done',
+  budget_exceeded: 'Budget Exceeded',
+  checks_failed: 'Checks Failed',
 };
 
+// Statuses without their own column are shown in the closest board column
+export function columnFor(status: TaskStatus): TaskStatus {
+  if (status === 'budget_exceeded') return 'review';
+  // Failing checks keep a task out of review
+  if (status === 'checks_failed') return 'in_progress';
+  return status;
+}
+
 export function canDropInColumn(fromStatus: TaskStatus, toStatus: TaskStatus): boolean {
   if (fromStatus === 'in_progress') {
     return false;
@@ -7,7 +7,8 @@ export default defineConfig({
     port: 5173,
     proxy: {
       '/api': {
-        target: 'http://localhost:9851',
+        // Set by the preview to the task's own backend
+        target: process.env.VITE_API_TARGET ?? 'http://localhost:9851',
         changeOrigin: true,
         ws: true,
       },
@@ -129,9 +129,14 @@ async function main() {
     process.exit(1);
   }
 
-  console.log('Starting server...');
+  // Arguments go to the binary: `npx eval-kanban task list`, `--port 8080`...
+  const args = process.argv.slice(2);
+  const isTaskCommand = args.includes('task');
+  if (!isTaskCommand) {
+    console.log('Starting server...');
+  }
 
-  const server = spawn(binPath, [], {
+  const server = spawn(binPath, args, {
     cwd: cwd,
     stdio: 'inherit',
     env: {